@@ -2,6 +2,8 @@ pub const LOG_PREFIX: &'static str = "app.log";
 
 pub const CATEGORIES_TABLE: &'static str = "categories";
 
+pub const TAGS_TABLE: &str = "tags";
+
 pub const COMMANDS_TABLE: &'static str = "commands";
 pub const COMMAND_GROUP_COLUMN: &'static str = "group_id";
 
@@ -11,6 +13,10 @@ pub const GROUP_PARENT_GROUP_COLUMN: &'static str = "parent_group_id";
 pub const WORKFLOWS_TABLE: &'static str = "workflows";
 pub const WORKFLOW_STEPS_TABLE: &'static str = "workflow_steps";
 
+pub const SHELLS_TABLE: &str = "shells";
+
+pub const TEMPLATES_TABLE: &str = "templates";
+
 pub const EXECUTION_HISTORY_TABLE: &'static str = "execution_history";
 pub const EXECUTION_HISTORY_LIMIT: i64 = 100;
 
@@ -20,3 +26,6 @@ pub const DATABASE_LOCKED_MESSAGE: &'static str =
     "Database is locked by another process. Please try again.";
 
 pub const MAX_LOG_LINES: usize = 10_000;
+
+/// Shown instead of secret env var values
+pub const SECRET_MASK: &str = "********";
@@ -0,0 +1,241 @@
+use super::{BulkAction, BulkResult, Command, Database, DatabaseError, Group, Result};
+use crate::constants::{
+    COMMANDS_TABLE, COMMAND_GROUP_COLUMN, GROUPS_TABLE, GROUP_PARENT_GROUP_COLUMN,
+};
+use rusqlite::{params, Connection, ToSql};
+use std::collections::{HashMap, HashSet};
+use tracing::{error, info, instrument};
+
+/// Current values of the columns a bulk action touches, read before the transaction
+struct BulkItem {
+    id: i64,
+    parent_id: Option<i64>,
+    category_id: Option<i64>,
+    is_favorite: bool,
+    env_vars: Option<HashMap<String, String>>,
+    secret_names: HashSet<String>,
+    working_directory: Option<String>,
+}
+
+impl From<Command> for BulkItem {
+    fn from(command: Command) -> Self {
+        Self {
+            id: command.id,
+            parent_id: command.group_id,
+            category_id: command.category_id,
+            is_favorite: command.is_favorite,
+            env_vars: command.env_vars,
+            secret_names: command
+                .secret_env_vars
+                .into_iter()
+                .flatten()
+                .map(|(name, _)| name)
+                .collect(),
+            working_directory: command.working_directory,
+        }
+    }
+}
+
+impl From<Group> for BulkItem {
+    fn from(group: Group) -> Self {
+        Self {
+            id: group.id,
+            parent_id: group.parent_group_id,
+            category_id: group.category_id,
+            is_favorite: group.is_favorite,
+            env_vars: group.env_vars,
+            secret_names: group
+                .secret_env_vars
+                .into_iter()
+                .flatten()
+                .map(|(name, _)| name)
+                .collect(),
+            working_directory: group.working_directory,
+        }
+    }
+}
+
+impl Database {
+    /// Applies the action to every command in one transaction, nothing changes when one of
+    /// them is missing or the action is invalid for any of them
+    #[instrument(skip(self))]
+    pub fn bulk_update_commands(&self, ids: &[i64], action: &BulkAction) -> Result<BulkResult> {
+        let items = Self::unique_ids(ids)
+            .map(|id| self.get_command(id).map(BulkItem::from))
+            .collect::<Result<Vec<_>>>()?;
+        if let BulkAction::Move {
+            group_id: Some(group_id),
+        } = action
+        {
+            self.get_group(*group_id)?;
+        }
+
+        self.bulk_update(COMMANDS_TABLE, COMMAND_GROUP_COLUMN, &items, action)
+    }
+
+    /// Applies the action to every group in one transaction, nothing changes when one of
+    /// them is missing or the action is invalid for any of them
+    #[instrument(skip(self))]
+    pub fn bulk_update_groups(&self, ids: &[i64], action: &BulkAction) -> Result<BulkResult> {
+        let items = Self::unique_ids(ids)
+            .map(|id| self.get_group(id).map(BulkItem::from))
+            .collect::<Result<Vec<_>>>()?;
+        if let BulkAction::Move {
+            group_id: Some(parent_id),
+        } = action
+        {
+            self.get_group(*parent_id)?;
+            for item in &items {
+                self.validate_no_circular_reference(item.id, *parent_id)?;
+            }
+        }
+
+        self.bulk_update(GROUPS_TABLE, GROUP_PARENT_GROUP_COLUMN, &items, action)
+    }
+
+    /// Ids in the given order, repeated ones only once
+    fn unique_ids(ids: &[i64]) -> impl Iterator<Item = i64> + '_ {
+        let mut seen = HashSet::new();
+        ids.iter().copied().filter(move |id| seen.insert(*id))
+    }
+
+    fn bulk_update(
+        &self,
+        table: &'static str,
+        parent_column: &'static str,
+        items: &[BulkItem],
+        action: &BulkAction,
+    ) -> Result<BulkResult> {
+        self.validate_bulk_action(table, items, action)?;
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let mut result = BulkResult::default();
+        for item in items {
+            if Self::apply_bulk_action(&tx, table, parent_column, item, action)? {
+                result.changed.push(item.id);
+            } else {
+                result.unchanged.push(item.id);
+            }
+        }
+        tx.commit()?;
+
+        info!(
+            table,
+            changed = result.changed.len(),
+            unchanged = result.unchanged.len(),
+            "Bulk action applied"
+        );
+        Ok(result)
+    }
+
+    fn validate_bulk_action(
+        &self,
+        table: &'static str,
+        items: &[BulkItem],
+        action: &BulkAction,
+    ) -> Result<()> {
+        match action {
+            BulkAction::SetCategory {
+                category_id: Some(category_id),
+            } => {
+                self.get_category(*category_id)?;
+            }
+            BulkAction::SetEnvVar { key, .. } => {
+                self.validate_field_length("env_vars", key, Self::MAX_NAME_LENGTH)?;
+                self.validate_env_var_names("env_vars", [key])?;
+                if let Some(item) = items.iter().find(|item| item.secret_names.contains(key)) {
+                    error!(key, id = item.id, table, "Env var is already a secret");
+                    return Err(DatabaseError::InvalidData {
+                        field: "env_vars",
+                        reason: format!("'{key}' is a secret env var of {table} {}", item.id),
+                    });
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Whether the item changed. A deleted item counts as changed even when it went with an
+    /// ancestor group deleted before it.
+    fn apply_bulk_action(
+        conn: &Connection,
+        table: &'static str,
+        parent_column: &'static str,
+        item: &BulkItem,
+        action: &BulkAction,
+    ) -> Result<bool> {
+        match action {
+            BulkAction::Move { group_id } => {
+                if item.parent_id == *group_id {
+                    return Ok(false);
+                }
+                let position = Self::next_position(conn, table, Some(parent_column), *group_id)?;
+                conn.execute(
+                    &format!(
+                        "UPDATE {table} SET {parent_column} = ?1, position = ?2 WHERE id = ?3"
+                    ),
+                    params![group_id, position, item.id],
+                )?;
+            }
+            BulkAction::SetCategory { category_id } => {
+                if item.category_id == *category_id {
+                    return Ok(false);
+                }
+                Self::set_bulk_column(conn, table, "category_id", item.id, category_id)?;
+            }
+            BulkAction::SetFavorite { is_favorite } => {
+                if item.is_favorite == *is_favorite {
+                    return Ok(false);
+                }
+                Self::set_bulk_column(conn, table, "is_favorite", item.id, is_favorite)?;
+            }
+            BulkAction::SetEnvVar { key, value } => {
+                let mut env_vars = item.env_vars.clone().unwrap_or_default();
+                let previous = match value {
+                    Some(value) => env_vars.insert(key.clone(), value.clone()),
+                    None => env_vars.remove(key),
+                };
+                if previous.as_ref() == value.as_ref() {
+                    return Ok(false);
+                }
+                let env_vars = Self::hashmap_to_string(&Some(env_vars).filter(|v| !v.is_empty()))?;
+                Self::set_bulk_column(conn, table, "env_vars", item.id, &env_vars)?;
+            }
+            BulkAction::SetWorkingDirectory { working_directory } => {
+                if item.working_directory == *working_directory {
+                    return Ok(false);
+                }
+                Self::set_bulk_column(
+                    conn,
+                    table,
+                    "working_directory",
+                    item.id,
+                    working_directory,
+                )?;
+            }
+            BulkAction::Delete => {
+                conn.execute(
+                    &format!("DELETE FROM {table} WHERE id = ?1"),
+                    params![item.id],
+                )?;
+            }
+        }
+        Ok(true)
+    }
+
+    fn set_bulk_column(
+        conn: &Connection,
+        table: &'static str,
+        column: &'static str,
+        id: i64,
+        value: &dyn ToSql,
+    ) -> Result<()> {
+        conn.execute(
+            &format!("UPDATE {table} SET {column} = ?1 WHERE id = ?2"),
+            params![value, id],
+        )?;
+        Ok(())
+    }
+}
@@ -1,11 +1,16 @@
+use super::explainer::strip_sudo;
+use super::secrets::SealedSecret;
 use super::{
-    CategoryFilter, Command, Database, ExecutionHistory, ExecutionStatus, GroupFilter, Result,
-    TriggeredBy, WithHistory,
+    CategoryFilter, Command, Database, DatabaseError, ExecutionHistory, ExecutionStatus,
+    GroupFilter, Result, TagFilter, TagTarget, TriggeredBy, WithHistory,
 };
 use crate::constants::{COMMANDS_TABLE, COMMAND_GROUP_COLUMN};
 use crate::database::helpers::QueryBuilder;
-use rusqlite::{named_params, params};
-use tracing::{debug, instrument, warn};
+use rusqlite::{named_params, params, Connection};
+use tracing::{debug, info, instrument, warn};
+
+/// Name of `migrate_sudo_commands` in `applied_migrations`
+const SUDO_MIGRATION: &str = "sudo_commands_run_elevated";
 
 impl Database {
     const EXECUTION_HISTORY_SELECT: &str = "SELECT c.*,
@@ -20,17 +25,49 @@ impl Database {
     #[instrument(skip(self, cmd), fields(name = %cmd.name))]
     pub fn create_command(&self, cmd: &Command) -> Result<i64> {
         self.validate_command(cmd)?;
+        self.validate_inherited_elevation(cmd)?;
+        let secret_values =
+            self.resolve_secret_values(COMMANDS_TABLE, None, &cmd.secret_env_vars)?;
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let id = Self::insert_command(&tx, cmd, &secret_values)?;
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// Inserts a validated command at the end of its group, inside the caller's transaction
+    pub(crate) fn insert_command(
+        conn: &Connection,
+        cmd: &Command,
+        secret_values: &[SealedSecret],
+    ) -> Result<i64> {
         let arguments_json = serde_json::to_string(&cmd.arguments)?;
         let env_vars_json = Self::hashmap_to_string(&cmd.env_vars)?;
+        let resource_limits_json = Self::option_to_json(&cmd.resource_limits)?;
+        let env_inheritance_json = Self::option_to_json(&cmd.env_inheritance)?;
+        let env_unset_json = Self::option_to_json(&cmd.env_unset)?;
+        let parameters_json = serde_json::to_string(&cmd.parameters)?;
+        let env_files_json = Self::option_to_json(&cmd.env_files)?;
+        let secret_env_names_json = Self::secret_names_to_json(&cmd.secret_env_vars)?;
+        let execution_target_json = Self::option_to_json(&cmd.execution_target)?;
+        let raw_arguments_json = serde_json::to_string(&cmd.raw_arguments)?;
+        let shell_mode_json = Self::option_to_json(&cmd.shell_mode)?;
+        let source_files_json = Self::option_to_json(&cmd.source_files)?;
+
+        let position = Self::next_position(
+            conn,
+            COMMANDS_TABLE,
+            Some(COMMAND_GROUP_COLUMN),
+            cmd.group_id,
+        )?;
 
-        let position =
-            self.get_position(COMMANDS_TABLE, Some(COMMAND_GROUP_COLUMN), cmd.group_id)?;
-
-        self.create(
+        let id = Self::insert_returning_id(
+            conn,
             COMMANDS_TABLE,
             "INSERT INTO
-            commands (name, command, arguments, description, group_id, position, working_directory, env_vars, shell, category_id, is_favorite)
-             VALUES (:name, :command, :arguments, :description, :group_id, :position, :working_directory, :env_vars, :shell, :category_id, :is_favorite)",
+            commands (name, command, arguments, description, group_id, position, working_directory, env_vars, shell, category_id, is_favorite, resource_limits, env_inheritance, env_unset, parameters, env_files, secret_env_names, run_elevated, execution_target, raw_arguments, shell_mode, source_files)
+             VALUES (:name, :command, :arguments, :description, :group_id, :position, :working_directory, :env_vars, :shell, :category_id, :is_favorite, :resource_limits, :env_inheritance, :env_unset, :parameters, :env_files, :secret_env_names, :run_elevated, :execution_target, :raw_arguments, :shell_mode, :source_files)",
             named_params! {
                 ":name": cmd.name,
                 ":command": cmd.command,
@@ -43,8 +80,24 @@ impl Database {
                 ":shell": cmd.shell,
                 ":category_id": cmd.category_id,
                 ":is_favorite": cmd.is_favorite,
+                ":resource_limits": resource_limits_json,
+                ":env_inheritance": env_inheritance_json,
+                ":env_unset": env_unset_json,
+                ":parameters": parameters_json,
+                ":env_files": env_files_json,
+                ":secret_env_names": secret_env_names_json,
+                ":run_elevated": cmd.run_elevated,
+                ":execution_target": execution_target_json,
+                ":raw_arguments": raw_arguments_json,
+                ":shell_mode": shell_mode_json,
+                ":source_files": source_files_json,
             },
-        )
+        )?;
+
+        if !secret_values.is_empty() {
+            Self::write_secret_values(conn, COMMANDS_TABLE, id, secret_values)?;
+        }
+        Ok(id)
     }
 
     #[instrument(skip(self))]
@@ -78,6 +131,7 @@ impl Database {
         &self,
         group_id: GroupFilter,
         category_id: CategoryFilter,
+        tags: TagFilter,
         favorites_only: bool,
         limit: Option<i64>,
         offset: Option<i64>,
@@ -104,6 +158,8 @@ impl Database {
             CategoryFilter::All => {}
         }
 
+        query_builder.add_tag_filter(&tags, TagTarget::Command, "c.id");
+
         if favorites_only {
             query_builder.add_condition_without_param("c.is_favorite = 1");
         }
@@ -219,18 +275,27 @@ impl Database {
         )
     }
 
-    // TODO: consider using transaction
+    /// The row, its parent group and its secrets are written in one transaction
     #[instrument(skip(self))]
     pub fn update_command(&self, cmd: &Command) -> Result<()> {
         self.validate_command(cmd)?;
+        self.validate_inherited_elevation(cmd)?;
         let old_cmd = self.get_command(cmd.id)?;
 
-        if old_cmd.group_id != cmd.group_id {
-            self.update_parent_group(COMMANDS_TABLE, COMMAND_GROUP_COLUMN, cmd.id, cmd.group_id)?;
-        }
-
         let arguments = serde_json::to_string(&cmd.arguments)?;
         let env_vars = Self::hashmap_to_string(&cmd.env_vars)?;
+        let resource_limits = Self::option_to_json(&cmd.resource_limits)?;
+        let env_inheritance = Self::option_to_json(&cmd.env_inheritance)?;
+        let env_unset = Self::option_to_json(&cmd.env_unset)?;
+        let parameters = serde_json::to_string(&cmd.parameters)?;
+        let env_files = Self::option_to_json(&cmd.env_files)?;
+        let secret_env_names = Self::secret_names_to_json(&cmd.secret_env_vars)?;
+        let execution_target = Self::option_to_json(&cmd.execution_target)?;
+        let raw_arguments = serde_json::to_string(&cmd.raw_arguments)?;
+        let shell_mode = Self::option_to_json(&cmd.shell_mode)?;
+        let source_files = Self::option_to_json(&cmd.source_files)?;
+        let secret_values =
+            self.resolve_secret_values(COMMANDS_TABLE, Some(cmd.id), &cmd.secret_env_vars)?;
 
         debug!(
             command_id = cmd.id,
@@ -238,9 +303,18 @@ impl Database {
             "Updating command"
         );
 
-        self.execute_db(
-            COMMANDS_TABLE,
-            cmd.id,
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        if old_cmd.group_id != cmd.group_id {
+            Self::update_parent_group(
+                &tx,
+                COMMANDS_TABLE,
+                COMMAND_GROUP_COLUMN,
+                cmd.id,
+                cmd.group_id,
+            )?;
+        }
+        tx.execute(
             "UPDATE commands SET
             name = :name,
             command = :command,
@@ -250,7 +324,18 @@ impl Database {
             env_vars = :env_vars,
             shell = :shell,
             category_id = :category_id,
-            is_favorite = :is_favorite
+            is_favorite = :is_favorite,
+            resource_limits = :resource_limits,
+            env_inheritance = :env_inheritance,
+            env_unset = :env_unset,
+            parameters = :parameters,
+            env_files = :env_files,
+            secret_env_names = :secret_env_names,
+            run_elevated = :run_elevated,
+            execution_target = :execution_target,
+            raw_arguments = :raw_arguments,
+            shell_mode = :shell_mode,
+            source_files = :source_files
             WHERE id = :id",
             named_params! {
                 ":name": cmd.name,
@@ -262,9 +347,25 @@ impl Database {
                 ":shell": cmd.shell,
                 ":category_id": cmd.category_id,
                 ":is_favorite": cmd.is_favorite,
+                ":resource_limits": resource_limits,
+                ":env_inheritance": env_inheritance,
+                ":env_unset": env_unset,
+                ":parameters": parameters,
+                ":env_files": env_files,
+                ":secret_env_names": secret_env_names,
+                ":run_elevated": cmd.run_elevated,
+                ":execution_target": execution_target,
+                ":raw_arguments": raw_arguments,
+                ":shell_mode": shell_mode,
+                ":source_files": source_files,
                 ":id": cmd.id
             },
-        )
+        )?;
+        Self::write_secret_values(&tx, COMMANDS_TABLE, cmd.id, &secret_values)?;
+        tx.commit()?;
+
+        info!(command_id = cmd.id, "Command updated");
+        Ok(())
     }
 
     fn get_position_parent_command(
@@ -316,13 +417,78 @@ impl Database {
         )
     }
 
+    /// `sudo <cmd>` hangs without a terminal to prompt on. A plain `sudo` prefix is removed and
+    /// `run_elevated` set instead, commands passing sudo flags such as `-u` are left alone.
+    /// Runs once, commands added later keep their `sudo`. Returns the number of migrated commands.
+    #[instrument(skip(self))]
+    pub fn migrate_sudo_commands(&self) -> Result<usize> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let applied: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM applied_migrations WHERE name = ?1)",
+            params![SUDO_MIGRATION],
+            |row| row.get(0),
+        )?;
+        if applied {
+            return Ok(0);
+        }
+
+        let candidates: Vec<(i64, String, Option<String>, Option<String>)> = {
+            let mut stmt = tx.prepare(
+                "SELECT id, command, arguments, raw_arguments FROM commands
+                 WHERE run_elevated = 0 AND (command = 'sudo' OR command LIKE 'sudo %')",
+            )?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+                })?
+                .collect::<rusqlite::Result<_>>()?;
+            rows
+        };
+
+        let mut migrated = 0;
+        for (id, command, arguments, raw_arguments) in candidates {
+            let mut arguments: Vec<String> = arguments
+                .and_then(|args| serde_json::from_str(&args).ok())
+                .unwrap_or_default();
+            let mut raw_arguments: Vec<usize> = raw_arguments
+                .and_then(|raw| serde_json::from_str(&raw).ok())
+                .unwrap_or_default();
+            let Some(command) = strip_plain_sudo(&command, &mut arguments, &mut raw_arguments)
+            else {
+                continue;
+            };
+            tx.execute(
+                "UPDATE commands SET command = ?1, arguments = ?2, raw_arguments = ?3,
+                 run_elevated = 1, updated_at = CURRENT_TIMESTAMP WHERE id = ?4",
+                params![
+                    command,
+                    serde_json::to_string(&arguments)?,
+                    serde_json::to_string(&raw_arguments)?,
+                    id
+                ],
+            )?;
+            migrated += 1;
+        }
+        tx.execute(
+            "INSERT INTO applied_migrations (name) VALUES (?1)",
+            params![SUDO_MIGRATION],
+        )?;
+        tx.commit()?;
+
+        if migrated > 0 {
+            info!(migrated, "Migrated sudo commands to run elevated");
+        }
+        Ok(migrated)
+    }
+
     /// the arguments/env_vars data is not being cross validated because
     /// 1. the data has already validation via rust type system (check create and update)
     /// 2. this would render the application in a stuck state as every get operation depends on this function
     /// -- FE requires updating to even view the command, and to view the command we require fetching the command
     /// -- If the default value is returned, user at least can retrieve the command/update with new value
     /// -- NOTE: we have to check before running the commands
-    fn row_to_command(row: &rusqlite::Row) -> rusqlite::Result<Command> {
+    pub(crate) fn row_to_command(row: &rusqlite::Row) -> rusqlite::Result<Command> {
         let args_str: String = row.get("arguments")?;
         let env_vars_str: Option<String> = row.get("env_vars")?;
 
@@ -346,14 +512,38 @@ impl Database {
             shell: row.get("shell")?,
             category_id: row.get("category_id")?,
             is_favorite: row.get("is_favorite")?,
+            resource_limits: Self::json_to_option("resource_limits", row.get("resource_limits")?),
+            env_inheritance: Self::json_to_option("env_inheritance", row.get("env_inheritance")?),
+            env_unset: Self::json_to_option("env_unset", row.get("env_unset")?),
+            parameters: Self::json_to_option("parameters", row.get("parameters")?)
+                .unwrap_or_default(),
+            env_files: Self::json_to_option("env_files", row.get("env_files")?),
+            secret_env_vars: Self::masked_secrets(row.get("secret_env_names")?),
+            run_elevated: row.get("run_elevated")?,
+            execution_target: Self::json_to_option(
+                "execution_target",
+                row.get("execution_target")?,
+            ),
+            raw_arguments: Self::json_to_option("raw_arguments", row.get("raw_arguments")?)
+                .unwrap_or_default(),
+            shell_mode: Self::json_to_option("shell_mode", row.get("shell_mode")?),
+            source_files: Self::json_to_option("source_files", row.get("source_files")?),
             created_at: row.get("created_at")?,
             updated_at: row.get("updated_at")?,
         })
     }
 
     /// Name, Command must not be empty
-    /// env var keys (alphanumeric + underscore + dash only)
-    fn validate_command(&self, cmd: &Command) -> Result<()> {
+    /// env var keys, allowlisted and unset names (alphanumeric + underscore + dash only)
+    /// parameter names unique, defaults valid for their kind
+    /// resource limits within the ranges accepted by the kernel
+    /// env file and source file paths must not be empty
+    /// secret names valid and not also plain env vars
+    /// container and host names of the execution target not empty or option like
+    /// raw argument indices within the arguments
+    /// shell mode one the shell has
+    /// not elevated when running in a container or over ssh
+    pub(crate) fn validate_command(&self, cmd: &Command) -> Result<()> {
         self.validate_field_length("name", &cmd.name, Self::MAX_NAME_LENGTH)?;
         self.validate_field_length("command", &cmd.command, Self::MAX_COMMAND_LENGTH)?;
         if let Some(desc) = &cmd.description {
@@ -361,6 +551,52 @@ impl Database {
         }
 
         self.validate_env_var_keys(&cmd.env_vars)?;
+        self.validate_resource_limits(&cmd.resource_limits)?;
+        self.validate_env_inheritance(&cmd.env_inheritance, &cmd.env_unset)?;
+        self.validate_parameters(&cmd.parameters)?;
+        self.validate_env_files(&cmd.env_files)?;
+        self.validate_secret_env_vars(&cmd.secret_env_vars, &cmd.env_vars)?;
+        self.validate_execution_target(&cmd.execution_target)?;
+        self.validate_source_files(&cmd.source_files)?;
+        self.validate_shell_mode(&cmd.shell, cmd.shell_mode)?;
+        self.validate_elevated_target(cmd.run_elevated, &cmd.execution_target)?;
+        if let Some(index) = cmd
+            .raw_arguments
+            .iter()
+            .find(|&&index| index >= cmd.arguments.len())
+        {
+            return Err(DatabaseError::InvalidData {
+                field: "raw_arguments",
+                reason: format!("There is no argument {index}"),
+            });
+        }
         Ok(())
     }
 }
+
+/// The command without its `sudo` prefix, which is either part of `command` or `command` itself
+/// with the real command in `arguments`, whose `raw_arguments` indices are then shifted down.
+/// `None` when sudo is not used or called with flags.
+fn strip_plain_sudo(
+    command: &str,
+    arguments: &mut Vec<String>,
+    raw_arguments: &mut Vec<usize>,
+) -> Option<String> {
+    let line = std::iter::once(command)
+        .chain(arguments.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let (is_privileged, effective) = strip_sudo(&line);
+    let without_prefix = line.strip_prefix("sudo")?.split_whitespace();
+    if !is_privileged || effective.is_empty() || !effective.split(' ').eq(without_prefix) {
+        return None;
+    }
+
+    if command == "sudo" {
+        raw_arguments.retain(|&index| index != 0);
+        raw_arguments.iter_mut().for_each(|index| *index -= 1);
+        Some(arguments.remove(0))
+    } else {
+        Some(command["sudo".len()..].trim_start().to_string())
+    }
+}
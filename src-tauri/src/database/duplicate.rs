@@ -0,0 +1,300 @@
+use super::secrets::SealedSecret;
+use super::{
+    CategoryFilter, Command, Database, Duplicated, Group, GroupFilter, GroupNode, Result,
+    TagFilter, TagTarget, Workflow, WorkflowStep,
+};
+use crate::constants::{
+    COMMANDS_TABLE, COMMAND_GROUP_COLUMN, GROUPS_TABLE, GROUP_PARENT_GROUP_COLUMN, WORKFLOWS_TABLE,
+};
+use regex::Regex;
+use rusqlite::{params, Connection};
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+use tracing::{info, instrument};
+
+/// `name (copy)` or `name (copy 3)`, copying a copy numbers it instead of nesting suffixes
+static COPY_SUFFIX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.*) \(copy(?: \d+)?\)$").expect("valid regex"));
+
+/// Command with the stored values of its secrets, read before the copy transaction
+struct CommandSource {
+    command: Command,
+    secret_values: Vec<SealedSecret>,
+}
+
+/// Group subtree with its commands, read before the copy transaction
+struct GroupSource {
+    group: Group,
+    secret_values: Vec<SealedSecret>,
+    commands: Vec<CommandSource>,
+    children: Vec<GroupSource>,
+}
+
+impl Database {
+    /// Copies a command with its secrets and tags to the end of its group
+    #[instrument(skip(self))]
+    pub fn duplicate_command(&self, id: i64) -> Result<i64> {
+        let source = self.command_source(self.get_command(id)?)?;
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let name = Self::copy_name(
+            &tx,
+            COMMANDS_TABLE,
+            Some(COMMAND_GROUP_COLUMN),
+            source.command.group_id,
+            &source.command.name,
+        )?;
+        let new_id = Self::insert_command_copy(&tx, &source, source.command.group_id, &name)?;
+        tx.commit()?;
+
+        info!(id, new_id, "Command duplicated");
+        Ok(new_id)
+    }
+
+    /// Copies a group with its subgroups and commands to the end of its parent. Only the copied
+    /// group gets a new name, everything below it keeps its name and order.
+    #[instrument(skip(self))]
+    pub fn duplicate_group(&self, id: i64) -> Result<Duplicated> {
+        let source = self.group_source(self.get_group_tree(id)?)?;
+        let group = &source.group;
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let name = Self::copy_name(
+            &tx,
+            GROUPS_TABLE,
+            Some(GROUP_PARENT_GROUP_COLUMN),
+            group.parent_group_id,
+            &group.name,
+        )?;
+        let mut duplicated = Duplicated::default();
+        let new_id = Self::insert_group_copy(
+            &tx,
+            &source,
+            group.parent_group_id,
+            Some(&name),
+            &mut duplicated,
+        )?;
+        duplicated.id = new_id;
+        tx.commit()?;
+
+        info!(
+            id,
+            new_id = duplicated.id,
+            groups = duplicated.groups.len(),
+            commands = duplicated.commands.len(),
+            "Group duplicated"
+        );
+        Ok(duplicated)
+    }
+
+    /// Copies a workflow with its steps. With `copy_commands` the steps point at copies of
+    /// their commands, made next to the originals, otherwise at the same commands.
+    #[instrument(skip(self))]
+    pub fn duplicate_workflow(&self, id: i64, copy_commands: bool) -> Result<Duplicated> {
+        let workflow = self.get_workflow(id)?;
+        let steps = self.get_workflow_steps(Some(id), None, false)?;
+        let mut commands = HashMap::new();
+        if copy_commands {
+            for step in &steps {
+                if !commands.contains_key(&step.command_id) {
+                    let source = self.command_source(self.get_command(step.command_id)?)?;
+                    commands.insert(step.command_id, source);
+                }
+            }
+        }
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let mut duplicated = Duplicated::default();
+
+        // commands are copied in step order, a command used by several steps only once
+        for step in &steps {
+            let Some(source) = commands.get(&step.command_id) else {
+                continue;
+            };
+            if duplicated.commands.contains_key(&step.command_id) {
+                continue;
+            }
+            let group_id = source.command.group_id;
+            let name = Self::copy_name(
+                &tx,
+                COMMANDS_TABLE,
+                Some(COMMAND_GROUP_COLUMN),
+                group_id,
+                &source.command.name,
+            )?;
+            let new_id = Self::insert_command_copy(&tx, source, group_id, &name)?;
+            duplicated.commands.insert(step.command_id, new_id);
+        }
+
+        let name = Self::copy_name(&tx, WORKFLOWS_TABLE, None, None, &workflow.name)?;
+        duplicated.id = Self::insert_workflow(&tx, &Workflow { name, ..workflow })?;
+        Self::copy_tags(&tx, TagTarget::Workflow, id, duplicated.id)?;
+
+        for step in &steps {
+            let command_id = duplicated
+                .commands
+                .get(&step.command_id)
+                .copied()
+                .unwrap_or(step.command_id);
+            let new_id = Self::insert_workflow_step(
+                &tx,
+                &WorkflowStep {
+                    workflow_id: duplicated.id,
+                    command_id,
+                    ..step.clone()
+                },
+            )?;
+            duplicated.workflow_steps.insert(step.id, new_id);
+        }
+        tx.commit()?;
+
+        info!(
+            id,
+            new_id = duplicated.id,
+            steps = duplicated.workflow_steps.len(),
+            commands = duplicated.commands.len(),
+            "Workflow duplicated"
+        );
+        Ok(duplicated)
+    }
+
+    fn command_source(&self, command: Command) -> Result<CommandSource> {
+        let secret_values = if command.secret_env_vars.is_some() {
+            self.get_sealed_secrets(COMMANDS_TABLE, command.id)?
+        } else {
+            Vec::new()
+        };
+        Ok(CommandSource {
+            command,
+            secret_values,
+        })
+    }
+
+    fn group_source(&self, node: GroupNode) -> Result<GroupSource> {
+        let GroupNode { group, children } = node;
+        let group_id = group.id;
+        let secret_values = if group.secret_env_vars.is_some() {
+            self.get_sealed_secrets(GROUPS_TABLE, group_id)?
+        } else {
+            Vec::new()
+        };
+        let commands = self
+            .get_commands(
+                GroupFilter::Group(group_id),
+                CategoryFilter::All,
+                TagFilter::All,
+                false,
+                None,
+                None,
+            )?
+            .into_iter()
+            .map(|command| self.command_source(command.item))
+            .collect::<Result<Vec<_>>>()?;
+        let children = children
+            .into_iter()
+            .map(|child| self.group_source(child))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(GroupSource {
+            group,
+            secret_values,
+            commands,
+            children,
+        })
+    }
+
+    /// Inserts the copy of a group below `parent_group_id`, then its commands and subgroups
+    fn insert_group_copy(
+        conn: &Connection,
+        source: &GroupSource,
+        parent_group_id: Option<i64>,
+        name: Option<&str>,
+        duplicated: &mut Duplicated,
+    ) -> Result<i64> {
+        let group = &source.group;
+        let copy = Group {
+            name: name.unwrap_or(&group.name).to_string(),
+            parent_group_id,
+            ..group.clone()
+        };
+        let new_id = Self::insert_group(conn, &copy, &source.secret_values)?;
+        Self::copy_tags(conn, TagTarget::Group, group.id, new_id)?;
+        duplicated.groups.insert(group.id, new_id);
+
+        for command in &source.commands {
+            let command_id =
+                Self::insert_command_copy(conn, command, Some(new_id), &command.command.name)?;
+            duplicated.commands.insert(command.command.id, command_id);
+        }
+        for child in &source.children {
+            Self::insert_group_copy(conn, child, Some(new_id), None, duplicated)?;
+        }
+        Ok(new_id)
+    }
+
+    fn insert_command_copy(
+        conn: &Connection,
+        source: &CommandSource,
+        group_id: Option<i64>,
+        name: &str,
+    ) -> Result<i64> {
+        let copy = Command {
+            name: name.to_string(),
+            group_id,
+            ..source.command.clone()
+        };
+        let new_id = Self::insert_command(conn, &copy, &source.secret_values)?;
+        Self::copy_tags(conn, TagTarget::Command, source.command.id, new_id)?;
+        Ok(new_id)
+    }
+
+    fn copy_tags(conn: &Connection, target: TagTarget, from: i64, to: i64) -> Result<()> {
+        let (link_table, link_column) = target.link();
+        conn.execute(
+            &format!(
+                "INSERT INTO {link_table} ({link_column}, tag_id)
+                 SELECT ?2, tag_id FROM {link_table} WHERE {link_column} = ?1"
+            ),
+            params![from, to],
+        )?;
+        Ok(())
+    }
+
+    /// `name (copy)`, or `name (copy N)` with the lowest N not taken by a sibling. Siblings
+    /// share `parent_id` in `parent_column`, without one every row of `table` is a sibling.
+    fn copy_name(
+        conn: &Connection,
+        table: &'static str,
+        parent_column: Option<&'static str>,
+        parent_id: Option<i64>,
+        name: &str,
+    ) -> Result<String> {
+        let base = COPY_SUFFIX
+            .captures(name)
+            .and_then(|captures| captures.get(1))
+            .map_or(name, |base| base.as_str());
+
+        let mut query = format!("SELECT name FROM {table}");
+        if let Some(parent_column) = parent_column {
+            query.push_str(&format!(" WHERE {parent_column} IS ?1"));
+        }
+        let params = if parent_column.is_some() {
+            params![parent_id]
+        } else {
+            params![]
+        };
+        let taken: HashSet<String> = conn
+            .prepare(&query)?
+            .query_map(params, |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let name = std::iter::once(format!("{base} (copy)"))
+            .chain((2..).map(|n| format!("{base} (copy {n})")))
+            .find(|candidate| !taken.contains(candidate))
+            .expect("the candidates are endless");
+        Ok(name)
+    }
+}
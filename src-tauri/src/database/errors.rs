@@ -21,6 +21,8 @@ pub enum DatabaseError {
     },
     DatabaseLocked,
     ConnectionFailed,
+    /// OS keyring unavailable or a secret value that can't be encrypted or decrypted
+    SecretStore(String),
     Internal(String),
 }
 
@@ -43,6 +45,7 @@ impl fmt::Display for DatabaseError {
             } => write!(f, "{} references non-existent ID {}", field, referenced_id),
             Self::DatabaseLocked => write!(f, "{}", DATABASE_LOCKED_MESSAGE),
             Self::ConnectionFailed => write!(f, "{}", CONNECTION_FAILED_MESSAGE),
+            Self::SecretStore(msg) => write!(f, "Secret store error: {}", msg),
             Self::Internal(msg) => write!(f, "Database error: {}", msg),
         }
     }
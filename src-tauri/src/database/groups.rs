@@ -1,26 +1,57 @@
-use super::{CategoryFilter, Database, DatabaseError, Group, GroupFilter, GroupNode, Result};
+use super::secrets::SealedSecret;
+use super::{
+    CategoryFilter, Database, DatabaseError, Group, GroupFilter, GroupNode, Result, TagFilter,
+    TagTarget,
+};
 use crate::constants::{GROUPS_TABLE, GROUP_PARENT_GROUP_COLUMN};
 use crate::database::helpers::QueryBuilder;
-use rusqlite::{named_params, params};
+use rusqlite::{named_params, params, Connection};
 use std::collections::{HashMap, HashSet};
-use tracing::{debug, instrument, warn};
+use tracing::{debug, info, instrument, warn};
 
 impl Database {
     #[instrument(skip(self, group), fields(name = %group.name))]
     pub fn create_group(&self, group: &Group) -> Result<i64> {
         self.validate_group(group)?;
-        let env_vars = Self::hashmap_to_string(&group.env_vars)?;
+        let secret_values =
+            self.resolve_secret_values(GROUPS_TABLE, None, &group.secret_env_vars)?;
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let id = Self::insert_group(&tx, group, &secret_values)?;
+        tx.commit()?;
+        Ok(id)
+    }
 
-        let position: i64 = self.get_position(
+    /// Inserts a validated group at the end of its parent, inside the caller's transaction
+    pub(crate) fn insert_group(
+        conn: &Connection,
+        group: &Group,
+        secret_values: &[SealedSecret],
+    ) -> Result<i64> {
+        let env_vars = Self::hashmap_to_string(&group.env_vars)?;
+        let resource_limits = Self::option_to_json(&group.resource_limits)?;
+        let env_inheritance = Self::option_to_json(&group.env_inheritance)?;
+        let env_unset = Self::option_to_json(&group.env_unset)?;
+        let variables = Self::option_to_json(&group.variables)?;
+        let env_files = Self::option_to_json(&group.env_files)?;
+        let secret_env_names = Self::secret_names_to_json(&group.secret_env_vars)?;
+        let execution_target = Self::option_to_json(&group.execution_target)?;
+        let shell_mode = Self::option_to_json(&group.shell_mode)?;
+        let source_files = Self::option_to_json(&group.source_files)?;
+
+        let position: i64 = Self::next_position(
+            conn,
             GROUPS_TABLE,
             Some(GROUP_PARENT_GROUP_COLUMN),
             group.parent_group_id,
         )?;
 
-        self.create(
+        let id = Self::insert_returning_id(
+            conn,
             GROUPS_TABLE,
-            "INSERT INTO groups (name, description, parent_group_id, position, working_directory, env_vars, shell, category_id, is_favorite, icon, color)
-             VALUES (:name, :description, :parent_group_id, :position, :working_directory, :env_vars, :shell, :category_id, :is_favorite, :icon, :color)",
+            "INSERT INTO groups (name, description, parent_group_id, position, working_directory, env_vars, shell, category_id, is_favorite, icon, color, resource_limits, env_inheritance, env_unset, variables, env_files, secret_env_names, execution_target, shell_mode, source_files)
+             VALUES (:name, :description, :parent_group_id, :position, :working_directory, :env_vars, :shell, :category_id, :is_favorite, :icon, :color, :resource_limits, :env_inheritance, :env_unset, :variables, :env_files, :secret_env_names, :execution_target, :shell_mode, :source_files)",
             named_params! {
                 ":name": group.name,
                 ":description": group.description,
@@ -33,8 +64,22 @@ impl Database {
                 ":is_favorite": group.is_favorite,
                 ":icon": group.icon,
                 ":color": group.color,
+                ":resource_limits": resource_limits,
+                ":env_inheritance": env_inheritance,
+                ":env_unset": env_unset,
+                ":variables": variables,
+                ":env_files": env_files,
+                ":secret_env_names": secret_env_names,
+                ":execution_target": execution_target,
+                ":shell_mode": shell_mode,
+                ":source_files": source_files,
             },
-        )
+        )?;
+
+        if !secret_values.is_empty() {
+            Self::write_secret_values(conn, GROUPS_TABLE, id, secret_values)?;
+        }
+        Ok(id)
     }
 
     #[instrument(skip(self), ret)]
@@ -52,6 +97,7 @@ impl Database {
         &self,
         parent_id: GroupFilter,
         category_id: CategoryFilter,
+        tags: TagFilter,
         favorites_only: bool,
     ) -> Result<Vec<Group>> {
         let mut query_builder = QueryBuilder::new();
@@ -75,6 +121,8 @@ impl Database {
             CategoryFilter::All => {}
         }
 
+        query_builder.add_tag_filter(&tags, TagTarget::Group, "groups.id");
+
         if favorites_only {
             query_builder.add_condition_without_param("is_favorite = 1");
         }
@@ -111,24 +159,30 @@ impl Database {
         )
     }
 
+    /// The row, its parent group and its secrets are written in one transaction
     #[instrument(skip(self))]
     pub fn update_group(&self, group: &Group) -> Result<()> {
         self.validate_group(group)?;
 
         let old_group = self.get_group(group.id)?;
-        if old_group.parent_group_id != group.parent_group_id {
-            if let Some(parent_id) = group.parent_group_id {
-                self.validate_no_circular_reference(group.id, parent_id)?;
-            }
-            self.update_parent_group(
-                GROUPS_TABLE,
-                GROUP_PARENT_GROUP_COLUMN,
-                group.id,
-                group.parent_group_id,
-            )?;
+        let parent_changed = old_group.parent_group_id != group.parent_group_id;
+        if let Some(parent_id) = group.parent_group_id.filter(|_| parent_changed) {
+            self.validate_no_circular_reference(group.id, parent_id)?;
         }
+        self.validate_elevated_descendants(group)?;
 
         let env_vars = Self::hashmap_to_string(&group.env_vars)?;
+        let resource_limits = Self::option_to_json(&group.resource_limits)?;
+        let env_inheritance = Self::option_to_json(&group.env_inheritance)?;
+        let env_unset = Self::option_to_json(&group.env_unset)?;
+        let variables = Self::option_to_json(&group.variables)?;
+        let env_files = Self::option_to_json(&group.env_files)?;
+        let secret_env_names = Self::secret_names_to_json(&group.secret_env_vars)?;
+        let execution_target = Self::option_to_json(&group.execution_target)?;
+        let shell_mode = Self::option_to_json(&group.shell_mode)?;
+        let source_files = Self::option_to_json(&group.source_files)?;
+        let secret_values =
+            self.resolve_secret_values(GROUPS_TABLE, Some(group.id), &group.secret_env_vars)?;
 
         debug!(
             command_id = group.id,
@@ -136,9 +190,18 @@ impl Database {
             "Updating Group"
         );
 
-        self.execute_db(
-            GROUPS_TABLE,
-            group.id,
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        if parent_changed {
+            Self::update_parent_group(
+                &tx,
+                GROUPS_TABLE,
+                GROUP_PARENT_GROUP_COLUMN,
+                group.id,
+                group.parent_group_id,
+            )?;
+        }
+        tx.execute(
             "UPDATE groups SET
             name = :name,
             description = :description,
@@ -147,7 +210,16 @@ impl Database {
             shell = :shell,
             category_id = :category_id,
             icon = :icon,
-            color = :color
+            color = :color,
+            resource_limits = :resource_limits,
+            env_inheritance = :env_inheritance,
+            env_unset = :env_unset,
+            variables = :variables,
+            env_files = :env_files,
+            secret_env_names = :secret_env_names,
+            execution_target = :execution_target,
+            shell_mode = :shell_mode,
+            source_files = :source_files
             WHERE id = :id",
             named_params! {
                 ":name": group.name,
@@ -158,9 +230,23 @@ impl Database {
                 ":category_id": group.category_id,
                 ":icon": group.icon,
                 ":color": group.color,
+                ":resource_limits": resource_limits,
+                ":env_inheritance": env_inheritance,
+                ":env_unset": env_unset,
+                ":variables": variables,
+                ":env_files": env_files,
+                ":secret_env_names": secret_env_names,
+                ":execution_target": execution_target,
+                ":shell_mode": shell_mode,
+                ":source_files": source_files,
                 ":id": group.id
             },
-        )
+        )?;
+        Self::write_secret_values(&tx, GROUPS_TABLE, group.id, &secret_values)?;
+        tx.commit()?;
+
+        info!(group_id = group.id, "Group updated");
+        Ok(())
     }
 
     #[instrument(skip(self))]
@@ -322,14 +408,36 @@ impl Database {
             is_favorite: row.get("is_favorite")?,
             icon: row.get("icon")?,
             color: row.get("color")?,
+            resource_limits: Self::json_to_option("resource_limits", row.get("resource_limits")?),
+            env_inheritance: Self::json_to_option("env_inheritance", row.get("env_inheritance")?),
+            env_unset: Self::json_to_option("env_unset", row.get("env_unset")?),
+            variables: Self::json_to_option("variables", row.get("variables")?),
+            env_files: Self::json_to_option("env_files", row.get("env_files")?),
+            secret_env_vars: Self::masked_secrets(row.get("secret_env_names")?),
+            execution_target: Self::json_to_option(
+                "execution_target",
+                row.get("execution_target")?,
+            ),
+            shell_mode: Self::json_to_option("shell_mode", row.get("shell_mode")?),
+            source_files: Self::json_to_option("source_files", row.get("source_files")?),
             created_at: row.get("created_at")?,
             updated_at: row.get("updated_at")?,
         })
     }
 
-    fn validate_group(&self, group: &Group) -> Result<()> {
+    pub(crate) fn validate_group(&self, group: &Group) -> Result<()> {
         self.validate_field_length("name", &group.name, Self::MAX_NAME_LENGTH)?;
         self.validate_env_var_keys(&group.env_vars)?;
+        self.validate_resource_limits(&group.resource_limits)?;
+        self.validate_env_inheritance(&group.env_inheritance, &group.env_unset)?;
+        if let Some(variables) = &group.variables {
+            self.validate_env_var_names("variables", variables.keys())?;
+        }
+        self.validate_env_files(&group.env_files)?;
+        self.validate_secret_env_vars(&group.secret_env_vars, &group.env_vars)?;
+        self.validate_execution_target(&group.execution_target)?;
+        self.validate_source_files(&group.source_files)?;
+        self.validate_shell_mode(&group.shell, group.shell_mode)?;
 
         Ok(())
     }
@@ -337,7 +445,11 @@ impl Database {
     /// group walks up the parent chain to detect cycle, it is a cycle if
     /// case 1 - we find the group being updated
     /// case 2 - if we find same parent more than once
-    fn validate_no_circular_reference(&self, group_id: i64, parent_id: i64) -> Result<()> {
+    pub(crate) fn validate_no_circular_reference(
+        &self,
+        group_id: i64,
+        parent_id: i64,
+    ) -> Result<()> {
         if group_id == parent_id {
             return Err(DatabaseError::CircularReference {
                 group_id,
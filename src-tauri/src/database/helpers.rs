@@ -1,6 +1,11 @@
 pub use crate::database::errors::{DatabaseError, Result};
-use crate::database::Database;
-use rusqlite::params;
+use crate::database::{
+    Command, CommandParameter, Database, EnvInheritance, ExecutionTarget, Group, ParameterKind,
+    ResourceLimits, ShellMode, TagFilter, TagTarget,
+};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use serde_json::Error;
 use std::collections::HashMap;
 use tracing::{debug, error, info, warn};
@@ -33,6 +38,41 @@ impl QueryBuilder {
         self
     }
 
+    /// Condition of a tag filter on the items of `target`, `id_column` is their id in the query.
+    /// The tag ids are bound as one JSON array.
+    pub fn add_tag_filter(
+        &mut self,
+        filter: &TagFilter,
+        target: TagTarget,
+        id_column: &str,
+    ) -> &mut Self {
+        let (link_table, link_column) = target.link();
+        let ids_json = |ids: &[i64]| {
+            let ids: Vec<String> = ids.iter().map(i64::to_string).collect();
+            format!("[{}]", ids.join(","))
+        };
+        match filter {
+            TagFilter::AnyOf(ids) => self.add_condition(
+                &format!(
+                    "EXISTS (SELECT 1 FROM {link_table} WHERE {link_column} = {id_column}
+                     AND tag_id IN (SELECT value FROM json_each(?)))"
+                ),
+                ids_json(ids),
+            ),
+            TagFilter::AllOf(ids) => self.add_condition(
+                &format!(
+                    "NOT EXISTS (SELECT 1 FROM json_each(?) WHERE value NOT IN (
+                     SELECT tag_id FROM {link_table} WHERE {link_column} = {id_column}))"
+                ),
+                ids_json(ids),
+            ),
+            TagFilter::None => self.add_condition_without_param(&format!(
+                "NOT EXISTS (SELECT 1 FROM {link_table} WHERE {link_column} = {id_column})"
+            )),
+            TagFilter::All => self,
+        }
+    }
+
     pub fn build(&self) -> (String, Vec<&dyn rusqlite::ToSql>) {
         let where_clause = if self.conditions.is_empty() {
             String::new()
@@ -77,11 +117,20 @@ impl Database {
         table: &'static str,
         sql: &str,
         params: P,
+    ) -> Result<i64> {
+        Self::insert_returning_id(&*self.conn()?, table, sql, params)
+    }
+
+    /// `create` on a given connection, e.g. a transaction spanning several inserts
+    pub(crate) fn insert_returning_id<P: rusqlite::Params>(
+        conn: &Connection,
+        table: &'static str,
+        sql: &str,
+        params: P,
     ) -> Result<i64> {
         let sql = format!("{} RETURNING id", sql);
 
-        let row_id: i64 = self
-            .conn()?
+        let row_id: i64 = conn
             .query_row(&sql, params, |row| row.get(0))
             .map_err(|e| {
                 error!(error = %e, table = table, "Failed to create");
@@ -172,8 +221,8 @@ impl Database {
 
     /// initial position is Self::POSITION_GAP instead of zero,
     /// starting from 0 forces renumbering every move to beginning operation
-    pub(crate) fn get_position(
-        &self,
+    pub(crate) fn next_position(
+        conn: &Connection,
         table: &'static str,
         parent_column: Option<&'static str>,
         parent_id: Option<i64>,
@@ -190,10 +239,8 @@ impl Database {
             params![]
         };
 
-        let position = Self::POSITION_GAP
-            + (self
-                .conn()?
-                .query_row(&query, params, |row| row.get::<_, i64>(0))?);
+        let position =
+            Self::POSITION_GAP + conn.query_row(&query, params, |row| row.get::<_, i64>(0))?;
 
         debug!(
             calculated_position = position,
@@ -204,17 +251,19 @@ impl Database {
         Ok(position)
     }
 
+    /// Moves the row to the end of its new parent inside the caller's transaction
     pub(crate) fn update_parent_group(
-        &self,
+        conn: &Connection,
         table: &'static str,
         parent_column: &'static str,
         id: i64,
         parent_id: Option<i64>,
     ) -> Result<()> {
-        let position = self.get_position(table, Some(parent_column), parent_id)?;
-        let query =
-            format!("UPDATE {table} SET {parent_column} = ?1, position = ?2 WHERE id = {id}");
-        self.execute_db(table, id, &query, params![parent_id, position])?;
+        let position = Self::next_position(conn, table, Some(parent_column), parent_id)?;
+        conn.execute(
+            &format!("UPDATE {table} SET {parent_column} = ?1, position = ?2 WHERE id = ?3"),
+            params![parent_id, position, id],
+        )?;
         Ok(())
     }
 
@@ -222,23 +271,203 @@ impl Database {
         &self,
         env_vars: &Option<HashMap<String, String>>,
     ) -> Result<()> {
-        if let Some(vars) = env_vars {
-            for key in vars.keys() {
-                if !key
-                    .chars()
-                    .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
-                {
-                    error!("Invalid env variable key: {}", key);
-                    return Err(DatabaseError::InvalidData {
-                        field: "env_vars",
-                        reason: format!("Invalid key '{key}': only alphanumeric, underscore, dash"),
-                    });
-                }
+        match env_vars {
+            Some(vars) => self.validate_env_var_names("env_vars", vars.keys()),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn validate_env_var_names<'a>(
+        &self,
+        field: &'static str,
+        names: impl IntoIterator<Item = &'a String>,
+    ) -> Result<()> {
+        for key in names {
+            if !key
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+            {
+                error!("Invalid env variable key: {}", key);
+                return Err(DatabaseError::InvalidData {
+                    field,
+                    reason: format!("Invalid key '{key}': only alphanumeric, underscore, dash"),
+                });
             }
         }
         Ok(())
     }
 
+    /// allowlist and unset entries follow the same naming rules as env var keys
+    pub(crate) fn validate_env_inheritance(
+        &self,
+        inheritance: &Option<EnvInheritance>,
+        unset: &Option<Vec<String>>,
+    ) -> Result<()> {
+        if let Some(EnvInheritance::Allowlist { variables }) = inheritance {
+            self.validate_env_var_names("env_inheritance", variables)?;
+        }
+        if let Some(keys) = unset {
+            self.validate_env_var_names("env_unset", keys)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn validate_env_files(&self, env_files: &Option<Vec<String>>) -> Result<()> {
+        if env_files
+            .iter()
+            .flatten()
+            .any(|path| path.trim().is_empty())
+        {
+            error!("Empty env file path");
+            return Err(DatabaseError::InvalidData {
+                field: "env_files",
+                reason: "env file path cannot be empty".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// A mode needs a shell that has it, checked when the shell is set on the same level.
+    /// An inherited shell is only known at spawn time, `resolve_spawn_context` checks it then.
+    pub(crate) fn validate_shell_mode(
+        &self,
+        shell: &Option<String>,
+        shell_mode: Option<ShellMode>,
+    ) -> Result<()> {
+        let (Some(name), Some(mode)) = (shell, shell_mode) else {
+            return Ok(());
+        };
+        let family = match self.get_shell_by_name(name) {
+            Ok(definition) => definition.family,
+            Err(DatabaseError::NotFound { .. }) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        if !family.supports_mode(mode) {
+            error!(shell = name, ?mode, "Shell mode not supported");
+            return Err(DatabaseError::InvalidData {
+                field: "shell_mode",
+                reason: format!(
+                    "'{name}' is a {} shell, it has no login or interactive mode",
+                    family.as_str()
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn validate_source_files(&self, source_files: &Option<Vec<String>>) -> Result<()> {
+        if source_files
+            .iter()
+            .flatten()
+            .any(|path| path.trim().is_empty())
+        {
+            error!("Empty source file path");
+            return Err(DatabaseError::InvalidData {
+                field: "source_files",
+                reason: "source file path cannot be empty".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Container names and hosts end up as arguments of docker/podman/ssh, they must not be
+    /// mistaken for options
+    pub(crate) fn validate_execution_target(&self, target: &Option<ExecutionTarget>) -> Result<()> {
+        let name = match target {
+            Some(ExecutionTarget::Container { container, .. }) => container,
+            Some(ExecutionTarget::Ssh { host }) => host,
+            Some(ExecutionTarget::Local) | None => return Ok(()),
+        };
+        if name.is_empty() || name.starts_with('-') || name.contains(char::is_whitespace) {
+            error!(name, "Invalid execution target");
+            return Err(DatabaseError::InvalidData {
+                field: "execution_target",
+                reason: format!("'{name}' is not a valid container or host name"),
+            });
+        }
+        Ok(())
+    }
+
+    /// Elevating the local docker/ssh client wouldn't elevate the command on the other side, it
+    /// would only run the client with root's keys. This is the target set on the same level,
+    /// `validate_inherited_elevation` and `resolve_spawn_context` check an inherited one.
+    pub(crate) fn validate_elevated_target(
+        &self,
+        run_elevated: bool,
+        target: &Option<ExecutionTarget>,
+    ) -> Result<()> {
+        if run_elevated && target.as_ref().is_some_and(|target| !target.is_local()) {
+            error!("Elevated command on a remote target");
+            return Err(DatabaseError::InvalidData {
+                field: "run_elevated",
+                reason: "A command running in a container or over ssh can't run elevated"
+                    .to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Same for the target an elevated command inherits from its group
+    pub(crate) fn validate_inherited_elevation(&self, cmd: &Command) -> Result<()> {
+        let Some(group_id) = cmd.group_id.filter(|_| cmd.execution_target.is_none()) else {
+            return Ok(());
+        };
+        let target = self
+            .get_group_ancestor_chain(group_id)?
+            .into_iter()
+            .find_map(|group| group.execution_target);
+        self.validate_elevated_target(cmd.run_elevated, &target)
+    }
+
+    /// A remote target set on or inherited by a group would reach the elevated commands below it
+    /// that don't set their own
+    pub(crate) fn validate_elevated_descendants(&self, group: &Group) -> Result<()> {
+        let target = match (&group.execution_target, group.parent_group_id) {
+            (Some(target), _) => Some(target.clone()),
+            (None, Some(parent_id)) => self
+                .get_group_ancestor_chain(parent_id)?
+                .into_iter()
+                .find_map(|group| group.execution_target),
+            (None, None) => None,
+        };
+        if !target.is_some_and(|target| !target.is_local()) {
+            return Ok(());
+        }
+
+        let elevated: Option<String> = self
+            .conn()?
+            .query_row(
+                "WITH RECURSIVE inheriting(id) AS (
+                    SELECT ?1
+                    UNION ALL
+                    SELECT g.id FROM groups g
+                    JOIN inheriting i ON g.parent_group_id = i.id
+                    WHERE g.execution_target IS NULL
+                )
+                SELECT name FROM commands
+                WHERE group_id IN (SELECT id FROM inheriting)
+                AND run_elevated = 1 AND execution_target IS NULL
+                ORDER BY position LIMIT 1",
+                params![group.id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(name) = elevated {
+            error!(
+                group_id = group.id,
+                command = name,
+                "Remote target for elevated command"
+            );
+            return Err(DatabaseError::InvalidData {
+                field: "execution_target",
+                reason: format!(
+                    "Command '{name}' runs elevated, it can't run in a container or over ssh"
+                ),
+            });
+        }
+        Ok(())
+    }
+
     /// Move command between two positions (calculates midpoint)
     /// prev_id None means move to top
     /// next_id None means move to bottom
@@ -355,6 +584,99 @@ impl Database {
         })
     }
 
+    pub(crate) fn option_to_json<T: Serialize>(
+        value: &Option<T>,
+    ) -> std::result::Result<Option<String>, Error> {
+        value.as_ref().map(serde_json::to_string).transpose()
+    }
+
+    /// same graceful degradation as `string_to_hashmap`, corrupted json falls back to None
+    pub(crate) fn json_to_option<T: DeserializeOwned>(
+        field: &'static str,
+        value_json: Option<String>,
+    ) -> Option<T> {
+        value_json.and_then(|json| {
+            serde_json::from_str(&json).ok().or_else(|| {
+                warn!(field, "Failed to parse json column, using None");
+                None
+            })
+        })
+    }
+
+    pub(crate) fn validate_parameters(&self, parameters: &[CommandParameter]) -> Result<()> {
+        let invalid = |reason: String| {
+            error!("Invalid parameters: {}", reason);
+            Err(DatabaseError::InvalidData {
+                field: "parameters",
+                reason,
+            })
+        };
+
+        self.validate_env_var_names("parameters", parameters.iter().map(|p| &p.name))?;
+
+        for (index, parameter) in parameters.iter().enumerate() {
+            if parameter.name.is_empty() {
+                return invalid("parameter name must not be empty".to_string());
+            }
+            if parameters[..index].iter().any(|p| p.name == parameter.name) {
+                return invalid(format!("duplicate parameter '{}'", parameter.name));
+            }
+            match &parameter.kind {
+                ParameterKind::Enum { options } if options.is_empty() => {
+                    return invalid(format!("'{}' has no options", parameter.name));
+                }
+                ParameterKind::Number {
+                    min: Some(min),
+                    max: Some(max),
+                } if min > max => {
+                    return invalid(format!("'{}' has min greater than max", parameter.name));
+                }
+                _ => {}
+            }
+            if let Some(default) = &parameter.default {
+                if let Err(reason) = parameter.validate_value(default) {
+                    return invalid(format!("default of '{}': {reason}", parameter.name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn validate_resource_limits(&self, limits: &Option<ResourceLimits>) -> Result<()> {
+        let Some(limits) = limits else {
+            return Ok(());
+        };
+
+        let invalid = |reason: &str| {
+            error!("Invalid resource limits: {}", reason);
+            Err(DatabaseError::InvalidData {
+                field: "resource_limits",
+                reason: reason.to_string(),
+            })
+        };
+
+        let counters = [
+            limits.max_memory_bytes,
+            limits.max_cpu_seconds,
+            limits.max_open_files,
+            limits.max_processes,
+        ];
+        if counters.iter().flatten().any(|&value| value == 0) {
+            return invalid("limits must be greater than zero");
+        }
+        if let Some(nice) = limits.nice {
+            if !(-20..=19).contains(&nice) {
+                return invalid("nice must be between -20 and 19");
+            }
+        }
+        if let Some(io_priority) = &limits.io_priority {
+            if io_priority.level > 7 {
+                return invalid("io priority level must be between 0 and 7");
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn get_items_groups_commands_count(
         &self,
         table: &'static str,
@@ -0,0 +1,530 @@
+use super::{
+    CategoryFilter, Command, ConflictResolution, Database, DatabaseError, Group, GroupFilter,
+    ImportConflict, ImportMode, ImportReport, LibraryExport, LibraryTagLink, Result, TagFilter,
+    TagTarget, Workflow, WorkflowStep,
+};
+use crate::constants::{
+    CATEGORIES_TABLE, COMMANDS_TABLE, GROUPS_TABLE, TAGS_TABLE, WORKFLOWS_TABLE,
+};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+use tracing::{info, instrument};
+
+const LIBRARY_FORMAT: &str = "tgui-library";
+/// Written into exports, documents of a newer version are rejected
+const LIBRARY_VERSION: u32 = 1;
+
+/// Ids of the document mapped to the rows they ended up as
+#[derive(Default)]
+struct IdMap {
+    categories: HashMap<i64, i64>,
+    /// Tags are referred to by name
+    tags: HashMap<String, i64>,
+    groups: HashMap<i64, i64>,
+    commands: HashMap<i64, i64>,
+    workflows: HashMap<i64, i64>,
+}
+
+/// Largest ids before the import, rows above them were created by it and are never merged into
+struct ExistingIds {
+    group: i64,
+    command: i64,
+    workflow: i64,
+}
+
+impl Database {
+    /// Categories, tags, groups, commands, workflows and their steps as one versioned document.
+    /// Secret env vars are left out, their values never leave the secrets file.
+    #[instrument(skip(self))]
+    pub fn export_library(&self) -> Result<LibraryExport> {
+        let exported_at: String =
+            self.conn()?
+                .query_row("SELECT CURRENT_TIMESTAMP", [], |row| row.get(0))?;
+
+        let groups =
+            self.get_groups(GroupFilter::All, CategoryFilter::All, TagFilter::All, false)?;
+        let groups = parents_first(&groups)
+            .into_iter()
+            .map(|group| Group {
+                secret_env_vars: None,
+                ..group.clone()
+            })
+            .collect();
+        let commands = self
+            .get_commands(
+                GroupFilter::All,
+                CategoryFilter::All,
+                TagFilter::All,
+                false,
+                None,
+                None,
+            )?
+            .into_iter()
+            .map(|command| Command {
+                secret_env_vars: None,
+                ..command.item
+            })
+            .collect();
+
+        Ok(LibraryExport {
+            format: LIBRARY_FORMAT.to_string(),
+            version: LIBRARY_VERSION,
+            exported_at,
+            categories: self.get_categories()?,
+            groups,
+            commands,
+            workflows: self.get_workflows(None, false)?,
+            workflow_steps: self.get_workflow_steps(None, None, false)?,
+            tags: self.get_tags()?,
+            tag_links: self.get_tag_links()?,
+        })
+    }
+
+    /// Imports a document written by `export_library` in a single transaction, a failure or
+    /// `dry_run` leaves the database untouched. Ids are remapped, categories and tags are matched
+    /// by name and conflicts are resolved as described on `ImportMode`.
+    #[instrument(skip(self, document))]
+    pub fn import_library(
+        &self,
+        document: &str,
+        mode: &ImportMode,
+        dry_run: bool,
+    ) -> Result<ImportReport> {
+        let library = Self::parse_library(document)?;
+
+        let groups = parents_first(&library.groups);
+        if groups.len() != library.groups.len() {
+            return Err(DatabaseError::InvalidData {
+                field: "groups",
+                reason: "Groups need unique ids and a parent that is part of the document"
+                    .to_string(),
+            });
+        }
+        let groups: Vec<Group> = groups
+            .into_iter()
+            .map(|group| Group {
+                secret_env_vars: None,
+                ..group.clone()
+            })
+            .collect();
+        let mut commands: Vec<Command> = library
+            .commands
+            .iter()
+            .map(|command| Command {
+                secret_env_vars: None,
+                ..command.clone()
+            })
+            .collect();
+        // each row is appended to its parent, a stable sort keeps the order within each parent
+        commands.sort_by_key(|command| command.position);
+        let mut workflows = library.workflows.clone();
+        workflows.sort_by_key(|workflow| workflow.position);
+        let mut workflow_steps = library.workflow_steps.clone();
+        workflow_steps.sort_by_key(|step| step.position);
+
+        for category in &library.categories {
+            self.validate_field_length("name", &category.name, Self::MAX_NAME_LENGTH)?;
+        }
+        for group in &groups {
+            self.validate_group(group)?;
+        }
+        for command in &commands {
+            self.validate_command(command)?;
+        }
+        for workflow in &workflows {
+            self.validate_field_length("name", &workflow.name, Self::MAX_NAME_LENGTH)?;
+        }
+        for name in library_tag_names(&library) {
+            self.validate_field_length("name", name, Self::MAX_NAME_LENGTH)?;
+        }
+        if let ImportMode::NewRoot { name } = mode {
+            self.validate_field_length("name", name, Self::MAX_NAME_LENGTH)?;
+        }
+
+        let mut report = ImportReport {
+            dry_run,
+            ..Default::default()
+        };
+        let mut ids = IdMap::default();
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        if *mode == ImportMode::Replace {
+            Self::clear_library(&tx)?;
+        }
+        let existing = ExistingIds {
+            group: Self::max_id(&tx, GROUPS_TABLE)?,
+            command: Self::max_id(&tx, COMMANDS_TABLE)?,
+            workflow: Self::max_id(&tx, WORKFLOWS_TABLE)?,
+        };
+        let root = match mode {
+            ImportMode::NewRoot { name } => {
+                let root = Group {
+                    name: name.clone(),
+                    ..Default::default()
+                };
+                report.groups_created += 1;
+                Some(Self::insert_group(&tx, &root, &[])?)
+            }
+            ImportMode::Merge | ImportMode::Replace => None,
+        };
+        let merge = *mode == ImportMode::Merge;
+
+        for category in &library.categories {
+            let found: Option<i64> = tx
+                .query_row(
+                    "SELECT id FROM categories WHERE name = ?1",
+                    params![category.name],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let id = match found {
+                Some(id) => {
+                    add_conflict(
+                        &mut report,
+                        CATEGORIES_TABLE,
+                        &category.name,
+                        ConflictResolution::Reused,
+                    );
+                    id
+                }
+                None => {
+                    report.categories_created += 1;
+                    Self::insert_returning_id(
+                        &tx,
+                        CATEGORIES_TABLE,
+                        "INSERT INTO categories (name, icon, color) VALUES (?1, ?2, ?3)",
+                        params![category.name, category.icon, category.color],
+                    )?
+                }
+            };
+            ids.categories.insert(category.id, id);
+        }
+
+        for name in library_tag_names(&library) {
+            let found: Option<i64> = tx
+                .query_row(
+                    "SELECT id FROM tags WHERE name = ?1",
+                    params![name],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let id = match found {
+                Some(id) => {
+                    add_conflict(&mut report, TAGS_TABLE, name, ConflictResolution::Reused);
+                    id
+                }
+                None => {
+                    let color = library
+                        .tags
+                        .iter()
+                        .find(|tag| tag.name.trim() == name)
+                        .and_then(|tag| tag.color.clone());
+                    report.tags_created += 1;
+                    Self::insert_returning_id(
+                        &tx,
+                        TAGS_TABLE,
+                        "INSERT INTO tags (name, color) VALUES (?1, ?2)",
+                        params![name, color],
+                    )?
+                }
+            };
+            ids.tags.insert(name.to_string(), id);
+        }
+
+        for group in &groups {
+            // parents come first, so they are mapped already
+            let parent_group_id = group
+                .parent_group_id
+                .map(|parent| ids.groups[&parent])
+                .or(root);
+            if merge {
+                let found: Option<i64> = tx
+                    .query_row(
+                        "SELECT id FROM groups WHERE name = ?1 AND parent_group_id IS ?2 AND id <= ?3
+                         ORDER BY position LIMIT 1",
+                        params![group.name, parent_group_id, existing.group],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                if let Some(id) = found {
+                    add_conflict(
+                        &mut report,
+                        GROUPS_TABLE,
+                        &group.name,
+                        ConflictResolution::Merged,
+                    );
+                    ids.groups.insert(group.id, id);
+                    continue;
+                }
+            }
+
+            let imported = Group {
+                parent_group_id,
+                category_id: remap(&ids.categories, group.category_id, "category_id")?,
+                ..group.clone()
+            };
+            ids.groups
+                .insert(group.id, Self::insert_group(&tx, &imported, &[])?);
+            report.groups_created += 1;
+        }
+
+        let mut skipped_commands = HashSet::new();
+        for command in &commands {
+            let group_id = remap(&ids.groups, command.group_id, "group_id")?.or(root);
+            if merge {
+                let found: Option<i64> = tx
+                    .query_row(
+                        "SELECT id FROM commands WHERE name = ?1 AND group_id IS ?2 AND id <= ?3
+                         ORDER BY position LIMIT 1",
+                        params![command.name, group_id, existing.command],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                if let Some(id) = found {
+                    add_conflict(
+                        &mut report,
+                        COMMANDS_TABLE,
+                        &command.name,
+                        ConflictResolution::Skipped,
+                    );
+                    ids.commands.insert(command.id, id);
+                    skipped_commands.insert(command.id);
+                    continue;
+                }
+            }
+
+            let imported = Command {
+                group_id,
+                category_id: remap(&ids.categories, command.category_id, "category_id")?,
+                ..command.clone()
+            };
+            ids.commands
+                .insert(command.id, Self::insert_command(&tx, &imported, &[])?);
+            report.commands_created += 1;
+        }
+
+        let mut skipped_workflows = HashSet::new();
+        for workflow in &workflows {
+            let taken = tx
+                .query_row(
+                    "SELECT 1 FROM workflows WHERE name = ?1 AND id <= ?2",
+                    params![workflow.name, existing.workflow],
+                    |_| Ok(()),
+                )
+                .optional()?
+                .is_some();
+            if taken {
+                let resolution = if merge {
+                    ConflictResolution::Skipped
+                } else {
+                    ConflictResolution::Duplicated
+                };
+                add_conflict(&mut report, WORKFLOWS_TABLE, &workflow.name, resolution);
+                if merge {
+                    skipped_workflows.insert(workflow.id);
+                    continue;
+                }
+            }
+
+            let imported = Workflow {
+                category_id: remap(&ids.categories, workflow.category_id, "category_id")?,
+                ..workflow.clone()
+            };
+            ids.workflows
+                .insert(workflow.id, Self::insert_workflow(&tx, &imported)?);
+            report.workflows_created += 1;
+        }
+
+        for step in &workflow_steps {
+            if skipped_workflows.contains(&step.workflow_id) {
+                continue;
+            }
+            let imported = WorkflowStep {
+                workflow_id: remap_required(&ids.workflows, step.workflow_id, "workflow_id")?,
+                command_id: remap_required(&ids.commands, step.command_id, "command_id")?,
+                ..step.clone()
+            };
+            Self::insert_workflow_step(&tx, &imported)?;
+            report.workflow_steps_created += 1;
+        }
+
+        // skipped commands and workflows keep the tags they have, merged groups get the new ones
+        for link in &library.tag_links {
+            let item_id = match link.target {
+                TagTarget::Group => remap_required(&ids.groups, link.item_id, "item_id")?,
+                TagTarget::Command if skipped_commands.contains(&link.item_id) => continue,
+                TagTarget::Command => remap_required(&ids.commands, link.item_id, "item_id")?,
+                TagTarget::Workflow if skipped_workflows.contains(&link.item_id) => continue,
+                TagTarget::Workflow => remap_required(&ids.workflows, link.item_id, "item_id")?,
+            };
+            let (link_table, link_column) = link.target.link();
+            tx.execute(
+                &format!(
+                    "INSERT OR IGNORE INTO {link_table} ({link_column}, tag_id) VALUES (?1, ?2)"
+                ),
+                params![item_id, ids.tags[link.tag.trim()]],
+            )?;
+        }
+
+        if dry_run {
+            tx.rollback()?;
+        } else {
+            tx.commit()?;
+        }
+
+        info!(
+            groups = report.groups_created,
+            commands = report.commands_created,
+            workflows = report.workflows_created,
+            conflicts = report.conflicts.len(),
+            dry_run,
+            "Library imported"
+        );
+        Ok(report)
+    }
+
+    fn parse_library(document: &str) -> Result<LibraryExport> {
+        let library: LibraryExport =
+            serde_json::from_str(document).map_err(|e| DatabaseError::InvalidData {
+                field: "document",
+                reason: e.to_string(),
+            })?;
+
+        if library.format != LIBRARY_FORMAT {
+            return Err(DatabaseError::InvalidData {
+                field: "format",
+                reason: format!("'{}' is not a command library export", library.format),
+            });
+        }
+        if library.version == 0 || library.version > LIBRARY_VERSION {
+            return Err(DatabaseError::InvalidData {
+                field: "version",
+                reason: format!(
+                    "Version {} is not supported, the newest is {LIBRARY_VERSION}",
+                    library.version
+                ),
+            });
+        }
+        Ok(library)
+    }
+
+    /// Deletes the library for `ImportMode::Replace`, execution history goes with it
+    fn clear_library(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "DELETE FROM secrets.env_secrets WHERE owner_table IN (?1, ?2)",
+            params![COMMANDS_TABLE, GROUPS_TABLE],
+        )?;
+        conn.execute_batch(
+            "DELETE FROM workflows;
+             DELETE FROM commands;
+             DELETE FROM groups;
+             DELETE FROM categories;
+             DELETE FROM tags;",
+        )?;
+        Ok(())
+    }
+
+    /// Tag names of every link, sorted by item so an item's tags stay together
+    fn get_tag_links(&self) -> Result<Vec<LibraryTagLink>> {
+        let conn = self.conn()?;
+        let mut links = Vec::new();
+        for target in [TagTarget::Group, TagTarget::Command, TagTarget::Workflow] {
+            let (link_table, link_column) = target.link();
+            let mut stmt = conn.prepare(&format!(
+                "SELECT l.{link_column}, t.name FROM {link_table} l
+                 JOIN tags t ON t.id = l.tag_id
+                 ORDER BY l.{link_column}, t.name"
+            ))?;
+            let rows = stmt.query_map([], |row| {
+                Ok(LibraryTagLink {
+                    target,
+                    item_id: row.get(0)?,
+                    tag: row.get(1)?,
+                })
+            })?;
+            for link in rows {
+                links.push(link?);
+            }
+        }
+        Ok(links)
+    }
+
+    fn max_id(conn: &Connection, table: &str) -> Result<i64> {
+        Ok(conn.query_row(
+            &format!("SELECT COALESCE(MAX(id), 0) FROM {table}"),
+            [],
+            |row| row.get(0),
+        )?)
+    }
+}
+
+/// Parents before their children, siblings in `position` order. Groups that can't be reached
+/// from a root, e.g. with a parent outside of `groups`, and repeated ids are left out.
+fn parents_first(groups: &[Group]) -> Vec<&Group> {
+    let mut children: HashMap<Option<i64>, Vec<&Group>> = HashMap::new();
+    for group in groups {
+        children
+            .entry(group.parent_group_id)
+            .or_default()
+            .push(group);
+    }
+    for siblings in children.values_mut() {
+        siblings.sort_by_key(|group| group.position);
+    }
+
+    let mut ordered = Vec::with_capacity(groups.len());
+    let mut seen = HashSet::new();
+    let mut stack: Vec<&Group> = children
+        .get(&None)
+        .map(|roots| roots.iter().rev().copied().collect())
+        .unwrap_or_default();
+    while let Some(group) = stack.pop() {
+        if !seen.insert(group.id) {
+            continue;
+        }
+        ordered.push(group);
+        if let Some(nested) = children.get(&Some(group.id)) {
+            stack.extend(nested.iter().rev());
+        }
+    }
+    ordered
+}
+
+/// Trimmed names of the document's tags and of the tags its links refer to, once each
+fn library_tag_names(library: &LibraryExport) -> Vec<&str> {
+    let mut seen = HashSet::new();
+    library
+        .tags
+        .iter()
+        .map(|tag| tag.name.trim())
+        .chain(library.tag_links.iter().map(|link| link.tag.trim()))
+        .filter(|name| seen.insert(*name))
+        .collect()
+}
+
+fn remap(ids: &HashMap<i64, i64>, id: Option<i64>, field: &'static str) -> Result<Option<i64>> {
+    id.map(|id| remap_required(ids, id, field)).transpose()
+}
+
+fn remap_required(ids: &HashMap<i64, i64>, id: i64, field: &'static str) -> Result<i64> {
+    ids.get(&id)
+        .copied()
+        .ok_or_else(|| DatabaseError::InvalidData {
+            field,
+            reason: format!("{id} is not part of the document"),
+        })
+}
+
+fn add_conflict(
+    report: &mut ImportReport,
+    entity: &'static str,
+    name: &str,
+    resolution: ConflictResolution,
+) {
+    report.conflicts.push(ImportConflict {
+        entity,
+        name: name.to_string(),
+        resolution,
+    });
+}
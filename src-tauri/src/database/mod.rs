@@ -3,33 +3,92 @@ use std::fs;
 use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
 use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::{info, warn};
 
+mod bulk;
 mod categories;
 mod commands;
+mod duplicate;
 mod groups;
 mod helpers;
+mod library;
+mod project_scan;
+mod search;
+mod secrets;
+mod secrets_key;
 mod settings;
+mod shell_history;
+mod shells;
+mod suggestions;
+mod tags;
+mod templates;
 
 mod errors;
 mod execution_history;
+mod explainer;
 pub mod models;
 mod workflows;
-mod explainer;
-
 
 pub use errors::{DatabaseError, Result};
 pub use models::*;
+pub use secrets_key::SecretsKey;
 
 #[cfg(test)]
 pub mod tests;
 
+/// JSON columns added since the first release, `CREATE TABLE IF NOT EXISTS` leaves the tables
+/// of an existing database as they are
+const ADDED_JSON_COLUMNS: &[(&str, &[&str])] = &[
+    (
+        "groups",
+        &[
+            "resource_limits",
+            "env_inheritance",
+            "env_unset",
+            "variables",
+            "env_files",
+            "secret_env_names",
+            "execution_target",
+            "shell_mode",
+            "source_files",
+        ],
+    ),
+    (
+        "commands",
+        &[
+            "resource_limits",
+            "env_inheritance",
+            "env_unset",
+            "parameters",
+            "env_files",
+            "secret_env_names",
+            "execution_target",
+            "raw_arguments",
+            "shell_mode",
+            "source_files",
+        ],
+    ),
+];
+/// Boolean columns added since the first release, they default to false
+const ADDED_FLAG_COLUMNS: &[(&str, &str)] = &[("commands", "run_elevated")];
+
 #[derive(Clone)]
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
+    /// `None` when the OS keyring is unavailable, secret env vars then can't be used
+    secrets_key: Option<SecretsKey>,
 }
 
 impl Database {
+    /// Opens the database with the secrets key from the OS keyring
     pub fn new(path: &Path) -> Result<Self> {
+        let secrets_key = SecretsKey::from_keyring()
+            .inspect_err(|e| warn!(error = %e, "Secret env vars unavailable"))
+            .ok();
+        Self::open(path, secrets_key)
+    }
+
+    pub fn open(path: &Path, secrets_key: Option<SecretsKey>) -> Result<Self> {
         let conn = Connection::open(path)?;
 
         conn.pragma_update(None, "foreign_keys", &"ON")?;
@@ -40,14 +99,65 @@ impl Database {
 
         let schema = include_str!("schema.sql");
         conn.execute_batch(schema)?;
+        Self::add_missing_columns(&conn)?;
+
+        // secret env var values are kept apart, the main database can be shared or backed up
+        let secrets_path = path.with_extension("secrets.db");
+        conn.execute(
+            "ATTACH DATABASE ?1 AS secrets",
+            [secrets_path.to_string_lossy()],
+        )?;
+        conn.pragma_update(Some("secrets"), "journal_mode", "WAL")?;
+        conn.pragma_update(Some("secrets"), "secure_delete", "ON")?;
+        conn.execute_batch(include_str!("secrets.sql"))?;
+        if let Some(key) = &secrets_key {
+            Self::encrypt_plain_secrets(&conn, key)?;
+        }
+
+        #[cfg(unix)]
+        Self::set_file_permissions(&secrets_path)?;
 
         let db = Self {
             conn: Arc::new(Mutex::new(conn)),
+            secrets_key,
         };
+        // the default shell is picked from the registry
+        db.seed_shells()?;
         db.initialize_settings()?;
         Ok(db)
     }
 
+    /// Brings the tables of a database created by an older version up to schema.sql, running it
+    /// again changes nothing
+    fn add_missing_columns(conn: &Connection) -> Result<()> {
+        // the definitions are the ones in schema.sql
+        let json_columns = ADDED_JSON_COLUMNS.iter().flat_map(|(table, columns)| {
+            columns.iter().map(move |column| {
+                let definition = format!("TEXT CHECK ({column} IS NULL OR json_valid({column}))");
+                (*table, *column, definition)
+            })
+        });
+        let flag_columns = ADDED_FLAG_COLUMNS.iter().map(|(table, column)| {
+            let definition = format!("BOOLEAN NOT NULL DEFAULT 0 CHECK({column} IN (0,1))");
+            (*table, *column, definition)
+        });
+
+        for (table, column, definition) in json_columns.chain(flag_columns) {
+            let exists: bool = conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2)",
+                [table, column],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                conn.execute_batch(&format!(
+                    "ALTER TABLE {table} ADD COLUMN {column} {definition}"
+                ))?;
+                info!(table, column, "Column added");
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn conn(&self) -> Result<MutexGuard<'_, Connection>> {
         self.conn
             .lock()
@@ -1,7 +1,8 @@
-
+use crate::constants::{COMMANDS_TABLE, GROUPS_TABLE, SECRET_MASK, WORKFLOWS_TABLE};
 use crate::utils::get_utc_timestamp_string;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 use std::ops::Deref;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -15,7 +16,18 @@ pub struct Category {
     pub created_at: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Label of commands, groups and workflows, unlike a category an item can have several
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    pub color: Option<String>,
+    #[serde(skip_deserializing, default)]
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Group {
     pub id: i64,
@@ -31,6 +43,23 @@ pub struct Group {
     pub is_favorite: bool,
     pub icon: Option<String>,
     pub color: Option<String>,
+    pub resource_limits: Option<ResourceLimits>,
+    pub env_inheritance: Option<EnvInheritance>,
+    /// Removed from the environment before `env_vars` of the same level are applied
+    pub env_unset: Option<Vec<String>>,
+    /// Values for `${NAME}` references in commands of this group and its subgroups,
+    /// not exported to the environment
+    pub variables: Option<HashMap<String, String>>,
+    /// Dotenv files loaded before `env_vars` of the same level, relative to the working directory
+    pub env_files: Option<Vec<String>>,
+    /// Stored outside of the main database and only exposed at spawn, see `SecretValue`
+    pub secret_env_vars: Option<HashMap<String, SecretValue>>,
+    /// Default target for the commands of this group and its subgroups
+    pub execution_target: Option<ExecutionTarget>,
+    /// Default shell mode for the commands of this group and its subgroups
+    pub shell_mode: Option<ShellMode>,
+    /// Sourced by the shell before the command, after the files of the parent groups
+    pub source_files: Option<Vec<String>>,
     #[serde(skip_deserializing, default)]
     pub created_at: String,
     #[serde(skip_deserializing, default)]
@@ -43,7 +72,7 @@ pub struct GroupNode {
     pub children: Vec<GroupNode>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Command {
     pub id: i64,
@@ -59,12 +88,250 @@ pub struct Command {
     pub shell: Option<String>,
     pub category_id: Option<i64>,
     pub is_favorite: bool,
+    pub resource_limits: Option<ResourceLimits>,
+    pub env_inheritance: Option<EnvInheritance>,
+    /// Removed from the environment before `env_vars` of the same level are applied
+    pub env_unset: Option<Vec<String>>,
+    /// Prompted for on each run and substituted for `{{name}}` in arguments and env vars
+    #[serde(default)]
+    pub parameters: Vec<CommandParameter>,
+    /// Dotenv files loaded before `env_vars` of the same level, relative to the working directory
+    pub env_files: Option<Vec<String>>,
+    /// Stored outside of the main database and only exposed at spawn, see `SecretValue`
+    pub secret_env_vars: Option<HashMap<String, SecretValue>>,
+    /// Launched through pkexec or `sudo -A`, see the `elevation_method` setting
+    #[serde(default)]
+    pub run_elevated: bool,
+    /// Where the command runs, the closest level that sets it wins
+    pub execution_target: Option<ExecutionTarget>,
+    /// Indices of `arguments` passed to the shell unquoted, so the shell expands them.
+    /// Without a shell every argument reaches the command as is.
+    #[serde(default)]
+    pub raw_arguments: Vec<usize>,
+    /// How the shell is started, the closest level that sets it wins. Overrides the login and
+    /// interactive flags of the shell registry entry.
+    pub shell_mode: Option<ShellMode>,
+    /// Sourced by the shell before the command, after the files of the groups
+    pub source_files: Option<Vec<String>>,
     #[serde(skip_deserializing, default)]
     pub created_at: String,
     #[serde(skip_deserializing, default)]
     pub updated_at: String,
 }
 
+/// Limits applied to a spawned process, every field is optional.
+/// Unset fields are inherited from the closest group that sets them.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceLimits {
+    /// `memory.max` of the cgroup when one is used, counting the memory actually in use by the
+    /// whole tree. Otherwise the data segment limit (RLIMIT_DATA) of each process: heap and
+    /// writable private mappings it allocated, whether touched or not, but not code, shared
+    /// memory or address space merely reserved.
+    pub max_memory_bytes: Option<u64>,
+    /// CPU time in seconds (RLIMIT_CPU), process receives SIGXCPU then SIGKILL
+    pub max_cpu_seconds: Option<u64>,
+    /// RLIMIT_NOFILE
+    pub max_open_files: Option<u64>,
+    /// `pids.max` of the execution cgroup, so it counts the process tree only. Not enforced
+    /// when no delegated cgroup is available, RLIMIT_NPROC would count every process of the user.
+    pub max_processes: Option<u64>,
+    /// -20 (highest priority) to 19, negative values need CAP_SYS_NICE
+    pub nice: Option<i32>,
+    pub io_priority: Option<IoPriority>,
+    /// Place the process tree into a per-execution cgroup (requires cgroup v2 delegation)
+    pub use_cgroup: Option<bool>,
+}
+
+impl ResourceLimits {
+    /// Fills the unset fields from `parent`, values already set win.
+    pub fn inherit_from(self, parent: &ResourceLimits) -> ResourceLimits {
+        ResourceLimits {
+            max_memory_bytes: self.max_memory_bytes.or(parent.max_memory_bytes),
+            max_cpu_seconds: self.max_cpu_seconds.or(parent.max_cpu_seconds),
+            max_open_files: self.max_open_files.or(parent.max_open_files),
+            max_processes: self.max_processes.or(parent.max_processes),
+            nice: self.nice.or(parent.nice),
+            io_priority: self.io_priority.or_else(|| parent.io_priority.clone()),
+            use_cgroup: self.use_cgroup.or(parent.use_cgroup),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == ResourceLimits::default()
+    }
+
+    /// A process count can only be enforced by a cgroup, so it asks for one as well
+    pub fn wants_cgroup(&self) -> bool {
+        self.use_cgroup == Some(true) || self.max_processes.is_some()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandParameter {
+    /// Referenced as `{{name}}`, same naming rules as env var keys
+    pub name: String,
+    pub label: Option<String>,
+    pub description: Option<String>,
+    #[serde(flatten)]
+    pub kind: ParameterKind,
+    pub default: Option<String>,
+    pub required: bool,
+}
+
+impl CommandParameter {
+    /// Returns the normalized value (trimmed numbers, lowercase booleans, expanded `~/` paths)
+    pub fn validate_value(&self, value: &str) -> Result<String, String> {
+        match &self.kind {
+            ParameterKind::String => Ok(value.to_string()),
+            ParameterKind::Number { min, max } => {
+                let number: f64 = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("'{value}' is not a number"))?;
+                if min.is_some_and(|min| number < min) || max.is_some_and(|max| number > max) {
+                    return Err(format!(
+                        "{number} is out of range ({} to {})",
+                        min.map_or("-".to_string(), |v| v.to_string()),
+                        max.map_or("-".to_string(), |v| v.to_string())
+                    ));
+                }
+                Ok(value.trim().to_string())
+            }
+            ParameterKind::Enum { options } => {
+                if options.iter().any(|option| option == value) {
+                    Ok(value.to_string())
+                } else {
+                    Err(format!("must be one of: {}", options.join(", ")))
+                }
+            }
+            ParameterKind::Path => match value.strip_prefix("~/") {
+                Some(rest) => dirs::home_dir()
+                    .map(|home| home.join(rest).to_string_lossy().into_owned())
+                    .ok_or_else(|| "home directory is not available".to_string()),
+                None => Ok(value.to_string()),
+            },
+            ParameterKind::Boolean => match value.to_ascii_lowercase().as_str() {
+                "true" | "1" | "yes" => Ok("true".to_string()),
+                "false" | "0" | "no" => Ok("false".to_string()),
+                _ => Err(format!("'{value}' is not a boolean")),
+            },
+        }
+    }
+}
+
+/// Values are always passed as strings, the kind only drives validation and the input shown
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ParameterKind {
+    String,
+    Number { min: Option<f64>, max: Option<f64> },
+    Enum { options: Vec<String> },
+    Path,
+    Boolean,
+}
+
+/// What a spawned process starts from before group/command `env_vars` are layered on top.
+/// The closest level that sets it wins.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum EnvInheritance {
+    /// The environment TGUI was launched with
+    #[default]
+    Full,
+    /// Nothing, only the configured variables
+    Clean,
+    /// Only the listed variables from TGUI's environment
+    Allowlist { variables: Vec<String> },
+}
+
+/// How a shell is started. Login and interactive shells read the rc files that set up
+/// aliases, nvm, pyenv or conda. Without a shell the mode has no effect.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShellMode {
+    /// Plain `-c`, no rc files
+    #[default]
+    NonInteractive,
+    /// `-l`, reads the profile files
+    Login,
+    /// `-i`, reads `.bashrc`/`.zshrc`
+    Interactive,
+}
+
+/// Where a spawned process runs. Remote targets run the command through `sh` on the other
+/// side, which records the PID so a kill reaches the remote process and not only the client.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExecutionTarget {
+    #[default]
+    Local,
+    /// `docker exec`/`podman exec` into a running container
+    Container {
+        runtime: ContainerRuntime,
+        container: String,
+    },
+    /// `ssh host`, the host may be any alias from the ssh config. Runs with `BatchMode`, so
+    /// key based authentication is required.
+    Ssh { host: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContainerRuntime {
+    Docker,
+    Podman,
+}
+
+/// Value of a secret env var. Read APIs only return `SECRET_MASK`, sending the mask back on update
+/// keeps the stored value. `Debug` never prints the value, so it stays out of tracing output.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct SecretValue(String);
+
+impl SecretValue {
+    pub fn masked() -> Self {
+        Self(SECRET_MASK.to_string())
+    }
+
+    pub fn is_masked(&self) -> bool {
+        self.0 == SECRET_MASK
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for SecretValue {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl fmt::Debug for SecretValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{SECRET_MASK:?}")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct IoPriority {
+    pub class: IoPriorityClass,
+    /// 0 (highest) to 7, ignored for the idle class
+    pub level: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IoPriorityClass {
+    RealTime,
+    BestEffort,
+    Idle,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct WithHistory<T> {
@@ -93,6 +360,449 @@ pub struct Template {
     pub updated_at: String,
 }
 
+/// Blueprint stored as JSON in `Template::structure`, see docs/09-template.md.
+/// String fields of groups and commands can reference variables as `{{key}}`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TemplateStructure {
+    #[serde(rename = "$schema", default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    pub name: String,
+    /// Semantic version of the template format, only 1.x is supported
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub variables: Vec<TemplateVariable>,
+    #[serde(default)]
+    pub groups: Vec<TemplateGroup>,
+    /// Commands outside of any group
+    #[serde(default)]
+    pub commands: Vec<TemplateCommand>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TemplateVariable {
+    pub key: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(rename = "type")]
+    pub kind: TemplateVariableType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateVariableType {
+    String,
+    /// Must exist when the template is applied
+    Path,
+    Number,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TemplateGroup {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_working_directory: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_env_vars: Option<HashMap<String, String>>,
+    /// Name or path of a shell from the registry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_shell: Option<String>,
+    #[serde(default)]
+    pub commands: Vec<TemplateCommand>,
+    #[serde(default)]
+    pub groups: Vec<TemplateGroup>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TemplateCommand {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub arguments: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_directory: Option<String>,
+    /// Category name, created when it doesn't exist
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_vars: Option<HashMap<String, String>>,
+    /// Asked for on each run of the created command, referenced as `\{{name}}` in the template
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parameters: Vec<CommandParameter>,
+    /// Name or path of a shell from the registry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell_mode: Option<ShellMode>,
+    /// Kept so a captured elevated command isn't silently weakened, the validation rejects it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub run_elevated: bool,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct TemplateValidation {
+    pub valid: bool,
+    pub errors: Vec<String>,
+}
+
+/// Value of a captured group replaced by a variable, e.g. the checkout directory as
+/// `project_dir`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CapturedVariable {
+    pub key: String,
+    pub value: String,
+    #[serde(rename = "type")]
+    pub kind: TemplateVariableType,
+    pub label: Option<String>,
+}
+
+/// Ids of everything `apply_template` created
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedTemplate {
+    pub group_ids: Vec<i64>,
+    pub command_ids: Vec<i64>,
+}
+
+/// The whole command library as written by `export_library`, groups come parents first. Items are
+/// imported in `position` order after what is already there, so siblings, commands of a group
+/// and steps of a workflow keep their order. Secret values are never exported.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryExport {
+    /// Always `tgui-library`, guards against importing some other JSON file
+    pub format: String,
+    pub version: u32,
+    #[serde(default)]
+    pub exported_at: String,
+    #[serde(default)]
+    pub categories: Vec<Category>,
+    #[serde(default)]
+    pub groups: Vec<Group>,
+    #[serde(default)]
+    pub commands: Vec<Command>,
+    #[serde(default)]
+    pub workflows: Vec<Workflow>,
+    #[serde(default)]
+    pub workflow_steps: Vec<WorkflowStep>,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    #[serde(default)]
+    pub tag_links: Vec<LibraryTagLink>,
+}
+
+/// Tag of a group, command or workflow of a `LibraryExport`, by name so an import links the
+/// tag of that name
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryTagLink {
+    pub target: TagTarget,
+    pub item_id: i64,
+    pub tag: String,
+}
+
+/// What happens to the existing library on import
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Groups with the name of an existing sibling are merged into it, commands and workflows
+    /// whose name is taken there are skipped
+    Merge,
+    /// Everything but shells, templates and settings is deleted first
+    Replace,
+    /// The imported library goes into a new root group called `name`
+    NewRoot { name: String },
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    /// An existing category or tag of the same name is used
+    Reused,
+    /// Contents went into the existing group
+    Merged,
+    /// Not imported, steps referring to a skipped command use the existing one
+    Skipped,
+    /// Imported next to the existing one of the same name
+    Duplicated,
+}
+
+/// Imported item whose name was already taken
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportConflict {
+    /// Table of the item, e.g. `commands`
+    pub entity: &'static str,
+    pub name: String,
+    pub resolution: ConflictResolution,
+}
+
+/// Outcome of `import_library`, with `dry_run` nothing was written
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub categories_created: usize,
+    pub tags_created: usize,
+    pub groups_created: usize,
+    pub commands_created: usize,
+    pub workflows_created: usize,
+    pub workflow_steps_created: usize,
+    pub conflicts: Vec<ImportConflict>,
+    pub dry_run: bool,
+}
+
+/// File `read_shell_history` takes commands from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HistorySource {
+    /// `~/.bash_history`, one command per line
+    Bash,
+    /// `~/.zsh_history`, plain or extended (`: <start>:<duration>;<command>`)
+    Zsh,
+    /// `fish_history`, a YAML list of `- cmd:` entries
+    Fish,
+    /// `alias name='value'` definitions, e.g. from `~/.bashrc`
+    Aliases,
+}
+
+/// Command found in a shell history, ready to be imported with `import_shell_commands`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellHistoryEntry {
+    /// The alias name or the first line of the command
+    pub name: String,
+    pub command: String,
+    pub arguments: Vec<String>,
+    /// Unquoted arguments holding pipes, redirections, globs or variables, see
+    /// `Command::raw_arguments`
+    #[serde(default)]
+    pub raw_arguments: Vec<usize>,
+    /// `NAME=value` assignments in front of the command
+    pub env_vars: Option<HashMap<String, String>>,
+    /// Shell of the history, only set when the line needs one to run
+    pub shell: Option<String>,
+    /// How often the line occurs, entries are ranked by it
+    #[serde(default)]
+    pub count: usize,
+}
+
+/// Task runner file `scan_project` found a task in
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskSource {
+    /// `scripts` of `package.json`, run with the package manager of the lock file
+    PackageJson,
+    Makefile,
+    Justfile,
+    /// Binaries and examples of `Cargo.toml`
+    Cargo,
+    /// Services of `docker-compose.yml` or `compose.yaml`
+    DockerCompose,
+}
+
+/// Task of a project, becomes a command running it in the project directory
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectTask {
+    /// The command line, e.g. `npm run build`
+    pub name: String,
+    pub command: String,
+    pub arguments: Vec<String>,
+    pub source: TaskSource,
+    /// The script of a package.json entry or the doc comment of a target or recipe
+    pub description: Option<String>,
+}
+
+/// Proposal of `scan_project`, `group` can be edited before it is created with
+/// `create_project_group`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectScan {
+    pub group: Group,
+    pub tasks: Vec<ProjectTask>,
+}
+
+/// Differences between a project group and its directory, see `rescan_project_group`
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectRescan {
+    /// Tasks without a command in the group yet
+    pub added: Vec<ProjectTask>,
+    /// Task commands of the group whose task is gone, they are left for the user to delete
+    pub removed: Vec<Command>,
+}
+
+/// Table a `search_all` result comes from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchKind {
+    Command,
+    Group,
+    Workflow,
+}
+
+/// Part of a searched field, `matched` parts contain a search term
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HighlightSegment {
+    pub text: String,
+    pub matched: bool,
+}
+
+/// Match of `search_all`, results are ordered by `rank`
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub kind: SearchKind,
+    pub id: i64,
+    pub name: Vec<HighlightSegment>,
+    /// Command line or description holding a match, `None` when only the name matched
+    pub detail: Option<Vec<HighlightSegment>>,
+    pub is_favorite: bool,
+    /// bm25 score, lower is better
+    pub rank: f64,
+}
+
+/// Command of the quick launcher, see `get_command_suggestions`
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandSuggestion {
+    #[serde(flatten)]
+    pub command: Command,
+    /// Decayed and success weighted run count, favourites boosted
+    pub frecency: f64,
+    /// Match quality and frecency combined, suggestions are ordered by it
+    pub score: f64,
+    /// Char indices of `name` the query matched, empty when only the command line matched
+    pub matched_indices: Vec<usize>,
+}
+
+/// Copy made by `duplicate_group` or `duplicate_workflow`
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Duplicated {
+    /// Id of the copy of the requested group or workflow
+    pub id: i64,
+    /// New ids of the copied groups, commands and workflow steps by their original id
+    pub groups: HashMap<i64, i64>,
+    pub commands: HashMap<i64, i64>,
+    pub workflow_steps: HashMap<i64, i64>,
+}
+
+/// Change applied to every command or group of a selection by `bulk_update_commands` and
+/// `bulk_update_groups`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(
+    tag = "action",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum BulkAction {
+    /// To the end of `group_id`, or the top level without one. For groups it is the new parent.
+    Move {
+        group_id: Option<i64>,
+    },
+    SetCategory {
+        category_id: Option<i64>,
+    },
+    SetFavorite {
+        is_favorite: bool,
+    },
+    /// Sets the env var, or removes it without a value
+    SetEnvVar {
+        key: String,
+        value: Option<String>,
+    },
+    SetWorkingDirectory {
+        working_directory: Option<String>,
+    },
+    /// Deleting a group deletes its subgroups and commands as well
+    Delete,
+}
+
+/// Outcome of a bulk action, ids in the order they were given
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkResult {
+    pub changed: Vec<i64>,
+    /// Already in the requested state, left untouched
+    pub unchanged: Vec<i64>,
+}
+
+/// Entry of the shell registry, commands and groups refer to it by `name`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellDefinition {
+    pub id: i64,
+    pub name: String,
+    /// Absolute path or a name looked up in PATH
+    pub path: String,
+    /// Arguments split like a shell would, the `{cmd}` argument is replaced by the command line
+    pub args_template: String,
+    /// Decides how the command and its arguments are quoted in the command line
+    #[serde(default)]
+    pub family: ShellFamily,
+    /// Prepends `-l`
+    #[serde(default)]
+    pub login: bool,
+    /// Prepends `-i`
+    #[serde(default)]
+    pub interactive: bool,
+    #[serde(skip_deserializing, default)]
+    pub created_at: String,
+    #[serde(skip_deserializing, default)]
+    pub updated_at: String,
+}
+
+/// Quoting rules a shell understands
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShellFamily {
+    /// sh, bash, zsh, dash, ksh, ...
+    #[default]
+    Posix,
+    Fish,
+    Nushell,
+    Powershell,
+    /// `%VAR%` is expanded even inside quotes
+    Cmd,
+}
+
+impl ShellFamily {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ShellFamily::Posix => "posix",
+            ShellFamily::Fish => "fish",
+            ShellFamily::Nushell => "nushell",
+            ShellFamily::Powershell => "powershell",
+            ShellFamily::Cmd => "cmd",
+        }
+    }
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "posix" => Ok(ShellFamily::Posix),
+            "fish" => Ok(ShellFamily::Fish),
+            "nushell" => Ok(ShellFamily::Nushell),
+            "powershell" => Ok(ShellFamily::Powershell),
+            "cmd" => Ok(ShellFamily::Cmd),
+            _ => Err(format!("Invalid shell family: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Workflow {
@@ -322,6 +1032,45 @@ pub enum CategoryFilter {
     All,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum TagFilter {
+    /// Items with at least one of the tags
+    AnyOf(Vec<i64>),
+    /// Items with every one of the tags
+    AllOf(Vec<i64>),
+    /// Items without tags
+    None,
+    All,
+}
+
+/// Kind of item a tag is attached to
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TagTarget {
+    Command,
+    Group,
+    Workflow,
+}
+
+impl TagTarget {
+    /// Link table and its column referencing the item
+    pub fn link(&self) -> (&'static str, &'static str) {
+        match self {
+            TagTarget::Command => ("command_tags", "command_id"),
+            TagTarget::Group => ("group_tags", "group_id"),
+            TagTarget::Workflow => ("workflow_tags", "workflow_id"),
+        }
+    }
+
+    pub fn table(&self) -> &'static str {
+        match self {
+            TagTarget::Command => COMMANDS_TABLE,
+            TagTarget::Group => GROUPS_TABLE,
+            TagTarget::Workflow => WORKFLOWS_TABLE,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ExplainResult {
@@ -0,0 +1,395 @@
+use super::{
+    CategoryFilter, Command, Database, DatabaseError, Group, GroupFilter, ProjectRescan,
+    ProjectScan, ProjectTask, Result, TagFilter, TaskSource,
+};
+use regex::Regex;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::LazyLock;
+use tracing::{info, instrument, warn};
+
+/// `build test: deps ## help`, the targets are checked one by one
+static MAKE_RULE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([^\s:=#%.$][^:=#%$]*?)\s*:([^=]|$)(.*)").expect("valid regex"));
+static MAKE_TARGET: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z0-9_-]+$").expect("valid regex"));
+/// `name param="x": deps`, settings and aliases use `:=` and don't match
+static JUST_RECIPE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^@?([A-Za-z_][A-Za-z0-9_-]*)((?:\s+[^:]*)?):([^=]|$)").expect("valid regex")
+});
+static TOML_NAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^name\s*=\s*"([^"]+)""#).expect("valid regex"));
+static COMPOSE_SERVICE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\s+)([A-Za-z0-9._-]+):\s*(#.*)?$").expect("valid regex"));
+
+const MAKEFILES: &[&str] = &["GNUmakefile", "makefile", "Makefile"];
+const JUSTFILES: &[&str] = &["justfile", "Justfile", ".justfile"];
+const COMPOSE_FILES: &[&str] = &[
+    "compose.yaml",
+    "compose.yml",
+    "docker-compose.yml",
+    "docker-compose.yaml",
+];
+/// Lock file deciding which package manager runs the scripts, npm without one
+const LOCK_FILES: &[(&str, &str)] = &[
+    ("pnpm-lock.yaml", "pnpm"),
+    ("yarn.lock", "yarn"),
+    ("bun.lockb", "bun"),
+    ("bun.lock", "bun"),
+];
+
+impl Database {
+    /// Proposes a group for a project directory with a command per task of its task runner
+    /// files. Nothing is saved, see `create_project_group`.
+    #[instrument(skip(self))]
+    pub fn scan_project(&self, dir: &Path) -> Result<ProjectScan> {
+        let dir = project_dir(dir)?;
+        let name = dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| dir.display().to_string());
+
+        Ok(ProjectScan {
+            group: Group {
+                name,
+                working_directory: Some(dir.display().to_string()),
+                ..Default::default()
+            },
+            tasks: scan_tasks(&dir),
+        })
+    }
+
+    /// Creates the group of a scan with its tasks in one transaction
+    #[instrument(skip(self, scan), fields(name = %scan.group.name))]
+    pub fn create_project_group(&self, scan: &ProjectScan) -> Result<i64> {
+        self.validate_group(&scan.group)?;
+        let commands = self.task_commands(&scan.tasks, None)?;
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let group_id = Self::insert_group(&tx, &scan.group, &[])?;
+        for command in &commands {
+            let command = Command {
+                group_id: Some(group_id),
+                ..command.clone()
+            };
+            Self::insert_command(&tx, &command, &[])?;
+        }
+        tx.commit()?;
+
+        info!(group_id, tasks = commands.len(), "Project group created");
+        Ok(group_id)
+    }
+
+    /// Scans the working directory of a project group again. Task commands are matched by
+    /// their command line, other commands of the group are left out of the comparison.
+    #[instrument(skip(self))]
+    pub fn rescan_project_group(&self, group_id: i64) -> Result<ProjectRescan> {
+        let group = self.get_group(group_id)?;
+        let Some(dir) = &group.working_directory else {
+            return Err(DatabaseError::InvalidData {
+                field: "working_directory",
+                reason: format!("Group '{}' has no project directory", group.name),
+            });
+        };
+        let tasks = scan_tasks(&project_dir(Path::new(dir))?);
+        let commands: Vec<Command> = self
+            .get_commands(
+                GroupFilter::Group(group_id),
+                CategoryFilter::All,
+                TagFilter::All,
+                false,
+                None,
+                None,
+            )?
+            .into_iter()
+            .map(|command| command.item)
+            .collect();
+
+        let existing: HashSet<(&str, &[String])> = commands
+            .iter()
+            .map(|c| (c.command.as_str(), c.arguments.as_slice()))
+            .collect();
+        let current: HashSet<(&str, &[String])> = tasks
+            .iter()
+            .map(|t| (t.command.as_str(), t.arguments.as_slice()))
+            .collect();
+
+        let removed = commands
+            .iter()
+            .filter(|c| is_task_command(c))
+            .filter(|c| !current.contains(&(c.command.as_str(), c.arguments.as_slice())))
+            .cloned()
+            .collect();
+        let added = tasks
+            .iter()
+            .filter(|t| !existing.contains(&(t.command.as_str(), t.arguments.as_slice())))
+            .cloned()
+            .collect();
+
+        Ok(ProjectRescan { added, removed })
+    }
+
+    /// Adds tasks found by `rescan_project_group` to the group, in one transaction
+    #[instrument(skip(self, tasks), fields(tasks = tasks.len()))]
+    pub fn add_project_tasks(&self, group_id: i64, tasks: &[ProjectTask]) -> Result<Vec<i64>> {
+        self.get_group(group_id)?;
+        let commands = self.task_commands(tasks, Some(group_id))?;
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let ids = commands
+            .iter()
+            .map(|command| Self::insert_command(&tx, command, &[]))
+            .collect::<Result<Vec<_>>>()?;
+        tx.commit()?;
+        Ok(ids)
+    }
+
+    fn task_commands(&self, tasks: &[ProjectTask], group_id: Option<i64>) -> Result<Vec<Command>> {
+        tasks
+            .iter()
+            .map(|task| {
+                let command = Command {
+                    name: task.name.clone(),
+                    command: task.command.clone(),
+                    arguments: task.arguments.clone(),
+                    description: task.description.clone(),
+                    group_id,
+                    ..Default::default()
+                };
+                self.validate_command(&command).map(|_| command)
+            })
+            .collect()
+    }
+}
+
+fn project_dir(dir: &Path) -> Result<std::path::PathBuf> {
+    dir.canonicalize()
+        .ok()
+        .filter(|dir| dir.is_dir())
+        .ok_or_else(|| DatabaseError::InvalidData {
+            field: "path",
+            reason: format!("'{}' is not an existing directory", dir.display()),
+        })
+}
+
+/// Tasks of every runner file in `dir`, grouped by runner in file order
+fn scan_tasks(dir: &Path) -> Vec<ProjectTask> {
+    let mut tasks = Vec::new();
+    tasks.extend(package_json_tasks(dir));
+    tasks.extend(first_file(dir, MAKEFILES).map_or_else(Vec::new, |c| makefile_tasks(&c)));
+    tasks.extend(first_file(dir, JUSTFILES).map_or_else(Vec::new, |c| justfile_tasks(&c)));
+    tasks.extend(cargo_tasks(dir));
+    tasks.extend(first_file(dir, COMPOSE_FILES).map_or_else(Vec::new, |c| compose_tasks(&c)));
+    tasks
+}
+
+fn first_file(dir: &Path, names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .find_map(|name| std::fs::read_to_string(dir.join(name)).ok())
+}
+
+fn task(source: TaskSource, command: &str, arguments: &[&str]) -> ProjectTask {
+    let arguments: Vec<String> = arguments.iter().map(|arg| arg.to_string()).collect();
+    ProjectTask {
+        name: format!("{command} {}", arguments.join(" ")),
+        command: command.to_string(),
+        arguments,
+        source,
+        description: None,
+    }
+}
+
+/// Commands of the shape `scan_tasks` creates, other commands of a group aren't reported as
+/// removed tasks
+fn is_task_command(command: &Command) -> bool {
+    let arguments: Vec<&str> = command.arguments.iter().map(String::as_str).collect();
+    matches!(
+        (command.command.as_str(), arguments.as_slice()),
+        ("npm" | "pnpm" | "yarn" | "bun", ["run", _])
+            | ("make" | "just", [_])
+            | ("cargo", ["run", "--bin" | "--example", _])
+            | ("docker", ["compose", "up", _])
+    )
+}
+
+fn package_json_tasks(dir: &Path) -> Vec<ProjectTask> {
+    let Ok(content) = std::fs::read_to_string(dir.join("package.json")) else {
+        return Vec::new();
+    };
+    let package: serde_json::Value = match serde_json::from_str(&content) {
+        Ok(package) => package,
+        Err(e) => {
+            warn!(error = %e, dir = %dir.display(), "Skipping invalid package.json");
+            return Vec::new();
+        }
+    };
+    let manager = LOCK_FILES
+        .iter()
+        .find(|(lock_file, _)| dir.join(lock_file).is_file())
+        .map_or("npm", |(_, manager)| manager);
+
+    package["scripts"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, script)| ProjectTask {
+            description: script.as_str().map(String::from),
+            ..task(TaskSource::PackageJson, manager, &["run", name])
+        })
+        .collect()
+}
+
+/// Targets of explicit rules, `## text` after the prerequisites becomes the description
+fn makefile_tasks(content: &str) -> Vec<ProjectTask> {
+    let mut seen = HashSet::new();
+    let mut tasks = Vec::new();
+    for captures in content.lines().filter_map(|line| MAKE_RULE.captures(line)) {
+        let description = captures[3]
+            .split_once("##")
+            .map(|(_, help)| help.trim().to_string())
+            .filter(|help| !help.is_empty());
+        for target in captures[1].split_whitespace() {
+            if MAKE_TARGET.is_match(target) && seen.insert(target.to_string()) {
+                tasks.push(ProjectTask {
+                    description: description.clone(),
+                    ..task(TaskSource::Makefile, "make", &[target])
+                });
+            }
+        }
+    }
+    tasks
+}
+
+/// Public recipes, a `#` comment line above one becomes its description
+fn justfile_tasks(content: &str) -> Vec<ProjectTask> {
+    let mut tasks = Vec::new();
+    let mut comment = None;
+    let mut private = false;
+    for line in content.lines() {
+        if let Some(text) = line.strip_prefix('#') {
+            comment = Some(text.trim().to_string()).filter(|text| !text.is_empty());
+            continue;
+        }
+        if line.starts_with('[') {
+            private |= line.contains("private");
+            continue;
+        }
+        if let Some(captures) = JUST_RECIPE.captures(line) {
+            let name = &captures[1];
+            if !private && !name.starts_with('_') {
+                tasks.push(ProjectTask {
+                    description: comment.clone(),
+                    ..task(TaskSource::Justfile, "just", &[name])
+                });
+            }
+        }
+        comment = None;
+        private = false;
+    }
+    tasks
+}
+
+/// Binaries and examples, declared in `Cargo.toml` or found at the default locations
+fn cargo_tasks(dir: &Path) -> Vec<ProjectTask> {
+    let Ok(content) = std::fs::read_to_string(dir.join("Cargo.toml")) else {
+        return Vec::new();
+    };
+
+    let mut package = None;
+    let mut bins = Vec::new();
+    let mut examples = Vec::new();
+    let mut section = "";
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            section = line;
+        } else if let Some(captures) = TOML_NAME.captures(line) {
+            let name = captures[1].to_string();
+            match section {
+                "[package]" => package = Some(name),
+                "[[bin]]" => bins.push(name),
+                "[[example]]" => examples.push(name),
+                _ => {}
+            }
+        }
+    }
+    // a manifest without a package is a workspace root
+    let Some(package) = package else {
+        return Vec::new();
+    };
+    if dir.join("src/main.rs").is_file() {
+        bins.push(package);
+    }
+    bins.extend(target_names(&dir.join("src/bin")));
+    examples.extend(target_names(&dir.join("examples")));
+
+    let mut seen = HashSet::new();
+    bins.retain(|name| seen.insert(name.clone()));
+    let mut seen = HashSet::new();
+    examples.retain(|name| seen.insert(name.clone()));
+
+    bins.iter()
+        .map(|name| task(TaskSource::Cargo, "cargo", &["run", "--bin", name]))
+        .chain(
+            examples
+                .iter()
+                .map(|name| task(TaskSource::Cargo, "cargo", &["run", "--example", name])),
+        )
+        .collect()
+}
+
+/// `name.rs` files and `name/main.rs` directories, sorted
+fn target_names(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "rs") && path.is_file() {
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+            } else if path.join("main.rs").is_file() {
+                Some(entry.file_name().to_string_lossy().into_owned())
+            } else {
+                None
+            }
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// Keys of the top level `services` mapping, read by indentation
+fn compose_tasks(content: &str) -> Vec<ProjectTask> {
+    let mut tasks = Vec::new();
+    let mut in_services = false;
+    let mut indent = None;
+    for line in content.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) {
+            in_services = line.trim_end() == "services:";
+            continue;
+        }
+        if !in_services {
+            continue;
+        }
+        if let Some(captures) = COMPOSE_SERVICE.captures(line) {
+            let level = captures[1].len();
+            if *indent.get_or_insert(level) == level {
+                let service = &captures[2];
+                tasks.push(task(
+                    TaskSource::DockerCompose,
+                    "docker",
+                    &["compose", "up", service],
+                ));
+            }
+        }
+    }
+    tasks
+}
@@ -1,4 +1,4 @@
--- Migration script not required for now, as app is not released yet
+-- A column added to an existing table also goes into ADDED_JSON_COLUMNS or ADDED_FLAG_COLUMNS in mod.rs
 CREATE TABLE IF NOT EXISTS categories (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
     name TEXT NOT NULL UNIQUE,
@@ -8,6 +8,14 @@ CREATE TABLE IF NOT EXISTS categories (
     CHECK (length(trim(name)) > 0)
 );
 
+CREATE TABLE IF NOT EXISTS tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    color TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    CHECK (length(trim(name)) > 0)
+);
+
 CREATE TABLE IF NOT EXISTS groups (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
     name TEXT NOT NULL,
@@ -21,11 +29,29 @@ CREATE TABLE IF NOT EXISTS groups (
     is_favorite BOOLEAN NOT NULL DEFAULT 0 CHECK(is_favorite IN (0,1)),
     icon TEXT,
     color TEXT,
+    resource_limits TEXT,
+    env_inheritance TEXT,
+    env_unset TEXT,
+    variables TEXT,
+    env_files TEXT,
+    secret_env_names TEXT,
+    execution_target TEXT,
+    shell_mode TEXT,
+    source_files TEXT, -- sourced by the shell before the command
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
     updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
     CHECK (parent_group_id IS NULL OR parent_group_id != id),
     CHECK (length(trim(name)) > 0),
-    CHECK (env_vars IS NULL OR json_valid(env_vars))
+    CHECK (env_vars IS NULL OR json_valid(env_vars)),
+    CHECK (resource_limits IS NULL OR json_valid(resource_limits)),
+    CHECK (env_inheritance IS NULL OR json_valid(env_inheritance)),
+    CHECK (env_unset IS NULL OR json_valid(env_unset)),
+    CHECK (variables IS NULL OR json_valid(variables)),
+    CHECK (env_files IS NULL OR json_valid(env_files)),
+    CHECK (secret_env_names IS NULL OR json_valid(secret_env_names)),
+    CHECK (execution_target IS NULL OR json_valid(execution_target)),
+    CHECK (shell_mode IS NULL OR json_valid(shell_mode)),
+    CHECK (source_files IS NULL OR json_valid(source_files))
 );
 
 CREATE UNIQUE INDEX IF NOT EXISTS idx_groups_position_unique ON groups(COALESCE(parent_group_id, -1), position);
@@ -43,12 +69,33 @@ CREATE TABLE IF NOT EXISTS commands (
     shell TEXT,
     category_id INTEGER REFERENCES categories(id) ON DELETE SET NULL,
     is_favorite BOOLEAN NOT NULL DEFAULT 0 CHECK(is_favorite IN (0,1)),
+    resource_limits TEXT,
+    env_inheritance TEXT,
+    env_unset TEXT,
+    parameters TEXT,
+    env_files TEXT,
+    secret_env_names TEXT,
+    run_elevated BOOLEAN NOT NULL DEFAULT 0 CHECK(run_elevated IN (0,1)),
+    execution_target TEXT,
+    raw_arguments TEXT, -- indices of arguments passed to the shell unquoted
+    shell_mode TEXT,
+    source_files TEXT, -- sourced by the shell before the command
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
     updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
     CHECK (length(trim(name)) > 0),
     CHECK (length(trim(command)) > 0),
     CHECK (env_vars IS NULL OR json_valid(env_vars)),
-    CHECK (arguments IS NULL OR json_valid(arguments))
+    CHECK (arguments IS NULL OR json_valid(arguments)),
+    CHECK (resource_limits IS NULL OR json_valid(resource_limits)),
+    CHECK (env_inheritance IS NULL OR json_valid(env_inheritance)),
+    CHECK (env_unset IS NULL OR json_valid(env_unset)),
+    CHECK (parameters IS NULL OR json_valid(parameters)),
+    CHECK (env_files IS NULL OR json_valid(env_files)),
+    CHECK (secret_env_names IS NULL OR json_valid(secret_env_names)),
+    CHECK (execution_target IS NULL OR json_valid(execution_target)),
+    CHECK (raw_arguments IS NULL OR json_valid(raw_arguments)),
+    CHECK (shell_mode IS NULL OR json_valid(shell_mode)),
+    CHECK (source_files IS NULL OR json_valid(source_files))
 );
 
 CREATE UNIQUE INDEX IF NOT EXISTS idx_commands_position_unique ON commands(COALESCE(group_id, -1), position);
@@ -94,6 +141,24 @@ CREATE TABLE IF NOT EXISTS workflow_steps (
     CHECK(condition IN ('always', 'on_success', 'on_failure'))
 );
 
+CREATE TABLE IF NOT EXISTS command_tags (
+    command_id INTEGER NOT NULL REFERENCES commands(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (command_id, tag_id)
+);
+
+CREATE TABLE IF NOT EXISTS group_tags (
+    group_id INTEGER NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (group_id, tag_id)
+);
+
+CREATE TABLE IF NOT EXISTS workflow_tags (
+    workflow_id INTEGER NOT NULL REFERENCES workflows(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (workflow_id, tag_id)
+);
+
 CREATE TABLE IF NOT EXISTS execution_history (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
     command_id INTEGER REFERENCES commands(id) ON DELETE CASCADE,
@@ -123,6 +188,35 @@ CREATE TABLE IF NOT EXISTS tldr_commands (
     platform TEXT NOT NULL DEFAULT 'common'
 );
 
+CREATE TABLE IF NOT EXISTS shells (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    path TEXT NOT NULL,
+    args_template TEXT NOT NULL, -- e.g. '-c {cmd}', split like a shell would
+    family TEXT NOT NULL DEFAULT 'posix', -- how arguments are quoted
+    login BOOLEAN NOT NULL DEFAULT 0 CHECK(login IN (0,1)),
+    interactive BOOLEAN NOT NULL DEFAULT 0 CHECK(interactive IN (0,1)),
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    CHECK (length(trim(name)) > 0),
+    CHECK(family IN ('posix', 'fish', 'nushell', 'powershell', 'cmd'))
+);
+
+-- Full-text index over commands, groups and workflows, filled by the search_index_* triggers
+CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(
+    name,
+    command,
+    arguments, -- space separated, not the JSON array
+    description,
+    kind UNINDEXED, -- 'command', 'group' or 'workflow'
+    item_id UNINDEXED,
+    tokenize = 'unicode61 remove_diacritics 2',
+    prefix = '2 3'
+);
+
+-- Terms of search_index, fuzzy matching picks close ones
+CREATE VIRTUAL TABLE IF NOT EXISTS search_vocab USING fts5vocab(search_index, 'row');
+
 CREATE TABLE IF NOT EXISTS settings (
     key TEXT PRIMARY KEY,
     value TEXT NOT NULL
@@ -134,6 +228,12 @@ CREATE TABLE IF NOT EXISTS schema_version (
 
 INSERT OR REPLACE INTO schema_version (version) VALUES (1);
 
+-- One-off data migrations that already ran, they must not touch rows created afterwards
+CREATE TABLE IF NOT EXISTS applied_migrations (
+    name TEXT PRIMARY KEY,
+    applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
 -- Indexes
 CREATE INDEX IF NOT EXISTS idx_groups_position ON groups(parent_group_id, position);
 CREATE INDEX IF NOT EXISTS idx_groups_category ON groups(category_id);
@@ -163,6 +263,10 @@ CREATE INDEX IF NOT EXISTS idx_execution_history_started ON execution_history(st
 CREATE INDEX IF NOT EXISTS idx_execution_history_command_time ON execution_history(command_id, started_at);
 CREATE INDEX IF NOT EXISTS idx_execution_history_workflow_time ON execution_history(workflow_id, started_at);
 
+CREATE INDEX IF NOT EXISTS idx_command_tags_tag ON command_tags(tag_id);
+CREATE INDEX IF NOT EXISTS idx_group_tags_tag ON group_tags(tag_id);
+CREATE INDEX IF NOT EXISTS idx_workflow_tags_tag ON workflow_tags(tag_id);
+
 CREATE INDEX IF NOT EXISTS idx_tldr_page_name ON tldr_commands(page_name);
 CREATE INDEX IF NOT EXISTS idx_tldr_cmd_name ON tldr_commands(command_name);
 
@@ -198,6 +302,12 @@ BEGIN
 UPDATE workflow_steps SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
 END;
 
+CREATE TRIGGER IF NOT EXISTS shells_update_timestamp
+AFTER UPDATE ON shells
+BEGIN
+UPDATE shells SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
 CREATE TRIGGER IF NOT EXISTS execution_history_timestamps
 AFTER UPDATE OF status ON execution_history
 BEGIN
@@ -216,3 +326,96 @@ SET
     END
 WHERE id = NEW.id;
 END;
+
+-- Search index
+CREATE TRIGGER IF NOT EXISTS search_index_command_insert
+AFTER INSERT ON commands
+BEGIN
+INSERT INTO search_index (name, command, arguments, description, kind, item_id)
+VALUES (
+    NEW.name,
+    NEW.command,
+    (SELECT group_concat(value, ' ') FROM json_each(COALESCE(NEW.arguments, '[]'))),
+    NEW.description,
+    'command',
+    NEW.id
+);
+END;
+
+CREATE TRIGGER IF NOT EXISTS search_index_command_update
+AFTER UPDATE OF name, command, arguments, description ON commands
+BEGIN
+UPDATE search_index
+SET
+    name = NEW.name,
+    command = NEW.command,
+    arguments = (SELECT group_concat(value, ' ') FROM json_each(COALESCE(NEW.arguments, '[]'))),
+    description = NEW.description
+WHERE kind = 'command' AND item_id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS search_index_command_delete
+AFTER DELETE ON commands
+BEGIN
+DELETE FROM search_index WHERE kind = 'command' AND item_id = OLD.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS search_index_group_insert
+AFTER INSERT ON groups
+BEGIN
+INSERT INTO search_index (name, description, kind, item_id)
+VALUES (NEW.name, NEW.description, 'group', NEW.id);
+END;
+
+CREATE TRIGGER IF NOT EXISTS search_index_group_update
+AFTER UPDATE OF name, description ON groups
+BEGIN
+UPDATE search_index
+SET name = NEW.name, description = NEW.description
+WHERE kind = 'group' AND item_id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS search_index_group_delete
+AFTER DELETE ON groups
+BEGIN
+DELETE FROM search_index WHERE kind = 'group' AND item_id = OLD.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS search_index_workflow_insert
+AFTER INSERT ON workflows
+BEGIN
+INSERT INTO search_index (name, description, kind, item_id)
+VALUES (NEW.name, NEW.description, 'workflow', NEW.id);
+END;
+
+CREATE TRIGGER IF NOT EXISTS search_index_workflow_update
+AFTER UPDATE OF name, description ON workflows
+BEGIN
+UPDATE search_index
+SET name = NEW.name, description = NEW.description
+WHERE kind = 'workflow' AND item_id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS search_index_workflow_delete
+AFTER DELETE ON workflows
+BEGIN
+DELETE FROM search_index WHERE kind = 'workflow' AND item_id = OLD.id;
+END;
+
+-- Items created before search_index existed, the triggers keep it up to date from then on
+INSERT INTO search_index (name, command, arguments, description, kind, item_id)
+SELECT name, command, arguments, description, kind, item_id FROM (
+    SELECT
+        name,
+        command,
+        (SELECT group_concat(value, ' ') FROM json_each(COALESCE(commands.arguments, '[]'))) AS arguments,
+        description,
+        'command' AS kind,
+        id AS item_id
+    FROM commands
+    UNION ALL
+    SELECT name, NULL, NULL, description, 'group', id FROM groups
+    UNION ALL
+    SELECT name, NULL, NULL, description, 'workflow', id FROM workflows
+)
+WHERE (SELECT COUNT(*) FROM search_index) = 0;
@@ -0,0 +1,183 @@
+use super::{Database, HighlightSegment, Result, SearchKind, SearchResult};
+use rusqlite::named_params;
+use tracing::{debug, instrument, warn};
+
+/// Put around matches by `highlight()`, never part of a tokenized term
+const MATCH_START: char = '\u{1}';
+const MATCH_END: char = '\u{2}';
+
+impl Database {
+    const SEARCH_QUERY: &str = "SELECT kind, item_id,
+        highlight(search_index, 0, :start, :end) AS name,
+        highlight(search_index, 1, :start, :end) AS command,
+        highlight(search_index, 2, :start, :end) AS arguments,
+        highlight(search_index, 3, :start, :end) AS description,
+        COALESCE(CASE kind
+            WHEN 'command' THEN (SELECT is_favorite FROM commands WHERE id = item_id)
+            WHEN 'group' THEN (SELECT is_favorite FROM groups WHERE id = item_id)
+            ELSE (SELECT is_favorite FROM workflows WHERE id = item_id)
+        END, 0) AS is_favorite,
+        bm25(search_index, 10.0, 5.0, 2.0, 1.0) AS score
+        FROM search_index
+        WHERE search_index MATCH :query
+        ORDER BY score * (CASE WHEN is_favorite THEN 1.5 ELSE 1.0 END), name
+        LIMIT :limit";
+
+    pub(crate) const DEFAULT_SEARCH_LIMIT: i64 = 50;
+
+    /// Ranked search over names, command lines and descriptions of commands, groups and
+    /// workflows. Every word has to match, as a prefix or within a small edit distance of an
+    /// indexed term; names weigh most and favourites are ranked up.
+    #[instrument(skip(self))]
+    pub fn search_all(&self, search_term: &str, limit: Option<i64>) -> Result<Vec<SearchResult>> {
+        let Some(query) = self.match_expression(search_term)? else {
+            return Ok(Vec::new());
+        };
+        debug!(query = %query, "Searching library");
+
+        let start = MATCH_START.to_string();
+        let end = MATCH_END.to_string();
+        self.query_database(
+            Self::SEARCH_QUERY,
+            named_params! {
+                ":start": start,
+                ":end": end,
+                ":query": query,
+                ":limit": limit.unwrap_or(Self::DEFAULT_SEARCH_LIMIT),
+            },
+            Self::row_to_search_result,
+        )
+    }
+
+    /// FTS5 query with a group per word, the word as a prefix or one of the close vocabulary
+    /// terms. Words are cut at the same characters the tokenizer splits on, so the user
+    /// can't inject FTS5 syntax.
+    fn match_expression(&self, search_term: &str) -> Result<Option<String>> {
+        let words: Vec<String> = search_term
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        if words.is_empty() {
+            return Ok(None);
+        }
+
+        let vocabulary: Vec<String> =
+            self.query_database("SELECT term FROM search_vocab", [], |row| row.get(0))?;
+        let groups: Vec<String> = words
+            .iter()
+            .map(|word| {
+                let alternatives: Vec<String> = std::iter::once(format!("\"{word}\"*"))
+                    .chain(
+                        fuzzy_terms(word, &vocabulary)
+                            .into_iter()
+                            .map(|term| format!("\"{term}\"")),
+                    )
+                    .collect();
+                format!("({})", alternatives.join(" OR "))
+            })
+            .collect();
+        Ok(Some(groups.join(" AND ")))
+    }
+
+    fn row_to_search_result(row: &rusqlite::Row) -> rusqlite::Result<SearchResult> {
+        let kind_str: String = row.get("kind")?;
+        let kind = match kind_str.as_str() {
+            "command" => SearchKind::Command,
+            "group" => SearchKind::Group,
+            "workflow" => SearchKind::Workflow,
+            other => {
+                warn!(
+                    kind = other,
+                    "Invalid search index kind, defaulting to command"
+                );
+                SearchKind::Command
+            }
+        };
+        let name: String = row.get("name")?;
+        let command: Option<String> = row.get("command")?;
+        let arguments: Option<String> = row.get("arguments")?;
+        let description: Option<String> = row.get("description")?;
+
+        let command_line = command.map(|command| match arguments.filter(|a| !a.is_empty()) {
+            Some(arguments) => format!("{command} {arguments}"),
+            None => command,
+        });
+        let detail = [command_line, description]
+            .into_iter()
+            .flatten()
+            .find(|text| text.contains(MATCH_START))
+            .map(|text| highlight_segments(&text));
+
+        Ok(SearchResult {
+            kind,
+            id: row.get("item_id")?,
+            name: highlight_segments(&name),
+            detail,
+            is_favorite: row.get("is_favorite")?,
+            rank: row.get("score")?,
+        })
+    }
+}
+
+/// Splits the output of `highlight()` at the match markers
+fn highlight_segments(text: &str) -> Vec<HighlightSegment> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut matched = false;
+    for c in text.chars() {
+        if c == MATCH_START || c == MATCH_END {
+            if !current.is_empty() {
+                segments.push(HighlightSegment {
+                    text: std::mem::take(&mut current),
+                    matched,
+                });
+            }
+            matched = c == MATCH_START;
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        segments.push(HighlightSegment {
+            text: current,
+            matched,
+        });
+    }
+    segments
+}
+
+/// Up to three indexed terms close to `word` that its prefix query doesn't match already.
+/// Short words have too many neighbours to be corrected.
+fn fuzzy_terms<'a>(word: &str, vocabulary: &'a [String]) -> Vec<&'a str> {
+    let max_distance = match word.chars().count() {
+        0..=3 => return Vec::new(),
+        4..=7 => 1,
+        _ => 2,
+    };
+    let mut terms: Vec<(usize, &str)> = vocabulary
+        .iter()
+        .filter(|term| !term.starts_with(word))
+        .filter_map(|term| {
+            let distance = edit_distance(word, term);
+            (distance <= max_distance).then_some((distance, term.as_str()))
+        })
+        .collect();
+    terms.sort();
+    terms.into_iter().take(3).map(|(_, term)| term).collect()
+}
+
+/// Levenshtein distance over chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,201 @@
+use super::{Database, DatabaseError, Result, SecretValue, SecretsKey};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use tracing::{error, info, instrument};
+
+/// Encrypted value of a secret env var, as stored in `secrets.env_secrets`
+pub(crate) type SealedSecret = (String, Vec<u8>);
+
+impl Database {
+    /// Clear text values of the secret env vars of a command or group, only meant for spawning.
+    /// `table` is `COMMANDS_TABLE` or `GROUPS_TABLE`.
+    #[instrument(skip(self))]
+    pub fn get_secret_env_vars(
+        &self,
+        table: &'static str,
+        owner_id: i64,
+    ) -> Result<HashMap<String, String>> {
+        let sealed = self.get_sealed_secrets(table, owner_id)?;
+        if sealed.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let key = self.secrets_key()?;
+        sealed
+            .into_iter()
+            .map(|(name, value)| {
+                let value = key.decrypt(&value, &Self::secret_context(table, &name))?;
+                Ok((name, value))
+            })
+            .collect()
+    }
+
+    /// Stored values as they are, still encrypted. Values are bound to the table and name only,
+    /// so they can be written for another owner as is, e.g. for a copy.
+    pub(crate) fn get_sealed_secrets(
+        &self,
+        table: &'static str,
+        owner_id: i64,
+    ) -> Result<Vec<SealedSecret>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT name, value FROM secrets.env_secrets
+             WHERE owner_table = ?1 AND owner_id = ?2 ORDER BY name",
+        )?;
+        let secrets = stmt
+            .query_map(params![table, owner_id], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(secrets)
+    }
+
+    /// Encrypted values to store for `secrets`, masked values are taken from what is already
+    /// stored. `owner_id` is `None` on create, nothing is stored yet so a masked value is an error.
+    pub(crate) fn resolve_secret_values(
+        &self,
+        table: &'static str,
+        owner_id: Option<i64>,
+        secrets: &Option<HashMap<String, SecretValue>>,
+    ) -> Result<Vec<SealedSecret>> {
+        let Some(secrets) = secrets.as_ref().filter(|secrets| !secrets.is_empty()) else {
+            return Ok(Vec::new());
+        };
+        let stored: HashMap<String, Vec<u8>> = match owner_id {
+            Some(id) if secrets.values().any(SecretValue::is_masked) => {
+                self.get_sealed_secrets(table, id)?.into_iter().collect()
+            }
+            _ => HashMap::new(),
+        };
+        let key = self.secrets_key()?;
+
+        secrets
+            .iter()
+            .map(|(name, value)| {
+                let value = if value.is_masked() {
+                    stored.get(name).cloned().ok_or_else(|| {
+                        error!(name, "No stored value for masked secret");
+                        DatabaseError::InvalidData {
+                            field: "secret_env_vars",
+                            reason: format!("No stored value for secret '{name}'"),
+                        }
+                    })?
+                } else {
+                    key.encrypt(value.expose(), &Self::secret_context(table, name))?
+                };
+                Ok((name.clone(), value))
+            })
+            .collect()
+    }
+
+    /// Replaces all stored secrets of a command or group inside the caller's transaction
+    pub(crate) fn write_secret_values(
+        conn: &Connection,
+        table: &'static str,
+        owner_id: i64,
+        values: &[SealedSecret],
+    ) -> Result<()> {
+        conn.execute(
+            "DELETE FROM secrets.env_secrets WHERE owner_table = ?1 AND owner_id = ?2",
+            params![table, owner_id],
+        )?;
+        for (name, value) in values {
+            conn.execute(
+                "INSERT INTO secrets.env_secrets (owner_table, owner_id, name, value)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![table, owner_id, name, value],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Values stored in clear text before they were encrypted are encrypted in place, then the
+    /// file is vacuumed so the old pages are gone as well
+    pub(crate) fn encrypt_plain_secrets(conn: &Connection, key: &SecretsKey) -> Result<()> {
+        let plain: Vec<(String, i64, String, String)> = conn
+            .prepare(
+                "SELECT owner_table, owner_id, name, value FROM secrets.env_secrets
+                 WHERE typeof(value) = 'text'",
+            )?
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        if plain.is_empty() {
+            return Ok(());
+        }
+
+        for (table, owner_id, name, value) in &plain {
+            let sealed = key.encrypt(value, &Self::secret_context(table, name))?;
+            conn.execute(
+                "UPDATE secrets.env_secrets SET value = ?1
+                 WHERE owner_table = ?2 AND owner_id = ?3 AND name = ?4",
+                params![sealed, table, owner_id, name],
+            )?;
+        }
+        conn.execute_batch("VACUUM secrets; PRAGMA secrets.wal_checkpoint(TRUNCATE);")?;
+        info!(count = plain.len(), "Plain text secrets encrypted");
+        Ok(())
+    }
+
+    fn secrets_key(&self) -> Result<&SecretsKey> {
+        self.secrets_key.as_ref().ok_or_else(|| {
+            DatabaseError::SecretStore(
+                "OS keyring unavailable, secret env vars can't be stored or read".to_string(),
+            )
+        })
+    }
+
+    /// Authenticated with each value
+    fn secret_context(table: &str, name: &str) -> String {
+        format!("{table}/{name}")
+    }
+
+    /// Only the names go into the main database
+    pub(crate) fn secret_names_to_json(
+        secrets: &Option<HashMap<String, SecretValue>>,
+    ) -> Result<Option<String>> {
+        let names = secrets.as_ref().map(|secrets| {
+            let mut names: Vec<&String> = secrets.keys().collect();
+            names.sort();
+            names
+        });
+        Ok(Self::option_to_json(&names)?)
+    }
+
+    pub(crate) fn masked_secrets(
+        names_json: Option<String>,
+    ) -> Option<HashMap<String, SecretValue>> {
+        let names: Vec<String> = Self::json_to_option("secret_env_names", names_json)?;
+        Some(
+            names
+                .into_iter()
+                .map(|name| (name, SecretValue::masked()))
+                .collect(),
+        )
+    }
+
+    /// Secret names follow the env var key rules and can't also be plain `env_vars`
+    pub(crate) fn validate_secret_env_vars(
+        &self,
+        secrets: &Option<HashMap<String, SecretValue>>,
+        env_vars: &Option<HashMap<String, String>>,
+    ) -> Result<()> {
+        let Some(secrets) = secrets else {
+            return Ok(());
+        };
+        self.validate_env_var_names("secret_env_vars", secrets.keys())?;
+
+        if let Some(name) = secrets.keys().find(|name| {
+            env_vars
+                .as_ref()
+                .is_some_and(|vars| vars.contains_key(*name))
+        }) {
+            error!(name, "Env var is both secret and plain");
+            return Err(DatabaseError::InvalidData {
+                field: "secret_env_vars",
+                reason: format!("'{name}' is also a plain env var"),
+            });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+-- Attached as `secrets` from its own file, values never end up in the main database.
+-- Values are encrypted with the key from the OS keyring, see `SecretsKey`. Files created
+-- before that declared `value` as TEXT, encrypted values are blobs either way.
+CREATE TABLE IF NOT EXISTS secrets.env_secrets (
+    owner_table TEXT NOT NULL CHECK (owner_table IN ('commands', 'groups')),
+    owner_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    value BLOB NOT NULL,
+    PRIMARY KEY (owner_table, owner_id, name)
+);
+
+-- Only temp triggers can reach into another database, they live as long as the connection.
+-- Table names can't be qualified in trigger bodies, `env_secrets` only exists in `secrets`.
+-- Deletes cascading from parent groups fire them as well.
+CREATE TEMP TRIGGER IF NOT EXISTS delete_command_secrets AFTER DELETE ON main.commands
+BEGIN
+    DELETE FROM env_secrets WHERE owner_table = 'commands' AND owner_id = OLD.id;
+END;
+
+CREATE TEMP TRIGGER IF NOT EXISTS delete_group_secrets AFTER DELETE ON main.groups
+BEGIN
+    DELETE FROM env_secrets WHERE owner_table = 'groups' AND owner_id = OLD.id;
+END;
@@ -0,0 +1,92 @@
+use super::{DatabaseError, Result};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::fmt;
+use std::sync::Arc;
+use tracing::{error, info};
+
+const KEYRING_SERVICE: &str = "tgui";
+const KEYRING_USER: &str = "secret-env-vars";
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+
+/// Key the secret env var values are encrypted with before they reach the secrets database.
+/// It is kept in the OS keyring (Secret Service, Keychain or Credential Manager), so a copy of
+/// the database files alone doesn't reveal the values.
+#[derive(Clone)]
+pub struct SecretsKey(Arc<ChaCha20Poly1305>);
+
+impl fmt::Debug for SecretsKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SecretsKey(..)")
+    }
+}
+
+impl SecretsKey {
+    /// Loads the key from the OS keyring, creating it on first use
+    pub fn from_keyring() -> Result<Self> {
+        let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(keyring_error)?;
+        match entry.get_secret() {
+            Ok(bytes) if bytes.len() == KEY_LENGTH => Ok(Self::from_key(Key::from_slice(&bytes))),
+            Ok(_) => Err(DatabaseError::SecretStore(
+                "the key in the OS keyring has the wrong length".to_string(),
+            )),
+            Err(keyring::Error::NoEntry) => {
+                let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+                entry.set_secret(&key).map_err(keyring_error)?;
+                info!("Secrets key created in the OS keyring");
+                Ok(Self::from_key(&key))
+            }
+            Err(e) => Err(keyring_error(e)),
+        }
+    }
+
+    /// Key only known to this process, values encrypted with it are lost with it
+    pub fn generate() -> Self {
+        Self::from_key(&ChaCha20Poly1305::generate_key(&mut OsRng))
+    }
+
+    fn from_key(key: &Key) -> Self {
+        Self(Arc::new(ChaCha20Poly1305::new(key)))
+    }
+
+    /// Nonce followed by the ciphertext. `context` is authenticated along with the value, a
+    /// stored value only decrypts for the secret it was written for.
+    pub(crate) fn encrypt(&self, value: &str, context: &str) -> Result<Vec<u8>> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: value.as_bytes(),
+            aad: context.as_bytes(),
+        };
+        let ciphertext = self.0.encrypt(&nonce, payload).map_err(|_| {
+            DatabaseError::SecretStore(format!("Failed to encrypt secret {context}"))
+        })?;
+        Ok([nonce.as_slice(), &ciphertext].concat())
+    }
+
+    pub(crate) fn decrypt(&self, stored: &[u8], context: &str) -> Result<String> {
+        let undecryptable = || {
+            error!(context, "Secret can't be decrypted");
+            DatabaseError::SecretStore(format!(
+                "Secret {context} can't be decrypted, its value has to be entered again"
+            ))
+        };
+        if stored.len() < NONCE_LENGTH {
+            return Err(undecryptable());
+        }
+        let (nonce, ciphertext) = stored.split_at(NONCE_LENGTH);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: context.as_bytes(),
+        };
+        let value = self
+            .0
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|_| undecryptable())?;
+        String::from_utf8(value).map_err(|_| undecryptable())
+    }
+}
+
+fn keyring_error(err: keyring::Error) -> DatabaseError {
+    DatabaseError::SecretStore(format!("OS keyring: {err}"))
+}
@@ -1,4 +1,6 @@
 use super::{Database, DatabaseError, Result};
+use crate::process::redaction::{self, Redactor};
+use crate::process::safety;
 use rusqlite::params;
 use std::collections::HashMap;
 use std::sync::LazyLock;
@@ -14,7 +16,11 @@ static DEFAULT_SETTINGS: LazyLock<HashMap<&'static str, &'static str>> = LazyLoc
         ("auto_scroll_logs", "true"),
         ("warn_before_kill", "true"),
         ("kill_process_tree_by_default", "false"),
-        ("available_shells", "[]"),
+        ("redact_common_tokens", "true"),
+        ("redaction_rules", "[]"),
+        ("confirm_dangerous_commands", "true"),
+        ("spawn_deny_list", "[]"),
+        ("elevation_method", "auto"),
         ("tldr_data_version", "0"), // TODO add log_retention_days
     ])
 });
@@ -29,13 +35,33 @@ impl Database {
                 params![key, value],
             )?;
         }
+        // replaced by the shells registry
+        self.conn()?
+            .execute("DELETE FROM settings WHERE key = 'available_shells'", [])?;
 
         self.update_default_shell()?;
         Ok(())
     }
 
+    /// Keeps `default_shell` pointing into the shell registry: a shell chosen in the settings
+    /// stays, otherwise the system shell is taken, or the first registered one when it isn't
+    /// registered
     pub fn update_default_shell(&self) -> Result<()> {
-        let default_shell = crate::process::shell::Shell::get_system_default_shell();
+        let shells = self.get_shells()?;
+        let current = self.get_setting("default_shell")?;
+        if shells.iter().any(|shell| shell.name == current) {
+            return Ok(());
+        }
+
+        let system = crate::process::shell::Shell::get_system_default_shell();
+        let default_shell = if shells.iter().any(|shell| shell.name == system) {
+            system
+        } else {
+            shells
+                .first()
+                .map(|shell| shell.name.clone())
+                .unwrap_or_default()
+        };
         self.conn()?.execute(
             "INSERT OR REPLACE INTO settings (key, value) VALUES ('default_shell', ?1)",
             params![default_shell],
@@ -112,7 +138,11 @@ impl Database {
                     reason: "Must be a positive number".to_string(),
                 }),
 
-            "auto_scroll_logs" | "warn_before_kill" | "kill_process_tree_by_default" => {
+            "auto_scroll_logs"
+            | "warn_before_kill"
+            | "kill_process_tree_by_default"
+            | "redact_common_tokens"
+            | "confirm_dangerous_commands" => {
                 if value == "true" || value == "false" {
                     Ok(())
                 } else {
@@ -123,6 +153,41 @@ impl Database {
                 }
             }
 
+            "redaction_rules" => redaction::parse_rules(value)
+                .and_then(|rules| Redactor::new(false, &rules))
+                .map(|_| ())
+                .map_err(|e| DatabaseError::InvalidData {
+                    field: "value",
+                    reason: e.to_string(),
+                }),
+
+            "elevation_method" => match value {
+                "auto" | "pkexec" | "sudo" => Ok(()),
+                _ => Err(DatabaseError::InvalidData {
+                    field: "value",
+                    reason: "Must be 'auto', 'pkexec' or 'sudo'".to_string(),
+                }),
+            },
+
+            // empty runs commands without a shell
+            "default_shell" => {
+                if value.is_empty() || self.get_shells()?.iter().any(|shell| shell.name == value) {
+                    Ok(())
+                } else {
+                    Err(DatabaseError::InvalidData {
+                        field: "value",
+                        reason: format!("Shell '{value}' is not in the shell registry"),
+                    })
+                }
+            }
+
+            "spawn_deny_list" => safety::parse_deny_list(value)
+                .map(|_| ())
+                .map_err(|reason| DatabaseError::InvalidData {
+                    field: "value",
+                    reason,
+                }),
+
             _ => Ok(()),
         }
     }
@@ -0,0 +1,369 @@
+use super::{Command, Database, DatabaseError, HistorySource, Result, ShellHistoryEntry};
+use regex::Regex;
+use rusqlite::params;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use tracing::{info, instrument};
+
+static ZSH_EXTENDED_PREFIX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^: *\d+:\d+;").expect("valid regex"));
+static BASH_TIMESTAMP: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^#\d+$").expect("valid regex"));
+static ENV_ASSIGNMENT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([A-Za-z_][A-Za-z0-9_]*)=(.*)$").expect("valid regex"));
+
+/// zsh escapes bytes of its special characters in the history file as this byte followed by the
+/// original byte xor 32
+const ZSH_META: u8 = 0x83;
+/// Characters that make a line depend on the shell, outside of quotes
+const SHELL_SYNTAX: &[char] = &['|', '&', ';', '<', '>', '(', ')', '$', '`', '*', '?', '\n'];
+
+impl Database {
+    /// Commands of a shell history or alias file, deduplicated and most frequent first. Without
+    /// `path` the usual location of `source` is read, for aliases every rc file that exists.
+    #[instrument(skip(self))]
+    pub fn read_shell_history(
+        &self,
+        source: HistorySource,
+        path: Option<&Path>,
+        limit: Option<usize>,
+    ) -> Result<Vec<ShellHistoryEntry>> {
+        let paths = match path {
+            Some(path) => vec![path.to_path_buf()],
+            None => default_paths(source)
+                .into_iter()
+                .filter(|path| path.is_file())
+                .collect(),
+        };
+        if paths.is_empty() {
+            return Err(DatabaseError::InvalidData {
+                field: "path",
+                reason: format!("No {} file found, pass its path", source.as_str()),
+            });
+        }
+
+        let mut content = String::new();
+        for path in &paths {
+            let bytes = std::fs::read(path).map_err(|e| DatabaseError::InvalidData {
+                field: "path",
+                reason: format!("Can't read '{}': {e}", path.display()),
+            })?;
+            let bytes = if source == HistorySource::Zsh {
+                unmetafy(bytes)
+            } else {
+                bytes
+            };
+            content.push_str(&String::from_utf8_lossy(&bytes));
+            content.push('\n');
+        }
+
+        let mut entries = parse_shell_history(source, &content);
+        if let Some(limit) = limit {
+            entries.truncate(limit);
+        }
+        Ok(entries)
+    }
+
+    /// Creates a command for each entry at the end of `group_id`, in one transaction. Entries
+    /// running the same command line as a command already in the group are skipped.
+    #[instrument(skip(self, entries), fields(entries = entries.len()))]
+    pub fn import_shell_commands(
+        &self,
+        entries: &[ShellHistoryEntry],
+        group_id: Option<i64>,
+    ) -> Result<Vec<i64>> {
+        if let Some(group_id) = group_id {
+            self.get_group(group_id)?;
+        }
+        let commands = entries
+            .iter()
+            .map(|entry| {
+                let command = Command {
+                    name: entry.name.clone(),
+                    command: entry.command.clone(),
+                    arguments: entry.arguments.clone(),
+                    raw_arguments: entry.raw_arguments.clone(),
+                    env_vars: entry.env_vars.clone(),
+                    shell: entry.shell.clone(),
+                    group_id,
+                    ..Default::default()
+                };
+                self.validate_command(&command).map(|_| command)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let mut existing: HashSet<(String, Vec<String>)> = tx
+            .prepare("SELECT command, arguments FROM commands WHERE group_id IS ?1")?
+            .query_map(params![group_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .into_iter()
+            .map(|(command, arguments)| Ok((command, serde_json::from_str(&arguments)?)))
+            .collect::<Result<_>>()?;
+
+        let mut ids = Vec::new();
+        for command in &commands {
+            if existing.insert((command.command.clone(), command.arguments.clone())) {
+                ids.push(Self::insert_command(&tx, command, &[])?);
+            }
+        }
+        tx.commit()?;
+
+        info!(
+            created = ids.len(),
+            skipped = commands.len() - ids.len(),
+            "Shell commands imported"
+        );
+        Ok(ids)
+    }
+}
+
+impl HistorySource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HistorySource::Bash => "bash",
+            HistorySource::Zsh => "zsh",
+            HistorySource::Fish => "fish",
+            HistorySource::Aliases => "aliases",
+        }
+    }
+
+    /// Registry name of the shell that runs lines of this source needing one
+    fn shell(&self) -> &'static str {
+        match self {
+            HistorySource::Bash => "bash",
+            HistorySource::Zsh => "zsh",
+            HistorySource::Fish => "fish",
+            HistorySource::Aliases => "sh",
+        }
+    }
+}
+
+fn default_paths(source: HistorySource) -> Vec<PathBuf> {
+    let home = dirs::home_dir().unwrap_or_default();
+    match source {
+        HistorySource::Bash => vec![home.join(".bash_history")],
+        HistorySource::Zsh => vec![home.join(".zsh_history")],
+        HistorySource::Fish => dirs::data_dir()
+            .map(|dir| dir.join("fish").join("fish_history"))
+            .into_iter()
+            .collect(),
+        HistorySource::Aliases => vec![
+            home.join(".bash_aliases"),
+            home.join(".bashrc"),
+            home.join(".zshrc"),
+        ],
+    }
+}
+
+/// Parses the content of a history or alias file. Identical command lines are merged and ranked
+/// by how often they occur, then by how recently. A later alias replaces an earlier one of the
+/// same name, like in the shell.
+pub fn parse_shell_history(source: HistorySource, content: &str) -> Vec<ShellHistoryEntry> {
+    let lines = match source {
+        HistorySource::Bash => bash_lines(content),
+        HistorySource::Zsh => zsh_lines(content),
+        HistorySource::Fish => fish_lines(content),
+        HistorySource::Aliases => alias_lines(content),
+    };
+
+    // key -> (index of the last occurrence, entry)
+    let mut ranked: HashMap<String, (usize, ShellHistoryEntry)> = HashMap::new();
+    for (index, (alias, line)) in lines.into_iter().enumerate() {
+        let key = alias.clone().unwrap_or_else(|| line.trim().to_string());
+        let Some(entry) = history_entry(alias, &line, source.shell()) else {
+            continue;
+        };
+        match ranked.get_mut(&key) {
+            Some((last, existing)) if source == HistorySource::Aliases => {
+                *last = index;
+                *existing = entry;
+            }
+            Some((last, existing)) => {
+                *last = index;
+                existing.count += 1;
+            }
+            None => {
+                ranked.insert(key, (index, entry));
+            }
+        }
+    }
+
+    let mut entries: Vec<_> = ranked.into_values().collect();
+    entries.sort_by(|(a_last, a), (b_last, b)| b.count.cmp(&a.count).then(b_last.cmp(a_last)));
+    entries.into_iter().map(|(_, entry)| entry).collect()
+}
+
+fn bash_lines(content: &str) -> Vec<(Option<String>, String)> {
+    content
+        .lines()
+        // written before each command when HISTTIMEFORMAT is set
+        .filter(|line| !line.trim().is_empty() && !BASH_TIMESTAMP.is_match(line))
+        .map(|line| (None, line.to_string()))
+        .collect()
+}
+
+/// Multi-line commands are stored with a backslash ending every line but the last
+fn zsh_lines(content: &str) -> Vec<(Option<String>, String)> {
+    let mut lines = Vec::new();
+    let mut current: Option<String> = None;
+    for line in content.lines() {
+        let line = match current.take() {
+            Some(mut previous) => {
+                previous.push('\n');
+                previous.push_str(line);
+                previous
+            }
+            None => ZSH_EXTENDED_PREFIX.replace(line, "").into_owned(),
+        };
+        match line.strip_suffix('\\') {
+            Some(continued) => current = Some(continued.to_string()),
+            None if !line.trim().is_empty() => lines.push((None, line)),
+            None => {}
+        }
+    }
+    lines.extend(current.map(|line| (None, line)));
+    lines
+}
+
+fn fish_lines(content: &str) -> Vec<(Option<String>, String)> {
+    content
+        .lines()
+        .filter_map(|line| line.strip_prefix("- cmd: "))
+        .map(|command| (None, unescape_fish(command)))
+        .collect()
+}
+
+/// fish writes newlines as `\n` and backslashes as `\\`
+fn unescape_fish(command: &str) -> String {
+    let mut unescaped = String::with_capacity(command.len());
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                unescaped.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                unescaped.push('\\');
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// `alias ll='ls -la' la='ls -A'`, options like zsh's `-g` are ignored
+fn alias_lines(content: &str) -> Vec<(Option<String>, String)> {
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("alias "))
+        .filter_map(shlex::split)
+        .flatten()
+        .filter(|definition| !definition.starts_with('-'))
+        .filter_map(|definition| {
+            let (name, value) = definition.split_once('=')?;
+            Some((Some(name.to_string()), value.to_string()))
+        })
+        .collect()
+}
+
+/// Splits a line into leading env assignments, the command and its arguments. `None` for lines
+/// that can't be split, are only assignments or start with shell syntax, e.g. `(cd x && make)`.
+fn history_entry(alias: Option<String>, line: &str, shell: &str) -> Option<ShellHistoryEntry> {
+    let line = line.trim();
+    let mut tokens = shlex::split(line)?.into_iter().peekable();
+    // words that were not quoted appear unchanged in the line
+    let words: HashSet<&str> = line.split_whitespace().collect();
+
+    let mut env_vars = HashMap::new();
+    while let Some(captures) = tokens
+        .peek()
+        .and_then(|token| ENV_ASSIGNMENT.captures(token))
+    {
+        env_vars.insert(captures[1].to_string(), captures[2].to_string());
+        tokens.next();
+    }
+    let command = tokens.next()?;
+    if words.contains(command.as_str()) && uses_shell_syntax(&command) {
+        return None;
+    }
+    let arguments: Vec<String> = tokens.collect();
+
+    let needs_shell = uses_shell_syntax(line);
+    let raw_arguments = if needs_shell {
+        arguments
+            .iter()
+            .enumerate()
+            .filter(|(_, arg)| words.contains(arg.as_str()) && uses_shell_syntax(arg))
+            .map(|(index, _)| index)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Some(ShellHistoryEntry {
+        name: alias.unwrap_or_else(|| entry_name(line)),
+        command,
+        arguments,
+        raw_arguments,
+        env_vars: (!env_vars.is_empty()).then_some(env_vars),
+        shell: needs_shell.then(|| shell.to_string()),
+        count: 1,
+    })
+}
+
+/// First line of the command, cut to the maximum name length
+fn entry_name(line: &str) -> String {
+    let first = line.lines().next().unwrap_or_default().trim();
+    let mut end = first.len().min(Database::MAX_NAME_LENGTH);
+    while !first.is_char_boundary(end) {
+        end -= 1;
+    }
+    first[..end].to_string()
+}
+
+/// Pipes, redirections, globs, expansions or a leading `~` outside of single quotes
+fn uses_shell_syntax(text: &str) -> bool {
+    let mut chars = text.chars();
+    let mut single_quoted = false;
+    let mut double_quoted = false;
+    let mut word_start = true;
+    while let Some(c) = chars.next() {
+        if single_quoted {
+            single_quoted = c != '\'';
+        } else if c == '\\' {
+            chars.next();
+        } else if double_quoted {
+            if c == '$' || c == '`' {
+                return true;
+            }
+            double_quoted = c != '"';
+        } else if c == '\'' {
+            single_quoted = true;
+        } else if c == '"' {
+            double_quoted = true;
+        } else if SHELL_SYNTAX.contains(&c) || (c == '~' && word_start) {
+            return true;
+        }
+        word_start = c.is_whitespace();
+    }
+    false
+}
+
+fn unmetafy(bytes: Vec<u8>) -> Vec<u8> {
+    let mut unmetafied = Vec::with_capacity(bytes.len());
+    let mut bytes = bytes.into_iter();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            ZSH_META => unmetafied.extend(bytes.next().map(|next| next ^ 32)),
+            _ => unmetafied.push(byte),
+        }
+    }
+    unmetafied
+}
@@ -0,0 +1,216 @@
+use super::{Database, DatabaseError, Result, ShellDefinition, ShellFamily, ShellMode};
+use crate::constants::SHELLS_TABLE;
+use crate::process::shell::{self, Shell};
+use rusqlite::{named_params, params};
+use tracing::{error, info, instrument, warn};
+
+/// Name of `seed_shells` in `applied_migrations`
+const SHELLS_SEEDED: &str = "builtin_shells_seeded";
+
+impl Database {
+    #[instrument(skip(self))]
+    pub fn create_shell(&self, shell: &ShellDefinition) -> Result<i64> {
+        self.validate_shell(shell)?;
+
+        self.create(
+            SHELLS_TABLE,
+            "INSERT INTO shells (name, path, args_template, family, login, interactive)
+             VALUES (:name, :path, :args_template, :family, :login, :interactive)",
+            named_params! {
+                ":name": shell.name,
+                ":path": shell.path,
+                ":args_template": shell.args_template,
+                ":family": shell.family.as_str(),
+                ":login": shell.login,
+                ":interactive": shell.interactive,
+            },
+        )
+    }
+
+    #[instrument(skip(self))]
+    pub fn get_shell(&self, id: i64) -> Result<ShellDefinition> {
+        self.query_row(
+            SHELLS_TABLE,
+            id,
+            "SELECT * FROM shells WHERE id = ?1",
+            Self::row_to_shell,
+        )
+    }
+
+    /// no id provided, so id in the not found error is set to a dummy value 0
+    #[instrument(skip(self))]
+    pub fn get_shell_by_name(&self, name: &str) -> Result<ShellDefinition> {
+        self.conn()?
+            .query_row(
+                "SELECT * FROM shells WHERE name = ?1",
+                params![name],
+                Self::row_to_shell,
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => DatabaseError::NotFound {
+                    entity: SHELLS_TABLE,
+                    id: 0,
+                },
+                _ => e.into(),
+            })
+    }
+
+    #[instrument(skip(self))]
+    pub fn get_shells(&self) -> Result<Vec<ShellDefinition>> {
+        self.query_database("SELECT * FROM shells ORDER BY name", [], Self::row_to_shell)
+    }
+
+    /// A renamed shell stays assigned to the commands and groups that used it
+    #[instrument(skip(self))]
+    pub fn update_shell(&self, shell: &ShellDefinition) -> Result<()> {
+        self.validate_shell(shell)?;
+        let previous = self.get_shell(shell.id)?;
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "UPDATE shells SET name = :name, path = :path, args_template = :args_template,
+             family = :family, login = :login, interactive = :interactive WHERE id = :id",
+            named_params! {
+                ":name": shell.name,
+                ":path": shell.path,
+                ":args_template": shell.args_template,
+                ":family": shell.family.as_str(),
+                ":login": shell.login,
+                ":interactive": shell.interactive,
+                ":id": shell.id,
+            },
+        )?;
+        if previous.name != shell.name {
+            for sql in [
+                "UPDATE commands SET shell = ?1 WHERE shell = ?2",
+                "UPDATE groups SET shell = ?1 WHERE shell = ?2",
+                "UPDATE settings SET value = ?1 WHERE key = 'default_shell' AND value = ?2",
+            ] {
+                tx.execute(sql, params![shell.name, previous.name])?;
+            }
+        }
+        tx.commit()?;
+
+        info!(
+            id = shell.id,
+            table = SHELLS_TABLE,
+            "Database operation successful"
+        );
+        Ok(())
+    }
+
+    /// Commands and groups still naming the shell fail to spawn until another one is chosen, the
+    /// default shell moves to another registered one
+    #[instrument(skip(self))]
+    pub fn delete_shell(&self, id: i64) -> Result<()> {
+        self.execute_db(
+            SHELLS_TABLE,
+            id,
+            "DELETE FROM shells WHERE id = ?1",
+            params![id],
+        )?;
+        self.update_default_shell()
+    }
+
+    /// Fills the registry with the shells detected on the system. Runs once, shells removed
+    /// afterwards are not added back. A registry filled by an older version is kept as it is.
+    #[instrument(skip(self))]
+    pub fn seed_shells(&self) -> Result<()> {
+        let applied: bool = self.conn()?.query_row(
+            "SELECT EXISTS(SELECT 1 FROM applied_migrations WHERE name = ?1)",
+            params![SHELLS_SEEDED],
+            |row| row.get(0),
+        )?;
+        if applied {
+            return Ok(());
+        }
+
+        let detected = shell::get_shells_cached();
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let count: i64 = tx.query_row("SELECT COUNT(*) FROM shells", [], |row| row.get(0))?;
+        if count == 0 {
+            info!(count = detected.len(), "Seeding shell registry");
+            for info in detected {
+                let shell = ShellDefinition::builtin(&info.name, &info.path);
+                tx.execute(
+                    "INSERT OR IGNORE INTO shells (name, path, args_template, family)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    params![
+                        shell.name,
+                        shell.path,
+                        shell.args_template,
+                        shell.family.as_str()
+                    ],
+                )?;
+            }
+        }
+        tx.execute(
+            "INSERT INTO applied_migrations (name) VALUES (?1)",
+            params![SHELLS_SEEDED],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn validate_shell(&self, shell: &ShellDefinition) -> Result<()> {
+        self.validate_field_length("name", &shell.name, Self::MAX_NAME_LENGTH)?;
+        self.validate_field_length("path", &shell.path, Self::MAX_COMMAND_LENGTH)?;
+        self.validate_field_length(
+            "args_template",
+            &shell.args_template,
+            Self::MAX_COMMAND_LENGTH,
+        )?;
+
+        if Shell::resolve_executable(&shell.path).is_none() {
+            error!(path = shell.path, "Shell executable not found");
+            return Err(DatabaseError::InvalidData {
+                field: "path",
+                reason: format!("'{}' is not an executable file", shell.path),
+            });
+        }
+        if shell.template_args().is_none() {
+            return Err(DatabaseError::InvalidData {
+                field: "args_template",
+                reason: format!(
+                    "'{}' must contain {} exactly once as a separate argument",
+                    shell.args_template,
+                    shell::CMD_PLACEHOLDER
+                ),
+            });
+        }
+        for (field, enabled, mode) in [
+            ("login", shell.login, ShellMode::Login),
+            ("interactive", shell.interactive, ShellMode::Interactive),
+        ] {
+            if enabled && !shell.family.supports_mode(mode) {
+                return Err(DatabaseError::InvalidData {
+                    field,
+                    reason: format!("{} shells have no {field} mode", shell.family.as_str()),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn row_to_shell(row: &rusqlite::Row) -> rusqlite::Result<ShellDefinition> {
+        let family_str: String = row.get("family")?;
+        let family = ShellFamily::from_str(&family_str).unwrap_or_else(|e| {
+            warn!(error = %e, "Invalid shell family, defaulting to posix");
+            ShellFamily::Posix
+        });
+
+        Ok(ShellDefinition {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            path: row.get("path")?,
+            args_template: row.get("args_template")?,
+            family,
+            login: row.get("login")?,
+            interactive: row.get("interactive")?,
+            created_at: row.get("created_at")?,
+            updated_at: row.get("updated_at")?,
+        })
+    }
+}
@@ -0,0 +1,157 @@
+use super::{Command, CommandSuggestion, Database, Result};
+use rusqlite::Row;
+use std::cmp::Ordering;
+use tracing::{debug, instrument};
+
+/// Added to a favourite's frecency so it is suggested before it has been run
+const FAVORITE_BONUS: f64 = 50.0;
+const FAVORITE_MULTIPLIER: f64 = 1.5;
+/// Weight of `ln(1 + frecency)` against the match score, a frequently run command beats a
+/// slightly better match
+const FRECENCY_WEIGHT: f64 = 10.0;
+/// Matches in the command line but not the name count for less
+const COMMAND_LINE_PENALTY: f64 = 0.5;
+
+const MATCH_BONUS: i64 = 16;
+const WORD_START_BONUS: i64 = 8;
+const CONSECUTIVE_BONUS: i64 = 6;
+const GAP_PENALTY: i64 = 1;
+
+impl Database {
+    /// Each run is worth 100 points in the first 4 days down to 10 after 3 months, scaled by
+    /// how it ended.
+    const FRECENCY_QUERY: &str = "SELECT c.*, COALESCE(f.frecency, 0.0) AS frecency
+        FROM commands c
+        LEFT JOIN (
+            SELECT command_id, SUM(
+                CASE
+                    WHEN age < 4 THEN 100
+                    WHEN age < 14 THEN 70
+                    WHEN age < 31 THEN 50
+                    WHEN age < 90 THEN 30
+                    ELSE 10
+                END * CASE status
+                    WHEN 'success' THEN 1.0
+                    WHEN 'running' THEN 1.0
+                    WHEN 'paused' THEN 0.8
+                    WHEN 'failed' THEN 0.4
+                    WHEN 'timeout' THEN 0.4
+                    ELSE 0.2
+                END
+            ) AS frecency
+            FROM (
+                SELECT command_id, status,
+                    julianday('now') - julianday(started_at) AS age
+                FROM execution_history
+                WHERE command_id IS NOT NULL
+            )
+            GROUP BY command_id
+        ) f ON f.command_id = c.id";
+
+    /// Commands for the quick launcher. Without a query they are ordered by frecency, with
+    /// one only commands whose name or command line contains its characters in order are
+    /// kept, ranked by match quality and frecency.
+    #[instrument(skip(self))]
+    pub fn get_command_suggestions(
+        &self,
+        query: &str,
+        limit: i64,
+    ) -> Result<Vec<CommandSuggestion>> {
+        let commands = self.query_database(Self::FRECENCY_QUERY, [], Self::row_to_frecency)?;
+        let query: Vec<char> = query
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .flat_map(char::to_lowercase)
+            .collect();
+        debug!(
+            commands = commands.len(),
+            query_length = query.len(),
+            "Ranking suggestions"
+        );
+
+        let mut suggestions: Vec<CommandSuggestion> = commands
+            .into_iter()
+            .filter_map(|(command, frecency)| {
+                let (match_score, matched_indices) = if query.is_empty() {
+                    (0.0, Vec::new())
+                } else if let Some((score, indices)) = fuzzy_match(&query, &command.name) {
+                    (score as f64, indices)
+                } else {
+                    let command_line =
+                        format!("{} {}", command.command, command.arguments.join(" "));
+                    let (score, _) = fuzzy_match(&query, &command_line)?;
+                    (score as f64 * COMMAND_LINE_PENALTY, Vec::new())
+                };
+                Some(CommandSuggestion {
+                    score: match_score + FRECENCY_WEIGHT * frecency.ln_1p(),
+                    command,
+                    frecency,
+                    matched_indices,
+                })
+            })
+            .collect();
+
+        suggestions.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.command.position.cmp(&b.command.position))
+                .then_with(|| a.command.id.cmp(&b.command.id))
+        });
+        suggestions.truncate(usize::try_from(limit).unwrap_or(0));
+        Ok(suggestions)
+    }
+
+    fn row_to_frecency(row: &Row) -> rusqlite::Result<(Command, f64)> {
+        let command = Self::row_to_command(row)?;
+        let mut frecency: f64 = row.get("frecency")?;
+        if command.is_favorite {
+            frecency = frecency * FAVORITE_MULTIPLIER + FAVORITE_BONUS;
+        }
+        Ok((command, frecency))
+    }
+}
+
+/// Matches the lowercase `query` as a subsequence of `text`, trying every start position
+/// of its first character. Matches at word starts and runs of consecutive characters score
+/// higher, skipped characters lower.
+fn fuzzy_match(query: &[char], text: &str) -> Option<(i64, Vec<usize>)> {
+    let text: Vec<char> = text
+        .chars()
+        .map(|c| c.to_lowercase().next().unwrap_or(c))
+        .collect();
+    let first = *query.first()?;
+
+    (0..text.len())
+        .filter(|&start| text[start] == first)
+        .filter_map(|start| {
+            let mut indices = vec![start];
+            let mut position = start + 1;
+            for &c in &query[1..] {
+                let offset = text[position..].iter().position(|&t| t == c)?;
+                indices.push(position + offset);
+                position += offset + 1;
+            }
+            Some((match_score(&text, &indices), indices))
+        })
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+}
+
+fn match_score(text: &[char], indices: &[usize]) -> i64 {
+    let mut score = 0;
+    for (n, &index) in indices.iter().enumerate() {
+        score += MATCH_BONUS;
+        if index == 0 || !text[index - 1].is_alphanumeric() {
+            score += WORD_START_BONUS;
+        }
+        if n > 0 {
+            let gap = (index - indices[n - 1] - 1) as i64;
+            score += if gap == 0 {
+                CONSECUTIVE_BONUS
+            } else {
+                -GAP_PENALTY * gap
+            };
+        }
+    }
+    score
+}
@@ -0,0 +1,179 @@
+use super::{Database, Result, Tag, TagTarget};
+use crate::constants::TAGS_TABLE;
+use rusqlite::params;
+use tracing::{info, instrument};
+
+impl Database {
+    #[instrument(skip(self))]
+    pub fn create_tag(&self, name: &str, color: Option<&str>) -> Result<i64> {
+        self.validate_field_length("name", name, Self::MAX_NAME_LENGTH)?;
+
+        self.create(
+            TAGS_TABLE,
+            "INSERT INTO tags (name, color) VALUES (?1, ?2)",
+            params![name.trim(), color],
+        )
+    }
+
+    #[instrument(skip(self))]
+    pub fn get_tag(&self, id: i64) -> Result<Tag> {
+        self.query_row(
+            TAGS_TABLE,
+            id,
+            "SELECT * FROM tags WHERE id = ?1",
+            Self::row_to_tag,
+        )
+    }
+
+    #[instrument(skip(self))]
+    pub fn get_tags(&self) -> Result<Vec<Tag>> {
+        self.query_database("SELECT * FROM tags ORDER BY name", [], Self::row_to_tag)
+    }
+
+    #[instrument(skip(self))]
+    pub fn update_tag(&self, id: i64, name: &str, color: Option<&str>) -> Result<()> {
+        self.validate_field_length("name", name, Self::MAX_NAME_LENGTH)?;
+
+        self.execute_db(
+            TAGS_TABLE,
+            id,
+            "UPDATE tags SET name = ?1, color = ?2 WHERE id = ?3",
+            params![name.trim(), color, id],
+        )
+    }
+
+    /// Links to the tag are removed with it
+    #[instrument(skip(self))]
+    pub fn delete_tag(&self, id: i64) -> Result<()> {
+        self.execute_db(
+            TAGS_TABLE,
+            id,
+            "DELETE FROM tags WHERE id = ?1",
+            params![id],
+        )
+    }
+
+    /// Tags of a command, group or workflow, by name
+    #[instrument(skip(self))]
+    pub fn get_item_tags(&self, target: TagTarget, item_id: i64) -> Result<Vec<Tag>> {
+        let (link_table, link_column) = target.link();
+        self.query_database(
+            &format!(
+                "SELECT t.* FROM tags t
+                 JOIN {link_table} l ON l.tag_id = t.id
+                 WHERE l.{link_column} = ?1
+                 ORDER BY t.name"
+            ),
+            params![item_id],
+            Self::row_to_tag,
+        )
+    }
+
+    /// Adding a tag the item already has is a no-op
+    #[instrument(skip(self))]
+    pub fn add_item_tag(&self, target: TagTarget, item_id: i64, tag_id: i64) -> Result<()> {
+        self.validate_tag_link(target, item_id, &[tag_id])?;
+        let (link_table, link_column) = target.link();
+        self.execute_db_raw(
+            link_table,
+            &format!("INSERT OR IGNORE INTO {link_table} ({link_column}, tag_id) VALUES (?1, ?2)"),
+            params![item_id, tag_id],
+        )?;
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    pub fn remove_item_tag(&self, target: TagTarget, item_id: i64, tag_id: i64) -> Result<()> {
+        let (link_table, link_column) = target.link();
+        self.execute_db(
+            link_table,
+            tag_id,
+            &format!("DELETE FROM {link_table} WHERE {link_column} = ?1 AND tag_id = ?2"),
+            params![item_id, tag_id],
+        )
+    }
+
+    /// Replaces the tags of an item in one transaction
+    #[instrument(skip(self))]
+    pub fn set_item_tags(&self, target: TagTarget, item_id: i64, tag_ids: &[i64]) -> Result<()> {
+        self.validate_tag_link(target, item_id, tag_ids)?;
+        let (link_table, link_column) = target.link();
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            &format!("DELETE FROM {link_table} WHERE {link_column} = ?1"),
+            params![item_id],
+        )?;
+        for tag_id in tag_ids {
+            tx.execute(
+                &format!(
+                    "INSERT OR IGNORE INTO {link_table} ({link_column}, tag_id) VALUES (?1, ?2)"
+                ),
+                params![item_id, tag_id],
+            )?;
+        }
+        tx.commit()?;
+
+        info!(
+            item_id,
+            tags = tag_ids.len(),
+            table = link_table,
+            "Tags set"
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    pub fn get_tag_command_count(&self, id: i64) -> Result<i64> {
+        self.get_tag_item_count(id, TagTarget::Command)
+    }
+
+    #[instrument(skip(self))]
+    pub fn get_tag_group_count(&self, id: i64) -> Result<i64> {
+        self.get_tag_item_count(id, TagTarget::Group)
+    }
+
+    #[instrument(skip(self))]
+    pub fn get_tag_workflow_count(&self, id: i64) -> Result<i64> {
+        self.get_tag_item_count(id, TagTarget::Workflow)
+    }
+
+    fn get_tag_item_count(&self, id: i64, target: TagTarget) -> Result<i64> {
+        let (link_table, _) = target.link();
+        self.query_row(
+            TAGS_TABLE,
+            id,
+            &format!(
+                "SELECT COUNT(l.tag_id) FROM tags t
+                 LEFT JOIN {link_table} l ON l.tag_id = t.id
+                 WHERE t.id = ?1
+                 GROUP BY t.id"
+            ),
+            |row| row.get(0),
+        )
+    }
+
+    /// Both ends have to exist, the foreign key error wouldn't say which one is missing
+    fn validate_tag_link(&self, target: TagTarget, item_id: i64, tag_ids: &[i64]) -> Result<()> {
+        self.query_row(
+            target.table(),
+            item_id,
+            &format!("SELECT id FROM {} WHERE id = ?1", target.table()),
+            |row| row.get::<_, i64>(0),
+        )?;
+        for &tag_id in tag_ids {
+            self.get_tag(tag_id)?;
+        }
+        Ok(())
+    }
+
+    fn row_to_tag(row: &rusqlite::Row) -> rusqlite::Result<Tag> {
+        Ok(Tag {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            color: row.get("color")?,
+            created_at: row.get("created_at")?,
+        })
+    }
+}
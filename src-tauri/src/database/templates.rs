@@ -0,0 +1,814 @@
+use super::{
+    AppliedTemplate, CapturedVariable, CategoryFilter, Command, Database, DatabaseError, Group,
+    GroupFilter, GroupNode, Result, ShellDefinition, TagFilter, Template, TemplateCommand,
+    TemplateGroup, TemplateStructure, TemplateValidation, TemplateVariable, TemplateVariableType,
+};
+use crate::constants::TEMPLATES_TABLE;
+use regex::Regex;
+use rusqlite::{named_params, params, Connection};
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path};
+use std::sync::LazyLock;
+use tracing::{error, info, instrument};
+
+/// `\{{...}}` is escaped, it is left for the created command as `{{...}}`, e.g. a parameter or
+/// a docker `--format` string
+static VARIABLE_REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\\?\{\{([^{}]*)\}\}").expect("valid regex"));
+static VARIABLE_KEY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z0-9_]+$").expect("valid regex"));
+static SEMVER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d+)\.\d+\.\d+([-+][0-9A-Za-z.+-]+)?$").expect("valid regex"));
+
+/// Groups nested deeper are rejected
+const MAX_TEMPLATE_DEPTH: usize = 9;
+const SUPPORTED_MAJOR_VERSION: &str = "1";
+/// Written into captured templates
+const TEMPLATE_VERSION: &str = "1.0.0";
+
+/// Group with everything below it, built and validated before anything is inserted
+struct PlannedGroup {
+    group: Group,
+    commands: Vec<PlannedCommand>,
+    groups: Vec<PlannedGroup>,
+}
+
+struct PlannedCommand {
+    command: Command,
+    category: Option<String>,
+}
+
+impl Database {
+    #[instrument(skip(self, template), fields(name = %template.name))]
+    pub fn create_template(&self, template: &Template) -> Result<i64> {
+        self.validate_template(template)?;
+
+        self.create(
+            TEMPLATES_TABLE,
+            "INSERT INTO templates (name, description, author, structure)
+             VALUES (:name, :description, :author, :structure)",
+            named_params! {
+                ":name": template.name,
+                ":description": template.description,
+                ":author": template.author,
+                ":structure": template.structure,
+            },
+        )
+    }
+
+    #[instrument(skip(self))]
+    pub fn get_template(&self, id: i64) -> Result<Template> {
+        self.query_row(
+            TEMPLATES_TABLE,
+            id,
+            "SELECT * FROM templates WHERE id = ?1",
+            Self::row_to_template,
+        )
+    }
+
+    #[instrument(skip(self))]
+    pub fn get_templates(&self) -> Result<Vec<Template>> {
+        self.query_database(
+            "SELECT * FROM templates ORDER BY name",
+            [],
+            Self::row_to_template,
+        )
+    }
+
+    #[instrument(skip(self, template), fields(id = template.id))]
+    pub fn update_template(&self, template: &Template) -> Result<()> {
+        self.validate_template(template)?;
+
+        self.execute_db(
+            TEMPLATES_TABLE,
+            template.id,
+            "UPDATE templates SET name = :name, description = :description, author = :author,
+             structure = :structure WHERE id = :id",
+            named_params! {
+                ":name": template.name,
+                ":description": template.description,
+                ":author": template.author,
+                ":structure": template.structure,
+                ":id": template.id,
+            },
+        )
+    }
+
+    #[instrument(skip(self))]
+    pub fn delete_template(&self, id: i64) -> Result<()> {
+        self.execute_db(
+            TEMPLATES_TABLE,
+            id,
+            "DELETE FROM templates WHERE id = ?1",
+            params![id],
+        )
+    }
+
+    /// Checks a structure against docs/09-template.md, every problem is reported
+    #[instrument(skip(self, structure))]
+    pub fn validate_template_structure(&self, structure: &str) -> TemplateValidation {
+        let errors = match serde_json::from_str::<TemplateStructure>(structure) {
+            Ok(structure) => self.template_errors(&structure),
+            Err(e) => vec![format!("Invalid template structure: {e}")],
+        };
+        TemplateValidation {
+            valid: errors.is_empty(),
+            errors,
+        }
+    }
+
+    /// Creates the groups and commands of a template with `values` substituted for its
+    /// variables, all or nothing. The top level groups and commands go into `parent_group_id`.
+    #[instrument(skip(self, values))]
+    pub fn apply_template(
+        &self,
+        id: i64,
+        values: &HashMap<String, String>,
+        parent_group_id: Option<i64>,
+    ) -> Result<AppliedTemplate> {
+        let template = self.get_template(id)?;
+        let structure = self.parse_template_structure(&template.structure)?;
+        let values = Self::template_values(&structure.variables, values)?;
+        let structure = Self::substitute_variables(&structure, &values);
+
+        // substituted values are checked like the template itself, e.g. for a `rm -rf` in one
+        let errors = self.template_errors(&structure);
+        if !errors.is_empty() {
+            return Err(DatabaseError::InvalidData {
+                field: "variables",
+                reason: errors.join("; "),
+            });
+        }
+        let structure = Self::map_template_strings(structure, &unescape_references);
+
+        let shells = self.get_shells()?;
+        let groups = structure
+            .groups
+            .iter()
+            .map(|group| self.plan_group(group, &shells))
+            .collect::<Result<Vec<_>>>()?;
+        let commands = structure
+            .commands
+            .iter()
+            .map(|command| self.plan_command(command, &shells))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut applied = AppliedTemplate::default();
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        for group in &groups {
+            Self::insert_planned_group(&tx, group, parent_group_id, &mut applied)?;
+        }
+        for command in &commands {
+            let id = Self::insert_planned_command(&tx, command, parent_group_id)?;
+            applied.command_ids.push(id);
+        }
+        tx.commit()?;
+
+        info!(
+            id,
+            groups = applied.group_ids.len(),
+            commands = applied.command_ids.len(),
+            "Template applied"
+        );
+        Ok(applied)
+    }
+
+    /// Template of a group with its subgroups and commands, to be saved with `create_template`.
+    /// Each variable's value is replaced by `{{key}}` wherever it occurs, `{{...}}` already in
+    /// the group is escaped. Only what the template format knows is captured, e.g. secrets,
+    /// env files and limits are left out.
+    #[instrument(skip(self, variables))]
+    pub fn capture_group_template(
+        &self,
+        group_id: i64,
+        name: &str,
+        variables: &[CapturedVariable],
+    ) -> Result<Template> {
+        if let Some(variable) = variables.iter().find(|v| v.value.is_empty()) {
+            return Err(DatabaseError::InvalidData {
+                field: "variables",
+                reason: format!("Variable '{}' has no value to replace", variable.key),
+            });
+        }
+
+        let tree = self.get_group_tree(group_id)?;
+        let categories: HashMap<i64, String> = self
+            .get_categories()?
+            .into_iter()
+            .map(|category| (category.id, category.name))
+            .collect();
+        let group = self.capture_group(&tree, &categories)?;
+
+        // longer values first, so a subdirectory isn't cut apart by its parent directory
+        let mut variables: Vec<&CapturedVariable> = variables.iter().collect();
+        variables.sort_by_key(|v| std::cmp::Reverse(v.value.len()));
+        let structure = TemplateStructure {
+            schema: None,
+            name: name.to_string(),
+            version: TEMPLATE_VERSION.to_string(),
+            description: tree.group.description.clone(),
+            author: None,
+            variables: variables
+                .iter()
+                .map(|v| TemplateVariable {
+                    key: v.key.clone(),
+                    label: v.label.clone().unwrap_or_else(|| v.key.clone()),
+                    kind: v.kind,
+                    default: None,
+                    required: true,
+                    description: None,
+                })
+                .collect(),
+            groups: vec![group],
+            commands: Vec::new(),
+        };
+        let structure = Self::map_template_strings(structure, &|string| {
+            variables
+                .iter()
+                .fold(escape_references(string), |string, v| {
+                    parameterize(&string, &v.value, &v.key)
+                })
+        });
+
+        let template = Template {
+            id: 0,
+            name: name.to_string(),
+            description: structure.description.clone(),
+            author: None,
+            structure: serde_json::to_string_pretty(&structure)?,
+            created_at: String::new(),
+            updated_at: String::new(),
+        };
+        self.validate_template(&template)?;
+        info!(group_id, "Group captured as template");
+        Ok(template)
+    }
+
+    fn capture_group(
+        &self,
+        node: &GroupNode,
+        categories: &HashMap<i64, String>,
+    ) -> Result<TemplateGroup> {
+        let commands = self
+            .get_commands(
+                GroupFilter::Group(node.group.id),
+                CategoryFilter::All,
+                TagFilter::All,
+                false,
+                None,
+                None,
+            )?
+            .into_iter()
+            .map(|command| TemplateCommand {
+                category: command
+                    .category_id
+                    .and_then(|id| categories.get(&id).cloned()),
+                name: command.item.name,
+                command: command.item.command,
+                arguments: command.item.arguments,
+                description: command.item.description,
+                working_directory: command.item.working_directory,
+                env_vars: command.item.env_vars,
+                parameters: command.item.parameters,
+                shell: command.item.shell,
+                shell_mode: command.item.shell_mode,
+                run_elevated: command.item.run_elevated,
+            })
+            .collect();
+
+        Ok(TemplateGroup {
+            name: node.group.name.clone(),
+            description: node.group.description.clone(),
+            default_working_directory: node.group.working_directory.clone(),
+            default_env_vars: node.group.env_vars.clone(),
+            default_shell: node.group.shell.clone(),
+            commands,
+            groups: node
+                .children
+                .iter()
+                .map(|child| self.capture_group(child, categories))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    fn validate_template(&self, template: &Template) -> Result<()> {
+        self.validate_field_length("name", &template.name, Self::MAX_NAME_LENGTH)?;
+        if let Some(description) = &template.description {
+            self.validate_field_length("description", description, Self::MAX_DESCRIPTION_LENGTH)?;
+        }
+        self.parse_template_structure(&template.structure)?;
+        Ok(())
+    }
+
+    fn parse_template_structure(&self, structure: &str) -> Result<TemplateStructure> {
+        let validation = self.validate_template_structure(structure);
+        if !validation.valid {
+            error!(errors = ?validation.errors, "Invalid template structure");
+            return Err(DatabaseError::InvalidData {
+                field: "structure",
+                reason: validation.errors.join("; "),
+            });
+        }
+        Ok(serde_json::from_str(structure)?)
+    }
+
+    fn template_errors(&self, structure: &TemplateStructure) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        match SEMVER.captures(&structure.version) {
+            None => errors.push(format!(
+                "Version '{}' is not a semantic version like 1.0.0",
+                structure.version
+            )),
+            Some(captures) if &captures[1] != SUPPORTED_MAJOR_VERSION => errors.push(format!(
+                "Version '{}' is not supported, only {SUPPORTED_MAJOR_VERSION}.x templates are",
+                structure.version
+            )),
+            Some(_) => {}
+        }
+
+        let mut keys = HashSet::new();
+        for variable in &structure.variables {
+            if !VARIABLE_KEY.is_match(&variable.key) {
+                errors.push(format!(
+                    "Variable '{}' has invalid key (use letters, digits and underscores)",
+                    variable.key
+                ));
+            }
+            if !keys.insert(variable.key.as_str()) {
+                errors.push(format!("Variable '{}' is defined twice", variable.key));
+            }
+            if let Some(default) = &variable.default {
+                if let Err(reason) = Self::check_variable_value(variable, default) {
+                    errors.push(format!("Default of variable '{}' {reason}", variable.key));
+                }
+            }
+        }
+
+        for group in &structure.groups {
+            self.group_errors(group, 1, &keys, &mut errors);
+        }
+        for command in &structure.commands {
+            self.command_errors(command, &keys, &mut errors);
+        }
+        errors
+    }
+
+    fn group_errors(
+        &self,
+        group: &TemplateGroup,
+        depth: usize,
+        keys: &HashSet<&str>,
+        errors: &mut Vec<String>,
+    ) {
+        if depth > MAX_TEMPLATE_DEPTH {
+            errors.push(format!(
+                "Group '{}' is nested deeper than {MAX_TEMPLATE_DEPTH} levels",
+                group.name
+            ));
+            return;
+        }
+
+        let strings = [&group.name]
+            .into_iter()
+            .chain(&group.description)
+            .chain(&group.default_working_directory)
+            .chain(&group.default_shell)
+            .chain(
+                group
+                    .default_env_vars
+                    .iter()
+                    .flat_map(|vars| vars.iter())
+                    .flat_map(|(k, v)| [k, v]),
+            );
+        Self::undefined_variables("Group", &group.name, strings, keys, errors);
+
+        for command in &group.commands {
+            self.command_errors(command, keys, errors);
+        }
+        for child in &group.groups {
+            self.group_errors(child, depth + 1, keys, errors);
+        }
+    }
+
+    fn command_errors(
+        &self,
+        command: &TemplateCommand,
+        keys: &HashSet<&str>,
+        errors: &mut Vec<String>,
+    ) {
+        let strings = [&command.name, &command.command]
+            .into_iter()
+            .chain(&command.arguments)
+            .chain(&command.description)
+            .chain(&command.working_directory)
+            .chain(&command.category)
+            .chain(&command.shell)
+            .chain(
+                command
+                    .env_vars
+                    .iter()
+                    .flat_map(|vars| vars.iter())
+                    .flat_map(|(k, v)| [k, v]),
+            );
+        Self::undefined_variables("Command", &command.name, strings, keys, errors);
+
+        let command_line = [&command.command]
+            .into_iter()
+            .chain(&command.arguments)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ");
+        match self.explain_command(&command_line) {
+            Ok(_) if command.run_elevated => errors.push(format!(
+                "Command '{}' runs with elevated privileges: 'run_elevated' is set",
+                command.name
+            )),
+            Ok(explanation) if explanation.is_privileged => errors.push(format!(
+                "Command '{}' runs with elevated privileges: '{command_line}'",
+                command.name
+            )),
+            Ok(explanation) if explanation.is_destructive => errors.push(format!(
+                "Command '{}' is a dangerous command: '{command_line}'",
+                command.name
+            )),
+            Ok(_) => {}
+            Err(e) => errors.push(format!("Command '{}' can't be checked: {e}", command.name)),
+        }
+    }
+
+    fn undefined_variables<'a>(
+        entity: &str,
+        name: &str,
+        strings: impl Iterator<Item = &'a String>,
+        keys: &HashSet<&str>,
+        errors: &mut Vec<String>,
+    ) {
+        let mut reported = HashSet::new();
+        for string in strings {
+            for captures in VARIABLE_REFERENCE.captures_iter(string) {
+                if is_escaped(&captures) {
+                    continue;
+                }
+                let key = captures[1].trim();
+                if !keys.contains(key) && reported.insert(key.to_string()) {
+                    errors.push(format!(
+                        "{entity} '{name}' uses undefined variable '{{{{{key}}}}}'"
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Supplied values win over defaults, missing optional variables become empty
+    fn template_values(
+        variables: &[TemplateVariable],
+        supplied: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>> {
+        let mut values = HashMap::new();
+        for variable in variables {
+            let value = supplied
+                .get(&variable.key)
+                .filter(|value| !value.is_empty())
+                .or(variable.default.as_ref());
+            let value = match value {
+                Some(value) => {
+                    Self::check_variable_value(variable, value).map_err(|reason| {
+                        DatabaseError::InvalidData {
+                            field: "variables",
+                            reason: format!("Variable '{}' {reason}", variable.key),
+                        }
+                    })?;
+                    value.clone()
+                }
+                None if variable.required => {
+                    return Err(DatabaseError::InvalidData {
+                        field: "variables",
+                        reason: format!("Variable '{}' is required", variable.key),
+                    });
+                }
+                None => String::new(),
+            };
+            values.insert(variable.key.clone(), value);
+        }
+        Ok(values)
+    }
+
+    fn check_variable_value(
+        variable: &TemplateVariable,
+        value: &str,
+    ) -> std::result::Result<(), String> {
+        match variable.kind {
+            TemplateVariableType::String => Ok(()),
+            TemplateVariableType::Number => value
+                .trim()
+                .parse::<f64>()
+                .map(|_| ())
+                .map_err(|_| format!("must be a number, got '{value}'")),
+            TemplateVariableType::Path => {
+                let expanded = match value.strip_prefix("~/") {
+                    Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+                    None => value.into(),
+                };
+                if expanded.exists() {
+                    Ok(())
+                } else {
+                    Err(format!("must be an existing path, '{value}' doesn't exist"))
+                }
+            }
+        }
+    }
+
+    fn substitute_variables(
+        structure: &TemplateStructure,
+        values: &HashMap<String, String>,
+    ) -> TemplateStructure {
+        Self::map_template_strings(structure.clone(), &|string| {
+            VARIABLE_REFERENCE
+                .replace_all(string, |captures: &regex::Captures| {
+                    let key = captures[1].trim();
+                    values
+                        .get(key)
+                        .filter(|_| !is_escaped(captures))
+                        .cloned()
+                        .unwrap_or_else(|| captures[0].to_string())
+                })
+                .into_owned()
+        })
+    }
+
+    /// Applies `f` to every string of the groups and commands, env var names included
+    fn map_template_strings(
+        mut structure: TemplateStructure,
+        f: &impl Fn(&str) -> String,
+    ) -> TemplateStructure {
+        fn map_env(vars: &mut Option<HashMap<String, String>>, f: &impl Fn(&str) -> String) {
+            if let Some(vars) = vars {
+                *vars = vars.iter().map(|(k, v)| (f(k), f(v))).collect();
+            }
+        }
+        fn map_command(command: &mut TemplateCommand, f: &impl Fn(&str) -> String) {
+            command.name = f(&command.name);
+            command.command = f(&command.command);
+            command.arguments.iter_mut().for_each(|arg| *arg = f(arg));
+            for field in [
+                &mut command.description,
+                &mut command.working_directory,
+                &mut command.category,
+                &mut command.shell,
+            ] {
+                *field = field.as_deref().map(f);
+            }
+            map_env(&mut command.env_vars, f);
+        }
+        fn map_group(group: &mut TemplateGroup, f: &impl Fn(&str) -> String) {
+            group.name = f(&group.name);
+            for field in [
+                &mut group.description,
+                &mut group.default_working_directory,
+                &mut group.default_shell,
+            ] {
+                *field = field.as_deref().map(f);
+            }
+            map_env(&mut group.default_env_vars, f);
+            group.commands.iter_mut().for_each(|c| map_command(c, f));
+            group.groups.iter_mut().for_each(|g| map_group(g, f));
+        }
+
+        structure.groups.iter_mut().for_each(|g| map_group(g, f));
+        structure
+            .commands
+            .iter_mut()
+            .for_each(|c| map_command(c, f));
+        structure
+    }
+
+    fn plan_group(
+        &self,
+        template: &TemplateGroup,
+        shells: &[ShellDefinition],
+    ) -> Result<PlannedGroup> {
+        let shell = template
+            .default_shell
+            .as_ref()
+            .map(|shell| {
+                Self::registry_shell(shells, shell, "default_shell", "Group", &template.name)
+            })
+            .transpose()?;
+        if let Some(dir) = &template.default_working_directory {
+            Self::check_template_path(&template.name, dir)?;
+        }
+
+        let group = Group {
+            name: template.name.clone(),
+            description: template.description.clone(),
+            working_directory: template.default_working_directory.clone(),
+            env_vars: template.default_env_vars.clone(),
+            shell,
+            ..Default::default()
+        };
+        self.validate_group(&group)?;
+
+        Ok(PlannedGroup {
+            group,
+            commands: template
+                .commands
+                .iter()
+                .map(|command| self.plan_command(command, shells))
+                .collect::<Result<_>>()?,
+            groups: template
+                .groups
+                .iter()
+                .map(|child| self.plan_group(child, shells))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    fn plan_command(
+        &self,
+        template: &TemplateCommand,
+        shells: &[ShellDefinition],
+    ) -> Result<PlannedCommand> {
+        if let Some(dir) = &template.working_directory {
+            Self::check_template_path(&template.name, dir)?;
+        }
+        let shell = template
+            .shell
+            .as_ref()
+            .map(|shell| Self::registry_shell(shells, shell, "shell", "Command", &template.name))
+            .transpose()?;
+        let command = Command {
+            name: template.name.clone(),
+            command: template.command.clone(),
+            arguments: template.arguments.clone(),
+            description: template.description.clone(),
+            working_directory: template.working_directory.clone(),
+            env_vars: template.env_vars.clone(),
+            parameters: template.parameters.clone(),
+            shell,
+            shell_mode: template.shell_mode,
+            run_elevated: template.run_elevated,
+            ..Default::default()
+        };
+        self.validate_command(&command)?;
+
+        Ok(PlannedCommand {
+            command,
+            category: template.category.clone().filter(|c| !c.trim().is_empty()),
+        })
+    }
+
+    /// Registry name of a shell a template refers to by name or path
+    fn registry_shell(
+        shells: &[ShellDefinition],
+        shell: &str,
+        field: &'static str,
+        entity: &str,
+        name: &str,
+    ) -> Result<String> {
+        shells
+            .iter()
+            .find(|s| s.name == shell || s.path == shell)
+            .map(|s| s.name.clone())
+            .ok_or_else(|| DatabaseError::InvalidData {
+                field,
+                reason: format!(
+                    "{entity} '{name}' uses shell '{shell}' which is not in the shell registry"
+                ),
+            })
+    }
+
+    /// Substituted paths can't climb out of the directory they were given
+    fn check_template_path(name: &str, path: &str) -> Result<()> {
+        if Path::new(path)
+            .components()
+            .any(|c| c == Component::ParentDir)
+        {
+            return Err(DatabaseError::InvalidData {
+                field: "working_directory",
+                reason: format!("'{name}' has working directory '{path}' containing '..'"),
+            });
+        }
+        Ok(())
+    }
+
+    fn insert_planned_group(
+        conn: &Connection,
+        planned: &PlannedGroup,
+        parent_group_id: Option<i64>,
+        applied: &mut AppliedTemplate,
+    ) -> Result<()> {
+        let group = Group {
+            parent_group_id,
+            ..planned.group.clone()
+        };
+        let id = Self::insert_group(conn, &group, &[])?;
+        applied.group_ids.push(id);
+
+        for command in &planned.commands {
+            let command_id = Self::insert_planned_command(conn, command, Some(id))?;
+            applied.command_ids.push(command_id);
+        }
+        for child in &planned.groups {
+            Self::insert_planned_group(conn, child, Some(id), applied)?;
+        }
+        Ok(())
+    }
+
+    fn insert_planned_command(
+        conn: &Connection,
+        planned: &PlannedCommand,
+        group_id: Option<i64>,
+    ) -> Result<i64> {
+        let category_id = planned
+            .category
+            .as_ref()
+            .map(|name| Self::category_id_by_name(conn, name))
+            .transpose()?;
+        let command = Command {
+            group_id,
+            category_id,
+            ..planned.command.clone()
+        };
+        Self::insert_command(conn, &command, &[])
+    }
+
+    fn category_id_by_name(conn: &Connection, name: &str) -> Result<i64> {
+        conn.execute(
+            "INSERT INTO categories (name) VALUES (?1) ON CONFLICT(name) DO NOTHING",
+            params![name],
+        )?;
+        Ok(conn.query_row(
+            "SELECT id FROM categories WHERE name = ?1",
+            params![name],
+            |row| row.get(0),
+        )?)
+    }
+
+    fn row_to_template(row: &rusqlite::Row) -> rusqlite::Result<Template> {
+        Ok(Template {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            description: row.get("description")?,
+            author: row.get("author")?,
+            structure: row.get("structure")?,
+            created_at: row.get("created_at")?,
+            updated_at: row.get("updated_at")?,
+        })
+    }
+}
+
+/// Replaces `value` by `{{key}}` where it isn't part of a longer name: `/home/me/app` matches in
+/// `/home/me/app/src` but not in `/home/me/app2`
+fn parameterize(string: &str, value: &str, key: &str) -> String {
+    let is_name_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.');
+    let extends_start = value.starts_with(is_name_char);
+    let extends_end = value.ends_with(is_name_char);
+
+    let mut result = String::with_capacity(string.len());
+    let mut copied = 0;
+    for (start, _) in string.match_indices(value) {
+        let end = start + value.len();
+        let before = string[..start].chars().next_back();
+        let after = string[end..].chars().next();
+        if start < copied
+            || (extends_start && before.is_some_and(is_name_char))
+            || (extends_end && after.is_some_and(is_name_char))
+        {
+            continue;
+        }
+        result.push_str(&string[copied..start]);
+        result.push_str(&format!("{{{{{key}}}}}"));
+        copied = end;
+    }
+    result.push_str(&string[copied..]);
+    result
+}
+
+fn is_escaped(captures: &regex::Captures) -> bool {
+    captures[0].starts_with('\\')
+}
+
+/// Escapes every `{{...}}`, an escaped one gets a second backslash that `unescape_references`
+/// keeps
+fn escape_references(string: &str) -> String {
+    VARIABLE_REFERENCE
+        .replace_all(string, |captures: &regex::Captures| {
+            format!("\\{}", &captures[0])
+        })
+        .into_owned()
+}
+
+/// `\{{...}}` becomes `{{...}}` once the variables are substituted
+fn unescape_references(string: &str) -> String {
+    VARIABLE_REFERENCE
+        .replace_all(string, |captures: &regex::Captures| {
+            let reference = &captures[0];
+            reference
+                .strip_prefix('\\')
+                .unwrap_or(reference)
+                .to_string()
+        })
+        .into_owned()
+}
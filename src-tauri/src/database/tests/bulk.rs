@@ -0,0 +1,218 @@
+use super::*;
+
+#[test]
+fn test_bulk_move_commands_appends_in_given_order() {
+    let test_db = TestDb::setup_test_db();
+    let target_id = test_db.create_test_group("Target");
+    let existing = test_db.create_test_command("Existing", "true", Some(target_id));
+    let first = test_db.create_test_command("First", "true", None);
+    let second = test_db.create_test_command("Second", "true", None);
+
+    let result = test_db
+        .db
+        .bulk_update_commands(
+            &[second, first, existing, second],
+            &BulkAction::Move {
+                group_id: Some(target_id),
+            },
+        )
+        .unwrap();
+    assert_eq!(result.changed, vec![second, first]);
+    assert_eq!(result.unchanged, vec![existing]);
+
+    let ids: Vec<i64> = test_db
+        .db
+        .get_commands(
+            GroupFilter::Group(target_id),
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+            None,
+            None,
+        )
+        .unwrap()
+        .iter()
+        .map(|c| c.id)
+        .collect();
+    assert_eq!(ids, vec![existing, second, first]);
+}
+
+#[test]
+fn test_bulk_update_is_all_or_nothing() {
+    let test_db = TestDb::setup_test_db();
+    let id = test_db.create_test_command("Build", "make", None);
+
+    assert!(matches!(
+        test_db
+            .db
+            .bulk_update_commands(&[id, 999], &BulkAction::Delete),
+        Err(DatabaseError::NotFound { .. })
+    ));
+    assert!(matches!(
+        test_db.db.bulk_update_commands(
+            &[id],
+            &BulkAction::SetCategory {
+                category_id: Some(999)
+            }
+        ),
+        Err(DatabaseError::NotFound { .. })
+    ));
+    assert!(matches!(
+        test_db.db.bulk_update_commands(
+            &[id],
+            &BulkAction::SetEnvVar {
+                key: "BAD KEY".to_string(),
+                value: Some("1".to_string())
+            }
+        ),
+        Err(DatabaseError::InvalidData {
+            field: "env_vars",
+            ..
+        })
+    ));
+    assert!(test_db.db.get_command(id).is_ok());
+}
+
+#[test]
+fn test_bulk_set_fields_on_commands() {
+    let test_db = TestDb::setup_test_db();
+    let category_id = test_db.create_test_category("Build");
+    let plain = test_db.create_test_command("Plain", "make", None);
+    let with_env = test_db.save_command_to_db(
+        &CommandBuilder::new("With env", "make")
+            .with_env("CC", "clang")
+            .build(),
+    );
+    let ids = [plain, with_env];
+
+    let result = test_db
+        .db
+        .bulk_update_commands(
+            &ids,
+            &BulkAction::SetCategory {
+                category_id: Some(category_id),
+            },
+        )
+        .unwrap();
+    assert_eq!(result.changed, ids);
+    test_db
+        .db
+        .bulk_update_commands(&ids, &BulkAction::SetFavorite { is_favorite: true })
+        .unwrap();
+    test_db
+        .db
+        .bulk_update_commands(
+            &ids,
+            &BulkAction::SetWorkingDirectory {
+                working_directory: Some("/srv/app".to_string()),
+            },
+        )
+        .unwrap();
+    let result = test_db
+        .db
+        .bulk_update_commands(
+            &ids,
+            &BulkAction::SetEnvVar {
+                key: "CC".to_string(),
+                value: Some("clang".to_string()),
+            },
+        )
+        .unwrap();
+    assert_eq!(result.changed, vec![plain]);
+    assert_eq!(result.unchanged, vec![with_env]);
+
+    for id in ids {
+        let command = test_db.db.get_command(id).unwrap();
+        assert_eq!(command.category_id, Some(category_id));
+        assert!(command.is_favorite);
+        assert_eq!(command.working_directory.as_deref(), Some("/srv/app"));
+        assert_eq!(command.env_vars.unwrap()["CC"], "clang");
+    }
+
+    test_db
+        .db
+        .bulk_update_commands(
+            &ids,
+            &BulkAction::SetEnvVar {
+                key: "CC".to_string(),
+                value: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(test_db.db.get_command(plain).unwrap().env_vars, None);
+}
+
+#[test]
+fn test_bulk_env_var_must_not_shadow_secret() {
+    let test_db = TestDb::setup_test_db();
+    let plain = test_db.create_test_command("Plain", "true", None);
+    let secret = test_db.save_command_to_db(
+        &CommandBuilder::new("Secret", "true")
+            .with_secret("TOKEN", "s3cr3t")
+            .build(),
+    );
+
+    assert!(matches!(
+        test_db.db.bulk_update_commands(
+            &[plain, secret],
+            &BulkAction::SetEnvVar {
+                key: "TOKEN".to_string(),
+                value: Some("plain".to_string())
+            }
+        ),
+        Err(DatabaseError::InvalidData { .. })
+    ));
+    assert_eq!(test_db.db.get_command(plain).unwrap().env_vars, None);
+}
+
+#[test]
+fn test_bulk_move_groups_rejects_cycles() {
+    let test_db = TestDb::setup_test_db();
+    let parent_id = test_db.create_test_group("Parent");
+    let child_id =
+        test_db.save_group_to_db(&GroupBuilder::new("Child").with_parent(parent_id).build());
+    let other_id = test_db.create_test_group("Other");
+
+    assert!(matches!(
+        test_db.db.bulk_update_groups(
+            &[other_id, parent_id],
+            &BulkAction::Move {
+                group_id: Some(child_id)
+            }
+        ),
+        Err(DatabaseError::CircularReference { .. })
+    ));
+    assert_eq!(
+        test_db.db.get_group(other_id).unwrap().parent_group_id,
+        None
+    );
+
+    let result = test_db
+        .db
+        .bulk_update_groups(&[child_id], &BulkAction::Move { group_id: None })
+        .unwrap();
+    assert_eq!(result.changed, vec![child_id]);
+    assert_eq!(
+        test_db.db.get_group(child_id).unwrap().parent_group_id,
+        None
+    );
+}
+
+#[test]
+fn test_bulk_delete_groups_with_selected_descendants() {
+    let test_db = TestDb::setup_test_db();
+    let parent_id = test_db.create_test_group("Parent");
+    let child_id =
+        test_db.save_group_to_db(&GroupBuilder::new("Child").with_parent(parent_id).build());
+    let command_id = test_db.create_test_command("Inside", "true", Some(child_id));
+    let kept_id = test_db.create_test_group("Kept");
+
+    let result = test_db
+        .db
+        .bulk_update_groups(&[parent_id, child_id], &BulkAction::Delete)
+        .unwrap();
+    assert_eq!(result.changed, vec![parent_id, child_id]);
+    assert!(test_db.db.get_group(child_id).is_err());
+    assert!(test_db.db.get_command(command_id).is_err());
+    assert!(test_db.db.get_group(kept_id).is_ok());
+}
@@ -1,5 +1,5 @@
 use super::*;
-use crate::constants::COMMANDS_TABLE;
+use crate::constants::{COMMANDS_TABLE, SECRET_MASK};
 use rusqlite::params;
 use std::collections::HashMap;
 
@@ -179,6 +179,364 @@ fn test_create_command_invalid_env_var_key() {
     ));
 }
 
+#[test]
+fn test_create_command_resource_limits_round_trip() {
+    let test_db = TestDb::setup_test_db();
+    let limits = ResourceLimits {
+        max_memory_bytes: Some(512 * 1024 * 1024),
+        max_open_files: Some(256),
+        nice: Some(10),
+        io_priority: Some(IoPriority {
+            class: IoPriorityClass::Idle,
+            level: 7,
+        }),
+        ..Default::default()
+    };
+    let command = CommandBuilder::new("Test", "pwd")
+        .with_resource_limits(limits.clone())
+        .build();
+
+    let id = test_db.db.create_command(&command).unwrap();
+    let retrieved = test_db.db.get_command(id).unwrap();
+    assert_eq!(retrieved.resource_limits, Some(limits));
+}
+
+#[test]
+fn test_create_command_invalid_resource_limits() {
+    let test_db = TestDb::setup_test_db();
+    let invalid = [
+        ResourceLimits {
+            nice: Some(20),
+            ..Default::default()
+        },
+        ResourceLimits {
+            max_open_files: Some(0),
+            ..Default::default()
+        },
+        ResourceLimits {
+            io_priority: Some(IoPriority {
+                class: IoPriorityClass::BestEffort,
+                level: 8,
+            }),
+            ..Default::default()
+        },
+    ];
+
+    for limits in invalid {
+        let command = CommandBuilder::new("Test", "pwd")
+            .with_resource_limits(limits)
+            .build();
+        let result = test_db.db.create_command(&command);
+        assert!(matches!(
+            result,
+            Err(DatabaseError::InvalidData {
+                field: "resource_limits",
+                ..
+            })
+        ));
+    }
+}
+
+#[test]
+fn test_create_command_env_inheritance_round_trip() {
+    let test_db = TestDb::setup_test_db();
+    let inheritance = EnvInheritance::Allowlist {
+        variables: vec!["PATH".to_string(), "LANG".to_string()],
+    };
+    let command = CommandBuilder::new("Test", "pwd")
+        .with_env_inheritance(inheritance.clone())
+        .with_env_unset(vec!["DISPLAY"])
+        .build();
+
+    let id = test_db.db.create_command(&command).unwrap();
+    let retrieved = test_db.db.get_command(id).unwrap();
+    assert_eq!(retrieved.env_inheritance, Some(inheritance));
+    assert_eq!(retrieved.env_unset, Some(vec!["DISPLAY".to_string()]));
+}
+
+#[test]
+fn test_create_command_invalid_env_inheritance_names() {
+    let test_db = TestDb::setup_test_db();
+    let command = CommandBuilder::new("Test", "pwd")
+        .with_env_inheritance(EnvInheritance::Allowlist {
+            variables: vec!["NOT VALID".to_string()],
+        })
+        .build();
+    assert!(matches!(
+        test_db.db.create_command(&command),
+        Err(DatabaseError::InvalidData {
+            field: "env_inheritance",
+            ..
+        })
+    ));
+
+    let command = CommandBuilder::new("Test", "pwd")
+        .with_env_unset(vec!["BAD=KEY"])
+        .build();
+    assert!(matches!(
+        test_db.db.create_command(&command),
+        Err(DatabaseError::InvalidData {
+            field: "env_unset",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_create_command_env_files_round_trip() {
+    let test_db = TestDb::setup_test_db();
+    let command = CommandBuilder::new("Test", "pwd")
+        .with_env_files(vec![".env", "config/.env.local"])
+        .build();
+
+    let id = test_db.db.create_command(&command).unwrap();
+    let mut retrieved = test_db.db.get_command(id).unwrap();
+    assert_eq!(retrieved.env_files, command.env_files);
+
+    retrieved.env_files = Some(vec![" ".to_string()]);
+    assert!(matches!(
+        test_db.db.update_command(&retrieved),
+        Err(DatabaseError::InvalidData {
+            field: "env_files",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_create_command_secret_env_vars_are_masked() {
+    let test_db = TestDb::setup_test_db();
+    let command = CommandBuilder::new("Deploy", "deploy")
+        .with_secret("API_TOKEN", "s3cr3t")
+        .build();
+
+    let id = test_db.db.create_command(&command).unwrap();
+    let retrieved = test_db.db.get_command(id).unwrap();
+    let secrets = retrieved.secret_env_vars.as_ref().unwrap();
+    assert!(secrets["API_TOKEN"].is_masked());
+    assert!(!format!("{command:?}").contains("s3cr3t"));
+
+    let stored: String = test_db
+        .db
+        .conn()
+        .unwrap()
+        .query_row(
+            "SELECT secret_env_names FROM commands WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(stored, r#"["API_TOKEN"]"#);
+    assert_eq!(
+        test_db.db.get_secret_env_vars(COMMANDS_TABLE, id).unwrap()["API_TOKEN"],
+        "s3cr3t"
+    );
+}
+
+#[test]
+fn test_update_command_masked_secret_keeps_stored_value() {
+    let test_db = TestDb::setup_test_db();
+    let id = test_db.save_command_to_db(
+        &CommandBuilder::new("Deploy", "deploy")
+            .with_secret("API_TOKEN", "s3cr3t")
+            .with_secret("OLD_TOKEN", "old")
+            .build(),
+    );
+
+    let mut command = test_db.db.get_command(id).unwrap();
+    let secrets = command.secret_env_vars.as_mut().unwrap();
+    secrets.remove("OLD_TOKEN");
+    secrets.insert("DB_PASSWORD".to_string(), SecretValue::from("hunter2"));
+    test_db.db.update_command(&command).unwrap();
+
+    let stored = test_db.db.get_secret_env_vars(COMMANDS_TABLE, id).unwrap();
+    assert_eq!(
+        stored,
+        HashMap::from([
+            ("API_TOKEN".to_string(), "s3cr3t".to_string()),
+            ("DB_PASSWORD".to_string(), "hunter2".to_string()),
+        ])
+    );
+
+    test_db.db.delete_command(id).unwrap();
+    assert!(test_db
+        .db
+        .get_secret_env_vars(COMMANDS_TABLE, id)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_secret_values_are_encrypted_at_rest() {
+    let test_db = TestDb::setup_test_db();
+    let id = test_db.save_command_to_db(
+        &CommandBuilder::new("Deploy", "deploy")
+            .with_secret("API_TOKEN", "s3cr3t")
+            .build(),
+    );
+
+    let stored: Vec<u8> = test_db
+        .db
+        .conn()
+        .unwrap()
+        .query_row(
+            "SELECT value FROM secrets.env_secrets WHERE owner_id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert!(!stored.windows(6).any(|window| window == b"s3cr3t"));
+}
+
+#[test]
+fn test_secrets_need_the_key_they_were_stored_with() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("test.db");
+    let key = SecretsKey::generate();
+
+    let db = Database::open(&path, Some(key.clone())).unwrap();
+    let id = db
+        .create_command(
+            &CommandBuilder::new("Deploy", "deploy")
+                .with_secret("API_TOKEN", "s3cr3t")
+                .build(),
+        )
+        .unwrap();
+    // written before values were encrypted
+    db.conn()
+        .unwrap()
+        .execute(
+            "INSERT INTO secrets.env_secrets (owner_table, owner_id, name, value)
+             VALUES ('commands', ?1, 'OLD_TOKEN', 'old')",
+            params![id],
+        )
+        .unwrap();
+    drop(db);
+
+    let db = Database::open(&path, Some(key)).unwrap();
+    assert_eq!(
+        db.get_secret_env_vars(COMMANDS_TABLE, id).unwrap(),
+        HashMap::from([
+            ("API_TOKEN".to_string(), "s3cr3t".to_string()),
+            ("OLD_TOKEN".to_string(), "old".to_string()),
+        ])
+    );
+    drop(db);
+
+    let db = Database::open(&path, Some(SecretsKey::generate())).unwrap();
+    assert!(matches!(
+        db.get_secret_env_vars(COMMANDS_TABLE, id),
+        Err(DatabaseError::SecretStore(_))
+    ));
+    drop(db);
+
+    let db = Database::open(&path, None).unwrap();
+    assert!(matches!(
+        db.get_secret_env_vars(COMMANDS_TABLE, id),
+        Err(DatabaseError::SecretStore(_))
+    ));
+    assert!(matches!(
+        db.create_command(
+            &CommandBuilder::new("Other", "deploy")
+                .with_secret("API_TOKEN", "s3cr3t")
+                .build()
+        ),
+        Err(DatabaseError::SecretStore(_))
+    ));
+    assert!(db
+        .create_command(&CommandBuilder::new("Plain", "deploy").build())
+        .is_ok());
+}
+
+#[test]
+fn test_create_command_invalid_secret_env_vars() {
+    let test_db = TestDb::setup_test_db();
+    let masked = CommandBuilder::new("Test", "pwd")
+        .with_secret("TOKEN", SECRET_MASK)
+        .build();
+    let duplicate = CommandBuilder::new("Test", "pwd")
+        .with_env("TOKEN", "plain")
+        .with_secret("TOKEN", "secret")
+        .build();
+
+    for command in [masked, duplicate] {
+        assert!(matches!(
+            test_db.db.create_command(&command),
+            Err(DatabaseError::InvalidData {
+                field: "secret_env_vars",
+                ..
+            })
+        ));
+    }
+}
+
+#[test]
+fn test_create_command_parameters_round_trip() {
+    let test_db = TestDb::setup_test_db();
+    let parameter = CommandParameter {
+        name: "env".to_string(),
+        label: Some("Environment".to_string()),
+        description: None,
+        kind: ParameterKind::Enum {
+            options: vec!["dev".to_string(), "prod".to_string()],
+        },
+        default: Some("dev".to_string()),
+        required: true,
+    };
+    let command = CommandBuilder::new("Deploy", "deploy")
+        .with_args(vec!["--env", "{{env}}"])
+        .with_parameter(parameter.clone())
+        .build();
+
+    let id = test_db.db.create_command(&command).unwrap();
+    let retrieved = test_db.db.get_command(id).unwrap();
+    assert_eq!(retrieved.parameters, vec![parameter]);
+}
+
+#[test]
+fn test_create_command_invalid_parameters() {
+    let test_db = TestDb::setup_test_db();
+    let base = CommandParameter {
+        name: "port".to_string(),
+        label: None,
+        description: None,
+        kind: ParameterKind::Number {
+            min: Some(1.0),
+            max: Some(10.0),
+        },
+        default: None,
+        required: false,
+    };
+
+    let invalid = [
+        vec![base.clone(), base.clone()],
+        vec![CommandParameter {
+            default: Some("11".to_string()),
+            ..base.clone()
+        }],
+        vec![CommandParameter {
+            name: "has space".to_string(),
+            ..base.clone()
+        }],
+        vec![CommandParameter {
+            kind: ParameterKind::Enum { options: vec![] },
+            ..base.clone()
+        }],
+    ];
+
+    for parameters in invalid {
+        let mut command = CommandBuilder::new("Test", "pwd").build();
+        command.parameters = parameters;
+        assert!(matches!(
+            test_db.db.create_command(&command),
+            Err(DatabaseError::InvalidData {
+                field: "parameters",
+                ..
+            })
+        ));
+    }
+}
+
 #[test]
 fn test_create_command_name_max_length_boundary() {
     let test_db = TestDb::setup_test_db();
@@ -364,7 +722,14 @@ fn test_get_commands_root_group() {
 
     let commands = test_db
         .db
-        .get_commands(GroupFilter::None, CategoryFilter::None, false, None, None)
+        .get_commands(
+            GroupFilter::None,
+            CategoryFilter::None,
+            TagFilter::All,
+            false,
+            None,
+            None,
+        )
         .unwrap();
     assert_eq!(commands.len(), 3);
     assert!(commands.iter().all(|c| c.group_id == None));
@@ -390,6 +755,7 @@ fn test_get_commands_by_none_category() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::None,
+            TagFilter::All,
             false,
             None,
             None,
@@ -417,6 +783,7 @@ fn test_get_commands_by_category() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::Category(category_id),
+            TagFilter::All,
             false,
             None,
             None,
@@ -459,6 +826,7 @@ fn test_get_commands_by_category_and_group() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::Category(category_id),
+            TagFilter::All,
             false,
             None,
             None,
@@ -481,7 +849,14 @@ fn get_commands_group_all_returns_every_command() {
 
     let result = test_db
         .db
-        .get_commands(GroupFilter::All, CategoryFilter::All, false, None, None)
+        .get_commands(
+            GroupFilter::All,
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+            None,
+            None,
+        )
         .unwrap();
 
     assert_eq!(result.len(), 4);
@@ -502,6 +877,7 @@ fn get_commands_group_id_returns_every_command() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::All,
+            TagFilter::All,
             false,
             None,
             None,
@@ -528,7 +904,14 @@ fn get_commands_category_none_excludes_categorised_commands() {
 
     let result = test_db
         .db
-        .get_commands(GroupFilter::All, CategoryFilter::None, false, None, None)
+        .get_commands(
+            GroupFilter::All,
+            CategoryFilter::None,
+            TagFilter::All,
+            false,
+            None,
+            None,
+        )
         .unwrap();
 
     assert_eq!(result.len(), 2);
@@ -545,7 +928,14 @@ fn get_commands_pagination_limit_only() {
 
     let result = test_db
         .db
-        .get_commands(GroupFilter::All, CategoryFilter::All, false, Some(3), None)
+        .get_commands(
+            GroupFilter::All,
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+            Some(3),
+            None,
+        )
         .unwrap();
 
     assert_eq!(result.len(), 3);
@@ -561,7 +951,14 @@ fn get_commands_pagination_offset_only() {
 
     let result = test_db
         .db
-        .get_commands(GroupFilter::All, CategoryFilter::All, false, None, Some(3))
+        .get_commands(
+            GroupFilter::All,
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+            None,
+            Some(3),
+        )
         .unwrap();
 
     assert_eq!(result.len(), 2);
@@ -580,6 +977,7 @@ fn get_commands_pagination_limit_and_offset() {
         .get_commands(
             GroupFilter::All,
             CategoryFilter::All,
+            TagFilter::All,
             false,
             Some(4),
             Some(6),
@@ -604,6 +1002,7 @@ fn test_get_favorite_commands() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::None,
+            TagFilter::All,
             true,
             None,
             None,
@@ -627,6 +1026,7 @@ fn test_get_commands_pagination_limit() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::None,
+            TagFilter::All,
             false,
             Some(3),
             None,
@@ -654,6 +1054,7 @@ fn test_get_commands_pagination_offset() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::None,
+            TagFilter::All,
             false,
             None,
             Some(2),
@@ -682,6 +1083,7 @@ fn test_get_commands_pagination_limit_and_offset() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::None,
+            TagFilter::All,
             false,
             Some(2),
             Some(1),
@@ -704,6 +1106,7 @@ fn test_get_commands_pagination_offset_beyond_total() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::None,
+            TagFilter::All,
             false,
             None,
             Some(100),
@@ -731,6 +1134,7 @@ fn test_get_commands_with_history_joined() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::None,
+            TagFilter::All,
             false,
             None,
             None,
@@ -757,7 +1161,14 @@ fn get_commands_no_history_returns_none() {
 
     let result = test_db
         .db
-        .get_commands(GroupFilter::All, CategoryFilter::All, false, None, None)
+        .get_commands(
+            GroupFilter::All,
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+            None,
+            None,
+        )
         .unwrap();
 
     assert_eq!(result.len(), 1);
@@ -790,7 +1201,14 @@ fn get_commands_workflow_history_excluded_from_join() {
 
     let result = test_db
         .db
-        .get_commands(GroupFilter::All, CategoryFilter::All, false, None, None)
+        .get_commands(
+            GroupFilter::All,
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+            None,
+            None,
+        )
         .unwrap();
 
     let history = result[0].history.as_ref().expect("expected history");
@@ -819,7 +1237,14 @@ fn get_commands_returns_most_recent_non_workflow_history() {
 
     let result = test_db
         .db
-        .get_commands(GroupFilter::All, CategoryFilter::All, false, None, None)
+        .get_commands(
+            GroupFilter::All,
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+            None,
+            None,
+        )
         .unwrap();
 
     let history = result[0].history.as_ref().expect("expected history");
@@ -848,6 +1273,7 @@ fn test_get_commands_with_history_status_updated() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::None,
+            TagFilter::All,
             false,
             None,
             None,
@@ -891,6 +1317,7 @@ fn test_get_commands_history_most_recent_only() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::None,
+            TagFilter::All,
             false,
             None,
             None,
@@ -924,6 +1351,7 @@ fn test_get_commands_excludes_workflow_history() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::None,
+            TagFilter::All,
             false,
             None,
             None,
@@ -1356,6 +1784,7 @@ fn test_move_command_between() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::None,
+            TagFilter::All,
             false,
             None,
             None,
@@ -1384,6 +1813,7 @@ fn test_move_command_to_top() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::None,
+            TagFilter::All,
             false,
             None,
             None,
@@ -1412,6 +1842,7 @@ fn test_move_command_to_bottom() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::None,
+            TagFilter::All,
             false,
             None,
             None,
@@ -1547,6 +1978,7 @@ fn test_move_command_renumber_preserves_order() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::All,
+            TagFilter::All,
             false,
             None,
             None,
@@ -1585,6 +2017,7 @@ fn update_command_group_change_places_at_end_of_new_group() {
         .get_commands(
             GroupFilter::Group(group1),
             CategoryFilter::All,
+            TagFilter::All,
             false,
             None,
             None,
@@ -1638,6 +2071,7 @@ fn test_position_gap_exhaustion_triggers_renumber() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::None,
+            TagFilter::All,
             false,
             None,
             None,
@@ -1666,6 +2100,7 @@ fn test_foreign_key_constraint_on_delete_group() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::None,
+            TagFilter::All,
             false,
             None,
             None,
@@ -1928,3 +2363,241 @@ fn test_row_to_execution_history_unknown_status() {
 
     assert!(result.is_err());
 }
+
+#[test]
+fn test_create_command_run_elevated_round_trip() {
+    let test_db = TestDb::setup_test_db();
+    let cmd = CommandBuilder::new("Upgrade", "apt upgrade")
+        .elevated()
+        .build();
+    let id = test_db.db.create_command(&cmd).unwrap();
+
+    let mut fetched = test_db.db.get_command(id).unwrap();
+    assert!(fetched.run_elevated);
+
+    fetched.run_elevated = false;
+    test_db.db.update_command(&fetched).unwrap();
+    assert!(!test_db.db.get_command(id).unwrap().run_elevated);
+}
+
+#[test]
+fn test_migrate_sudo_commands_strips_plain_sudo_prefix() {
+    let test_db = TestDb::setup_test_db();
+    let db = &test_db.db;
+    let inline = db
+        .create_command(&CommandBuilder::new("Update", "sudo  apt update").build())
+        .unwrap();
+    let split = db
+        .create_command(
+            &CommandBuilder::new("Restart", "sudo")
+                .with_args(vec!["systemctl", "restart", "$SERVICES"])
+                .with_raw_arguments(vec![2])
+                .build(),
+        )
+        .unwrap();
+    let with_flags = db
+        .create_command(&CommandBuilder::new("Psql", "sudo -u postgres psql").build())
+        .unwrap();
+    let flags_in_args = db
+        .create_command(
+            &CommandBuilder::new("Shell", "sudo")
+                .with_args(vec!["-i"])
+                .build(),
+        )
+        .unwrap();
+    let plain = db
+        .create_command(&CommandBuilder::new("Echo", "echo sudo").build())
+        .unwrap();
+
+    assert_eq!(db.migrate_sudo_commands().unwrap(), 2);
+
+    let cmd = db.get_command(inline).unwrap();
+    assert_eq!(cmd.command, "apt update");
+    assert!(cmd.run_elevated);
+
+    let cmd = db.get_command(split).unwrap();
+    assert_eq!(cmd.command, "systemctl");
+    assert_eq!(cmd.arguments, vec!["restart", "$SERVICES"]);
+    assert_eq!(cmd.raw_arguments, vec![1]);
+    assert!(cmd.run_elevated);
+    db.update_command(&cmd).unwrap();
+
+    for id in [with_flags, flags_in_args, plain] {
+        let cmd = db.get_command(id).unwrap();
+        assert!(!cmd.run_elevated, "{} should not be migrated", cmd.name);
+    }
+    assert_eq!(
+        db.get_command(with_flags).unwrap().command,
+        "sudo -u postgres psql"
+    );
+
+    // runs once, a sudo command added afterwards is kept as it is
+    let later = db
+        .create_command(&CommandBuilder::new("Later", "sudo apt upgrade").build())
+        .unwrap();
+    assert_eq!(db.migrate_sudo_commands().unwrap(), 0);
+    let cmd = db.get_command(later).unwrap();
+    assert_eq!(cmd.command, "sudo apt upgrade");
+    assert!(!cmd.run_elevated);
+}
+
+#[test]
+fn test_create_command_execution_target_round_trip_and_validation() {
+    let test_db = TestDb::setup_test_db();
+    let target = ExecutionTarget::Ssh {
+        host: "deploy@build-box".to_string(),
+    };
+    let id = test_db
+        .db
+        .create_command(
+            &CommandBuilder::new("Build", "make")
+                .with_target(target.clone())
+                .build(),
+        )
+        .unwrap();
+    assert_eq!(
+        test_db.db.get_command(id).unwrap().execution_target,
+        Some(target)
+    );
+
+    for name in ["", "-oProxyCommand=evil", "two words"] {
+        let cmd = CommandBuilder::new("Bad", "make")
+            .with_target(ExecutionTarget::Container {
+                runtime: ContainerRuntime::Docker,
+                container: name.to_string(),
+            })
+            .build();
+        let result = test_db.db.create_command(&cmd);
+        assert!(
+            matches!(
+                result,
+                Err(DatabaseError::InvalidData {
+                    field: "execution_target",
+                    ..
+                })
+            ),
+            "{name:?} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn test_elevated_commands_cannot_run_on_remote_targets() {
+    let test_db = TestDb::setup_test_db();
+    let db = &test_db.db;
+    let ssh = ExecutionTarget::Ssh {
+        host: "build-box".to_string(),
+    };
+    let is_rejected = |result: Result<()>, expected: &str| match result {
+        Err(DatabaseError::InvalidData { field, .. }) => field == expected,
+        _ => false,
+    };
+
+    let own = CommandBuilder::new("Upgrade", "apt")
+        .elevated()
+        .with_target(ssh.clone())
+        .build();
+    assert!(is_rejected(
+        db.create_command(&own).map(|_| ()),
+        "run_elevated"
+    ));
+
+    let remote_group = db
+        .create_group(&GroupBuilder::new("Remote").with_target(ssh.clone()).build())
+        .unwrap();
+    let inherited = CommandBuilder::new("Upgrade", "apt")
+        .elevated()
+        .with_group(remote_group)
+        .build();
+    assert!(is_rejected(
+        db.create_command(&inherited).map(|_| ()),
+        "run_elevated"
+    ));
+    let local = CommandBuilder::new("Upgrade", "apt")
+        .elevated()
+        .with_group(remote_group)
+        .with_target(ExecutionTarget::Local)
+        .build();
+    assert!(db.create_command(&local).is_ok());
+
+    // a remote target on the group would reach the elevated command of a subgroup
+    let group_id = db
+        .create_group(&GroupBuilder::new("Servers").build())
+        .unwrap();
+    let nested_id = db
+        .create_group(&GroupBuilder::new("Web").with_parent(group_id).build())
+        .unwrap();
+    db.create_command(
+        &CommandBuilder::new("Restart", "systemctl")
+            .elevated()
+            .with_group(nested_id)
+            .build(),
+    )
+    .unwrap();
+    let mut group = db.get_group(group_id).unwrap();
+    group.execution_target = Some(ssh);
+    assert!(is_rejected(db.update_group(&group), "execution_target"));
+    assert_eq!(db.get_group(group_id).unwrap().execution_target, None);
+}
+
+#[test]
+fn test_create_command_raw_arguments_round_trip_and_validation() {
+    let test_db = TestDb::setup_test_db();
+    let id = test_db
+        .db
+        .create_command(
+            &CommandBuilder::new("Logs", "ls")
+                .with_args(vec!["-la", "*.log"])
+                .with_raw_arguments(vec![1])
+                .build(),
+        )
+        .unwrap();
+    assert_eq!(test_db.db.get_command(id).unwrap().raw_arguments, vec![1]);
+
+    let cmd = CommandBuilder::new("Bad", "ls")
+        .with_args(vec!["*.log"])
+        .with_raw_arguments(vec![1])
+        .build();
+    assert!(matches!(
+        test_db.db.create_command(&cmd),
+        Err(DatabaseError::InvalidData {
+            field: "raw_arguments",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_create_command_shell_mode_and_source_files_round_trip() {
+    let test_db = TestDb::setup_test_db();
+    let mut cmd = CommandBuilder::new("Python", "python").build();
+    cmd.shell_mode = Some(ShellMode::Interactive);
+    cmd.source_files = Some(vec![".venv/bin/activate".to_string()]);
+    let id = test_db.db.create_command(&cmd).unwrap();
+
+    let stored = test_db.db.get_command(id).unwrap();
+    assert_eq!(stored.shell_mode, Some(ShellMode::Interactive));
+    assert_eq!(stored.source_files, cmd.source_files);
+}
+
+#[test]
+fn test_create_command_shell_mode_needs_a_shell_that_has_it() {
+    let test_db = TestDb::setup_test_db();
+    let mut shell = ShellDefinition::builtin("pwsh-like", "/bin/sh");
+    shell.family = ShellFamily::Powershell;
+    test_db.db.create_shell(&shell).unwrap();
+
+    let mut cmd = CommandBuilder::new("Profile", "Get-Date").build();
+    cmd.shell = Some("pwsh-like".to_string());
+    cmd.shell_mode = Some(ShellMode::Login);
+    assert!(matches!(
+        test_db.db.create_command(&cmd),
+        Err(DatabaseError::InvalidData {
+            field: "shell_mode",
+            ..
+        })
+    ));
+
+    cmd.shell_mode = Some(ShellMode::NonInteractive);
+    test_db.db.create_command(&cmd).unwrap();
+}
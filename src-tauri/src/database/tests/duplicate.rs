@@ -0,0 +1,223 @@
+use super::*;
+use crate::constants::{COMMANDS_TABLE, GROUPS_TABLE};
+
+fn group_command_names(test_db: &TestDb, group_id: i64) -> Vec<String> {
+    test_db
+        .db
+        .get_commands(
+            GroupFilter::Group(group_id),
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+            None,
+            None,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|c| c.item.name)
+        .collect()
+}
+
+#[test]
+fn test_duplicate_command_copies_fields_secrets_and_tags() {
+    let test_db = TestDb::setup_test_db();
+    let group_id = test_db.create_test_group("Ops");
+    let tag_id = test_db.db.create_tag("deploy", None).unwrap();
+    let id = test_db.save_command_to_db(
+        &CommandBuilder::new("Deploy", "kubectl")
+            .with_group(group_id)
+            .with_args(vec!["apply", "-f", "app.yaml"])
+            .with_env("KUBECONFIG", "~/.kube/prod")
+            .with_secret("TOKEN", "s3cr3t")
+            .build(),
+    );
+    test_db
+        .db
+        .add_item_tag(TagTarget::Command, id, tag_id)
+        .unwrap();
+
+    let copy_id = test_db.db.duplicate_command(id).unwrap();
+    let original = test_db.db.get_command(id).unwrap();
+    let copy = test_db.db.get_command(copy_id).unwrap();
+
+    assert_eq!(copy.name, "Deploy (copy)");
+    assert_eq!(copy.group_id, Some(group_id));
+    assert_eq!(copy.arguments, original.arguments);
+    assert_eq!(copy.env_vars, original.env_vars);
+    assert!(copy.position > original.position);
+    assert_eq!(
+        test_db
+            .db
+            .get_secret_env_vars(COMMANDS_TABLE, copy_id)
+            .unwrap()["TOKEN"],
+        "s3cr3t"
+    );
+    assert_eq!(
+        test_db
+            .db
+            .get_item_tags(TagTarget::Command, copy_id)
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn test_duplicate_names_count_up_among_siblings() {
+    let test_db = TestDb::setup_test_db();
+    let group_id = test_db.create_test_group("Group");
+    let id = test_db.create_test_command("Build", "make", Some(group_id));
+    // same name in another group doesn't count
+    test_db.create_test_command("Build (copy)", "make", None);
+
+    let first = test_db.db.duplicate_command(id).unwrap();
+    let second = test_db.db.duplicate_command(id).unwrap();
+    let of_copy = test_db.db.duplicate_command(first).unwrap();
+
+    assert_eq!(test_db.db.get_command(first).unwrap().name, "Build (copy)");
+    assert_eq!(
+        test_db.db.get_command(second).unwrap().name,
+        "Build (copy 2)"
+    );
+    assert_eq!(
+        test_db.db.get_command(of_copy).unwrap().name,
+        "Build (copy 3)"
+    );
+}
+
+#[test]
+fn test_duplicate_group_copies_subtree() {
+    let test_db = TestDb::setup_test_db();
+    let root_id = test_db.save_group_to_db(
+        &GroupBuilder::new("Backend")
+            .with_env("RUST_LOG", "debug")
+            .with_secret("DB_PASSWORD", "hunter2")
+            .build(),
+    );
+    let child_id = test_db.save_group_to_db(&GroupBuilder::new("Db").with_parent(root_id).build());
+    let build_id = test_db.create_test_command("Build", "cargo", Some(root_id));
+    test_db.create_test_command("Test", "cargo", Some(root_id));
+    test_db.create_test_command("Migrate", "diesel", Some(child_id));
+
+    let duplicated = test_db.db.duplicate_group(root_id).unwrap();
+    assert_eq!(duplicated.groups.len(), 2);
+    assert_eq!(duplicated.commands.len(), 3);
+    assert_eq!(duplicated.groups[&root_id], duplicated.id);
+
+    let copy = test_db.db.get_group(duplicated.id).unwrap();
+    assert_eq!(copy.name, "Backend (copy)");
+    assert_eq!(copy.parent_group_id, None);
+    assert_eq!(
+        copy.env_vars,
+        test_db.db.get_group(root_id).unwrap().env_vars
+    );
+    assert_eq!(
+        test_db
+            .db
+            .get_secret_env_vars(GROUPS_TABLE, duplicated.id)
+            .unwrap()["DB_PASSWORD"],
+        "hunter2"
+    );
+
+    let child_copy = test_db.db.get_group(duplicated.groups[&child_id]).unwrap();
+    assert_eq!(child_copy.name, "Db");
+    assert_eq!(child_copy.parent_group_id, Some(duplicated.id));
+    assert_eq!(
+        group_command_names(&test_db, duplicated.id),
+        vec!["Build", "Test"]
+    );
+    assert_eq!(
+        group_command_names(&test_db, child_copy.id),
+        vec!["Migrate"]
+    );
+    assert_eq!(
+        test_db
+            .db
+            .get_command(duplicated.commands[&build_id])
+            .unwrap()
+            .group_id,
+        Some(duplicated.id)
+    );
+
+    // the original is untouched
+    assert_eq!(
+        group_command_names(&test_db, root_id),
+        vec!["Build", "Test"]
+    );
+}
+
+#[test]
+fn test_duplicate_nested_group_stays_under_parent() {
+    let test_db = TestDb::setup_test_db();
+    let parent_id = test_db.create_test_group("Parent");
+    let child_id =
+        test_db.save_group_to_db(&GroupBuilder::new("Child").with_parent(parent_id).build());
+
+    let duplicated = test_db.db.duplicate_group(child_id).unwrap();
+    let copy = test_db.db.get_group(duplicated.id).unwrap();
+    assert_eq!(copy.parent_group_id, Some(parent_id));
+    assert!(copy.position > test_db.db.get_group(child_id).unwrap().position);
+}
+
+#[test]
+fn test_duplicate_workflow_shares_or_copies_commands() {
+    let test_db = TestDb::setup_test_db();
+    let workflow_id = test_db.create_test_workflow("Release");
+    let build_id = test_db.create_test_command("Build", "cargo", None);
+    let publish_id = test_db.create_test_command("Publish", "cargo", None);
+    for command_id in [build_id, publish_id, build_id] {
+        test_db.create_test_workflow_step(workflow_id, command_id);
+    }
+
+    let shared = test_db.db.duplicate_workflow(workflow_id, false).unwrap();
+    assert_eq!(
+        test_db.db.get_workflow(shared.id).unwrap().name,
+        "Release (copy)"
+    );
+    assert!(shared.commands.is_empty());
+    let steps = test_db
+        .db
+        .get_workflow_steps(Some(shared.id), None, false)
+        .unwrap();
+    let command_ids: Vec<i64> = steps.iter().map(|s| s.command_id).collect();
+    assert_eq!(command_ids, vec![build_id, publish_id, build_id]);
+    assert_eq!(shared.workflow_steps.len(), 3);
+
+    let copied = test_db.db.duplicate_workflow(workflow_id, true).unwrap();
+    assert_eq!(
+        test_db.db.get_workflow(copied.id).unwrap().name,
+        "Release (copy 2)"
+    );
+    assert_eq!(copied.commands.len(), 2);
+    let build_copy = copied.commands[&build_id];
+    assert_eq!(
+        test_db.db.get_command(build_copy).unwrap().name,
+        "Build (copy)"
+    );
+    let steps = test_db
+        .db
+        .get_workflow_steps(Some(copied.id), None, false)
+        .unwrap();
+    let command_ids: Vec<i64> = steps.iter().map(|s| s.command_id).collect();
+    assert_eq!(
+        command_ids,
+        vec![build_copy, copied.commands[&publish_id], build_copy]
+    );
+}
+
+#[test]
+fn test_duplicate_missing_items() {
+    let test_db = TestDb::setup_test_db();
+    assert!(matches!(
+        test_db.db.duplicate_command(999),
+        Err(DatabaseError::NotFound { .. })
+    ));
+    assert!(matches!(
+        test_db.db.duplicate_group(999),
+        Err(DatabaseError::NotFound { .. })
+    ));
+    assert!(matches!(
+        test_db.db.duplicate_workflow(999, true),
+        Err(DatabaseError::NotFound { .. })
+    ));
+}
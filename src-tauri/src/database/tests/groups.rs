@@ -1,5 +1,5 @@
 use super::*;
-use crate::constants::GROUPS_TABLE;
+use crate::constants::{COMMANDS_TABLE, GROUPS_TABLE};
 
 #[test]
 fn test_group_builder_pattern() {
@@ -34,7 +34,7 @@ fn test_get_groups_empty() {
     let test_db = TestDb::setup_test_db();
     let groups = test_db
         .db
-        .get_groups(GroupFilter::All, CategoryFilter::All, false)
+        .get_groups(GroupFilter::All, CategoryFilter::All, TagFilter::All, false)
         .unwrap();
     assert!(groups.is_empty());
 }
@@ -207,6 +207,79 @@ fn test_create_group_invalid_env_var_key() {
     ));
 }
 
+#[test]
+fn test_update_group_resource_limits() {
+    let test_db = TestDb::setup_test_db();
+    let group_id = test_db.create_test_group("Test");
+
+    let mut group = test_db.db.get_group(group_id).unwrap();
+    assert_eq!(group.resource_limits, None);
+
+    group.resource_limits = Some(ResourceLimits {
+        max_processes: Some(64),
+        use_cgroup: Some(true),
+        ..Default::default()
+    });
+    test_db.db.update_group(&group).unwrap();
+    let retrieved = test_db.db.get_group(group_id).unwrap();
+    assert_eq!(retrieved.resource_limits, group.resource_limits);
+
+    group.resource_limits = Some(ResourceLimits {
+        nice: Some(-21),
+        ..Default::default()
+    });
+    assert!(matches!(
+        test_db.db.update_group(&group),
+        Err(DatabaseError::InvalidData {
+            field: "resource_limits",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_update_group_env_inheritance() {
+    let test_db = TestDb::setup_test_db();
+    let group_id = test_db
+        .db
+        .create_group(
+            &GroupBuilder::new("Test")
+                .with_env_unset(vec!["HOME"])
+                .build(),
+        )
+        .unwrap();
+
+    let mut group = test_db.db.get_group(group_id).unwrap();
+    assert_eq!(group.env_unset, Some(vec!["HOME".to_string()]));
+    group.env_inheritance = Some(EnvInheritance::Clean);
+    group.env_unset = Some(vec!["DISPLAY".to_string()]);
+    test_db.db.update_group(&group).unwrap();
+
+    let retrieved = test_db.db.get_group(group_id).unwrap();
+    assert_eq!(retrieved.env_inheritance, Some(EnvInheritance::Clean));
+    assert_eq!(retrieved.env_unset, group.env_unset);
+}
+
+#[test]
+fn test_update_group_env_files() {
+    let test_db = TestDb::setup_test_db();
+    let group_id = test_db
+        .db
+        .create_group(
+            &GroupBuilder::new("Test")
+                .with_env_files(vec![".env"])
+                .build(),
+        )
+        .unwrap();
+
+    let mut group = test_db.db.get_group(group_id).unwrap();
+    assert_eq!(group.env_files, Some(vec![".env".to_string()]));
+    group.env_files = None;
+    test_db.db.update_group(&group).unwrap();
+
+    assert_eq!(test_db.db.get_group(group_id).unwrap().env_files, None);
+}
+
 #[test]
 fn test_create_group_circular_reference_direct() {
     let test_db = TestDb::setup_test_db();
@@ -269,7 +342,12 @@ fn test_get_groups_by_parent() {
 
     let children = test_db
         .db
-        .get_groups(GroupFilter::Group(parent_id), CategoryFilter::None, false)
+        .get_groups(
+            GroupFilter::Group(parent_id),
+            CategoryFilter::None,
+            TagFilter::All,
+            false,
+        )
         .unwrap();
     assert_eq!(children.len(), 2);
     assert!(children
@@ -289,7 +367,12 @@ fn test_get_root_groups() {
 
     let roots = test_db
         .db
-        .get_groups(GroupFilter::None, CategoryFilter::None, false)
+        .get_groups(
+            GroupFilter::None,
+            CategoryFilter::None,
+            TagFilter::All,
+            false,
+        )
         .unwrap();
     assert_eq!(roots.len(), 3); // Root1, Root2, Parent
 }
@@ -314,6 +397,7 @@ fn test_get_groups_by_category() {
         .get_groups(
             GroupFilter::Group(group_id),
             CategoryFilter::Category(category_id),
+            TagFilter::All,
             false,
         )
         .unwrap();
@@ -333,7 +417,12 @@ fn test_get_favorite_groups() {
 
     let favorites = test_db
         .db
-        .get_groups(GroupFilter::None, CategoryFilter::None, true)
+        .get_groups(
+            GroupFilter::None,
+            CategoryFilter::None,
+            TagFilter::All,
+            true,
+        )
         .unwrap();
     assert_eq!(favorites.len(), 2);
 }
@@ -348,7 +437,12 @@ fn test_get_groups_filter_none_returns_only_root_groups() {
 
     let groups = test_db
         .db
-        .get_groups(GroupFilter::None, CategoryFilter::All, false)
+        .get_groups(
+            GroupFilter::None,
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+        )
         .unwrap();
 
     assert_eq!(groups.len(), 1);
@@ -369,7 +463,12 @@ fn test_get_groups_filter_group_returns_only_children_of_that_parent() {
 
     let groups = test_db
         .db
-        .get_groups(GroupFilter::Group(parent_a), CategoryFilter::All, false)
+        .get_groups(
+            GroupFilter::Group(parent_a),
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+        )
         .unwrap();
 
     assert_eq!(groups.len(), 1);
@@ -389,7 +488,7 @@ fn test_get_groups_filter_all_returns_every_group() {
 
     let groups = test_db
         .db
-        .get_groups(GroupFilter::All, CategoryFilter::All, false)
+        .get_groups(GroupFilter::All, CategoryFilter::All, TagFilter::All, false)
         .unwrap();
 
     assert_eq!(groups.len(), 4);
@@ -406,7 +505,12 @@ fn test_get_groups_category_filter_none_excludes_categorized_groups() {
 
     let groups = test_db
         .db
-        .get_groups(GroupFilter::All, CategoryFilter::None, false)
+        .get_groups(
+            GroupFilter::All,
+            CategoryFilter::None,
+            TagFilter::All,
+            false,
+        )
         .unwrap();
 
     assert_eq!(groups.len(), 1);
@@ -425,7 +529,12 @@ fn test_get_groups_category_filter_category_returns_matching_only() {
 
     let groups = test_db
         .db
-        .get_groups(GroupFilter::All, CategoryFilter::Category(cat_id), false)
+        .get_groups(
+            GroupFilter::All,
+            CategoryFilter::Category(cat_id),
+            TagFilter::All,
+            false,
+        )
         .unwrap();
 
     assert_eq!(groups.len(), 1);
@@ -441,7 +550,7 @@ fn test_get_groups_ordered_by_position() {
 
     let groups = test_db
         .db
-        .get_groups(GroupFilter::All, CategoryFilter::All, false)
+        .get_groups(GroupFilter::All, CategoryFilter::All, TagFilter::All, false)
         .unwrap();
 
     assert_eq!(groups[0].id, id_1);
@@ -545,7 +654,12 @@ fn test_get_favorite_group_with_parent() {
 
     let favorites = test_db
         .db
-        .get_groups(GroupFilter::Group(group_id), CategoryFilter::None, true)
+        .get_groups(
+            GroupFilter::Group(group_id),
+            CategoryFilter::None,
+            TagFilter::All,
+            true,
+        )
         .unwrap();
 
     assert_eq!(favorites.len(), 1);
@@ -660,6 +774,7 @@ fn test_delete_group_cascades_to_commands() {
         .get_commands(
             GroupFilter::Group(group_id),
             CategoryFilter::None,
+            TagFilter::All,
             false,
             None,
             None,
@@ -687,11 +802,52 @@ fn test_delete_group_cascades_to_child_groups() {
 
     let commands = test_db
         .db
-        .get_groups(GroupFilter::Group(group_id), CategoryFilter::None, false)
+        .get_groups(
+            GroupFilter::Group(group_id),
+            CategoryFilter::None,
+            TagFilter::All,
+            false,
+        )
         .unwrap();
     assert_eq!(commands.len(), 0);
 }
 
+#[test]
+fn test_delete_group_removes_secrets_of_subtree() {
+    let test_db = TestDb::setup_test_db();
+    let group_id = test_db.save_group_to_db(
+        &GroupBuilder::new("Deletable")
+            .with_secret("GROUP_TOKEN", "g")
+            .build(),
+    );
+    let child_id = test_db.save_group_to_db(
+        &GroupBuilder::new("child")
+            .with_parent(group_id)
+            .with_secret("CHILD_TOKEN", "c")
+            .build(),
+    );
+    let command_id = test_db.save_command_to_db(
+        &CommandBuilder::new("cmd", "echo")
+            .with_group(child_id)
+            .with_secret("CMD_TOKEN", "x")
+            .build(),
+    );
+
+    test_db.db.delete_group(group_id).unwrap();
+
+    for (table, id) in [
+        (GROUPS_TABLE, group_id),
+        (GROUPS_TABLE, child_id),
+        (COMMANDS_TABLE, command_id),
+    ] {
+        assert!(test_db
+            .db
+            .get_secret_env_vars(table, id)
+            .unwrap()
+            .is_empty());
+    }
+}
+
 #[test]
 fn test_delete_group_not_found() {
     let test_db = TestDb::setup_test_db();
@@ -985,7 +1141,12 @@ fn test_move_group_to_end() {
 
     let children = test_db
         .db
-        .get_groups(GroupFilter::Group(parent_id), CategoryFilter::None, false)
+        .get_groups(
+            GroupFilter::Group(parent_id),
+            CategoryFilter::None,
+            TagFilter::All,
+            false,
+        )
         .unwrap();
     assert_eq!(children[0].id, id1);
     assert_eq!(children[1].id, id2);
@@ -1005,7 +1166,12 @@ fn test_move_group_to_top() {
 
     let children = test_db
         .db
-        .get_groups(GroupFilter::Group(parent_id), CategoryFilter::None, false)
+        .get_groups(
+            GroupFilter::Group(parent_id),
+            CategoryFilter::None,
+            TagFilter::All,
+            false,
+        )
         .unwrap();
     assert_eq!(children[0].id, id1);
     assert_eq!(children[1].id, id3);
@@ -1029,7 +1195,12 @@ fn test_move_group_to_middle() {
 
     let children = test_db
         .db
-        .get_groups(GroupFilter::Group(parent_id), CategoryFilter::None, false)
+        .get_groups(
+            GroupFilter::Group(parent_id),
+            CategoryFilter::None,
+            TagFilter::All,
+            false,
+        )
         .unwrap();
     assert_eq!(children[0].id, id1);
     assert_eq!(children[1].id, id3);
@@ -1326,3 +1497,27 @@ fn test_update_group_icon_and_color() {
     assert_eq!(retrieved.icon, Some("🚀".to_string()));
     assert_eq!(retrieved.color, Some("#FF5733".to_string()));
 }
+
+#[test]
+fn test_update_group_shell_mode_and_source_files() {
+    let test_db = TestDb::setup_test_db();
+    let group_id = test_db.create_test_group("Node");
+
+    let mut group = test_db.db.get_group(group_id).unwrap();
+    group.shell_mode = Some(ShellMode::Login);
+    group.source_files = Some(vec!["~/.nvm/nvm.sh".to_string()]);
+    test_db.db.update_group(&group).unwrap();
+
+    let retrieved = test_db.db.get_group(group_id).unwrap();
+    assert_eq!(retrieved.shell_mode, Some(ShellMode::Login));
+    assert_eq!(retrieved.source_files, group.source_files);
+
+    group.source_files = Some(vec![" ".to_string()]);
+    assert!(matches!(
+        test_db.db.update_group(&group),
+        Err(DatabaseError::InvalidData {
+            field: "source_files",
+            ..
+        })
+    ));
+}
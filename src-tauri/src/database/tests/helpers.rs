@@ -172,27 +172,33 @@ fn string_to_hashmap_valid_json_round_trips() {
 }
 
 #[test]
-fn get_position_first_item_gets_position_gap() {
+fn next_position_first_item_gets_position_gap() {
     let test_db = TestDb::setup_test_db();
     // "commands" table is empty for root (group_id IS NULL)
-    let pos = test_db
-        .db
-        .get_position("commands", Some("group_id"), None)
-        .unwrap();
+    let pos = Database::next_position(
+        &*test_db.db.conn().unwrap(),
+        "commands",
+        Some("group_id"),
+        None,
+    )
+    .unwrap();
 
     // COALESCE(MAX, -1) + 1 = 0; then + POSITION_GAP(1000) = 1000
     assert_eq!(pos, Database::POSITION_GAP);
 }
 
 #[test]
-fn get_position_subsequent_items_increment() {
+fn next_position_subsequent_items_increment() {
     let test_db = TestDb::setup_test_db();
     test_db.create_test_command("Test", "echo", None);
 
-    let pos = test_db
-        .db
-        .get_position("commands", Some("group_id"), None)
-        .unwrap();
+    let pos = Database::next_position(
+        &*test_db.db.conn().unwrap(),
+        "commands",
+        Some("group_id"),
+        None,
+    )
+    .unwrap();
 
     // After first insert (position=1000), MAX=1000, COALESCE+1=1001, +1000=2001
     assert!(pos > Database::POSITION_GAP);
@@ -273,7 +279,14 @@ fn renumber_produces_gap_spaced_positions_after_exhaustion() {
 
     let commands = test_db
         .db
-        .get_commands(GroupFilter::None, CategoryFilter::All, false, None, None)
+        .get_commands(
+            GroupFilter::None,
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+            None,
+            None,
+        )
         .unwrap();
 
     let mut positions: Vec<i64> = commands.iter().map(|w| w.item.position).collect();
@@ -113,7 +113,12 @@ fn test_concurrent_transactions_isolated() {
 
     let count_before = test_db
         .db
-        .get_groups(GroupFilter::None, CategoryFilter::None, false)
+        .get_groups(
+            GroupFilter::None,
+            CategoryFilter::None,
+            TagFilter::All,
+            false,
+        )
         .unwrap()
         .len();
 
@@ -130,7 +135,12 @@ fn test_concurrent_transactions_isolated() {
 
     let groups = test_db
         .db
-        .get_groups(GroupFilter::None, CategoryFilter::None, false)
+        .get_groups(
+            GroupFilter::None,
+            CategoryFilter::None,
+            TagFilter::All,
+            false,
+        )
         .unwrap();
     assert_eq!(groups.len(), count_before + 1);
 }
@@ -601,3 +611,66 @@ fn test_workflow_execution_creates_linked_history_rows() {
 //     assert_eq!(running[0].id, exec_a);
 //     assert_eq!(running[0].pid, Some(1001));
 // }
+
+#[test]
+fn test_open_adds_new_columns_to_a_baseline_database() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("test.db");
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute_batch(include_str!("baseline_schema.sql"))
+        .unwrap();
+    conn.execute(
+        "INSERT INTO commands (name, command, arguments, position) VALUES (?1, ?2, ?3, 0)",
+        params!["Build", "cargo", r#"["build"]"#],
+    )
+    .unwrap();
+    drop(conn);
+
+    let db = Database::open(&path, Some(SecretsKey::generate())).unwrap();
+    let old = &db
+        .get_commands(
+            GroupFilter::All,
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+            None,
+            None,
+        )
+        .unwrap()[0]
+        .item;
+    assert_eq!(old.arguments, vec!["build"]);
+    assert!(!old.run_elevated);
+    assert!(old.resource_limits.is_none());
+
+    let mut command = CommandBuilder::new("Test", "cargo")
+        .with_args(vec!["test", "{{filter}}"])
+        .with_parameter(CommandParameter {
+            name: "filter".to_string(),
+            label: None,
+            description: None,
+            kind: ParameterKind::String,
+            default: None,
+            required: false,
+        })
+        .build();
+    command.shell_mode = Some(ShellMode::Login);
+    command.shell = Some("sh".to_string());
+    let id = db.create_command(&command).unwrap();
+    assert_eq!(
+        db.get_command(id).unwrap().shell_mode,
+        Some(ShellMode::Login)
+    );
+    let group_id = db
+        .create_group(
+            &GroupBuilder::new("Backend")
+                .with_env("RUST_LOG", "debug")
+                .build(),
+        )
+        .unwrap();
+    assert!(db.get_group(group_id).is_ok());
+    drop(db);
+
+    // every column is there already, opening again changes nothing
+    let db = Database::open(&path, None).unwrap();
+    assert_eq!(db.search_all("build", None).unwrap().len(), 1);
+}
@@ -0,0 +1,421 @@
+use super::*;
+
+/// Category, a group with a subgroup, two commands with a secret and a workflow using them
+fn populate(test_db: &TestDb) {
+    let category_id = test_db.create_test_category("Build");
+    let group_id = test_db.save_group_to_db(
+        &GroupBuilder::new("Backend")
+            .with_category(category_id)
+            .with_secret("TOKEN", "hunter2")
+            .build(),
+    );
+    let nested_id =
+        test_db.save_group_to_db(&GroupBuilder::new("Db").with_parent(group_id).build());
+    let test_id = test_db.create_test_command("Test", "cargo", Some(group_id));
+    let build_id = test_db.save_command_to_db(
+        &CommandBuilder::new("Build", "cargo")
+            .with_args(vec!["build"])
+            .with_group(group_id)
+            .with_category(category_id)
+            .with_secret("API_KEY", "s3cret")
+            .build(),
+    );
+    test_db.create_test_command("Migrate", "sqlx", Some(nested_id));
+
+    let workflow_id = test_db.create_test_workflow("Release");
+    test_db.create_test_workflow_step(workflow_id, build_id);
+    test_db.create_test_workflow_step(workflow_id, test_id);
+}
+
+fn export_json(db: &Database) -> String {
+    serde_json::to_string(&db.export_library().unwrap()).unwrap()
+}
+
+fn group_named(db: &Database, name: &str) -> Group {
+    db.get_groups(GroupFilter::All, CategoryFilter::All, TagFilter::All, false)
+        .unwrap()
+        .into_iter()
+        .find(|g| g.name == name)
+        .unwrap()
+}
+
+fn command_names(db: &Database, group_id: i64) -> Vec<String> {
+    db.get_commands(
+        GroupFilter::Group(group_id),
+        CategoryFilter::All,
+        TagFilter::All,
+        false,
+        None,
+        None,
+    )
+    .unwrap()
+    .into_iter()
+    .map(|c| c.item.name)
+    .collect()
+}
+
+#[test]
+fn test_export_library_leaves_secrets_out() {
+    let test_db = TestDb::setup_test_db();
+    populate(&test_db);
+
+    let export = test_db.db.export_library().unwrap();
+    assert_eq!(export.format, "tgui-library");
+    assert_eq!(export.version, 1);
+    assert_eq!(export.categories.len(), 1);
+    assert_eq!(export.commands.len(), 3);
+    assert_eq!(export.workflow_steps.len(), 2);
+    let group_names: Vec<_> = export.groups.iter().map(|g| g.name.as_str()).collect();
+    assert_eq!(group_names, vec!["Backend", "Db"]);
+
+    let json = export_json(&test_db.db);
+    assert!(!json.contains("hunter2"));
+    assert!(!json.contains("s3cret"));
+    assert!(!json.contains("API_KEY"));
+}
+
+#[test]
+fn test_import_library_round_trip_remaps_ids() {
+    let source = TestDb::setup_test_db();
+    populate(&source);
+    let json = export_json(&source.db);
+
+    let target = TestDb::setup_test_db();
+    // shifts the ids so a missed remap points at the wrong rows
+    target.create_test_category("Other");
+    let other = target.create_test_group("Other");
+    target.create_test_command("Other", "true", Some(other));
+
+    let report = target
+        .db
+        .import_library(&json, &ImportMode::Merge, false)
+        .unwrap();
+    assert_eq!(report.categories_created, 1);
+    assert_eq!(report.groups_created, 2);
+    assert_eq!(report.commands_created, 3);
+    assert_eq!(report.workflows_created, 1);
+    assert_eq!(report.workflow_steps_created, 2);
+    assert!(report.conflicts.is_empty());
+
+    let db = &target.db;
+    let backend = group_named(db, "Backend");
+    let db_group = group_named(db, "Db");
+    assert_eq!(db_group.parent_group_id, Some(backend.id));
+    let category = db.get_category(backend.category_id.unwrap()).unwrap();
+    assert_eq!(category.name, "Build");
+    assert_eq!(command_names(db, backend.id), vec!["Test", "Build"]);
+    assert_eq!(command_names(db, db_group.id), vec!["Migrate"]);
+
+    let workflow = &db.get_workflows(None, false).unwrap()[0];
+    let steps = db
+        .get_workflow_steps_command_populated(workflow.id, false)
+        .unwrap();
+    let step_commands: Vec<_> = steps.iter().map(|(_, c)| c.name.as_str()).collect();
+    assert_eq!(step_commands, vec!["Build", "Test"]);
+    assert_eq!(steps[0].1.group_id, Some(backend.id));
+}
+
+#[test]
+fn test_import_library_links_tags_by_name() {
+    let source = TestDb::setup_test_db();
+    populate(&source);
+    let db = &source.db;
+    let backend = group_named(db, "Backend");
+    let build = db
+        .get_commands(
+            GroupFilter::Group(backend.id),
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+            None,
+            None,
+        )
+        .unwrap()
+        .into_iter()
+        .find(|c| c.item.name == "Build")
+        .unwrap()
+        .item;
+    let workflow = &db.get_workflows(None, false).unwrap()[0];
+    let ci = db.create_tag("ci", Some("#00ff00")).unwrap();
+    let rust = db.create_tag("rust", Some("#ff8800")).unwrap();
+    db.add_item_tag(TagTarget::Group, backend.id, rust).unwrap();
+    db.add_item_tag(TagTarget::Command, build.id, ci).unwrap();
+    db.add_item_tag(TagTarget::Command, build.id, rust).unwrap();
+    db.add_item_tag(TagTarget::Workflow, workflow.id, ci)
+        .unwrap();
+
+    let export = db.export_library().unwrap();
+    assert_eq!(export.tags.len(), 2);
+    assert_eq!(export.tag_links.len(), 4);
+    assert!(export.tag_links.contains(&LibraryTagLink {
+        target: TagTarget::Command,
+        item_id: build.id,
+        tag: "rust".to_string(),
+    }));
+
+    let target = TestDb::setup_test_db();
+    let existing = target.db.create_tag("ci", None).unwrap();
+    let report = target
+        .db
+        .import_library(&export_json(db), &ImportMode::Merge, false)
+        .unwrap();
+    assert_eq!(report.tags_created, 1);
+    assert!(report.conflicts.contains(&ImportConflict {
+        entity: "tags",
+        name: "ci".to_string(),
+        resolution: ConflictResolution::Reused,
+    }));
+
+    let db = &target.db;
+    let tag_names = |target, item_id| {
+        db.get_item_tags(target, item_id)
+            .unwrap()
+            .into_iter()
+            .map(|tag| tag.name)
+            .collect::<Vec<_>>()
+    };
+    let backend = group_named(db, "Backend");
+    assert_eq!(tag_names(TagTarget::Group, backend.id), vec!["rust"]);
+    let build = db
+        .get_commands(
+            GroupFilter::Group(backend.id),
+            CategoryFilter::All,
+            TagFilter::AllOf(vec![existing]),
+            false,
+            None,
+            None,
+        )
+        .unwrap();
+    assert_eq!(build.len(), 1);
+    assert_eq!(
+        tag_names(TagTarget::Command, build[0].item.id),
+        vec!["ci", "rust"]
+    );
+    let workflow = &db.get_workflows(None, false).unwrap()[0];
+    assert_eq!(tag_names(TagTarget::Workflow, workflow.id), vec!["ci"]);
+    let rust = db.get_tags().unwrap().pop().unwrap();
+    assert_eq!(rust.color.as_deref(), Some("#ff8800"));
+}
+
+#[test]
+fn test_import_library_keeps_position_order() {
+    let source = TestDb::setup_test_db();
+    populate(&source);
+    source.create_test_group("Frontend");
+    let mut export = source.db.export_library().unwrap();
+    // a document written by hand needn't list its items in order
+    export.groups.reverse();
+    export.commands.reverse();
+    export.workflow_steps.reverse();
+
+    let target = TestDb::setup_test_db();
+    let existing = target.create_test_group("Existing");
+    target.create_test_command("Existing", "true", Some(existing));
+    target
+        .db
+        .import_library(
+            &serde_json::to_string(&export).unwrap(),
+            &ImportMode::Merge,
+            false,
+        )
+        .unwrap();
+
+    let db = &target.db;
+    let roots: Vec<_> = db
+        .get_groups(
+            GroupFilter::None,
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|g| g.name)
+        .collect();
+    assert_eq!(roots, vec!["Existing", "Backend", "Frontend"]);
+    let backend = group_named(db, "Backend");
+    assert_eq!(command_names(db, backend.id), vec!["Test", "Build"]);
+
+    let workflow = &db.get_workflows(None, false).unwrap()[0];
+    let steps = db
+        .get_workflow_steps_command_populated(workflow.id, false)
+        .unwrap();
+    let step_commands: Vec<_> = steps.iter().map(|(_, c)| c.name.as_str()).collect();
+    assert_eq!(step_commands, vec!["Build", "Test"]);
+}
+
+#[test]
+fn test_import_library_merge_reports_conflicts() {
+    let test_db = TestDb::setup_test_db();
+    populate(&test_db);
+    let mut export = test_db.db.export_library().unwrap();
+    let backend = group_named(&test_db.db, "Backend");
+    let mut lint = CommandBuilder::new("Lint", "cargo").build();
+    lint.id = 1000;
+    lint.group_id = Some(backend.id);
+    export.commands.push(lint);
+
+    let report = test_db
+        .db
+        .import_library(
+            &serde_json::to_string(&export).unwrap(),
+            &ImportMode::Merge,
+            false,
+        )
+        .unwrap();
+
+    assert_eq!(report.groups_created, 0);
+    assert_eq!(report.commands_created, 1);
+    assert_eq!(report.workflows_created, 0);
+    assert_eq!(report.workflow_steps_created, 0);
+    assert_eq!(
+        report.conflicts[0],
+        ImportConflict {
+            entity: "categories",
+            name: "Build".to_string(),
+            resolution: ConflictResolution::Reused,
+        }
+    );
+    let count = |resolution| {
+        report
+            .conflicts
+            .iter()
+            .filter(|c| c.resolution == resolution)
+            .count()
+    };
+    assert_eq!(count(ConflictResolution::Merged), 2);
+    assert_eq!(count(ConflictResolution::Skipped), 4);
+    assert_eq!(
+        command_names(&test_db.db, backend.id),
+        vec!["Test", "Build", "Lint"]
+    );
+}
+
+#[test]
+fn test_import_library_replace_and_new_root() {
+    let source = TestDb::setup_test_db();
+    populate(&source);
+    let json = export_json(&source.db);
+
+    let target = TestDb::setup_test_db();
+    target.create_test_group("Old");
+    target.create_test_workflow("Release");
+    target
+        .db
+        .import_library(&json, &ImportMode::Replace, false)
+        .unwrap();
+    let roots = target
+        .db
+        .get_groups(
+            GroupFilter::None,
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+        )
+        .unwrap();
+    assert_eq!(roots.len(), 1);
+    assert_eq!(roots[0].name, "Backend");
+    assert_eq!(target.db.get_workflows(None, false).unwrap().len(), 1);
+
+    let report = target
+        .db
+        .import_library(
+            &json,
+            &ImportMode::NewRoot {
+                name: "Imported".to_string(),
+            },
+            false,
+        )
+        .unwrap();
+    assert_eq!(report.groups_created, 3);
+    assert_eq!(
+        report.conflicts.last().unwrap().resolution,
+        ConflictResolution::Duplicated
+    );
+    let root = group_named(&target.db, "Imported");
+    let nested = target
+        .db
+        .get_groups(
+            GroupFilter::Group(root.id),
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+        )
+        .unwrap();
+    assert_eq!(nested[0].name, "Backend");
+    assert_eq!(target.db.get_workflows(None, false).unwrap().len(), 2);
+}
+
+#[test]
+fn test_import_library_failure_and_dry_run_write_nothing() {
+    let source = TestDb::setup_test_db();
+    populate(&source);
+    let mut export = source.db.export_library().unwrap();
+
+    let target = TestDb::setup_test_db();
+    let report = target
+        .db
+        .import_library(
+            &serde_json::to_string(&export).unwrap(),
+            &ImportMode::Merge,
+            true,
+        )
+        .unwrap();
+    assert!(report.dry_run);
+    assert_eq!(report.commands_created, 3);
+
+    // the groups and commands are inserted before the broken step is reached
+    export.workflow_steps[1].command_id = 9999;
+    let result = target.db.import_library(
+        &serde_json::to_string(&export).unwrap(),
+        &ImportMode::Merge,
+        false,
+    );
+    assert!(matches!(
+        result,
+        Err(DatabaseError::InvalidData {
+            field: "command_id",
+            ..
+        })
+    ));
+
+    assert!(target.db.get_categories().unwrap().is_empty());
+    assert!(target
+        .db
+        .get_groups(GroupFilter::All, CategoryFilter::All, TagFilter::All, false)
+        .unwrap()
+        .is_empty());
+    assert!(target.db.get_workflows(None, false).unwrap().is_empty());
+}
+
+#[test]
+fn test_import_library_rejects_other_documents() {
+    let test_db = TestDb::setup_test_db();
+    let import = |document: &str| {
+        test_db
+            .db
+            .import_library(document, &ImportMode::Merge, false)
+    };
+
+    assert!(matches!(
+        import("not json"),
+        Err(DatabaseError::InvalidData {
+            field: "document",
+            ..
+        })
+    ));
+    assert!(matches!(
+        import(r#"{"format": "something-else", "version": 1}"#),
+        Err(DatabaseError::InvalidData {
+            field: "format",
+            ..
+        })
+    ));
+    assert!(matches!(
+        import(r#"{"format": "tgui-library", "version": 2}"#),
+        Err(DatabaseError::InvalidData {
+            field: "version",
+            ..
+        })
+    ));
+}
@@ -1,12 +1,22 @@
 use super::*;
+mod bulk;
 mod categories;
 mod commands;
+mod duplicate;
 mod execution_history;
 mod explainer;
 mod groups;
 pub mod helpers;
 mod integration;
+mod library;
+mod project_scan;
+mod search;
 mod settings;
+mod shell_history;
+mod shells;
+mod suggestions;
+mod tags;
+mod templates;
 mod workflows;
 
 use tempfile::TempDir;
@@ -23,7 +33,7 @@ impl TestDb {
     pub fn setup_test_db() -> Self {
         let temp_dir = TempDir::new().unwrap();
         let db_path = temp_dir.path().join("test.db");
-        let db = Database::new(&db_path).unwrap();
+        let db = Database::open(&db_path, Some(SecretsKey::generate())).unwrap();
 
         TestDb {
             db,
@@ -96,6 +106,17 @@ impl CommandBuilder {
                 shell: None,
                 category_id: None,
                 is_favorite: false,
+                resource_limits: None,
+                env_inheritance: None,
+                env_unset: None,
+                parameters: vec![],
+                env_files: None,
+                secret_env_vars: None,
+                run_elevated: false,
+                execution_target: None,
+                raw_arguments: vec![],
+                shell_mode: None,
+                source_files: None,
                 created_at: String::new(),
                 updated_at: String::new(),
             },
@@ -107,6 +128,21 @@ impl CommandBuilder {
         self
     }
 
+    pub fn with_target(mut self, target: ExecutionTarget) -> Self {
+        self.command.execution_target = Some(target);
+        self
+    }
+
+    pub fn elevated(mut self) -> Self {
+        self.command.run_elevated = true;
+        self
+    }
+
+    pub fn with_raw_arguments(mut self, indices: Vec<usize>) -> Self {
+        self.command.raw_arguments = indices;
+        self
+    }
+
     pub fn with_args(mut self, args: Vec<&str>) -> Self {
         self.command.arguments = args.into_iter().map(String::from).collect();
         self
@@ -123,6 +159,40 @@ impl CommandBuilder {
         self
     }
 
+    pub fn with_resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.command.resource_limits = Some(limits);
+        self
+    }
+
+    pub fn with_env_inheritance(mut self, inheritance: EnvInheritance) -> Self {
+        self.command.env_inheritance = Some(inheritance);
+        self
+    }
+
+    pub fn with_env_unset(mut self, keys: Vec<&str>) -> Self {
+        self.command.env_unset = Some(keys.into_iter().map(String::from).collect());
+        self
+    }
+
+    pub fn with_env_files(mut self, paths: Vec<&str>) -> Self {
+        self.command.env_files = Some(paths.into_iter().map(String::from).collect());
+        self
+    }
+
+    pub fn with_secret(mut self, key: &str, value: &str) -> Self {
+        let secrets = self
+            .command
+            .secret_env_vars
+            .get_or_insert_with(HashMap::new);
+        secrets.insert(key.to_string(), SecretValue::from(value));
+        self
+    }
+
+    pub fn with_parameter(mut self, parameter: CommandParameter) -> Self {
+        self.command.parameters.push(parameter);
+        self
+    }
+
     pub fn build(self) -> Command {
         self.command
     }
@@ -151,6 +221,15 @@ impl GroupBuilder {
                 is_favorite: false,
                 icon: None,
                 color: None,
+                resource_limits: None,
+                env_inheritance: None,
+                env_unset: None,
+                variables: None,
+                env_files: None,
+                secret_env_vars: None,
+                execution_target: None,
+                shell_mode: None,
+                source_files: None,
                 created_at: String::new(),
                 updated_at: String::new(),
             },
@@ -173,6 +252,43 @@ impl GroupBuilder {
         self
     }
 
+    pub fn with_resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.group.resource_limits = Some(limits);
+        self
+    }
+
+    pub fn with_env_inheritance(mut self, inheritance: EnvInheritance) -> Self {
+        self.group.env_inheritance = Some(inheritance);
+        self
+    }
+
+    pub fn with_env_unset(mut self, keys: Vec<&str>) -> Self {
+        self.group.env_unset = Some(keys.into_iter().map(String::from).collect());
+        self
+    }
+
+    pub fn with_env_files(mut self, paths: Vec<&str>) -> Self {
+        self.group.env_files = Some(paths.into_iter().map(String::from).collect());
+        self
+    }
+
+    pub fn with_secret(mut self, key: &str, value: &str) -> Self {
+        let secrets = self.group.secret_env_vars.get_or_insert_with(HashMap::new);
+        secrets.insert(key.to_string(), SecretValue::from(value));
+        self
+    }
+
+    pub fn with_target(mut self, target: ExecutionTarget) -> Self {
+        self.group.execution_target = Some(target);
+        self
+    }
+
+    pub fn with_variable(mut self, key: &str, value: &str) -> Self {
+        let variables = self.group.variables.get_or_insert_with(HashMap::new);
+        variables.insert(key.to_string(), value.to_string());
+        self
+    }
+
     pub fn build(self) -> Group {
         self.group
     }
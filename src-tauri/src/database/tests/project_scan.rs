@@ -0,0 +1,145 @@
+use super::*;
+use std::fs;
+use std::path::Path;
+
+fn write(dir: &Path, name: &str, content: &str) {
+    let path = dir.join(name);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+fn task_names(tasks: &[ProjectTask]) -> Vec<&str> {
+    tasks.iter().map(|t| t.name.as_str()).collect()
+}
+
+fn project() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    write(
+        dir.path(),
+        "package.json",
+        r#"{"name": "web", "scripts": {"dev": "vite", "build": "vite build"}}"#,
+    );
+    write(dir.path(), "pnpm-lock.yaml", "");
+    write(
+        dir.path(),
+        "Makefile",
+        "CC := gcc\n.PHONY: test lint\nbuild lint: deps ## Build it\n\tgcc main.c\n%.o: %.c\n\t$(CC) $<\ntest:\n\t./run\nout/main.o: main.c\n",
+    );
+    write(
+        dir.path(),
+        "justfile",
+        "set shell := [\"bash\", \"-c\"]\nalias b := build\n\n# Deploy to an environment\ndeploy env=\"staging\": build\n    ./deploy {{env}}\n\n[private]\nhelper:\n    true\n_hidden:\n    true\n",
+    );
+    write(
+        dir.path(),
+        "Cargo.toml",
+        "[package]\nname = \"app\"\n\n[[bin]]\nname = \"worker\"\npath = \"src/worker.rs\"\n\n[dependencies]\nname = \"not-a-target\"\n",
+    );
+    write(dir.path(), "src/main.rs", "fn main() {}");
+    write(dir.path(), "examples/demo.rs", "fn main() {}");
+    write(
+        dir.path(),
+        "docker-compose.yml",
+        "version: \"3\"\nservices:\n  # the database\n  db:\n    image: postgres\n    ports:\n      - 5432\n  web:\n    build: .\nvolumes:\n  data:\n",
+    );
+    dir
+}
+
+#[test]
+fn test_scan_project_finds_tasks_of_every_runner() {
+    let test_db = TestDb::setup_test_db();
+    let dir = project();
+
+    let scan = test_db.db.scan_project(dir.path()).unwrap();
+    let canonical = dir.path().canonicalize().unwrap();
+    assert_eq!(
+        scan.group.working_directory.as_deref(),
+        Some(canonical.to_str().unwrap())
+    );
+    assert_eq!(
+        task_names(&scan.tasks),
+        vec![
+            "pnpm run build",
+            "pnpm run dev",
+            "make build",
+            "make lint",
+            "make test",
+            "just deploy",
+            "cargo run --bin worker",
+            "cargo run --bin app",
+            "cargo run --example demo",
+            "docker compose up db",
+            "docker compose up web",
+        ]
+    );
+    assert_eq!(scan.tasks[0].description.as_deref(), Some("vite build"));
+    assert_eq!(scan.tasks[2].description.as_deref(), Some("Build it"));
+    assert_eq!(
+        scan.tasks[5].description.as_deref(),
+        Some("Deploy to an environment")
+    );
+    assert_eq!(scan.tasks[5].source, TaskSource::Justfile);
+}
+
+#[test]
+fn test_scan_project_rejects_missing_directory() {
+    let test_db = TestDb::setup_test_db();
+    let result = test_db.db.scan_project(Path::new("/nonexistent/project"));
+    assert!(matches!(
+        result,
+        Err(DatabaseError::InvalidData { field: "path", .. })
+    ));
+}
+
+#[test]
+fn test_rescan_project_group_reports_added_and_removed_tasks() {
+    let test_db = TestDb::setup_test_db();
+    let dir = project();
+    let scan = test_db.db.scan_project(dir.path()).unwrap();
+    let group_id = test_db.db.create_project_group(&scan).unwrap();
+    // not a task, never reported as removed
+    test_db.create_test_command("Logs", "tail", Some(group_id));
+
+    let commands = test_db
+        .db
+        .get_commands(
+            GroupFilter::Group(group_id),
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+            None,
+            None,
+        )
+        .unwrap();
+    assert_eq!(commands.len(), scan.tasks.len() + 1);
+    assert_eq!(commands[0].working_directory, None);
+
+    let rescan = test_db.db.rescan_project_group(group_id).unwrap();
+    assert!(rescan.added.is_empty());
+    assert!(rescan.removed.is_empty());
+
+    write(
+        dir.path(),
+        "package.json",
+        r#"{"scripts": {"dev": "vite", "preview": "vite preview"}}"#,
+    );
+    let rescan = test_db.db.rescan_project_group(group_id).unwrap();
+    assert_eq!(task_names(&rescan.added), vec!["pnpm run preview"]);
+    let removed: Vec<_> = rescan.removed.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(removed, vec!["pnpm run build"]);
+
+    let ids = test_db
+        .db
+        .add_project_tasks(group_id, &rescan.added)
+        .unwrap();
+    assert_eq!(
+        test_db.db.get_command(ids[0]).unwrap().group_id,
+        Some(group_id)
+    );
+    assert!(test_db
+        .db
+        .rescan_project_group(group_id)
+        .unwrap()
+        .added
+        .is_empty());
+}
@@ -0,0 +1,186 @@
+use super::*;
+
+fn matched(segments: &[HighlightSegment]) -> Vec<&str> {
+    segments
+        .iter()
+        .filter(|segment| segment.matched)
+        .map(|segment| segment.text.as_str())
+        .collect()
+}
+
+fn result_ids(results: &[SearchResult]) -> Vec<(SearchKind, i64)> {
+    results.iter().map(|r| (r.kind, r.id)).collect()
+}
+
+#[test]
+fn test_search_all_finds_commands_groups_and_workflows() {
+    let test_db = TestDb::setup_test_db();
+    let group_id = test_db.create_test_group("Deployment");
+    let command_id = test_db.save_command_to_db(
+        &CommandBuilder::new("Release build", "cargo")
+            .with_args(vec!["build", "--release"])
+            .build(),
+    );
+    let workflow_id = test_db.create_test_workflow("Deploy everything");
+    test_db.create_test_command("Unrelated", "ls", None);
+
+    let results = test_db.db.search_all("depl", None).unwrap();
+    assert_eq!(
+        result_ids(&results),
+        vec![
+            (SearchKind::Group, group_id),
+            (SearchKind::Workflow, workflow_id)
+        ]
+    );
+    assert_eq!(matched(&results[0].name), vec!["Deployment"]);
+    assert_eq!(results[0].detail, None);
+
+    let results = test_db.db.search_all("release", None).unwrap();
+    assert_eq!(
+        result_ids(&results),
+        vec![(SearchKind::Command, command_id)]
+    );
+    let detail = results[0].detail.as_ref().unwrap();
+    let text: String = detail.iter().map(|s| s.text.as_str()).collect();
+    assert_eq!(text, "cargo build --release");
+    assert_eq!(matched(detail), vec!["release"]);
+}
+
+#[test]
+fn test_search_all_requires_every_word() {
+    let test_db = TestDb::setup_test_db();
+    let build_id = test_db.save_command_to_db(
+        &CommandBuilder::new("Build", "cargo")
+            .with_args(vec!["build"])
+            .build(),
+    );
+    test_db.save_command_to_db(
+        &CommandBuilder::new("Test", "cargo")
+            .with_args(vec!["test"])
+            .build(),
+    );
+
+    let results = test_db.db.search_all("cargo bui", None).unwrap();
+    assert_eq!(result_ids(&results), vec![(SearchKind::Command, build_id)]);
+}
+
+#[test]
+fn test_search_all_tolerates_typos() {
+    let test_db = TestDb::setup_test_db();
+    let id = test_db.create_test_command("Restart postgres", "systemctl", None);
+
+    let results = test_db.db.search_all("postgers", None).unwrap();
+    assert_eq!(result_ids(&results), vec![(SearchKind::Command, id)]);
+    assert_eq!(matched(&results[0].name), vec!["postgres"]);
+
+    // too short to be corrected
+    assert!(test_db.db.search_all("pst", None).unwrap().is_empty());
+}
+
+#[test]
+fn test_search_all_ranks_names_and_favorites_first() {
+    let test_db = TestDb::setup_test_db();
+    let in_description = test_db.save_command_to_db(&Command {
+        description: Some("Runs the docker daemon".to_string()),
+        ..CommandBuilder::new("Daemon", "dockerd").build()
+    });
+    let in_name = test_db.create_test_command("Docker prune", "docker", None);
+
+    let results = test_db.db.search_all("docker", None).unwrap();
+    assert_eq!(
+        result_ids(&results),
+        vec![
+            (SearchKind::Command, in_name),
+            (SearchKind::Command, in_description)
+        ]
+    );
+
+    let first = test_db.create_test_command("Docker ps", "docker", None);
+    let second = test_db.create_test_command("Docker ps", "docker", None);
+    test_db.db.toggle_command_favorite(second).unwrap();
+    let results = test_db.db.search_all("docker ps", None).unwrap();
+    assert_eq!(
+        result_ids(&results),
+        vec![(SearchKind::Command, second), (SearchKind::Command, first)]
+    );
+    assert!(results[0].is_favorite);
+}
+
+#[test]
+fn test_search_all_index_follows_updates_and_deletes() {
+    let test_db = TestDb::setup_test_db();
+    let group_id = test_db.create_test_group("Backend");
+    let command_id = test_db.create_test_command("Migrate", "diesel", Some(group_id));
+
+    let mut command = test_db.db.get_command(command_id).unwrap();
+    command.name = "Seed".to_string();
+    test_db.db.update_command(&command).unwrap();
+    assert!(test_db.db.search_all("migrate", None).unwrap().is_empty());
+    assert_eq!(test_db.db.search_all("seed", None).unwrap().len(), 1);
+
+    let workflow_id = test_db.create_test_workflow("Nightly");
+    let mut workflow = test_db.db.get_workflow(workflow_id).unwrap();
+    workflow.description = Some("Backups".to_string());
+    test_db.db.update_workflow(&workflow).unwrap();
+    assert_eq!(test_db.db.search_all("backup", None).unwrap().len(), 1);
+
+    // commands of the group go with it
+    test_db.db.delete_group(group_id).unwrap();
+    assert!(test_db.db.search_all("seed", None).unwrap().is_empty());
+    assert!(test_db.db.search_all("backend", None).unwrap().is_empty());
+}
+
+#[test]
+fn test_search_index_is_filled_for_existing_items_on_open() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("test.db");
+    let db = Database::open(&path, None).unwrap();
+    let group_id = db
+        .create_group(&GroupBuilder::new("Deployment").build())
+        .unwrap();
+    let command_id = db
+        .create_command(
+            &CommandBuilder::new("Ship", "cargo")
+                .with_args(vec!["deploy", "--release"])
+                .with_group(group_id)
+                .build(),
+        )
+        .unwrap();
+    let workflow_id = db
+        .create_workflow(&WorkflowBuilder::new("Deploy everything").build())
+        .unwrap();
+    // a database from before the index was added
+    db.conn()
+        .unwrap()
+        .execute("DELETE FROM search_index", [])
+        .unwrap();
+    drop(db);
+
+    let expected = vec![
+        (SearchKind::Group, group_id),
+        (SearchKind::Command, command_id),
+        (SearchKind::Workflow, workflow_id),
+    ];
+    let db = Database::open(&path, None).unwrap();
+    let results = result_ids(&db.search_all("depl", None).unwrap());
+    assert_eq!(results.len(), 3);
+    assert!(expected.iter().all(|item| results.contains(item)));
+    drop(db);
+
+    // an index that isn't empty is left alone, nothing is added twice
+    let db = Database::open(&path, None).unwrap();
+    assert_eq!(db.search_all("depl", None).unwrap().len(), 3);
+}
+
+#[test]
+fn test_search_all_ignores_query_syntax() {
+    let test_db = TestDb::setup_test_db();
+    let id = test_db.create_test_command("Tail logs", "tail", None);
+
+    assert!(test_db.db.search_all("", None).unwrap().is_empty());
+    assert!(test_db.db.search_all("  \"*  ", None).unwrap().is_empty());
+    let results = test_db.db.search_all("\"tail\" OR NEAR(", None).unwrap();
+    assert!(results.is_empty());
+    let results = test_db.db.search_all("tail*", Some(1)).unwrap();
+    assert_eq!(result_ids(&results), vec![(SearchKind::Command, id)]);
+}
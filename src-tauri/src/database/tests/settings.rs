@@ -134,6 +134,22 @@ fn test_initialize_settings_is_idempotent() {
     assert_eq!(test_db.db.get_setting("theme").unwrap(), "dark");
 }
 
+#[test]
+fn test_set_setting_default_shell_must_be_registered() {
+    let test_db = TestDb::setup_test_db();
+
+    let result = test_db
+        .db
+        .set_setting("default_shell", "tgui-no-such-shell");
+    assert!(matches!(
+        result,
+        Err(DatabaseError::InvalidData { field: "value", .. })
+    ));
+
+    test_db.db.set_setting("default_shell", "sh").unwrap();
+    assert_eq!(test_db.db.get_setting("default_shell").unwrap(), "sh");
+}
+
 #[test]
 fn test_set_setting_max_concurrent_processes_validates_number() {
     let test_db = TestDb::setup_test_db();
@@ -211,17 +227,82 @@ fn test_set_setting_kill_process_tree_validates_boolean() {
 }
 
 #[test]
-fn test_set_setting_available_shells_accepts_any_string() {
+fn test_available_shells_is_no_longer_a_setting() {
     let test_db = TestDb::setup_test_db();
-    // falls through to _ => Ok(()), no validation on this key
+    assert!(!test_db
+        .db
+        .get_all_settings()
+        .unwrap()
+        .contains_key("available_shells"));
+    assert!(matches!(
+        test_db.db.set_setting("available_shells", "[]"),
+        Err(DatabaseError::InvalidData { field: "key", .. })
+    ));
+}
+
+#[test]
+fn test_set_setting_redaction_rules_validates_patterns() {
+    let test_db = TestDb::setup_test_db();
+
+    for invalid in [
+        "not json",
+        r#"[{"name": "broken", "pattern": "(unclosed"}]"#,
+    ] {
+        let result = test_db.db.set_setting("redaction_rules", invalid);
+        assert!(matches!(
+            result,
+            Err(DatabaseError::InvalidData { field: "value", .. })
+        ));
+    }
+
+    let valid = r#"[{"name": "ticket", "pattern": "TICKET-[0-9]+"}]"#;
+    test_db.db.set_setting("redaction_rules", valid).unwrap();
+    assert_eq!(test_db.db.get_setting("redaction_rules").unwrap(), valid);
+
+    let result = test_db.db.set_setting("redact_common_tokens", "yes");
+    assert!(matches!(
+        result,
+        Err(DatabaseError::InvalidData { field: "value", .. })
+    ));
+}
+
+#[test]
+fn test_set_setting_spawn_deny_list_validates_patterns() {
+    let test_db = TestDb::setup_test_db();
+
+    for invalid in [r#"{"pattern": "rm"}"#, r#"["(unclosed"]"#] {
+        let result = test_db.db.set_setting("spawn_deny_list", invalid);
+        assert!(matches!(
+            result,
+            Err(DatabaseError::InvalidData { field: "value", .. })
+        ));
+    }
+
     test_db
         .db
-        .set_setting("available_shells", r#"["/bin/bash","/bin/zsh"]"#)
+        .set_setting("spawn_deny_list", r#"["^mkfs", "--no-preserve-root"]"#)
         .unwrap();
-    assert_eq!(
-        test_db.db.get_setting("available_shells").unwrap(),
-        r#"["/bin/bash","/bin/zsh"]"#
-    );
+    let result = test_db.db.set_setting("confirm_dangerous_commands", "no");
+    assert!(matches!(
+        result,
+        Err(DatabaseError::InvalidData { field: "value", .. })
+    ));
+}
+
+#[test]
+fn test_set_setting_elevation_method_validates_value() {
+    let test_db = TestDb::setup_test_db();
+
+    let result = test_db.db.set_setting("elevation_method", "doas");
+    assert!(matches!(
+        result,
+        Err(DatabaseError::InvalidData { field: "value", .. })
+    ));
+
+    for value in ["auto", "pkexec", "sudo"] {
+        test_db.db.set_setting("elevation_method", value).unwrap();
+        assert_eq!(test_db.db.get_setting("elevation_method").unwrap(), value);
+    }
 }
 
 #[test]
@@ -236,7 +317,6 @@ fn test_get_all_settings_returns_all_default_keys() {
         "auto_scroll_logs",
         "warn_before_kill",
         "kill_process_tree_by_default",
-        "available_shells",
         "default_shell",
     ] {
         assert!(all.contains_key(*key), "missing key: {key}");
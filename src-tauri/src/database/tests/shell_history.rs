@@ -0,0 +1,153 @@
+use super::*;
+use crate::database::shell_history::parse_shell_history;
+use std::io::Write;
+
+fn names(entries: &[ShellHistoryEntry]) -> Vec<&str> {
+    entries.iter().map(|e| e.name.as_str()).collect()
+}
+
+#[test]
+fn test_bash_history_ranked_by_frequency_then_recency() {
+    let entries = parse_shell_history(
+        HistorySource::Bash,
+        "#1700000000\ngit status\nls -la\ncargo test\ngit status\nls -la\ngit status\n\nmake\n",
+    );
+
+    assert_eq!(
+        names(&entries),
+        vec!["git status", "ls -la", "make", "cargo test"]
+    );
+    assert_eq!(entries[0].count, 3);
+    assert_eq!(entries[0].command, "git");
+    assert_eq!(entries[0].arguments, vec!["status"]);
+    assert_eq!(entries[0].shell, None);
+}
+
+#[test]
+fn test_zsh_extended_and_multiline_history() {
+    let entries = parse_shell_history(
+        HistorySource::Zsh,
+        ": 1700000000:0;docker compose up -d\n: 1700000005:2;echo one \\\ntwo\nplain --line\n",
+    );
+
+    let compose = entries.iter().find(|e| e.command == "docker").unwrap();
+    assert_eq!(compose.arguments, vec!["compose", "up", "-d"]);
+    let echo = entries.iter().find(|e| e.command == "echo").unwrap();
+    assert_eq!(echo.name, "echo one");
+    // the newline separates commands, so the line needs the shell
+    assert_eq!(echo.shell.as_deref(), Some("zsh"));
+    assert!(entries.iter().any(|e| e.command == "plain"));
+}
+
+#[test]
+fn test_fish_history_unescapes_commands() {
+    let entries = parse_shell_history(
+        HistorySource::Fish,
+        "- cmd: grep -r 'a\\\\b' src\n  when: 1700000000\n  paths:\n    - src\n- cmd: npm run dev\n  when: 1700000001\n",
+    );
+
+    assert_eq!(names(&entries), vec!["npm run dev", "grep -r 'a\\b' src"]);
+    assert_eq!(entries[1].arguments, vec!["-r", "a\\b", "src"]);
+}
+
+#[test]
+fn test_aliases_later_definition_wins() {
+    let entries = parse_shell_history(
+        HistorySource::Aliases,
+        "alias ll='ls -l'\n# comment\nalias -g gs=\"git status\" la='ls -A'\nalias ll='ls -la'\nexport PATH=x\n",
+    );
+
+    let mut names = names(&entries);
+    names.sort();
+    assert_eq!(names, vec!["gs", "la", "ll"]);
+    let ll = entries.iter().find(|e| e.name == "ll").unwrap();
+    assert_eq!(ll.arguments, vec!["-la"]);
+    assert_eq!(ll.count, 1);
+}
+
+#[test]
+fn test_shell_syntax_becomes_raw_arguments_and_env_vars() {
+    let entries = parse_shell_history(
+        HistorySource::Bash,
+        "RUST_LOG=debug cargo run -- \"*.rs\" | tee out.log\n(cd x && make)\necho 'it''s\n",
+    );
+
+    // the subshell can't be split into a command, the unbalanced quote not at all
+    assert_eq!(entries.len(), 1);
+    let entry = &entries[0];
+    assert_eq!(entry.command, "cargo");
+    assert_eq!(
+        entry.arguments,
+        vec!["run", "--", "*.rs", "|", "tee", "out.log"]
+    );
+    // the quoted glob stays quoted, the pipe is left to the shell
+    assert_eq!(entry.raw_arguments, vec![3]);
+    assert_eq!(entry.shell.as_deref(), Some("bash"));
+    assert_eq!(
+        entry
+            .env_vars
+            .as_ref()
+            .unwrap()
+            .get("RUST_LOG")
+            .map(String::as_str),
+        Some("debug")
+    );
+}
+
+#[test]
+fn test_read_shell_history_unmetafies_zsh() {
+    let test_db = TestDb::setup_test_db();
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    // 'é' is 0xC3 0xA9, zsh stores 0xA9 as 0x83 0x89
+    file.write_all(b": 1700000000:0;echo caf\xc3\x83\x89\n: 1700000001:0;ls\n")
+        .unwrap();
+
+    let entries = test_db
+        .db
+        .read_shell_history(HistorySource::Zsh, Some(file.path()), Some(1))
+        .unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].name, "ls");
+
+    let entries = test_db
+        .db
+        .read_shell_history(HistorySource::Zsh, Some(file.path()), None)
+        .unwrap();
+    assert!(entries.iter().any(|e| e.arguments == vec!["café"]));
+}
+
+#[test]
+fn test_import_shell_commands_skips_existing_command_lines() {
+    let test_db = TestDb::setup_test_db();
+    let group_id = test_db.create_test_group("Imported");
+    test_db.save_command_to_db(
+        &CommandBuilder::new("Status", "git")
+            .with_args(vec!["status"])
+            .with_group(group_id)
+            .build(),
+    );
+    let entries = parse_shell_history(
+        HistorySource::Bash,
+        "git status\nls -la | head\ngit status\nls -la | head\nmake\n",
+    );
+
+    let ids = test_db
+        .db
+        .import_shell_commands(&entries, Some(group_id))
+        .unwrap();
+    assert_eq!(ids.len(), 2);
+
+    let piped = test_db.db.get_command(ids[0]).unwrap();
+    assert_eq!(piped.name, "ls -la | head");
+    assert_eq!(piped.group_id, Some(group_id));
+    assert_eq!(piped.raw_arguments, vec![1]);
+    assert_eq!(piped.shell.as_deref(), Some("bash"));
+    assert_eq!(test_db.db.get_command(ids[1]).unwrap().command, "make");
+
+    // nothing new on the second run
+    assert!(test_db
+        .db
+        .import_shell_commands(&entries, Some(group_id))
+        .unwrap()
+        .is_empty());
+}
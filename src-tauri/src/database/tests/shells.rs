@@ -0,0 +1,268 @@
+use super::*;
+use crate::constants::SHELLS_TABLE;
+use std::os::unix::fs::PermissionsExt;
+
+fn custom_shell(name: &str, path: &str) -> ShellDefinition {
+    let mut shell = ShellDefinition::builtin(name, path);
+    shell.args_template = "-lc {cmd}".to_string();
+    shell
+}
+
+fn write_script(dir: &std::path::Path, mode: u32) -> String {
+    let path = dir.join("wrapper");
+    std::fs::write(&path, "#!/bin/sh\nexec sh \"$@\"\n").unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn test_registry_is_seeded_with_detected_shells() {
+    let test_db = TestDb::setup_test_db();
+    let shells = test_db.db.get_shells().unwrap();
+
+    assert!(!shells.is_empty());
+    let sh = shells.iter().find(|shell| shell.name == "sh").unwrap();
+    assert_eq!(sh.args_template, "-c {cmd}");
+    assert!(!sh.login && !sh.interactive);
+}
+
+#[test]
+fn test_seeding_keeps_existing_registry() {
+    let test_db = TestDb::setup_test_db();
+    let sh = test_db.db.get_shell_by_name("sh").unwrap();
+    test_db.db.delete_shell(sh.id).unwrap();
+
+    test_db.db.seed_shells().unwrap();
+    assert!(matches!(
+        test_db.db.get_shell_by_name("sh"),
+        Err(DatabaseError::NotFound { .. })
+    ));
+}
+
+#[test]
+fn test_seeding_runs_once() {
+    let test_db = TestDb::setup_test_db();
+    // registry filled by a version that didn't record the seeding
+    test_db
+        .db
+        .conn()
+        .unwrap()
+        .execute(
+            "DELETE FROM applied_migrations WHERE name = 'builtin_shells_seeded'",
+            [],
+        )
+        .unwrap();
+    let sh = test_db.db.get_shell_by_name("sh").unwrap();
+    test_db.db.delete_shell(sh.id).unwrap();
+    test_db.db.seed_shells().unwrap();
+    assert!(test_db.db.get_shell_by_name("sh").is_err());
+
+    for shell in test_db.db.get_shells().unwrap() {
+        test_db.db.delete_shell(shell.id).unwrap();
+    }
+    test_db.db.seed_shells().unwrap();
+    assert!(test_db.db.get_shells().unwrap().is_empty());
+    assert_eq!(test_db.db.get_setting("default_shell").unwrap(), "");
+}
+
+#[test]
+fn test_default_shell_follows_the_registry() {
+    let test_db = TestDb::setup_test_db();
+    let dir = tempfile::TempDir::new().unwrap();
+    let id = test_db
+        .db
+        .create_shell(&custom_shell("wrapped", &write_script(dir.path(), 0o755)))
+        .unwrap();
+    test_db.db.set_setting("default_shell", "wrapped").unwrap();
+
+    test_db.db.initialize_settings().unwrap();
+    assert_eq!(test_db.db.get_setting("default_shell").unwrap(), "wrapped");
+
+    let mut shell = test_db.db.get_shell(id).unwrap();
+    shell.name = "wrapped-login".to_string();
+    test_db.db.update_shell(&shell).unwrap();
+    assert_eq!(
+        test_db.db.get_setting("default_shell").unwrap(),
+        "wrapped-login"
+    );
+
+    test_db.db.delete_shell(id).unwrap();
+    let default_shell = test_db.db.get_setting("default_shell").unwrap();
+    assert!(test_db.db.get_shell_by_name(&default_shell).is_ok());
+}
+
+#[test]
+fn test_create_and_get_custom_shell() {
+    let test_db = TestDb::setup_test_db();
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = write_script(dir.path(), 0o755);
+
+    let mut shell = custom_shell("wrapped", &path);
+    shell.interactive = true;
+    shell.family = ShellFamily::Fish;
+    let id = test_db.db.create_shell(&shell).unwrap();
+
+    let stored = test_db.db.get_shell(id).unwrap();
+    assert_eq!(stored.name, "wrapped");
+    assert_eq!(stored.path, path);
+    assert_eq!(stored.args_template, "-lc {cmd}");
+    assert!(stored.interactive);
+    assert_eq!(stored.family, ShellFamily::Fish);
+    assert_eq!(test_db.db.get_shell_by_name("wrapped").unwrap().id, id);
+}
+
+#[test]
+fn test_create_shell_resolves_bare_names_in_path() {
+    let test_db = TestDb::setup_test_db();
+    assert!(test_db
+        .db
+        .create_shell(&custom_shell("login-sh", "sh"))
+        .is_ok());
+
+    let result = test_db
+        .db
+        .create_shell(&custom_shell("missing", "tgui-no-such-shell"));
+    assert!(matches!(
+        result,
+        Err(DatabaseError::InvalidData { field: "path", .. })
+    ));
+}
+
+#[test]
+fn test_create_shell_rejects_non_executable_path() {
+    let test_db = TestDb::setup_test_db();
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = write_script(dir.path(), 0o644);
+
+    let result = test_db.db.create_shell(&custom_shell("wrapped", &path));
+    assert!(matches!(
+        result,
+        Err(DatabaseError::InvalidData { field: "path", .. })
+    ));
+}
+
+#[test]
+fn test_create_shell_rejects_template_without_placeholder() {
+    let test_db = TestDb::setup_test_db();
+    let mut shell = custom_shell("broken", "/bin/sh");
+    shell.args_template = "-c".to_string();
+
+    let result = test_db.db.create_shell(&shell);
+    assert!(matches!(
+        result,
+        Err(DatabaseError::InvalidData {
+            field: "args_template",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_create_shell_rejects_modes_the_family_lacks() {
+    let test_db = TestDb::setup_test_db();
+    let mut shell = custom_shell("cmd-like", "/bin/sh");
+    shell.family = ShellFamily::Cmd;
+    shell.login = true;
+
+    let result = test_db.db.create_shell(&shell);
+    assert!(matches!(
+        result,
+        Err(DatabaseError::InvalidData { field: "login", .. })
+    ));
+
+    shell.family = ShellFamily::Fish;
+    test_db.db.create_shell(&shell).unwrap();
+}
+
+#[test]
+fn test_create_shell_duplicate_name() {
+    let test_db = TestDb::setup_test_db();
+    let result = test_db.db.create_shell(&custom_shell("sh", "/bin/sh"));
+    assert!(matches!(result, Err(DatabaseError::Internal(msg)) if msg.contains("UNIQUE")));
+}
+
+#[test]
+fn test_get_shell_by_name_is_case_sensitive() {
+    let test_db = TestDb::setup_test_db();
+    for name in ["", "SH", "rm", "/bin/sh", "../../../bin/sh"] {
+        assert!(
+            matches!(
+                test_db.db.get_shell_by_name(name),
+                Err(DatabaseError::NotFound {
+                    entity: SHELLS_TABLE,
+                    ..
+                })
+            ),
+            "'{name}' should not be in the registry"
+        );
+    }
+}
+
+#[test]
+fn test_rename_shell_updates_commands_and_groups() {
+    let test_db = TestDb::setup_test_db();
+    let id = test_db
+        .db
+        .create_shell(&custom_shell("login-sh", "/bin/sh"))
+        .unwrap();
+
+    let mut group = GroupBuilder::new("Group").build();
+    group.shell = Some("login-sh".to_string());
+    let group_id = test_db.save_group_to_db(&group);
+    let mut command = CommandBuilder::new("Cmd", "ls").build();
+    command.shell = Some("login-sh".to_string());
+    let command_id = test_db.save_command_to_db(&command);
+
+    let mut shell = test_db.db.get_shell(id).unwrap();
+    shell.name = "posix-login".to_string();
+    test_db.db.update_shell(&shell).unwrap();
+
+    assert_eq!(
+        test_db.db.get_command(command_id).unwrap().shell.as_deref(),
+        Some("posix-login")
+    );
+    assert_eq!(
+        test_db.db.get_group(group_id).unwrap().shell.as_deref(),
+        Some("posix-login")
+    );
+}
+
+#[test]
+fn test_update_shell_validates_and_reports_missing() {
+    let test_db = TestDb::setup_test_db();
+    let mut shell = test_db.db.get_shell_by_name("sh").unwrap();
+    shell.args_template = "{cmd} {cmd}".to_string();
+    assert!(matches!(
+        test_db.db.update_shell(&shell),
+        Err(DatabaseError::InvalidData {
+            field: "args_template",
+            ..
+        })
+    ));
+
+    let mut missing = custom_shell("ghost", "/bin/sh");
+    missing.id = 9999;
+    assert!(matches!(
+        test_db.db.update_shell(&missing),
+        Err(DatabaseError::NotFound { .. })
+    ));
+}
+
+#[test]
+fn test_delete_shell() {
+    let test_db = TestDb::setup_test_db();
+    let id = test_db
+        .db
+        .create_shell(&custom_shell("login-sh", "/bin/sh"))
+        .unwrap();
+    test_db.db.delete_shell(id).unwrap();
+
+    assert!(matches!(
+        test_db.db.get_shell(id),
+        Err(DatabaseError::NotFound { .. })
+    ));
+    assert!(matches!(
+        test_db.db.delete_shell(id),
+        Err(DatabaseError::NotFound { .. })
+    ));
+}
@@ -0,0 +1,124 @@
+use super::*;
+use rusqlite::params;
+
+fn record_run(test_db: &TestDb, command_id: i64, status: ExecutionStatus, days_ago: i64) {
+    let id = test_db.save_execution_history(
+        &ExecutionHistoryBuilder::new()
+            .with_command(command_id)
+            .build(),
+    );
+    test_db
+        .db
+        .update_execution_history_status(id, status, None)
+        .unwrap();
+    test_db
+        .db
+        .conn()
+        .unwrap()
+        .execute(
+            "UPDATE execution_history SET started_at = datetime('now', ?1) WHERE id = ?2",
+            params![format!("-{days_ago} days"), id],
+        )
+        .unwrap();
+}
+
+fn suggestion_ids(suggestions: &[CommandSuggestion]) -> Vec<i64> {
+    suggestions.iter().map(|s| s.command.id).collect()
+}
+
+#[test]
+fn test_suggestions_without_query_are_ordered_by_frecency() {
+    let test_db = TestDb::setup_test_db();
+    let never_run = test_db.create_test_command("Never run", "true", None);
+    let old = test_db.create_test_command("Old", "true", None);
+    let recent = test_db.create_test_command("Recent", "true", None);
+    let frequent = test_db.create_test_command("Frequent", "true", None);
+
+    record_run(&test_db, old, ExecutionStatus::Success, 200);
+    record_run(&test_db, recent, ExecutionStatus::Success, 1);
+    for days_ago in [20, 25, 40] {
+        record_run(&test_db, frequent, ExecutionStatus::Success, days_ago);
+    }
+
+    let suggestions = test_db.db.get_command_suggestions("", 10).unwrap();
+    assert_eq!(
+        suggestion_ids(&suggestions),
+        vec![frequent, recent, old, never_run]
+    );
+    assert_eq!(suggestions[0].frecency, 130.0);
+    assert_eq!(suggestions[3].frecency, 0.0);
+
+    let suggestions = test_db.db.get_command_suggestions("", 2).unwrap();
+    assert_eq!(suggestion_ids(&suggestions), vec![frequent, recent]);
+}
+
+#[test]
+fn test_suggestions_weight_failures_less_and_boost_favorites() {
+    let test_db = TestDb::setup_test_db();
+    let failing = test_db.create_test_command("Failing", "false", None);
+    let passing = test_db.create_test_command("Passing", "true", None);
+    let favorite = test_db.create_test_command("Favorite", "true", None);
+
+    record_run(&test_db, failing, ExecutionStatus::Failed, 1);
+    record_run(&test_db, failing, ExecutionStatus::Failed, 1);
+    record_run(&test_db, passing, ExecutionStatus::Success, 1);
+    test_db.db.toggle_command_favorite(favorite).unwrap();
+
+    let suggestions = test_db.db.get_command_suggestions("", 10).unwrap();
+    assert_eq!(
+        suggestion_ids(&suggestions),
+        vec![passing, failing, favorite]
+    );
+    assert_eq!(suggestions[1].frecency, 80.0);
+    assert_eq!(suggestions[2].frecency, 50.0);
+}
+
+#[test]
+fn test_suggestions_match_name_fuzzily() {
+    let test_db = TestDb::setup_test_db();
+    let build = test_db.create_test_command("Build Release", "cargo", None);
+    let bump = test_db.create_test_command("Bump version", "npm", None);
+    test_db.create_test_command("Lint", "cargo", None);
+
+    let suggestions = test_db.db.get_command_suggestions("bre", 10).unwrap();
+    assert_eq!(suggestion_ids(&suggestions), vec![build]);
+    assert_eq!(suggestions[0].matched_indices, vec![0, 6, 7]);
+
+    let suggestions = test_db.db.get_command_suggestions("bu", 10).unwrap();
+    assert_eq!(suggestion_ids(&suggestions), vec![build, bump]);
+    assert_eq!(suggestions[1].matched_indices, vec![0, 1]);
+
+    assert!(test_db
+        .db
+        .get_command_suggestions("xyz", 10)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_suggestions_fall_back_to_command_line() {
+    let test_db = TestDb::setup_test_db();
+    let id = test_db.save_command_to_db(
+        &CommandBuilder::new("Serve", "python3")
+            .with_args(vec!["-m", "http.server"])
+            .build(),
+    );
+
+    let suggestions = test_db.db.get_command_suggestions("http", 10).unwrap();
+    assert_eq!(suggestion_ids(&suggestions), vec![id]);
+    assert!(suggestions[0].matched_indices.is_empty());
+}
+
+#[test]
+fn test_suggestions_frecency_outranks_slightly_better_match() {
+    let test_db = TestDb::setup_test_db();
+    let exact = test_db.create_test_command("deploy", "true", None);
+    let used = test_db.create_test_command("redeploy", "true", None);
+    for _ in 0..5 {
+        record_run(&test_db, used, ExecutionStatus::Success, 1);
+    }
+
+    let suggestions = test_db.db.get_command_suggestions("deploy", 10).unwrap();
+    assert_eq!(suggestion_ids(&suggestions), vec![used, exact]);
+    assert!(suggestions[0].score > suggestions[1].score);
+}
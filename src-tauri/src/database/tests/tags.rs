@@ -0,0 +1,247 @@
+use super::*;
+
+fn command_ids(test_db: &TestDb, tags: TagFilter) -> Vec<i64> {
+    test_db
+        .db
+        .get_commands(
+            GroupFilter::All,
+            CategoryFilter::All,
+            tags,
+            false,
+            None,
+            None,
+        )
+        .unwrap()
+        .iter()
+        .map(|c| c.id)
+        .collect()
+}
+
+#[test]
+fn test_tag_crud() {
+    let test_db = TestDb::setup_test_db();
+    let id = test_db.db.create_tag("docker", Some("#2496ED")).unwrap();
+    test_db.db.create_tag("backend", None).unwrap();
+
+    let tag = test_db.db.get_tag(id).unwrap();
+    assert_eq!(tag.name, "docker");
+    assert_eq!(tag.color.as_deref(), Some("#2496ED"));
+
+    test_db.db.update_tag(id, "containers", None).unwrap();
+    let names: Vec<_> = test_db
+        .db
+        .get_tags()
+        .unwrap()
+        .into_iter()
+        .map(|t| t.name)
+        .collect();
+    assert_eq!(names, vec!["backend", "containers"]);
+
+    test_db.db.delete_tag(id).unwrap();
+    assert!(matches!(
+        test_db.db.get_tag(id),
+        Err(DatabaseError::NotFound { .. })
+    ));
+}
+
+#[test]
+fn test_tag_names_are_unique_and_not_empty() {
+    let test_db = TestDb::setup_test_db();
+    test_db.db.create_tag("docker", None).unwrap();
+
+    assert!(test_db.db.create_tag("docker", None).is_err());
+    assert!(matches!(
+        test_db.db.create_tag("  ", None),
+        Err(DatabaseError::InvalidData { field: "name", .. })
+    ));
+    assert!(matches!(
+        test_db.db.update_tag(999, "other", None),
+        Err(DatabaseError::NotFound { .. })
+    ));
+}
+
+#[test]
+fn test_item_tags_link_and_unlink() {
+    let test_db = TestDb::setup_test_db();
+    let docker = test_db.db.create_tag("docker", None).unwrap();
+    let backend = test_db.db.create_tag("backend", None).unwrap();
+    let command_id = test_db.create_test_command("Up", "docker", None);
+
+    test_db
+        .db
+        .add_item_tag(TagTarget::Command, command_id, docker)
+        .unwrap();
+    test_db
+        .db
+        .add_item_tag(TagTarget::Command, command_id, docker)
+        .unwrap();
+    test_db
+        .db
+        .add_item_tag(TagTarget::Command, command_id, backend)
+        .unwrap();
+    let tags = test_db
+        .db
+        .get_item_tags(TagTarget::Command, command_id)
+        .unwrap();
+    assert_eq!(tags.len(), 2);
+    assert_eq!(tags[0].name, "backend");
+
+    test_db
+        .db
+        .remove_item_tag(TagTarget::Command, command_id, docker)
+        .unwrap();
+    assert!(matches!(
+        test_db
+            .db
+            .remove_item_tag(TagTarget::Command, command_id, docker),
+        Err(DatabaseError::NotFound { .. })
+    ));
+
+    test_db
+        .db
+        .set_item_tags(TagTarget::Command, command_id, &[docker])
+        .unwrap();
+    let tags = test_db
+        .db
+        .get_item_tags(TagTarget::Command, command_id)
+        .unwrap();
+    assert_eq!(tags, vec![test_db.db.get_tag(docker).unwrap()]);
+}
+
+#[test]
+fn test_item_tags_require_existing_item_and_tag() {
+    let test_db = TestDb::setup_test_db();
+    let tag_id = test_db.db.create_tag("docker", None).unwrap();
+    let workflow_id = test_db.create_test_workflow("Deploy");
+
+    assert!(matches!(
+        test_db.db.add_item_tag(TagTarget::Group, 999, tag_id),
+        Err(DatabaseError::NotFound {
+            entity: "groups",
+            ..
+        })
+    ));
+    assert!(matches!(
+        test_db
+            .db
+            .add_item_tag(TagTarget::Workflow, workflow_id, 999),
+        Err(DatabaseError::NotFound { entity: "tags", .. })
+    ));
+    // nothing is replaced when one of the tags is missing
+    test_db
+        .db
+        .set_item_tags(TagTarget::Workflow, workflow_id, &[tag_id])
+        .unwrap();
+    assert!(test_db
+        .db
+        .set_item_tags(TagTarget::Workflow, workflow_id, &[999])
+        .is_err());
+    assert_eq!(
+        test_db
+            .db
+            .get_item_tags(TagTarget::Workflow, workflow_id)
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn test_get_commands_tag_filters() {
+    let test_db = TestDb::setup_test_db();
+    let docker = test_db.db.create_tag("docker", None).unwrap();
+    let backend = test_db.db.create_tag("backend", None).unwrap();
+    let both = test_db.create_test_command("Both", "docker", None);
+    let docker_only = test_db.create_test_command("Docker only", "docker", None);
+    let untagged = test_db.create_test_command("Untagged", "ls", None);
+    test_db
+        .db
+        .set_item_tags(TagTarget::Command, both, &[docker, backend])
+        .unwrap();
+    test_db
+        .db
+        .set_item_tags(TagTarget::Command, docker_only, &[docker])
+        .unwrap();
+
+    assert_eq!(
+        command_ids(&test_db, TagFilter::All),
+        vec![both, docker_only, untagged]
+    );
+    assert_eq!(
+        command_ids(&test_db, TagFilter::AnyOf(vec![docker, backend])),
+        vec![both, docker_only]
+    );
+    assert_eq!(
+        command_ids(&test_db, TagFilter::AllOf(vec![docker, backend])),
+        vec![both]
+    );
+    assert_eq!(command_ids(&test_db, TagFilter::None), vec![untagged]);
+    assert!(command_ids(&test_db, TagFilter::AnyOf(vec![])).is_empty());
+}
+
+#[test]
+fn test_get_groups_tag_filter_combines_with_other_filters() {
+    let test_db = TestDb::setup_test_db();
+    let tag_id = test_db.db.create_tag("frontend", None).unwrap();
+    let parent_id = test_db.create_test_group("Parent");
+    let child_id =
+        test_db.save_group_to_db(&GroupBuilder::new("Child").with_parent(parent_id).build());
+    test_db
+        .db
+        .add_item_tag(TagTarget::Group, parent_id, tag_id)
+        .unwrap();
+    test_db
+        .db
+        .add_item_tag(TagTarget::Group, child_id, tag_id)
+        .unwrap();
+
+    let groups = test_db
+        .db
+        .get_groups(
+            GroupFilter::None,
+            CategoryFilter::All,
+            TagFilter::AnyOf(vec![tag_id]),
+            false,
+        )
+        .unwrap();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].id, parent_id);
+}
+
+#[test]
+fn test_tag_counts_and_cascades() {
+    let test_db = TestDb::setup_test_db();
+    let tag_id = test_db.db.create_tag("ops", None).unwrap();
+    let group_id = test_db.create_test_group("Ops");
+    let first = test_db.create_test_command("First", "true", Some(group_id));
+    let second = test_db.create_test_command("Second", "true", None);
+    for command_id in [first, second] {
+        test_db
+            .db
+            .add_item_tag(TagTarget::Command, command_id, tag_id)
+            .unwrap();
+    }
+    test_db
+        .db
+        .add_item_tag(TagTarget::Group, group_id, tag_id)
+        .unwrap();
+
+    assert_eq!(test_db.db.get_tag_command_count(tag_id).unwrap(), 2);
+    assert_eq!(test_db.db.get_tag_group_count(tag_id).unwrap(), 1);
+    assert_eq!(test_db.db.get_tag_workflow_count(tag_id).unwrap(), 0);
+    assert!(matches!(
+        test_db.db.get_tag_command_count(999),
+        Err(DatabaseError::NotFound { .. })
+    ));
+
+    test_db.db.delete_group(group_id).unwrap();
+    assert_eq!(test_db.db.get_tag_command_count(tag_id).unwrap(), 1);
+    assert_eq!(test_db.db.get_tag_group_count(tag_id).unwrap(), 0);
+
+    test_db.db.delete_tag(tag_id).unwrap();
+    assert!(test_db
+        .db
+        .get_item_tags(TagTarget::Command, second)
+        .unwrap()
+        .is_empty());
+}
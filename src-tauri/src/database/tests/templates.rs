@@ -0,0 +1,485 @@
+use super::*;
+use serde_json::json;
+
+fn template(structure: serde_json::Value) -> Template {
+    Template {
+        id: 0,
+        name: "Web Project".to_string(),
+        description: Some("Frontend and backend".to_string()),
+        author: None,
+        structure: structure.to_string(),
+        created_at: String::new(),
+        updated_at: String::new(),
+    }
+}
+
+fn web_project() -> serde_json::Value {
+    json!({
+        "$schema": "https://tgui.dev/schemas/template-v1.json",
+        "name": "Web Project",
+        "version": "1.0.0",
+        "variables": [
+            {"key": "project_dir", "label": "Project Directory", "type": "path", "required": true},
+            {"key": "port", "label": "Port", "type": "number", "default": "8000"}
+        ],
+        "groups": [{
+            "name": "Web Project",
+            "default_working_directory": "{{project_dir}}",
+            "default_env_vars": {"PORT": "{{port}}"},
+            "default_shell": "sh",
+            "groups": [{
+                "name": "Frontend",
+                "default_working_directory": "{{project_dir}}/frontend",
+                "commands": [
+                    {"name": "npm install", "command": "npm", "arguments": ["install"], "category": "Node.js"}
+                ]
+            }]
+        }],
+        "commands": [{
+            "name": "Start Server",
+            "command": "python",
+            "arguments": ["-m", "http.server", "{{port}}"],
+            "working_directory": "{{project_dir}}/public",
+            "category": "Python"
+        }]
+    })
+}
+
+fn errors(db: &Database, structure: serde_json::Value) -> Vec<String> {
+    let validation = db.validate_template_structure(&structure.to_string());
+    assert_eq!(validation.valid, validation.errors.is_empty());
+    validation.errors
+}
+
+#[test]
+fn test_template_crud() {
+    let test_db = TestDb::setup_test_db();
+    let db = &test_db.db;
+
+    let id = db.create_template(&template(web_project())).unwrap();
+    let mut stored = db.get_template(id).unwrap();
+    assert_eq!(stored.name, "Web Project");
+    assert_eq!(db.get_templates().unwrap().len(), 1);
+
+    stored.name = "Renamed".to_string();
+    db.update_template(&stored).unwrap();
+    assert_eq!(db.get_template(id).unwrap().name, "Renamed");
+
+    db.delete_template(id).unwrap();
+    assert!(matches!(
+        db.get_template(id),
+        Err(DatabaseError::NotFound { .. })
+    ));
+}
+
+#[test]
+fn test_create_template_rejects_invalid_structure() {
+    let test_db = TestDb::setup_test_db();
+    let mut structure = web_project();
+    structure["version"] = json!("one");
+
+    let result = test_db.db.create_template(&template(structure));
+    assert!(matches!(
+        result,
+        Err(DatabaseError::InvalidData {
+            field: "structure",
+            ..
+        })
+    ));
+    assert!(test_db.db.get_templates().unwrap().is_empty());
+}
+
+#[test]
+fn test_validate_template_reports_every_problem() {
+    let test_db = TestDb::setup_test_db();
+    let errors = errors(
+        &test_db.db,
+        json!({
+            "name": "Broken",
+            "version": "2.0.0",
+            "variables": [{"key": "project-dir", "type": "string"}],
+            "commands": [
+                {"name": "Install", "command": "{{python_ver}}", "arguments": ["-m", "pip"]},
+                {"name": "Wipe", "command": "rm", "arguments": ["-rf", "/"]},
+                {"name": "Root", "command": "sudo", "arguments": ["apt", "update"]}
+            ]
+        }),
+    );
+
+    assert_eq!(
+        errors,
+        vec![
+            "Version '2.0.0' is not supported, only 1.x templates are",
+            "Variable 'project-dir' has invalid key (use letters, digits and underscores)",
+            "Command 'Install' uses undefined variable '{{python_ver}}'",
+            "Command 'Wipe' is a dangerous command: 'rm -rf /'",
+            "Command 'Root' runs with elevated privileges: 'sudo apt update'",
+        ]
+    );
+}
+
+#[test]
+fn test_validate_template_limits_nesting_depth() {
+    let test_db = TestDb::setup_test_db();
+    let mut group = json!({"name": "level 10"});
+    for level in (1..10).rev() {
+        group = json!({"name": format!("level {level}"), "groups": [group]});
+    }
+
+    let errors = errors(
+        &test_db.db,
+        json!({"name": "Deep", "version": "1.0.0", "variables": [], "groups": [group]}),
+    );
+    assert_eq!(
+        errors,
+        vec!["Group 'level 10' is nested deeper than 9 levels"]
+    );
+}
+
+#[test]
+fn test_validate_template_reports_malformed_json() {
+    let test_db = TestDb::setup_test_db();
+    let validation = test_db
+        .db
+        .validate_template_structure(r#"{"name": "No version", "variables": []}"#);
+    assert!(!validation.valid);
+    assert!(validation.errors[0].contains("missing field `version`"));
+}
+
+#[test]
+fn test_apply_template_creates_the_tree() {
+    let test_db = TestDb::setup_test_db();
+    let db = &test_db.db;
+    let project = tempfile::TempDir::new().unwrap();
+    let project_dir = project.path().to_string_lossy().into_owned();
+    let parent_id = db
+        .create_group(&GroupBuilder::new("Projects").build())
+        .unwrap();
+    let id = db.create_template(&template(web_project())).unwrap();
+
+    let values = HashMap::from([("project_dir".to_string(), project_dir.clone())]);
+    let applied = db.apply_template(id, &values, Some(parent_id)).unwrap();
+    assert_eq!(applied.group_ids.len(), 2);
+    assert_eq!(applied.command_ids.len(), 2);
+
+    let root = db.get_group(applied.group_ids[0]).unwrap();
+    assert_eq!(root.parent_group_id, Some(parent_id));
+    assert_eq!(
+        root.working_directory.as_deref(),
+        Some(project_dir.as_str())
+    );
+    assert_eq!(root.env_vars.unwrap()["PORT"], "8000");
+    assert_eq!(root.shell.as_deref(), Some("sh"));
+
+    let frontend = db.get_group(applied.group_ids[1]).unwrap();
+    assert_eq!(frontend.parent_group_id, Some(root.id));
+    assert_eq!(
+        frontend.working_directory,
+        Some(format!("{project_dir}/frontend"))
+    );
+
+    let npm = db.get_command(applied.command_ids[0]).unwrap();
+    assert_eq!(npm.group_id, Some(frontend.id));
+    let server = db.get_command(applied.command_ids[1]).unwrap();
+    assert_eq!(server.group_id, Some(parent_id));
+    assert_eq!(server.arguments, vec!["-m", "http.server", "8000"]);
+
+    let categories: Vec<String> = db
+        .get_categories()
+        .unwrap()
+        .into_iter()
+        .map(|c| c.name)
+        .collect();
+    assert_eq!(categories, vec!["Node.js", "Python"]);
+    assert_eq!(
+        npm.category_id,
+        db.get_categories().unwrap().first().map(|c| c.id)
+    );
+}
+
+#[test]
+fn test_apply_template_twice_reuses_categories() {
+    let test_db = TestDb::setup_test_db();
+    let db = &test_db.db;
+    let project = tempfile::TempDir::new().unwrap();
+    let id = db.create_template(&template(web_project())).unwrap();
+    let values = HashMap::from([(
+        "project_dir".to_string(),
+        project.path().to_string_lossy().into_owned(),
+    )]);
+
+    db.apply_template(id, &values, None).unwrap();
+    db.apply_template(id, &values, None).unwrap();
+    assert_eq!(db.get_categories().unwrap().len(), 2);
+}
+
+#[test]
+fn test_apply_template_checks_variables_before_creating_anything() {
+    let test_db = TestDb::setup_test_db();
+    let db = &test_db.db;
+    let project = tempfile::TempDir::new().unwrap();
+    let project_dir = project.path().to_string_lossy().into_owned();
+    let id = db.create_template(&template(web_project())).unwrap();
+
+    let cases = [
+        (HashMap::new(), "Variable 'project_dir' is required"),
+        (
+            HashMap::from([("project_dir".to_string(), "/nonexistent_tgui".to_string())]),
+            "must be an existing path",
+        ),
+        (
+            HashMap::from([
+                ("project_dir".to_string(), project_dir.clone()),
+                ("port".to_string(), "eighty".to_string()),
+            ]),
+            "must be a number",
+        ),
+    ];
+    for (values, expected) in cases {
+        match db.apply_template(id, &values, None) {
+            Err(DatabaseError::InvalidData { reason, .. }) => {
+                assert!(reason.contains(expected), "{reason}")
+            }
+            other => panic!("Expected invalid data, got {other:?}"),
+        }
+    }
+    assert!(db
+        .get_groups(GroupFilter::All, CategoryFilter::All, TagFilter::All, false)
+        .unwrap()
+        .is_empty());
+    assert!(db.get_categories().unwrap().is_empty());
+}
+
+#[test]
+fn test_apply_template_checks_substituted_values() {
+    let test_db = TestDb::setup_test_db();
+    let db = &test_db.db;
+    let id = db
+        .create_template(&template(json!({
+            "name": "Cleanup",
+            "version": "1.0.0",
+            "variables": [
+                {"key": "tool", "type": "string", "required": true},
+                {"key": "dir", "type": "string", "default": "build"}
+            ],
+            "commands": [{
+                "name": "Clean",
+                "command": "{{tool}}",
+                "arguments": ["-rf", "/"],
+                "working_directory": "/srv/{{dir}}"
+            }]
+        })))
+        .unwrap();
+
+    let values = HashMap::from([("tool".to_string(), "rm".to_string())]);
+    assert!(matches!(
+        db.apply_template(id, &values, None),
+        Err(DatabaseError::InvalidData {
+            field: "variables",
+            ..
+        })
+    ));
+
+    let values = HashMap::from([
+        ("tool".to_string(), "echo".to_string()),
+        ("dir".to_string(), "../etc".to_string()),
+    ]);
+    assert!(matches!(
+        db.apply_template(id, &values, None),
+        Err(DatabaseError::InvalidData {
+            field: "working_directory",
+            ..
+        })
+    ));
+    assert!(db
+        .get_commands(
+            GroupFilter::All,
+            CategoryFilter::All,
+            TagFilter::All,
+            false,
+            None,
+            None
+        )
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_capture_group_template_round_trips_to_another_checkout() {
+    let test_db = TestDb::setup_test_db();
+    let db = &test_db.db;
+    let category_id = db.create_category("Node.js", None, None).unwrap();
+
+    let mut root = GroupBuilder::new("App")
+        .with_env("APP_HOME", "/home/me/app")
+        .build();
+    root.working_directory = Some("/home/me/app".to_string());
+    root.shell = Some("sh".to_string());
+    let root_id = db.create_group(&root).unwrap();
+    let mut frontend = GroupBuilder::new("Frontend").with_parent(root_id).build();
+    frontend.working_directory = Some("/home/me/app/frontend".to_string());
+    let frontend_id = db.create_group(&frontend).unwrap();
+    db.create_command(
+        &CommandBuilder::new("Build", "npm")
+            .with_args(vec!["run", "build", "--out=/home/me/app2/dist"])
+            .with_group(frontend_id)
+            .with_category(category_id)
+            .with_secret("NPM_TOKEN", "s3cret")
+            .build(),
+    )
+    .unwrap();
+
+    let template = db
+        .capture_group_template(
+            root_id,
+            "App setup",
+            &[CapturedVariable {
+                key: "project_dir".to_string(),
+                value: "/home/me/app".to_string(),
+                kind: TemplateVariableType::Path,
+                label: Some("Checkout".to_string()),
+            }],
+        )
+        .unwrap();
+    assert_eq!(template.id, 0);
+    assert!(!template.structure.contains("s3cret"));
+
+    let structure: TemplateStructure = serde_json::from_str(&template.structure).unwrap();
+    assert_eq!(structure.variables[0].key, "project_dir");
+    assert!(structure.variables[0].required);
+    let group = &structure.groups[0];
+    assert_eq!(
+        group.default_working_directory.as_deref(),
+        Some("{{project_dir}}")
+    );
+    assert_eq!(
+        group.default_env_vars.as_ref().unwrap()["APP_HOME"],
+        "{{project_dir}}"
+    );
+    let child = &group.groups[0];
+    assert_eq!(
+        child.default_working_directory.as_deref(),
+        Some("{{project_dir}}/frontend")
+    );
+    // a longer name that only starts with the value stays as it is
+    assert_eq!(
+        child.commands[0].arguments,
+        vec!["run", "build", "--out=/home/me/app2/dist"]
+    );
+    assert_eq!(child.commands[0].category.as_deref(), Some("Node.js"));
+
+    let id = db.create_template(&template).unwrap();
+    let checkout = tempfile::TempDir::new().unwrap();
+    let checkout_dir = checkout.path().to_string_lossy().into_owned();
+    let values = HashMap::from([("project_dir".to_string(), checkout_dir.clone())]);
+    let applied = db.apply_template(id, &values, None).unwrap();
+
+    let copy = db.get_group(applied.group_ids[0]).unwrap();
+    assert_eq!(
+        copy.working_directory.as_deref(),
+        Some(checkout_dir.as_str())
+    );
+    assert_eq!(copy.shell.as_deref(), Some("sh"));
+    let command = db.get_command(applied.command_ids[0]).unwrap();
+    assert_eq!(command.category_id, Some(category_id));
+    assert!(command.secret_env_vars.is_none());
+}
+
+#[test]
+fn test_capture_group_template_keeps_parameters_and_literal_braces() {
+    let test_db = TestDb::setup_test_db();
+    let db = &test_db.db;
+    let group_id = db.create_group(&GroupBuilder::new("Ops").build()).unwrap();
+    let parameter = CommandParameter {
+        name: "service".to_string(),
+        label: None,
+        description: None,
+        kind: ParameterKind::String,
+        default: Some("web".to_string()),
+        required: true,
+    };
+    let mut deploy = CommandBuilder::new("Restart", "systemctl")
+        .with_args(vec!["restart", "{{service}}"])
+        .with_parameter(parameter.clone())
+        .with_group(group_id)
+        .build();
+    deploy.shell = Some("sh".to_string());
+    deploy.shell_mode = Some(ShellMode::Login);
+    db.create_command(&deploy).unwrap();
+    db.create_command(
+        &CommandBuilder::new("Containers", "docker")
+            .with_args(vec!["ps", "--format", "{{.Names}}"])
+            .with_group(group_id)
+            .build(),
+    )
+    .unwrap();
+
+    let template = db.capture_group_template(group_id, "Ops", &[]).unwrap();
+    let structure: TemplateStructure = serde_json::from_str(&template.structure).unwrap();
+    let restart = &structure.groups[0].commands[0];
+    assert_eq!(restart.arguments, vec!["restart", r"\{{service}}"]);
+    assert_eq!(restart.parameters, vec![parameter.clone()]);
+    assert_eq!(restart.shell.as_deref(), Some("sh"));
+    assert_eq!(restart.shell_mode, Some(ShellMode::Login));
+
+    let id = db.create_template(&template).unwrap();
+    let applied = db.apply_template(id, &HashMap::new(), None).unwrap();
+    let restart = db.get_command(applied.command_ids[0]).unwrap();
+    assert_eq!(restart.arguments, vec!["restart", "{{service}}"]);
+    assert_eq!(restart.parameters, vec![parameter]);
+    assert_eq!(restart.shell.as_deref(), Some("sh"));
+    assert_eq!(restart.shell_mode, Some(ShellMode::Login));
+    let containers = db.get_command(applied.command_ids[1]).unwrap();
+    assert_eq!(containers.arguments, vec!["ps", "--format", "{{.Names}}"]);
+}
+
+#[test]
+fn test_capture_group_template_rejects_elevated_commands() {
+    let test_db = TestDb::setup_test_db();
+    let db = &test_db.db;
+    let group_id = db.create_group(&GroupBuilder::new("Ops").build()).unwrap();
+    let mut update = CommandBuilder::new("Update", "apt")
+        .with_args(vec!["update"])
+        .with_group(group_id)
+        .build();
+    update.run_elevated = true;
+    db.create_command(&update).unwrap();
+
+    let result = db.capture_group_template(group_id, "Ops", &[]);
+    assert!(matches!(
+        result,
+        Err(DatabaseError::InvalidData { field: "structure", reason })
+            if reason.contains("elevated privileges")
+    ));
+}
+
+#[test]
+fn test_capture_group_template_requires_values() {
+    let test_db = TestDb::setup_test_db();
+    let group_id = test_db
+        .db
+        .create_group(&GroupBuilder::new("App").build())
+        .unwrap();
+
+    let result = test_db.db.capture_group_template(
+        group_id,
+        "App",
+        &[CapturedVariable {
+            key: "dir".to_string(),
+            value: String::new(),
+            kind: TemplateVariableType::String,
+            label: None,
+        }],
+    );
+    assert!(matches!(
+        result,
+        Err(DatabaseError::InvalidData {
+            field: "variables",
+            ..
+        })
+    ));
+    assert!(matches!(
+        test_db.db.capture_group_template(9999, "Missing", &[]),
+        Err(DatabaseError::NotFound { .. })
+    ));
+}
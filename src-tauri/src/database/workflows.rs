@@ -3,7 +3,7 @@ use super::{
 };
 use crate::constants::{WORKFLOWS_TABLE, WORKFLOW_STEPS_TABLE};
 use crate::database::helpers::QueryBuilder;
-use rusqlite::{named_params, params};
+use rusqlite::{named_params, params, Connection};
 use tracing::{debug, info, instrument, warn};
 
 impl Database {
@@ -11,9 +11,15 @@ impl Database {
     pub fn create_workflow(&self, workflow: &Workflow) -> Result<i64> {
         self.validate_field_length("name", &workflow.name, Self::MAX_NAME_LENGTH)?;
 
-        let position: i64 = self.get_position(WORKFLOWS_TABLE, None, None)?;
+        Self::insert_workflow(&*self.conn()?, workflow)
+    }
+
+    /// Inserts a validated workflow at the end of the list, inside the caller's transaction
+    pub(crate) fn insert_workflow(conn: &Connection, workflow: &Workflow) -> Result<i64> {
+        let position = Self::next_position(conn, WORKFLOWS_TABLE, None, None)?;
 
-        self.create(
+        Self::insert_returning_id(
+            conn,
             WORKFLOWS_TABLE,
             "INSERT INTO workflows (name, description, category_id, is_favorite, execution_mode, position)
              VALUES (:name, :description, :category_id, :is_favorite, :execution_mode, :position)",
@@ -152,13 +158,20 @@ impl Database {
         self.get_workflow(flow_step.workflow_id)?;
         self.get_command(flow_step.command_id)?;
 
-        let position = self.get_position(
+        Self::insert_workflow_step(&*self.conn()?, flow_step)
+    }
+
+    /// Inserts a step at the end of its workflow, inside the caller's transaction
+    pub(crate) fn insert_workflow_step(conn: &Connection, flow_step: &WorkflowStep) -> Result<i64> {
+        let position = Self::next_position(
+            conn,
             WORKFLOW_STEPS_TABLE,
             Some("workflow_id"),
             Some(flow_step.workflow_id),
         )?;
 
-        self.create(
+        Self::insert_returning_id(
+            conn,
             WORKFLOW_STEPS_TABLE,
             "INSERT INTO workflow_steps (workflow_id, command_id, position, condition, timeout_seconds, auto_retry_count, enabled, continue_on_failure)
              VALUES (:workflow_id, :command_id, :position, :condition, :timeout_seconds, :auto_retry_count, :enabled, :continue_on_failure)",
@@ -216,77 +229,46 @@ impl Database {
         workflow_id: i64,
         enabled_only: bool,
     ) -> Result<Vec<(WorkflowStep, Command)>> {
-        let query = if enabled_only {
-            "SELECT 
-            ws.id as ws_id, ws.workflow_id, ws.command_id, ws.position as ws_position,
-            ws.condition, ws.timeout_seconds, ws.auto_retry_count, ws.enabled, 
-            ws.continue_on_failure, ws.created_at as ws_created_at, ws.updated_at as ws_updated_at,
-            c.id as cmd_id, c.name, c.command, c.arguments, c.description, c.group_id,
-            c.position as cmd_position, c.working_directory, c.env_vars, c.shell,
-            c.category_id, c.is_favorite, c.created_at as cmd_created_at, c.updated_at as cmd_updated_at
-         FROM workflow_steps ws
-         JOIN commands c ON ws.command_id = c.id
-         WHERE ws.workflow_id = ?1 AND ws.enabled = 1
-         ORDER BY ws.position"
-        } else {
-            "SELECT 
-            ws.id as ws_id, ws.workflow_id, ws.command_id, ws.position as ws_position,
-            ws.condition, ws.timeout_seconds, ws.auto_retry_count, ws.enabled, 
-            ws.continue_on_failure, ws.created_at as ws_created_at, ws.updated_at as ws_updated_at,
-            c.id as cmd_id, c.name, c.command, c.arguments, c.description, c.group_id,
-            c.position as cmd_position, c.working_directory, c.env_vars, c.shell,
-            c.category_id, c.is_favorite, c.created_at as cmd_created_at, c.updated_at as cmd_updated_at
+        // commands columns keep their names so `row_to_command` can map them,
+        // step columns are aliased to avoid clashing with them
+        let mut query = "SELECT c.*,
+            ws.id as ws_id, ws.workflow_id as ws_workflow_id, ws.command_id as ws_command_id,
+            ws.position as ws_position, ws.condition as ws_condition,
+            ws.timeout_seconds as ws_timeout_seconds, ws.auto_retry_count as ws_auto_retry_count,
+            ws.enabled as ws_enabled, ws.continue_on_failure as ws_continue_on_failure,
+            ws.created_at as ws_created_at, ws.updated_at as ws_updated_at
          FROM workflow_steps ws
          JOIN commands c ON ws.command_id = c.id
-         WHERE ws.workflow_id = ?1
-         ORDER BY ws.position"
-        };
+         WHERE ws.workflow_id = ?1"
+            .to_string();
+
+        if enabled_only {
+            query.push_str(" AND ws.enabled = 1");
+        }
+        query.push_str(" ORDER BY ws.position");
 
-        self.query_database(query, params![workflow_id], |row| {
-            let condition_str: String = row.get("condition")?;
+        self.query_database(&query, params![workflow_id], |row| {
+            let condition_str: String = row.get("ws_condition")?;
             let condition = StepCondition::from_str(&condition_str).unwrap_or_else(|e| {
                 warn!(error = %e, "Invalid condition, defaulting to always");
                 StepCondition::Always
             });
 
-            let argument_str: String = row.get("arguments")?;
-            let arguments = serde_json::from_str(&argument_str).unwrap_or_else(|e| {
-                warn!(error = %e, "Failed to parse arguments, using default");
-                Vec::new()
-            });
-            let env_vars_str: Option<String> = row.get("env_vars")?;
-            let env_vars = Self::string_to_hashmap(env_vars_str);
-
             let step = WorkflowStep {
                 id: row.get("ws_id")?,
-                workflow_id: row.get("workflow_id")?,
-                command_id: row.get("command_id")?,
+                workflow_id: row.get("ws_workflow_id")?,
+                command_id: row.get("ws_command_id")?,
                 position: row.get("ws_position")?,
                 condition,
-                timeout_seconds: row.get("timeout_seconds")?,
-                auto_retry_count: row.get("auto_retry_count")?,
-                enabled: row.get("enabled")?,
-                continue_on_failure: row.get("continue_on_failure")?,
+                timeout_seconds: row.get("ws_timeout_seconds")?,
+                auto_retry_count: row.get("ws_auto_retry_count")?,
+                enabled: row.get("ws_enabled")?,
+                continue_on_failure: row.get("ws_continue_on_failure")?,
                 created_at: row.get("ws_created_at")?,
                 updated_at: row.get("ws_updated_at")?,
             };
 
-            let cmd = Command {
-                id: row.get("cmd_id")?,
-                name: row.get("name")?,
-                command: row.get("command")?,
-                arguments,
-                description: row.get("description")?,
-                group_id: row.get("group_id")?,
-                position: row.get("cmd_position")?,
-                working_directory: row.get("working_directory")?,
-                env_vars,
-                shell: row.get("shell")?,
-                category_id: row.get("category_id")?,
-                is_favorite: row.get("is_favorite")?,
-                created_at: row.get("cmd_created_at")?,
-                updated_at: row.get("cmd_updated_at")?,
-            };
+            let cmd = Self::row_to_command(row)?;
 
             Ok((step, cmd))
         })
@@ -1,7 +1,9 @@
 use crate::database::{
-    CategoryFilter, Command, Database, ExecutionHistory, ExplainResult, GroupFilter, WithHistory,
+    BulkAction, BulkResult, CategoryFilter, Command, CommandSuggestion, Database, ExecutionHistory,
+    ExplainResult, GroupFilter, HistorySource, ShellHistoryEntry, TagFilter, WithHistory,
 };
 use crate::handlers::serialize_errors::SerializableError;
+use std::path::PathBuf;
 use tauri::State;
 
 #[tauri::command]
@@ -19,9 +21,11 @@ pub fn get_commands(
     db: State<'_, Database>,
     parent_id: GroupFilter,
     category_id: CategoryFilter,
+    tags: Option<TagFilter>,
     favorites_only: bool,
 ) -> Result<Vec<WithHistory<Command>>, SerializableError> {
-    db.get_commands(parent_id, category_id, favorites_only, None, None)
+    let tags = tags.unwrap_or(TagFilter::All);
+    db.get_commands(parent_id, category_id, tags, favorites_only, None, None)
         .map_err(|e| e.into())
 }
 
@@ -33,6 +37,16 @@ pub fn get_recent_commands(
     db.get_recent_commands(limit).map_err(|e| e.into())
 }
 
+#[tauri::command]
+pub fn get_command_suggestions(
+    db: State<'_, Database>,
+    query: String,
+    limit: i64,
+) -> Result<Vec<CommandSuggestion>, SerializableError> {
+    db.get_command_suggestions(&query, limit)
+        .map_err(|e| e.into())
+}
+
 #[tauri::command]
 pub fn get_command_count(
     db: State<'_, Database>,
@@ -65,6 +79,20 @@ pub fn delete_command(db: State<'_, Database>, id: i64) -> Result<(), Serializab
     db.delete_command(id).map_err(|e| e.into())
 }
 
+#[tauri::command]
+pub fn duplicate_command(db: State<'_, Database>, id: i64) -> Result<i64, SerializableError> {
+    db.duplicate_command(id).map_err(|e| e.into())
+}
+
+#[tauri::command]
+pub fn bulk_update_commands(
+    db: State<'_, Database>,
+    ids: Vec<i64>,
+    action: BulkAction,
+) -> Result<BulkResult, SerializableError> {
+    db.bulk_update_commands(&ids, &action).map_err(|e| e.into())
+}
+
 #[tauri::command]
 pub fn toggle_command_favorite(db: State<'_, Database>, id: i64) -> Result<(), SerializableError> {
     db.toggle_command_favorite(id).map_err(|e| e.into())
@@ -87,6 +115,31 @@ pub fn get_latest_execution_for_command(
 }
 
 #[tauri::command]
-pub fn explain_command(db: State<Database>, input: String) -> Result<ExplainResult, SerializableError> {
+pub fn explain_command(
+    db: State<Database>,
+    input: String,
+) -> Result<ExplainResult, SerializableError> {
     db.explain_command(&input).map_err(|e| e.into())
 }
+
+/// Candidates for `import_shell_commands`, `path` defaults to the usual file of `source`
+#[tauri::command]
+pub fn read_shell_history(
+    db: State<'_, Database>,
+    source: HistorySource,
+    path: Option<PathBuf>,
+    limit: Option<usize>,
+) -> Result<Vec<ShellHistoryEntry>, SerializableError> {
+    db.read_shell_history(source, path.as_deref(), limit)
+        .map_err(|e| e.into())
+}
+
+#[tauri::command]
+pub fn import_shell_commands(
+    db: State<'_, Database>,
+    entries: Vec<ShellHistoryEntry>,
+    group_id: Option<i64>,
+) -> Result<Vec<i64>, SerializableError> {
+    db.import_shell_commands(&entries, group_id)
+        .map_err(|e| e.into())
+}
@@ -1,5 +1,9 @@
-use crate::database::{CategoryFilter, Database, Group, GroupFilter, GroupNode};
+use crate::database::{
+    BulkAction, BulkResult, CategoryFilter, Database, Duplicated, Group, GroupFilter, GroupNode,
+    ProjectRescan, ProjectScan, ProjectTask, TagFilter,
+};
 use crate::handlers::serialize_errors::SerializableError;
+use std::path::PathBuf;
 use tauri::State;
 
 #[tauri::command]
@@ -17,9 +21,11 @@ pub fn get_groups(
     db: State<'_, Database>,
     parent_id: GroupFilter,
     category_id: CategoryFilter,
+    tags: Option<TagFilter>,
     favorites_only: bool,
 ) -> Result<Vec<Group>, SerializableError> {
-    db.get_groups(parent_id, category_id, favorites_only)
+    let tags = tags.unwrap_or(TagFilter::All);
+    db.get_groups(parent_id, category_id, tags, favorites_only)
         .map_err(|err| err.into())
 }
 
@@ -64,3 +70,52 @@ pub fn get_group_path(
 pub fn toggle_group_favorite(db: State<'_, Database>, id: i64) -> Result<(), SerializableError> {
     db.toggle_group_favorite(id).map_err(|err| err.into())
 }
+
+#[tauri::command]
+pub fn duplicate_group(db: State<'_, Database>, id: i64) -> Result<Duplicated, SerializableError> {
+    db.duplicate_group(id).map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn bulk_update_groups(
+    db: State<'_, Database>,
+    ids: Vec<i64>,
+    action: BulkAction,
+) -> Result<BulkResult, SerializableError> {
+    db.bulk_update_groups(&ids, &action)
+        .map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn scan_project(
+    db: State<'_, Database>,
+    dir: PathBuf,
+) -> Result<ProjectScan, SerializableError> {
+    db.scan_project(&dir).map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn create_project_group(
+    db: State<'_, Database>,
+    scan: ProjectScan,
+) -> Result<i64, SerializableError> {
+    db.create_project_group(&scan).map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn rescan_project_group(
+    db: State<'_, Database>,
+    group_id: i64,
+) -> Result<ProjectRescan, SerializableError> {
+    db.rescan_project_group(group_id).map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn add_project_tasks(
+    db: State<'_, Database>,
+    group_id: i64,
+    tasks: Vec<ProjectTask>,
+) -> Result<Vec<i64>, SerializableError> {
+    db.add_project_tasks(group_id, &tasks)
+        .map_err(|err| err.into())
+}
@@ -0,0 +1,20 @@
+use crate::database::{Database, ImportMode, ImportReport, LibraryExport};
+use crate::handlers::serialize_errors::SerializableError;
+use tauri::State;
+
+#[tauri::command]
+pub fn export_library(db: State<'_, Database>) -> Result<LibraryExport, SerializableError> {
+    db.export_library().map_err(|err| err.into())
+}
+
+/// `document` is the JSON of an export, with `dry_run` the report is computed and rolled back
+#[tauri::command]
+pub fn import_library(
+    db: State<'_, Database>,
+    document: String,
+    mode: ImportMode,
+    dry_run: Option<bool>,
+) -> Result<ImportReport, SerializableError> {
+    db.import_library(&document, &mode, dry_run.unwrap_or(false))
+        .map_err(|err| err.into())
+}
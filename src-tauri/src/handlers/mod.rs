@@ -1,9 +1,14 @@
 pub mod categories;
 pub mod commands;
 pub mod groups;
+pub mod library;
 pub mod logger;
 pub mod process_handler;
+pub mod search;
 pub mod serialize_errors;
+pub mod shells;
+pub mod tags;
+pub mod templates;
 pub mod workflows;
 
 pub mod execution_history;
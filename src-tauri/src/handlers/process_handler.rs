@@ -1,7 +1,10 @@
 use crate::database::{Database, TriggeredBy};
 use crate::handlers::serialize_errors::SerializableError;
 use crate::process::manager::ProcessManager;
-use crate::process::models::{LogLineEvent, ProcessInfo, SpawnContext, TrayStatus};
+use crate::process::models::{LogLineEvent, ProcessInfo, SpawnContext, SpawnPreview, TrayStatus};
+use crate::process::preflight::PreflightProblem;
+use crate::process::safety::SafetyCheck;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tauri::State;
 use tracing::debug;
@@ -13,26 +16,100 @@ pub async fn resolve_command_context(
 ) -> Result<SpawnContext, SerializableError> {
     pm.resolve_spawn_context(command_id)
         .await
+        .map(|context| context.masked())
         .map_err(|e| SerializableError::from(e))
 }
 
+/// Destructive or privileged commands fail with `CONFIRMATION_REQUIRED` unless
+/// `confirmation_token` from `check_command_safety` is passed
 #[tauri::command]
 pub async fn spawn_command(
     command_id: i64,
+    confirmation_token: Option<String>,
     pm: State<'_, Arc<ProcessManager>>,
 ) -> Result<i64, SerializableError> {
     debug!(command_id, "Spawning command");
-    let context = pm
+    let mut context = pm
         .resolve_spawn_context(command_id)
         .await
         .map_err(|e| SerializableError::from(e))?;
+    context.confirmation_token = confirmation_token;
     let execution_id = pm
         .spawn_command(context, TriggeredBy::Manual)
         .await
-        .map_err(|e| SerializableError::from(e.to_string()))?;
+        .map_err(SerializableError::from)?;
+    Ok(execution_id)
+}
+
+/// Same as `spawn_command` with values for the command's parameters
+#[tauri::command]
+pub async fn spawn_command_with_params(
+    command_id: i64,
+    params: HashMap<String, String>,
+    confirmation_token: Option<String>,
+    pm: State<'_, Arc<ProcessManager>>,
+) -> Result<i64, SerializableError> {
+    debug!(command_id, "Spawning command with parameters");
+    let mut context = pm
+        .resolve_spawn_context_with_params(command_id, &params)
+        .await?;
+    context.confirmation_token = confirmation_token;
+    let execution_id = pm
+        .spawn_command(context, TriggeredBy::Manual)
+        .await
+        .map_err(SerializableError::from)?;
     Ok(execution_id)
 }
 
+/// What `spawn_command` would decide for the command, with the token to confirm it
+#[tauri::command]
+pub async fn check_command_safety(
+    command_id: i64,
+    params: Option<HashMap<String, String>>,
+    pm: State<'_, Arc<ProcessManager>>,
+) -> Result<SafetyCheck, SerializableError> {
+    let context = pm
+        .resolve_spawn_context_with_params(command_id, &params.unwrap_or_default())
+        .await?;
+    pm.check_spawn_safety(&context)
+        .map_err(SerializableError::from)
+}
+
+/// Resolved executable, argv, working directory, env and shell, nothing is spawned
+#[tauri::command]
+pub async fn preview_spawn(
+    command_id: i64,
+    params: Option<HashMap<String, String>>,
+    pm: State<'_, Arc<ProcessManager>>,
+) -> Result<SpawnPreview, SerializableError> {
+    pm.preview_spawn(command_id, &params.unwrap_or_default())
+        .await
+}
+
+/// Problems that would stop the command from starting, for the editor to show before a run
+#[tauri::command]
+pub async fn validate_command(
+    command_id: i64,
+    params: Option<HashMap<String, String>>,
+    pm: State<'_, Arc<ProcessManager>>,
+) -> Result<Vec<PreflightProblem>, SerializableError> {
+    let context = pm
+        .resolve_spawn_context_with_params(command_id, &params.unwrap_or_default())
+        .await?;
+    Ok(pm.validate_command(&context))
+}
+
+/// Answer to an `askpass-request` event, `None` cancels the prompt
+#[tauri::command]
+pub async fn answer_askpass(
+    execution_id: i64,
+    answer: Option<String>,
+    pm: State<'_, Arc<ProcessManager>>,
+) -> Result<(), SerializableError> {
+    pm.answer_askpass(execution_id, answer)
+        .map_err(|e| SerializableError::from(e.to_string()))
+}
+
 #[tauri::command]
 pub async fn kill_process(
     execution_id: i64,
@@ -112,7 +189,9 @@ pub async fn get_tray_status(
     })
 }
 
+/// Names of the shell registry entries
 #[tauri::command]
-pub fn get_valid_shells() -> Vec<String> {
-    crate::process::shell::get_allowed_shells()
+pub fn get_valid_shells(db: State<'_, Database>) -> Result<Vec<String>, SerializableError> {
+    let shells = db.get_shells().map_err(SerializableError::from)?;
+    Ok(shells.into_iter().map(|shell| shell.name).collect())
 }
@@ -0,0 +1,12 @@
+use crate::database::{Database, SearchResult};
+use crate::handlers::serialize_errors::SerializableError;
+use tauri::State;
+
+#[tauri::command]
+pub fn search_all(
+    db: State<'_, Database>,
+    search_term: String,
+    limit: Option<i64>,
+) -> Result<Vec<SearchResult>, SerializableError> {
+    db.search_all(&search_term, limit).map_err(|err| err.into())
+}
@@ -1,6 +1,8 @@
 use crate::constants::{CONNECTION_FAILED_MESSAGE, DATABASE_LOCKED_MESSAGE};
 use crate::database::DatabaseError;
-use crate::process::errors::ProcessKillError;
+use crate::process::errors::{ProcessKillError, ProcessSpawnError};
+use crate::process::interpolation::InterpolationError;
+use crate::process::parameters::ParameterError;
 use serde::Serialize;
 
 #[derive(Debug, Serialize)]
@@ -54,6 +56,10 @@ impl From<DatabaseError> for SerializableError {
                 code: "DATABASE_CONNECTION_FAIL".to_string(),
                 message: CONNECTION_FAILED_MESSAGE.to_string(),
             },
+            DatabaseError::SecretStore(msg) => SerializableError {
+                code: "SECRET_STORE".to_string(),
+                message: format!("Secret store error: {}", msg),
+            },
             DatabaseError::Internal(msg) => SerializableError {
                 code: "INTERNAL".to_string(),
                 message: format!("Database error: {}", msg),
@@ -81,6 +87,10 @@ impl From<ProcessKillError> for SerializableError {
                 code: "NO_PERMISSION".to_string(),
                 message: "".to_string(),
             },
+            ProcessKillError::ElevatedWithoutCgroup => SerializableError {
+                code: "NO_PERMISSION".to_string(),
+                message: err.to_string(),
+            },
             ProcessKillError::PlatformError(message) => SerializableError {
                 code: "PLATFORM_ERROR".to_string(),
                 message,
@@ -96,3 +106,55 @@ impl From<ProcessKillError> for SerializableError {
         }
     }
 }
+
+impl From<InterpolationError> for SerializableError {
+    fn from(err: InterpolationError) -> Self {
+        let code = match err {
+            InterpolationError::Undefined { .. } => "UNDEFINED_VARIABLE",
+            InterpolationError::Circular { .. } => "CIRCULAR_VARIABLE",
+            InterpolationError::Unterminated { .. } | InterpolationError::InvalidName { .. } => {
+                "INVALID_VARIABLE"
+            }
+        };
+        SerializableError {
+            code: code.to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<ParameterError> for SerializableError {
+    fn from(err: ParameterError) -> Self {
+        let code = match err {
+            ParameterError::Missing(_) => "MISSING_PARAMETER",
+            ParameterError::Invalid { .. } => "INVALID_PARAMETER",
+            ParameterError::Unknown(_) => "UNKNOWN_PARAMETER",
+        };
+        SerializableError {
+            code: code.to_string(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<ProcessSpawnError> for SerializableError {
+    fn from(err: ProcessSpawnError) -> Self {
+        let code = match err {
+            ProcessSpawnError::SpawnFailed(_) | ProcessSpawnError::NoPid => "SPAWN_FAILED",
+            ProcessSpawnError::DatabaseError(_) => "INTERNAL",
+            ProcessSpawnError::ExecutableNotFound(_) => "EXECUTABLE_NOT_FOUND",
+            ProcessSpawnError::InvalidWorkingDirectory(_) => "INVALID_DIRECTORY",
+            ProcessSpawnError::PermissionDenied(_) => "NO_PERMISSION",
+            ProcessSpawnError::InvalidShell(_) => "INVALID_SHELL",
+            ProcessSpawnError::InvalidEnvVar(_) => "INVALID_ENV_VAR",
+            ProcessSpawnError::EnvFileError { .. } => "ENV_FILE_ERROR",
+            ProcessSpawnError::ConfirmationRequired(_) => "CONFIRMATION_REQUIRED",
+            ProcessSpawnError::Denied(_) => "COMMAND_DENIED",
+            ProcessSpawnError::InvalidDenyList(_) => "INVALID_DENY_LIST",
+        };
+        SerializableError {
+            code: code.to_string(),
+            message: err.to_string(),
+        }
+    }
+}
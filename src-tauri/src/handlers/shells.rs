@@ -0,0 +1,34 @@
+use crate::database::{Database, ShellDefinition};
+use crate::handlers::serialize_errors::SerializableError;
+use tauri::State;
+
+#[tauri::command]
+pub fn create_shell(
+    db: State<'_, Database>,
+    shell: ShellDefinition,
+) -> Result<i64, SerializableError> {
+    db.create_shell(&shell).map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn get_shell(db: State<'_, Database>, id: i64) -> Result<ShellDefinition, SerializableError> {
+    db.get_shell(id).map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn get_shells(db: State<'_, Database>) -> Result<Vec<ShellDefinition>, SerializableError> {
+    db.get_shells().map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn update_shell(
+    db: State<'_, Database>,
+    shell: ShellDefinition,
+) -> Result<(), SerializableError> {
+    db.update_shell(&shell).map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn delete_shell(db: State<'_, Database>, id: i64) -> Result<(), SerializableError> {
+    db.delete_shell(id).map_err(|err| err.into())
+}
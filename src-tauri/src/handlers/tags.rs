@@ -0,0 +1,94 @@
+use crate::database::{Database, Tag, TagTarget};
+use crate::handlers::serialize_errors::SerializableError;
+use tauri::State;
+
+#[tauri::command]
+pub fn create_tag(
+    db: State<'_, Database>,
+    name: &str,
+    color: Option<&str>,
+) -> Result<i64, SerializableError> {
+    db.create_tag(name, color).map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn get_tag(db: State<'_, Database>, id: i64) -> Result<Tag, SerializableError> {
+    db.get_tag(id).map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn get_tags(db: State<'_, Database>) -> Result<Vec<Tag>, SerializableError> {
+    db.get_tags().map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn update_tag(
+    db: State<'_, Database>,
+    id: i64,
+    name: &str,
+    color: Option<&str>,
+) -> Result<(), SerializableError> {
+    db.update_tag(id, name, color).map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn delete_tag(db: State<'_, Database>, id: i64) -> Result<(), SerializableError> {
+    db.delete_tag(id).map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn get_item_tags(
+    db: State<'_, Database>,
+    target: TagTarget,
+    item_id: i64,
+) -> Result<Vec<Tag>, SerializableError> {
+    db.get_item_tags(target, item_id).map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn add_item_tag(
+    db: State<'_, Database>,
+    target: TagTarget,
+    item_id: i64,
+    tag_id: i64,
+) -> Result<(), SerializableError> {
+    db.add_item_tag(target, item_id, tag_id)
+        .map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn remove_item_tag(
+    db: State<'_, Database>,
+    target: TagTarget,
+    item_id: i64,
+    tag_id: i64,
+) -> Result<(), SerializableError> {
+    db.remove_item_tag(target, item_id, tag_id)
+        .map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn set_item_tags(
+    db: State<'_, Database>,
+    target: TagTarget,
+    item_id: i64,
+    tag_ids: Vec<i64>,
+) -> Result<(), SerializableError> {
+    db.set_item_tags(target, item_id, &tag_ids)
+        .map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn get_tag_command_count(db: State<'_, Database>, id: i64) -> Result<i64, SerializableError> {
+    db.get_tag_command_count(id).map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn get_tag_group_count(db: State<'_, Database>, id: i64) -> Result<i64, SerializableError> {
+    db.get_tag_group_count(id).map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn get_tag_workflow_count(db: State<'_, Database>, id: i64) -> Result<i64, SerializableError> {
+    db.get_tag_workflow_count(id).map_err(|err| err.into())
+}
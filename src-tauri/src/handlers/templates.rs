@@ -0,0 +1,64 @@
+use crate::database::{AppliedTemplate, CapturedVariable, Database, Template, TemplateValidation};
+use crate::handlers::serialize_errors::SerializableError;
+use std::collections::HashMap;
+use tauri::State;
+
+#[tauri::command]
+pub fn create_template(
+    db: State<'_, Database>,
+    template: Template,
+) -> Result<i64, SerializableError> {
+    db.create_template(&template).map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn get_template(db: State<'_, Database>, id: i64) -> Result<Template, SerializableError> {
+    db.get_template(id).map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn get_templates(db: State<'_, Database>) -> Result<Vec<Template>, SerializableError> {
+    db.get_templates().map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn update_template(
+    db: State<'_, Database>,
+    template: Template,
+) -> Result<(), SerializableError> {
+    db.update_template(&template).map_err(|err| err.into())
+}
+
+#[tauri::command]
+pub fn delete_template(db: State<'_, Database>, id: i64) -> Result<(), SerializableError> {
+    db.delete_template(id).map_err(|err| err.into())
+}
+
+/// Lets the editor show every problem of a structure before it is saved
+#[tauri::command]
+pub fn validate_template(db: State<'_, Database>, structure: String) -> TemplateValidation {
+    db.validate_template_structure(&structure)
+}
+
+#[tauri::command]
+pub fn apply_template(
+    db: State<'_, Database>,
+    id: i64,
+    values: HashMap<String, String>,
+    parent_group_id: Option<i64>,
+) -> Result<AppliedTemplate, SerializableError> {
+    db.apply_template(id, &values, parent_group_id)
+        .map_err(|err| err.into())
+}
+
+/// Unsaved template of a group subtree, the editor saves it with `create_template`
+#[tauri::command]
+pub fn capture_group_template(
+    db: State<'_, Database>,
+    group_id: i64,
+    name: String,
+    variables: Vec<CapturedVariable>,
+) -> Result<Template, SerializableError> {
+    db.capture_group_template(group_id, &name, &variables)
+        .map_err(|err| err.into())
+}
@@ -1,4 +1,4 @@
-use crate::database::{Command, Database, Workflow, WorkflowStep};
+use crate::database::{Command, Database, Duplicated, Workflow, WorkflowStep};
 use crate::handlers::serialize_errors::SerializableError;
 use tauri::State;
 
@@ -38,6 +38,17 @@ pub fn delete_workflow(db: State<'_, Database>, id: i64) -> Result<(), Serializa
     db.delete_workflow(id).map_err(|err| err.into())
 }
 
+/// With `copy_commands` the steps of the copy run copies of the commands
+#[tauri::command]
+pub fn duplicate_workflow(
+    db: State<'_, Database>,
+    id: i64,
+    copy_commands: bool,
+) -> Result<Duplicated, SerializableError> {
+    db.duplicate_workflow(id, copy_commands)
+        .map_err(|err| err.into())
+}
+
 #[tauri::command]
 pub fn toggle_favorite_workflow(db: State<'_, Database>, id: i64) -> Result<(), SerializableError> {
     db.toggle_favorite_workflow(id).map_err(|err| err.into())
@@ -6,7 +6,8 @@ mod utils;
 
 use crate::database::Database;
 use crate::handlers::{
-    categories, commands, execution_history, groups, process_handler, settings, workflows,
+    categories, commands, execution_history, groups, library, process_handler, search, settings,
+    shells, tags, templates, workflows,
 };
 use crate::process::manager::ProcessManager;
 use handlers::logger;
@@ -35,6 +36,16 @@ fn find_tldr_dir(resources: &PathBuf) -> Option<PathBuf> {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // started by `sudo -A` as askpass helper of an elevated command
+    #[cfg(unix)]
+    if let Some(socket) = std::env::var_os(process::elevation::ASKPASS_SOCKET_ENV) {
+        let prompt = std::env::args().nth(1).unwrap_or_default();
+        std::process::exit(process::elevation::askpass_client(
+            Path::new(&socket),
+            &prompt,
+        ));
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_dialog::init())
@@ -58,6 +69,7 @@ pub fn run() {
                 error!(error = %e, "Database initialization failed");
                 e
             })?;
+            db.migrate_sudo_commands()?;
 
             #[cfg(debug_assertions)]
             let resources_dir =
@@ -95,6 +107,34 @@ pub fn run() {
             categories::update_category,
             categories::delete_category,
             categories::get_category_command_count,
+            tags::create_tag,
+            tags::get_tag,
+            tags::get_tags,
+            tags::update_tag,
+            tags::delete_tag,
+            tags::get_item_tags,
+            tags::add_item_tag,
+            tags::remove_item_tag,
+            tags::set_item_tags,
+            tags::get_tag_command_count,
+            tags::get_tag_group_count,
+            tags::get_tag_workflow_count,
+            shells::create_shell,
+            shells::get_shell,
+            shells::get_shells,
+            shells::update_shell,
+            shells::delete_shell,
+            templates::create_template,
+            templates::get_template,
+            templates::get_templates,
+            templates::update_template,
+            templates::delete_template,
+            templates::validate_template,
+            templates::apply_template,
+            templates::capture_group_template,
+            library::export_library,
+            library::import_library,
+            search::search_all,
             groups::create_group,
             groups::get_group,
             groups::get_groups,
@@ -104,22 +144,34 @@ pub fn run() {
             groups::get_group_path,
             groups::get_group_tree,
             groups::toggle_group_favorite,
+            groups::duplicate_group,
+            groups::bulk_update_groups,
+            groups::scan_project,
+            groups::create_project_group,
+            groups::rescan_project_group,
+            groups::add_project_tasks,
             commands::create_command,
             commands::get_command,
             commands::get_commands,
             commands::get_recent_commands,
+            commands::get_command_suggestions,
             commands::update_command,
             commands::delete_command,
+            commands::duplicate_command,
+            commands::bulk_update_commands,
             commands::search_commands,
             commands::move_command_between,
             commands::toggle_command_favorite,
             commands::get_latest_execution_for_command,
             commands::explain_command,
+            commands::read_shell_history,
+            commands::import_shell_commands,
             workflows::create_workflow,
             workflows::get_workflow,
             workflows::get_workflows,
             workflows::update_workflow,
             workflows::delete_workflow,
+            workflows::duplicate_workflow,
             workflows::toggle_favorite_workflow,
             workflows::get_workflow_count_for_category,
             workflows::move_workflow_between,
@@ -140,6 +192,11 @@ pub fn run() {
             execution_history::cleanup_history_older_than,
             execution_history::get_execution_stats,
             process_handler::spawn_command,
+            process_handler::spawn_command_with_params,
+            process_handler::check_command_safety,
+            process_handler::validate_command,
+            process_handler::preview_spawn,
+            process_handler::answer_askpass,
             process_handler::kill_process,
             process_handler::get_running_processes,
             process_handler::get_process_status,
@@ -0,0 +1,152 @@
+use crate::database::ResourceLimits;
+use std::fs::File;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+#[cfg(target_os = "linux")]
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// A cgroup v2 created for a single execution, so memory and pids limits cover the whole tree.
+///
+/// Only used when the cgroup TGUI runs in has a writable (delegated) parent, e.g. when started
+/// from a systemd user session. The child joins it between fork and exec via `cgroup.procs`.
+#[derive(Debug)]
+pub struct ExecutionCgroup {
+    path: PathBuf,
+    procs: File,
+}
+
+impl ExecutionCgroup {
+    /// Returns `None` when cgroups were not requested or are unavailable, callers then rely on rlimits only.
+    /// Elevated executions always ask for one, `cgroup.kill` is the only way to stop a root process.
+    pub fn for_limits(execution_id: i64, limits: &ResourceLimits, elevated: bool) -> Option<Self> {
+        if !limits.wants_cgroup() && !elevated {
+            return None;
+        }
+
+        match Self::create(execution_id, limits) {
+            Ok(cgroup) => {
+                debug!(execution_id, path = ?cgroup.path, "Created execution cgroup");
+                Some(cgroup)
+            }
+            Err(e) => {
+                warn!(execution_id, error = %e, "cgroup unavailable, falling back to rlimits");
+                if let Some(max_processes) = limits.max_processes {
+                    warn!(execution_id, max_processes, "Process limit not enforced");
+                }
+                if elevated {
+                    warn!(execution_id, "Elevated process cannot be killed");
+                }
+                None
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn create(execution_id: i64, limits: &ResourceLimits) -> io::Result<Self> {
+        use std::fs::{self, OpenOptions};
+
+        let parent = delegated_parent()?;
+        // Controllers must be enabled on the parent before children can use them
+        let _ = fs::write(parent.join("cgroup.subtree_control"), "+memory +pids");
+
+        let path = parent.join(format!("tgui-exec-{}", execution_id));
+        fs::create_dir(&path)?;
+
+        let configure = || -> io::Result<File> {
+            if let Some(bytes) = limits.max_memory_bytes {
+                fs::write(path.join("memory.max"), bytes.to_string())?;
+            }
+            if let Some(count) = limits.max_processes {
+                fs::write(path.join("pids.max"), count.to_string())?;
+            }
+            OpenOptions::new()
+                .write(true)
+                .open(path.join("cgroup.procs"))
+        };
+
+        match configure() {
+            Ok(procs) => Ok(Self { path, procs }),
+            Err(e) => {
+                let _ = fs::remove_dir(&path);
+                Err(e)
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn create(_execution_id: i64, _limits: &ResourceLimits) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "cgroups are only available on Linux",
+        ))
+    }
+
+    #[cfg(unix)]
+    pub fn procs_fd(&self) -> std::os::fd::RawFd {
+        use std::os::fd::AsRawFd;
+        self.procs.as_raw_fd()
+    }
+
+    /// Kills every process still in the cgroup, including ones that left the process group
+    pub fn kill_all(&self) -> io::Result<()> {
+        std::fs::write(self.path.join("cgroup.kill"), "1")
+    }
+
+    /// Removes the cgroup once the execution is over, killing leftovers that keep it busy
+    pub async fn release(self) {
+        let Self { path, procs } = self;
+        drop(procs);
+
+        for _ in 0..10 {
+            match std::fs::remove_dir(&path) {
+                Ok(()) => return,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return,
+                Err(_) => {
+                    let _ = std::fs::write(path.join("cgroup.kill"), "1");
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                }
+            }
+        }
+        warn!(path = ?path, "Failed to remove execution cgroup");
+    }
+}
+
+/// The cgroup TGUI itself runs in can't have both processes and children (no internal
+/// processes rule), so executions are created next to it, inside its parent.
+#[cfg(target_os = "linux")]
+fn delegated_parent() -> io::Result<PathBuf> {
+    let content = std::fs::read_to_string("/proc/self/cgroup")?;
+    let own = content
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "cgroup v2 is not mounted"))?;
+
+    let own = PathBuf::from(CGROUP_ROOT).join(own.trim_start_matches('/'));
+    let parent = own
+        .parent()
+        .filter(|p| p.starts_with(CGROUP_ROOT))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::PermissionDenied, "no delegated cgroup"))?;
+
+    if !is_writable(parent) {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("cgroup {} is not delegated", parent.display()),
+        ));
+    }
+    Ok(parent.to_path_buf())
+}
+
+#[cfg(target_os = "linux")]
+fn is_writable(path: &std::path::Path) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: c_path is a valid NUL terminated string
+    unsafe { libc::access(c_path.as_ptr(), libc::W_OK) == 0 }
+}
@@ -0,0 +1,202 @@
+use crate::process::errors::ProcessSpawnError;
+use std::path::Path;
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+#[error("line {line}: {reason}")]
+pub struct DotenvError {
+    pub line: usize,
+    pub reason: String,
+}
+
+/// Reads and parses a dotenv file, see `parse` for the supported syntax
+pub fn load(path: &Path) -> Result<Vec<(String, String)>, ProcessSpawnError> {
+    let env_file_error = |reason: String| ProcessSpawnError::EnvFileError {
+        path: path.display().to_string(),
+        reason,
+    };
+
+    let content = std::fs::read_to_string(path).map_err(|e| env_file_error(e.to_string()))?;
+    parse(&content).map_err(|e| env_file_error(e.to_string()))
+}
+
+/// Parses `KEY=value` lines with an optional `export ` prefix and `#` comments.
+///
+/// - unquoted values are trimmed, ` #` starts a comment
+/// - single quoted values are literal, also for `${VAR}` references
+/// - double quoted values support `\n`, `\t`, `\r`, `\"`, `\\` and `\$` escapes
+/// - quoted values can span multiple lines
+///
+/// Values are returned ready for interpolation: `${` that must stay literal is escaped as `$${`.
+pub fn parse(content: &str) -> Result<Vec<(String, String)>, DotenvError> {
+    let mut parser = Parser {
+        chars: content.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    let mut entries = Vec::new();
+
+    loop {
+        parser.skip_blank_lines_and_comments();
+        if parser.at_end() {
+            break;
+        }
+
+        let line = parser.line;
+        let error = |reason: &str| DotenvError {
+            line,
+            reason: reason.to_string(),
+        };
+
+        let mut key = parser.take_while(|c| !c.is_whitespace() && c != '=' && c != '#');
+        if key == "export" {
+            parser.skip_inline_whitespace();
+            key = parser.take_while(|c| !c.is_whitespace() && c != '=' && c != '#');
+        }
+        if key.is_empty() {
+            return Err(error("expected KEY=VALUE"));
+        }
+        if !key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(error(&format!("invalid key '{key}'")));
+        }
+
+        parser.skip_inline_whitespace();
+        if parser.peek() != Some('=') {
+            return Err(error(&format!("expected '=' after '{key}'")));
+        }
+        parser.pos += 1;
+        parser.skip_inline_whitespace();
+
+        let value = match parser.peek() {
+            Some('\'') => parser
+                .single_quoted()
+                .ok_or_else(|| error("unterminated single quote"))?,
+            Some('"') => parser
+                .double_quoted()
+                .ok_or_else(|| error("unterminated double quote"))?,
+            _ => parser.unquoted(),
+        };
+
+        parser.skip_inline_whitespace();
+        match parser.peek() {
+            None | Some('\n') | Some('#') => parser.skip_line(),
+            Some('\r') if parser.peek_at(1).is_none_or(|c| c == '\n') => parser.skip_line(),
+            Some(_) => return Err(error("unexpected characters after value")),
+        }
+
+        entries.push((key, value));
+    }
+
+    Ok(entries)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn at_end(&self) -> bool {
+        self.pos >= self.chars.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let mut taken = String::new();
+        while let Some(c) = self.peek().filter(|c| predicate(*c)) {
+            taken.push(c);
+            self.pos += 1;
+        }
+        taken
+    }
+
+    fn skip_inline_whitespace(&mut self) {
+        self.take_while(|c| c == ' ' || c == '\t');
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(c) = self.bump() {
+            if c == '\n' {
+                break;
+            }
+        }
+    }
+
+    fn skip_blank_lines_and_comments(&mut self) {
+        loop {
+            self.take_while(|c| c.is_whitespace() && c != '\n');
+            match self.peek() {
+                Some('\n') => {
+                    self.bump();
+                }
+                Some('#') => self.skip_line(),
+                _ => break,
+            }
+        }
+    }
+
+    fn unquoted(&mut self) -> String {
+        let mut value = String::new();
+        while let Some(c) = self.peek() {
+            if c == '\n' || (c == '#' && value.ends_with([' ', '\t'])) {
+                break;
+            }
+            value.push(c);
+            self.pos += 1;
+        }
+        value.trim_end().to_string()
+    }
+
+    fn single_quoted(&mut self) -> Option<String> {
+        self.bump();
+        let mut value = String::new();
+        loop {
+            match self.bump()? {
+                '\'' => return Some(value.replace("${", "$${")),
+                c => value.push(c),
+            }
+        }
+    }
+
+    fn double_quoted(&mut self) -> Option<String> {
+        self.bump();
+        let mut value = String::new();
+        loop {
+            match self.bump()? {
+                '"' => return Some(value),
+                '\\' => match self.bump()? {
+                    'n' => value.push('\n'),
+                    't' => value.push('\t'),
+                    'r' => value.push('\r'),
+                    // keep an escaped `${` literal through interpolation
+                    '$' if self.peek() == Some('{') => value.push_str("$$"),
+                    c @ ('"' | '\\' | '$') => value.push(c),
+                    c => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                },
+                c => value.push(c),
+            }
+        }
+    }
+}
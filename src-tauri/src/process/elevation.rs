@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Set for the askpass helper, TGUI runs as the helper when started with it
+pub const ASKPASS_SOCKET_ENV: &str = "TGUI_ASKPASS_SOCKET";
+/// Set by `AskpassServer::env_vars` for sudo itself, not passed on to the command
+const ASKPASS_ENV_KEYS: [&str; 2] = ["SUDO_ASKPASS", ASKPASS_SOCKET_ENV];
+
+/// How commands with `run_elevated` are launched
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ElevationMethod {
+    /// The polkit agent of the desktop session prompts for the password
+    Pkexec,
+    /// `sudo -A` with TGUI as askpass helper, the prompt is forwarded to the UI
+    Sudo,
+}
+
+impl ElevationMethod {
+    /// `elevation_method` setting: `auto` prefers pkexec when both are installed.
+    /// `None` when the chosen tool is not installed.
+    pub fn from_setting(value: &str) -> Option<Self> {
+        let installed = |method: Self| is_installed(method.program()).then_some(method);
+        match value {
+            "pkexec" => installed(Self::Pkexec),
+            "sudo" => installed(Self::Sudo),
+            _ => installed(Self::Pkexec).or_else(|| installed(Self::Sudo)),
+        }
+    }
+
+    pub fn program(self) -> &'static str {
+        match self {
+            Self::Pkexec => "pkexec",
+            Self::Sudo => "sudo",
+        }
+    }
+
+    /// pkexec always starts from a clean environment, so commands that set env vars are refused
+    /// rather than run without them. sudo passes them on, see `wrap`.
+    pub fn check_env(self, env_keys: &[&str]) -> Result<(), String> {
+        match self {
+            Self::Pkexec if !env_keys.is_empty() => Err(format!(
+                "pkexec clears the environment, {} would not reach the command. Use sudo to run \
+                 elevated or remove them",
+                env_keys.join(", ")
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Prefixes an already built executable and arguments. pkexec starts in root's home, so the
+    /// working directory is passed on through `env --chdir`. sudo keeps `env_keys` by name with
+    /// `--preserve-env`, their values stay out of the process list. It refuses to run when the
+    /// sudoers policy doesn't allow that (`SETENV`), rather than dropping them.
+    pub fn wrap(
+        self,
+        executable: String,
+        arguments: Vec<String>,
+        working_directory: &Path,
+        env_keys: &[String],
+    ) -> (String, Vec<String>) {
+        let prefix = match self {
+            Self::Pkexec => vec![
+                "env".to_string(),
+                format!("--chdir={}", working_directory.display()),
+            ],
+            Self::Sudo => {
+                let kept: Vec<&str> = env_keys
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|key| !ASKPASS_ENV_KEYS.contains(key))
+                    .collect();
+                let mut prefix = vec!["-A".to_string()];
+                if !kept.is_empty() {
+                    prefix.push(format!("--preserve-env={}", kept.join(",")));
+                }
+                prefix.push("--".to_string());
+                prefix
+            }
+        };
+        let args = prefix
+            .into_iter()
+            .chain(std::iter::once(executable))
+            .chain(arguments)
+            .collect();
+        (self.program().to_string(), args)
+    }
+}
+
+fn is_installed(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+/// Askpass helper mode: sudo starts TGUI with the prompt as only argument, the prompt is sent to
+/// the running app and the answer printed on stdout. Returns the exit code, non zero when the
+/// prompt was cancelled.
+#[cfg(unix)]
+pub fn askpass_client(socket: &Path, prompt: &str) -> i32 {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+
+    let answer = UnixStream::connect(socket).and_then(|mut stream| {
+        stream.write_all(format!("{}\n", prompt.replace('\n', " ")).as_bytes())?;
+        stream.shutdown(std::net::Shutdown::Write)?;
+        let mut answer = String::new();
+        stream.read_to_string(&mut answer)?;
+        Ok(answer)
+    });
+
+    match answer {
+        Ok(answer) if !answer.is_empty() => {
+            println!("{answer}");
+            0
+        }
+        Ok(_) => 1,
+        Err(e) => {
+            eprintln!("tgui askpass: {e}");
+            1
+        }
+    }
+}
+
+/// Listens for askpass prompts of one execution, the socket lives in a private temp dir that is
+/// removed on drop
+#[cfg(unix)]
+pub struct AskpassServer {
+    dir: tempfile::TempDir,
+    task: tokio::task::JoinHandle<()>,
+}
+
+#[cfg(unix)]
+impl AskpassServer {
+    /// `on_prompt` is called for each prompt, a `None` answer cancels it
+    pub fn start<F, Fut>(on_prompt: F) -> std::io::Result<Self>
+    where
+        F: Fn(String) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = Option<String>> + Send,
+    {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let dir = tempfile::Builder::new().prefix("tgui-askpass-").tempdir()?;
+        let listener = tokio::net::UnixListener::bind(dir.path().join("askpass.sock"))?;
+
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (reader, mut writer) = stream.into_split();
+                let mut prompt = String::new();
+                if BufReader::new(reader).read_line(&mut prompt).await.is_err() {
+                    continue;
+                }
+                let answer = on_prompt(prompt.trim_end().to_string()).await;
+                if let Some(answer) = answer {
+                    let _ = writer.write_all(answer.as_bytes()).await;
+                }
+                let _ = writer.shutdown().await;
+            }
+        });
+
+        Ok(Self { dir, task })
+    }
+
+    pub fn socket_path(&self) -> std::path::PathBuf {
+        self.dir.path().join("askpass.sock")
+    }
+
+    /// Env vars that make `sudo -A` ask through this server
+    pub fn env_vars(&self) -> std::io::Result<Vec<(String, String)>> {
+        let exe = std::env::current_exe()?;
+        Ok(vec![
+            (
+                ASKPASS_ENV_KEYS[0].to_string(),
+                exe.to_string_lossy().into_owned(),
+            ),
+            (
+                ASKPASS_ENV_KEYS[1].to_string(),
+                self.socket_path().to_string_lossy().into_owned(),
+            ),
+        ])
+    }
+}
+
+#[cfg(unix)]
+impl Drop for AskpassServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -12,6 +12,9 @@ pub enum ProcessKillError {
     #[error("Permission denied")]
     PermissionDenied,
 
+    #[error("Elevated process runs as root and can only be killed through a cgroup, which is unavailable")]
+    ElevatedWithoutCgroup,
+
     #[error("Platform-specific error: {0}")]
     PlatformError(String),
 
@@ -38,6 +41,16 @@ pub enum ProcessSpawnError {
     PermissionDenied(String),
     #[error("Invalid shell: {0}")]
     InvalidShell(String),
+    #[error("Invalid environment variable: {0}")]
+    InvalidEnvVar(String),
+    #[error("Failed to load env file {path}: {reason}")]
+    EnvFileError { path: String, reason: String },
+    #[error("Command is {0}, confirm to run it")]
+    ConfirmationRequired(String),
+    #[error("Command matches deny list pattern '{0}'")]
+    Denied(String),
+    #[error("{0}, no command runs until the spawn deny list is fixed")]
+    InvalidDenyList(String),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -48,4 +61,6 @@ pub enum ProcessManagerError {
     NotRunning(i64),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("No pending password prompt for process: {0}")]
+    NoPendingPrompt(i64),
 }
@@ -0,0 +1,228 @@
+use std::collections::{BTreeMap, HashMap};
+
+pub const EXECUTION_ID: &str = "EXECUTION_ID";
+const EXECUTION_ID_REF: &str = "${EXECUTION_ID}";
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum InterpolationError {
+    #[error("Undefined variable '{name}' in {field}")]
+    Undefined { name: String, field: String },
+    #[error("Circular variable reference: {}", chain.join(" -> "))]
+    Circular { chain: Vec<String> },
+    #[error("Unterminated variable reference in {field}")]
+    Unterminated { field: String },
+    #[error("Invalid variable name '{name}' in {field}")]
+    InvalidName { name: String, field: String },
+}
+
+type Result<T> = std::result::Result<T, InterpolationError>;
+
+/// Resolves `${NAME}` references, `$${` is a literal `${`. `$NAME` without braces is left
+/// for the shell.
+///
+/// Lookup order: env vars (configured and inherited) → group variables → built-ins.
+/// Env vars are applied level by level (root group → command): a value may reference vars
+/// of the same level, and a self reference like `PATH=${HOME}/bin:${PATH}` reads the value
+/// from the level below. Inherited values are taken literally.
+#[derive(Debug, Default)]
+pub struct Interpolator {
+    env: BTreeMap<String, String>,
+    variables: HashMap<String, String>,
+    resolved_variables: HashMap<String, String>,
+    builtins: HashMap<&'static str, String>,
+    uses_execution_id: bool,
+}
+
+impl Interpolator {
+    pub fn new(
+        variables: HashMap<String, String>,
+        builtins: HashMap<&'static str, String>,
+    ) -> Self {
+        Self {
+            variables,
+            builtins,
+            ..Default::default()
+        }
+    }
+
+    /// Base environment, values are not interpolated
+    pub fn inherit(&mut self, env: BTreeMap<String, String>) {
+        self.env = env;
+    }
+
+    pub fn unset(&mut self, keys: &Option<Vec<String>>) {
+        for key in keys.iter().flatten() {
+            self.env.remove(key);
+        }
+    }
+
+    pub fn apply_env_level(&mut self, level: &Option<HashMap<String, String>>) -> Result<()> {
+        let Some(level) = level else {
+            return Ok(());
+        };
+
+        let mut keys: Vec<&String> = level.keys().collect();
+        keys.sort();
+
+        let mut resolved = HashMap::new();
+        for key in keys {
+            self.resolve_level_key(key, level, &mut resolved, &mut Vec::new())?;
+        }
+        self.env.extend(resolved);
+        Ok(())
+    }
+
+    pub fn expand(&mut self, input: &str, field: &str) -> Result<String> {
+        expand(input, field, &mut |name| self.lookup(name))
+    }
+
+    /// True when `${EXECUTION_ID}` was left in place to be filled in once the id is known
+    pub fn uses_execution_id(&self) -> bool {
+        self.uses_execution_id
+    }
+
+    pub fn into_env(self) -> BTreeMap<String, String> {
+        self.env
+    }
+
+    fn resolve_level_key(
+        &mut self,
+        key: &str,
+        level: &HashMap<String, String>,
+        resolved: &mut HashMap<String, String>,
+        stack: &mut Vec<String>,
+    ) -> Result<String> {
+        if let Some(value) = resolved.get(key) {
+            return Ok(value.clone());
+        }
+        if stack.iter().any(|k| k == key) {
+            return Err(circular(stack, key));
+        }
+
+        stack.push(key.to_string());
+        let field = format!("env var {key}");
+        let value = expand(&level[key], &field, &mut |name| {
+            if name != key && level.contains_key(name) {
+                return self
+                    .resolve_level_key(name, level, resolved, stack)
+                    .map(Some);
+            }
+            self.lookup(name)
+        })?;
+        stack.pop();
+
+        resolved.insert(key.to_string(), value.clone());
+        Ok(value)
+    }
+
+    fn lookup(&mut self, name: &str) -> Result<Option<String>> {
+        if let Some(value) = self.env.get(name) {
+            return Ok(Some(value.clone()));
+        }
+        if self.variables.contains_key(name) {
+            return self.resolve_variable(name, &mut Vec::new()).map(Some);
+        }
+        Ok(self.builtin(name))
+    }
+
+    /// Group variables can reference other variables and built-ins, not env vars
+    fn resolve_variable(&mut self, name: &str, stack: &mut Vec<String>) -> Result<String> {
+        if let Some(value) = self.resolved_variables.get(name) {
+            return Ok(value.clone());
+        }
+        if stack.iter().any(|k| k == name) {
+            return Err(circular(stack, name));
+        }
+
+        stack.push(name.to_string());
+        let raw = self.variables[name].clone();
+        let field = format!("variable {name}");
+        let value = expand(&raw, &field, &mut |inner| {
+            if self.variables.contains_key(inner) {
+                return self.resolve_variable(inner, stack).map(Some);
+            }
+            Ok(self.builtin(inner))
+        })?;
+        stack.pop();
+
+        self.resolved_variables
+            .insert(name.to_string(), value.clone());
+        Ok(value)
+    }
+
+    fn builtin(&mut self, name: &str) -> Option<String> {
+        // the id only exists once the execution history row is created
+        if name == EXECUTION_ID {
+            self.uses_execution_id = true;
+            return Some(EXECUTION_ID_REF.to_string());
+        }
+        self.builtins.get(name).cloned()
+    }
+}
+
+/// Fills in the `${EXECUTION_ID}` references left by `Interpolator`
+pub fn fill_execution_id(value: &str, execution_id: i64) -> String {
+    value.replace(EXECUTION_ID_REF, &execution_id.to_string())
+}
+
+fn circular(stack: &[String], name: &str) -> InterpolationError {
+    let start = stack.iter().position(|k| k == name).unwrap_or(0);
+    let mut chain = stack[start..].to_vec();
+    chain.push(name.to_string());
+    InterpolationError::Circular { chain }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+fn expand<F>(input: &str, field: &str, lookup: &mut F) -> Result<String>
+where
+    F: FnMut(&str) -> Result<Option<String>>,
+{
+    if !input.contains("${") {
+        return Ok(input.to_string());
+    }
+
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find("${") {
+        // `$${` escapes the reference
+        if rest[..start].ends_with('$') {
+            output.push_str(&rest[..start - 1]);
+            output.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| InterpolationError::Unterminated {
+                field: field.to_string(),
+            })?;
+
+        let name = &after[..end];
+        if !is_valid_name(name) {
+            return Err(InterpolationError::InvalidName {
+                name: name.to_string(),
+                field: field.to_string(),
+            });
+        }
+
+        let value = lookup(name)?.ok_or_else(|| InterpolationError::Undefined {
+            name: name.to_string(),
+            field: field.to_string(),
+        })?;
+        output.push_str(&value);
+        rest = &after[end + 1..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
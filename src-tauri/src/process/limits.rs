@@ -0,0 +1,108 @@
+use crate::database::{IoPriorityClass, ResourceLimits};
+use std::io;
+
+/// Plain copy of `ResourceLimits` that can be moved into the `pre_exec` closure.
+/// `max_processes` is left out, it is only enforced through the cgroup's `pids.max`.
+/// `max_memory_bytes` is RLIMIT_DATA rather than RLIMIT_AS, runtimes like the JVM, node and Go
+/// reserve far more address space than they ever use and would fail to start.
+///
+/// Everything in `apply` runs in the forked child before exec, so it must stay
+/// async-signal-safe: no allocation, no locks, only raw syscalls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RawLimits {
+    max_memory_bytes: Option<u64>,
+    max_cpu_seconds: Option<u64>,
+    max_open_files: Option<u64>,
+    nice: Option<i32>,
+    io_priority: Option<i32>,
+}
+
+impl From<&ResourceLimits> for RawLimits {
+    fn from(limits: &ResourceLimits) -> Self {
+        Self {
+            max_memory_bytes: limits.max_memory_bytes,
+            max_cpu_seconds: limits.max_cpu_seconds,
+            max_open_files: limits.max_open_files,
+            nice: limits.nice,
+            io_priority: limits.io_priority.as_ref().map(|p| {
+                // ioprio value layout: class in the top 3 bits, level in the lower 13
+                let class = match p.class {
+                    IoPriorityClass::RealTime => 1,
+                    IoPriorityClass::BestEffort => 2,
+                    IoPriorityClass::Idle => 3,
+                };
+                (class << 13) | i32::from(p.level)
+            }),
+        }
+    }
+}
+
+impl RawLimits {
+    /// For a process that joins an `ExecutionCgroup`, whose `memory.max` already limits memory
+    pub fn in_cgroup(self) -> Self {
+        Self {
+            max_memory_bytes: None,
+            ..self
+        }
+    }
+
+    pub fn apply(&self) -> io::Result<()> {
+        if let Some(nice) = self.nice {
+            // SAFETY: plain syscall on the calling (child) process
+            let ret = unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) };
+            if ret != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Some(io_priority) = self.io_priority {
+            const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+            // SAFETY: ioprio_set has no libc wrapper, arguments follow ioprio_set(2)
+            let ret =
+                unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, io_priority) };
+            if ret != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        set_rlimit(libc::RLIMIT_DATA, self.max_memory_bytes)?;
+        set_rlimit(libc::RLIMIT_CPU, self.max_cpu_seconds)?;
+        set_rlimit(libc::RLIMIT_NOFILE, self.max_open_files)?;
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+type RlimitResource = libc::__rlimit_resource_t;
+#[cfg(not(target_os = "linux"))]
+type RlimitResource = libc::c_int;
+
+/// Soft and hard limits are set to the same value so the command cannot raise it again
+fn set_rlimit(resource: RlimitResource, value: Option<u64>) -> io::Result<()> {
+    let Some(value) = value else {
+        return Ok(());
+    };
+
+    let limit = libc::rlimit {
+        rlim_cur: value as libc::rlim_t,
+        rlim_max: value as libc::rlim_t,
+    };
+    // SAFETY: `limit` is a valid rlimit living on the stack for the duration of the call
+    if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Moves the calling process into the cgroup whose `cgroup.procs` is open as `procs_fd`.
+/// Writing "0" means "the writing process", which is the child at this point.
+pub fn enter_cgroup(procs_fd: libc::c_int) -> io::Result<()> {
+    let pid = b"0";
+    // SAFETY: fd was opened by the parent before fork and stays open until spawn returns
+    let written = unsafe { libc::write(procs_fd, pid.as_ptr().cast(), pid.len()) };
+    if written < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
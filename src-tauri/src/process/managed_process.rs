@@ -1,11 +1,11 @@
-use crate::database::ExecutionStatus;
+use crate::database::{ExecutionStatus, ExecutionTarget};
+use crate::process::cgroup::ExecutionCgroup;
 use crate::process::errors::{ProcessKillError, ProcessSpawnError};
 use crate::process::log_buffer::LogBuffer;
 use crate::process::models::{
-    KillMode, LogLineEvent, ProcessStartedEvent, ProcessStatus, ProcessStatusChangedEvent,
-    ProcessStoppedEvent, SpawnContext, StreamingConfig,
+    AskpassRequestEvent, KillMode, LogLineEvent, ProcessStartedEvent, ProcessStatus,
+    ProcessStatusChangedEvent, ProcessStoppedEvent, SpawnContext, StreamingConfig,
 };
-use crate::process::shell;
 use crate::process::signals::ProcessHandle;
 use crate::process::streaming::LogStreamer;
 use crate::utils::get_utc_timestamp_string;
@@ -24,6 +24,8 @@ pub struct ManagedProcess {
     pub start_time: String,
     pub context: SpawnContext,
     kill_tx: Option<oneshot::Sender<KillMode>>,
+    /// False for elevated processes without a cgroup, root owned processes can't be signalled
+    killable: bool,
 }
 
 #[derive(Debug)]
@@ -32,6 +34,7 @@ pub enum ProcessEvent {
     StatusChanged(ProcessStatusChangedEvent),
     Stopped(ProcessStoppedEvent),
     LogBatch(Vec<Arc<LogLineEvent>>),
+    AskpassRequested(AskpassRequestEvent),
 }
 
 impl ManagedProcess {
@@ -44,21 +47,19 @@ impl ManagedProcess {
         debug!(
             execution_id,
             command_id = context.command_id,
-            executable = %context.executable,
+            executable = %context.mask(&context.executable),
             "Spawning process"
         );
 
-        // build command via shell resolver
-        let result = shell::build_exec(
-            &context.executable,
-            &context.arguments,
-            context.shell.as_deref(),
-        );
+        let (executable, args) = context.invocation(execution_id);
 
-        let mut cmd = Command::new(&result.executable);
-        cmd.args(&result.args);
+        let mut cmd = Command::new(&executable);
+        cmd.args(&args);
 
         cmd.current_dir(&context.working_directory);
+        if context.clear_env {
+            cmd.env_clear();
+        }
         for (key, value) in &context.env_vars {
             cmd.env(key, value);
         }
@@ -68,15 +69,32 @@ impl ManagedProcess {
             .stderr(std::process::Stdio::piped())
             .stdin(std::process::Stdio::null());
 
-        // Process group (for tree killing)
+        if !context.resource_limits.is_empty() {
+            debug!(execution_id, limits = ?context.resource_limits, "Applying resource limits");
+        }
+        let elevated = context.elevation.is_some();
+        let cgroup = ExecutionCgroup::for_limits(execution_id, &context.resource_limits, elevated);
+        let killable = !elevated || cgroup.is_some();
+
+        // Process group (for tree killing) and resource limits
         #[cfg(unix)]
         {
+            use crate::process::limits::{self, RawLimits};
             use libc::{prctl, PR_SET_PDEATHSIG, SIGKILL};
+            let raw_limits = match &cgroup {
+                Some(_) => RawLimits::from(&context.resource_limits).in_cgroup(),
+                None => RawLimits::from(&context.resource_limits),
+            };
+            let cgroup_procs_fd = cgroup.as_ref().map(|c| c.procs_fd());
             unsafe {
                 cmd.pre_exec(move || {
                     if kill_process_tree {
                         libc::setpgid(0, 0);
                     }
+                    if let Some(fd) = cgroup_procs_fd {
+                        limits::enter_cgroup(fd)?;
+                    }
+                    raw_limits.apply()?;
                     // If parent (TGUI) dies, kernel sends SIGKILL to this process immediately
                     let ret = prctl(PR_SET_PDEATHSIG, SIGKILL, 0, 0, 0);
                     if ret != 0 {
@@ -89,8 +107,21 @@ impl ManagedProcess {
         }
 
         // spawn child
-        let mut child = cmd.spawn().map_err(ProcessSpawnError::SpawnFailed)?;
-        let pid = child.id().ok_or(ProcessSpawnError::NoPid)?;
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                if let Some(cgroup) = cgroup {
+                    cgroup.release().await;
+                }
+                return Err(ProcessSpawnError::SpawnFailed(e));
+            }
+        };
+        let Some(pid) = child.id() else {
+            if let Some(cgroup) = cgroup {
+                cgroup.release().await;
+            }
+            return Err(ProcessSpawnError::NoPid);
+        };
         let start_time = get_utc_timestamp_string();
 
         info!(execution_id, pid, command = %context.name, "Process spawned successfully");
@@ -107,7 +138,11 @@ impl ManagedProcess {
             pid,
             stdout,
             stderr,
-            StreamingConfig::default(),
+            StreamingConfig {
+                secrets: context.secrets.clone(),
+                redactor: context.redactor.clone(),
+                ..StreamingConfig::default()
+            },
             event_sender.clone(),
         );
 
@@ -136,6 +171,8 @@ impl ManagedProcess {
             warn!(error = ?e, "Failed to send Started event");
         }
 
+        let target = context.target.clone();
+
         // Spawn monitor task that owns the child and kill_rx
         tokio::spawn(async move {
             let mut process_handle = ProcessHandle { pid, child };
@@ -170,8 +207,16 @@ impl ManagedProcess {
                         timestamp: get_utc_timestamp_string(),
                     })).await;
 
-                    match mode {
-                        KillMode::Graceful => {
+                    kill_remote(&target, execution_id, matches!(mode, KillMode::Force)).await;
+
+                    match (mode, &cgroup) {
+                        // Runs as root, only the cgroup can stop it and that can't be done gracefully
+                        (_, Some(cgroup)) if elevated => {
+                            if let Err(e) = cgroup.kill_all() {
+                                error!(error = %e, "Killing elevated process through its cgroup failed");
+                            }
+                        }
+                        (KillMode::Graceful, _) => {
                             // Send SIGTERM
                             if let Err(e) = process_handle.graceful_kill().await {
                                 error!(error = %e, "Graceful kill failed");
@@ -182,15 +227,20 @@ impl ManagedProcess {
                                 Ok(None) => {
                                     // Timeout, escalate to force kill
                                     warn!("Graceful kill timed out, escalating to force kill");
+                                    kill_remote(&target, execution_id, true).await;
                                     let _ = process_handle.force_kill().await;
                                 }
                                 _ => {} // Exited gracefully or error
                             }
                         }
-                        KillMode::Force => {
+                        (KillMode::Force, _) => {
                             if let Err(e) = process_handle.force_kill().await {
                                 error!(error = %e, "Force kill failed");
                             }
+                            // Also catches descendants that moved to their own process group
+                            if let Some(cgroup) = &cgroup {
+                                let _ = cgroup.kill_all();
+                            }
                         }
                     }
 
@@ -202,6 +252,10 @@ impl ManagedProcess {
             // wait for last log batch to flush
             let _ = streaming_handle.await;
 
+            if let Some(cgroup) = cgroup {
+                cgroup.release().await;
+            }
+
             // Finalize - emit stopped event regardless of how we got here
             let (new_status, exit_code) = match exit_status {
                 Ok(status) => {
@@ -280,11 +334,15 @@ impl ManagedProcess {
             start_time,
             context,
             kill_tx: Some(kill_tx),
+            killable,
         })
     }
 
     /// Send graceful kill signal (returns immediately, monitor handles the wait)
     pub async fn graceful_kill(&mut self) -> Result<(), ProcessKillError> {
+        if !self.killable {
+            return Err(ProcessKillError::ElevatedWithoutCgroup);
+        }
         self.kill_tx
             .take()
             .ok_or(ProcessKillError::AlreadyExited)?
@@ -294,6 +352,9 @@ impl ManagedProcess {
 
     /// Send force kill signal (returns immediately, monitor handles the wait)
     pub async fn force_kill(&mut self) -> Result<(), ProcessKillError> {
+        if !self.killable {
+            return Err(ProcessKillError::ElevatedWithoutCgroup);
+        }
         self.kill_tx
             .take()
             .ok_or(ProcessKillError::AlreadyExited)?
@@ -327,3 +388,27 @@ impl ManagedProcess {
         matches!(*self.status.read().await, ProcessStatus::Running { .. })
     }
 }
+
+/// Signals the process on a container or ssh target, the local client is killed as usual
+async fn kill_remote(target: &ExecutionTarget, execution_id: i64, force: bool) {
+    let Some((program, args)) = target.kill_command(execution_id, force) else {
+        return;
+    };
+    let output = tokio::time::timeout(
+        tokio::time::Duration::from_secs(10),
+        Command::new(&program).args(&args).output(),
+    )
+    .await;
+    match output {
+        Ok(Ok(output)) if output.status.success() => {
+            debug!(execution_id, force, "Remote process signalled")
+        }
+        Ok(Ok(output)) => warn!(
+            execution_id,
+            stderr = %String::from_utf8_lossy(&output.stderr),
+            "Remote kill failed"
+        ),
+        Ok(Err(e)) => error!(execution_id, error = %e, "Failed to run remote kill"),
+        Err(_) => warn!(execution_id, "Remote kill timed out"),
+    }
+}
@@ -1,23 +1,61 @@
-use crate::database::{Database, ExecutionHistory, ExecutionStatus, TriggeredBy};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use crate::constants::{COMMANDS_TABLE, GROUPS_TABLE};
+use crate::database::{
+    Database, DatabaseError, EnvInheritance, ExecutionHistory, ExecutionStatus, Group, SecretValue,
+    ShellDefinition, ShellFamily, TriggeredBy,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
 use crate::handlers::serialize_errors::SerializableError;
+use crate::process::dotenv;
+#[cfg(unix)]
+use crate::process::elevation::AskpassServer;
+use crate::process::elevation::ElevationMethod;
 use crate::process::errors::{ProcessKillError, ProcessManagerError, ProcessSpawnError};
+use crate::process::interpolation::Interpolator;
 use crate::process::managed_process::{ManagedProcess, ProcessEvent};
-use crate::process::models::{LogLineEvent, OrphanedProcess, ProcessInfo, SpawnContext};
+use crate::process::models::{
+    AskpassRequestEvent, LogLineEvent, OrphanedProcess, PreviewEnvVar, ProcessInfo, SpawnContext,
+    SpawnPreview, ValueSource,
+};
+use crate::process::parameters;
+use crate::process::preflight::{self, PreflightProblem, ProblemKind};
+use crate::process::redaction::{self, Redactor};
+use crate::process::safety::{self, SafetyCheck};
 use crate::process::shell;
+use crate::utils::get_local_date_string;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
 use dashmap::DashMap;
 use std::sync::Arc;
 use tauri::{AppHandle, Emitter};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 
+/// How long a confirmation token from `check_spawn_safety` can be redeemed
+pub const CONFIRMATION_TTL: Duration = Duration::from_secs(300);
+
+/// Where `resolve_with_sources` took the inherited values from, keys without a source are
+/// inherited from TGUI's environment
+struct ResolvedSources {
+    working_directory: ValueSource,
+    env: BTreeMap<String, ValueSource>,
+    shell: Option<ValueSource>,
+}
+
 pub struct ProcessManager {
     processes: DashMap<i64, ManagedProcess>,
     db: Database,
     app_handle: Option<AppHandle>,
     event_sender: mpsc::Sender<ProcessEvent>,
+    /// Issued confirmation tokens with the command line they confirm, each redeemed once
+    confirmations: DashMap<String, (String, Instant)>,
+    /// Askpass listeners of running `sudo -A` processes, dropped when the process stops
+    #[cfg(unix)]
+    askpass_servers: DashMap<i64, AskpassServer>,
+    /// Open password prompts waiting for `answer_askpass`
+    askpass_answers: Arc<DashMap<i64, oneshot::Sender<Option<String>>>>,
 }
 
 impl ProcessManager {
@@ -29,6 +67,10 @@ impl ProcessManager {
             db,
             app_handle,
             event_sender,
+            confirmations: DashMap::new(),
+            #[cfg(unix)]
+            askpass_servers: DashMap::new(),
+            askpass_answers: Arc::new(DashMap::new()),
         });
 
         let pm_clone = pm.clone();
@@ -42,31 +84,75 @@ impl ProcessManager {
 
     pub async fn spawn_command(
         &self,
-        context: SpawnContext,
+        mut context: SpawnContext,
         triggered_by: TriggeredBy,
     ) -> Result<i64, ProcessSpawnError> {
-        if let Some(ref shell) = context.shell {
-            if !shell::is_valid_shell(shell) {
-                return Err(ProcessSpawnError::InvalidShell(format!(
-                    "Shell '{shell}' is not allowed. Permitted: {}",
-                    shell::get_allowed_shells().join(", ")
-                )));
-            }
-        }
+        self.load_shell_definition(&mut context)?;
 
         let command_id = context.command_id;
 
+        if let Some(problem) = preflight::check(&context, context.shell_definition.as_ref())
+            .into_iter()
+            .find(PreflightProblem::is_error)
+        {
+            warn!(
+                command_id,
+                problem = problem.message,
+                "Pre-flight check failed"
+            );
+            return Err(problem.into());
+        }
+
+        let (check, command_line) = self.assess_spawn_safety(&context)?;
+        if let Some(pattern) = check.denied_by {
+            warn!(command_id, pattern, "Spawn blocked by deny list");
+            return Err(ProcessSpawnError::Denied(pattern));
+        }
+        let safety_override = if check.requires_confirmation {
+            if !self.redeem_confirmation(context.confirmation_token.as_deref(), &command_line) {
+                return Err(ProcessSpawnError::ConfirmationRequired(check.reason()));
+            }
+            info!(
+                command_id,
+                reason = check.reason(),
+                "Running confirmed command"
+            );
+            Some(check.to_override())
+        } else {
+            None
+        };
+
         let execution_id = self
             .db
-            .create_execution_history(&ExecutionHistory::new_with_command(
-                command_id,
-                triggered_by,
-            ))
+            .create_execution_history(&ExecutionHistory {
+                context: context.history_context(safety_override),
+                ..ExecutionHistory::new_with_command(command_id, triggered_by)
+            })
             .map_err(|e| {
                 error!(error = %e, "Failed to create execution history");
                 ProcessSpawnError::DatabaseError("Could not create execution history".to_string())
             })?;
 
+        if context.uses_execution_id {
+            context.fill_execution_id(execution_id);
+        }
+        context.redactor = self.redactor();
+
+        #[cfg(unix)]
+        let askpass = match context.elevation {
+            Some(ElevationMethod::Sudo) => match self.start_askpass(execution_id, &mut context) {
+                Ok(server) => Some(server),
+                Err(e) => {
+                    error!(execution_id, error = %e, "Failed to start askpass helper");
+                    if let Err(db_err) = self.db.kill_failed_execution(execution_id) {
+                        error!(execution_id, error = %db_err, "Failed to mark failed execution");
+                    }
+                    return Err(ProcessSpawnError::SpawnFailed(e));
+                }
+            },
+            _ => None,
+        };
+
         let kill_tree = self
             .db
             .get_setting("kill_process_tree_by_default")
@@ -96,10 +182,183 @@ impl ProcessManager {
         }
 
         self.processes.insert(execution_id, process);
+        #[cfg(unix)]
+        if let Some(server) = askpass {
+            self.askpass_servers.insert(execution_id, server);
+        }
         info!(execution_id, command_id, "Command spawned and tracked");
         Ok(execution_id)
     }
 
+    /// Explains the command line of `context` and checks it against the deny list and the
+    /// `confirm_dangerous_commands` setting. A command that requires confirmation gets a token
+    /// for one `spawn_command` of this command line within `CONFIRMATION_TTL`.
+    pub fn check_spawn_safety(
+        &self,
+        context: &SpawnContext,
+    ) -> Result<SafetyCheck, ProcessSpawnError> {
+        let (mut check, command_line) = self.assess_spawn_safety(context)?;
+        if check.requires_confirmation && check.denied_by.is_none() {
+            check.confirmation_token = Some(self.issue_confirmation(command_line));
+        }
+        Ok(check)
+    }
+
+    /// The check without a token, along with the unmasked command line it was made for.
+    /// A deny list that can't be parsed blocks everything until it is fixed.
+    fn assess_spawn_safety(
+        &self,
+        context: &SpawnContext,
+    ) -> Result<(SafetyCheck, String), ProcessSpawnError> {
+        // the explainer splits command lines the POSIX way
+        let command_line = shell::join_command(
+            &context.executable,
+            &context.arguments,
+            &context.raw_arguments,
+            ShellFamily::Posix,
+        );
+        // the explanation is shown as is, secrets are no commands so masking them first is safe
+        let masked_line = context.mask(&command_line);
+        let mut explanation = self.db.explain_command(&masked_line).map_err(|e| {
+            error!(error = %e, "Failed to explain command");
+            ProcessSpawnError::DatabaseError("Could not check command safety".to_string())
+        })?;
+
+        explanation.is_privileged |= context.elevation.is_some();
+
+        let deny_list = match self.db.get_setting("spawn_deny_list") {
+            Ok(json) => safety::parse_deny_list(&json).map_err(|e| {
+                error!(error = %e, "Spawn deny list is invalid");
+                ProcessSpawnError::InvalidDenyList(e)
+            })?,
+            Err(DatabaseError::NotFound { .. }) => Vec::new(),
+            Err(e) => {
+                error!(error = %e, "Failed to read spawn deny list");
+                return Err(ProcessSpawnError::DatabaseError(
+                    "Could not check command safety".to_string(),
+                ));
+            }
+        };
+        let denied_by =
+            safety::denied_by(&deny_list, &command_line).map(|pattern| pattern.to_string());
+
+        let confirm = self
+            .db
+            .get_setting("confirm_dangerous_commands")
+            .map(|v| v != "false")
+            .unwrap_or(true);
+        let requires_confirmation =
+            confirm && (explanation.is_destructive || explanation.is_privileged);
+
+        let check = SafetyCheck {
+            command_line: masked_line,
+            explanation,
+            denied_by,
+            requires_confirmation,
+            confirmation_token: None,
+        };
+        Ok((check, command_line))
+    }
+
+    fn issue_confirmation(&self, command_line: String) -> String {
+        let now = Instant::now();
+        self.confirmations
+            .retain(|_, (_, issued)| now.duration_since(*issued) < CONFIRMATION_TTL);
+
+        let mut bytes = [0u8; 16];
+        OsRng.fill_bytes(&mut bytes);
+        let token: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+        self.confirmations
+            .insert(token.clone(), (command_line, now));
+        token
+    }
+
+    /// Consumes the token, it is only accepted for the command line it was issued for and
+    /// before it expires
+    fn redeem_confirmation(&self, token: Option<&str>, command_line: &str) -> bool {
+        token
+            .and_then(|token| self.confirmations.remove(token))
+            .is_some_and(|(_, (confirmed_line, issued))| {
+                confirmed_line == command_line && issued.elapsed() < CONFIRMATION_TTL
+            })
+    }
+
+    /// Everything about `context` that would make the spawn fail or misbehave, the same check
+    /// `spawn_command` runs before it creates the history row
+    pub fn validate_command(&self, context: &SpawnContext) -> Vec<PreflightProblem> {
+        let shell = match (&context.shell_definition, &context.shell) {
+            (Some(definition), _) => Ok(Some(definition.clone())),
+            (None, Some(name)) => self.shell_definition(name).map(Some),
+            (None, None) => Ok(None),
+        };
+        match shell {
+            Ok(shell) => preflight::check(context, shell.as_ref()),
+            Err(e) => {
+                let mut problems = vec![PreflightProblem::error(
+                    ProblemKind::InvalidShell,
+                    "shell",
+                    e.to_string(),
+                )];
+                problems.extend(preflight::check(context, None));
+                problems
+            }
+        }
+    }
+
+    /// Answers the open password prompt of a `sudo -A` process, `None` cancels it
+    pub fn answer_askpass(
+        &self,
+        execution_id: i64,
+        answer: Option<String>,
+    ) -> Result<(), ProcessManagerError> {
+        let (_, sender) = self
+            .askpass_answers
+            .remove(&execution_id)
+            .ok_or(ProcessManagerError::NoPendingPrompt(execution_id))?;
+        sender
+            .send(answer)
+            .map_err(|_| ProcessManagerError::NoPendingPrompt(execution_id))
+    }
+
+    /// Prompts are forwarded to the UI as `askpass-request` events
+    #[cfg(unix)]
+    fn start_askpass(
+        &self,
+        execution_id: i64,
+        context: &mut SpawnContext,
+    ) -> std::io::Result<AskpassServer> {
+        let event_sender = self.event_sender.clone();
+        let answers = self.askpass_answers.clone();
+        let server = AskpassServer::start(move |prompt| {
+            let event_sender = event_sender.clone();
+            let answers = answers.clone();
+            async move {
+                let (sender, receiver) = oneshot::channel();
+                answers.insert(execution_id, sender);
+                let event = AskpassRequestEvent {
+                    execution_id,
+                    prompt,
+                };
+                if event_sender
+                    .send(ProcessEvent::AskpassRequested(event))
+                    .await
+                    .is_err()
+                {
+                    answers.remove(&execution_id);
+                    return None;
+                }
+                receiver.await.ok().flatten()
+            }
+        })?;
+
+        let env_vars = server.env_vars()?;
+        context
+            .env_vars
+            .retain(|(key, _)| env_vars.iter().all(|(k, _)| k != key));
+        context.env_vars.extend(env_vars);
+        Ok(server)
+    }
+
     pub async fn kill_process(
         &self,
         execution_id: i64,
@@ -156,7 +415,7 @@ impl ProcessManager {
             pid: process.pid,
             command_id: process.command_id,
             command_name: process.command_name.clone(),
-            command: process.context.executable.clone(),
+            command: process.context.mask(&process.context.executable),
             status,
             start_time: process.start_time.clone(),
             exit_code: None,
@@ -240,27 +499,128 @@ impl ProcessManager {
         &self,
         command_id: i64,
     ) -> Result<SpawnContext, SerializableError> {
+        self.resolve_spawn_context_with_params(command_id, &HashMap::new())
+            .await
+    }
+
+    /// `params` are the values for the command's declared parameters, defaults fill the rest
+    pub async fn resolve_spawn_context_with_params(
+        &self,
+        command_id: i64,
+        params: &HashMap<String, String>,
+    ) -> Result<SpawnContext, SerializableError> {
+        self.resolve_with_sources(command_id, params)
+            .await
+            .map(|(context, _)| context)
+    }
+
+    /// The resolved invocation of a command without spawning it, to see what inheritance made
+    /// of it
+    pub async fn preview_spawn(
+        &self,
+        command_id: i64,
+        params: &HashMap<String, String>,
+    ) -> Result<SpawnPreview, SerializableError> {
+        let (mut context, sources) = self.resolve_with_sources(command_id, params).await?;
+        self.load_shell_definition(&mut context)?;
+
+        // the execution id isn't known yet, wrappers naming files after it get 0
+        let (executable, arguments) = context.invocation(0);
+        let env_vars = context
+            .env_vars
+            .iter()
+            .map(|(key, value)| PreviewEnvVar {
+                key: key.clone(),
+                value: context.mask(value),
+                source: sources
+                    .env
+                    .get(key)
+                    .cloned()
+                    .unwrap_or(ValueSource::Inherited),
+            })
+            .collect();
+
+        let masked = context.masked();
+
+        Ok(SpawnPreview {
+            executable: context.mask(&executable),
+            arguments: arguments.iter().map(|arg| context.mask(arg)).collect(),
+            working_directory: masked.working_directory,
+            remote_working_directory: masked.remote_working_directory,
+            working_directory_source: sources.working_directory,
+            env_vars,
+            shell: context.shell.clone(),
+            shell_source: sources.shell,
+        })
+    }
+
+    async fn resolve_with_sources(
+        &self,
+        command_id: i64,
+        params: &HashMap<String, String>,
+    ) -> Result<(SpawnContext, ResolvedSources), SerializableError> {
         let cmd = self
             .db
             .get_command(command_id)
             .map_err(SerializableError::from)?;
 
+        let parameter_values = parameters::resolve_values(&cmd.parameters, params)?;
+
         let ancestors = cmd
             .group_id
             .and_then(|gid| self.db.get_group_ancestor_chain(gid).ok())
             .unwrap_or_default();
 
-        let working_directory = cmd
+        let group_dir = ancestors
+            .iter()
+            .find_map(|g| g.working_directory.as_deref())
+            .and_then(Self::expand_home);
+
+        let mut variables = HashMap::new();
+        for group in ancestors.iter().rev() {
+            if let Some(ref vars) = group.variables {
+                variables.extend(vars.iter().map(|(k, v)| (k.clone(), v.clone())));
+            }
+        }
+
+        let mut interpolator = Interpolator::new(variables, Self::builtin_variables(group_dir));
+
+        let env_inheritance = cmd
+            .env_inheritance
+            .clone()
+            .or_else(|| ancestors.iter().find_map(|g| g.env_inheritance.clone()))
+            .unwrap_or_default();
+        interpolator.inherit(Self::inherited_env(&env_inheritance));
+
+        let target = cmd
+            .execution_target
+            .clone()
+            .or_else(|| ancestors.iter().find_map(|g| g.execution_target.clone()))
+            .unwrap_or_default();
+
+        // env files are located relative to the working directory, so it is resolved before
+        // any configured env var is applied and can't reference them
+        let configured_directory = cmd
             .working_directory
             .clone()
             .or_else(|| ancestors.iter().find_map(|g| g.working_directory.clone()))
-            .and_then(|wd| {
-                if wd.starts_with("~/") {
-                    dirs::home_dir().map(|home| home.join(&wd[2..]))
-                } else {
-                    Some(PathBuf::from(wd))
-                }
+            .map(|wd| interpolator.expand(&wd, "working_directory"))
+            .transpose()?;
+        // the directory of a remote target only exists there, the client runs in the home dir
+        let (local_directory, remote_working_directory) = if target.is_local() {
+            (
+                configured_directory.and_then(|wd| Self::expand_home(&wd)),
+                None,
+            )
+        } else {
+            (None, configured_directory)
+        };
+        let working_directory_source =
+            Self::source_of(cmd.working_directory.is_some(), &ancestors, |g| {
+                g.working_directory.is_some()
             })
+            .unwrap_or(ValueSource::Default);
+        let working_directory = local_directory
             .or_else(|| dirs::home_dir())
             .unwrap_or_else(|| PathBuf::from("/"));
 
@@ -274,37 +634,355 @@ impl ProcessManager {
             });
         }
 
+        let mut secrets = Vec::new();
+        let mut env_sources = BTreeMap::new();
+        for group in ancestors.iter().rev() {
+            interpolator.unset(&group.env_unset);
+            let group_secrets =
+                self.load_secrets(GROUPS_TABLE, group.id, &group.secret_env_vars, &mut secrets)?;
+            let level = Self::load_env_level(
+                &mut interpolator,
+                &group.env_files,
+                &group.env_vars,
+                group_secrets,
+                &working_directory,
+            )?;
+            interpolator.apply_env_level(&level)?;
+            Self::record_env_sources(
+                &mut env_sources,
+                &group.env_unset,
+                &level,
+                ValueSource::from(group),
+            );
+        }
+        let cmd_env_vars = cmd.env_vars.as_ref().map(|vars| {
+            vars.iter()
+                .map(|(k, v)| (k.clone(), parameters::substitute(v, &parameter_values)))
+                .collect()
+        });
+        interpolator.unset(&cmd.env_unset);
+        let cmd_secrets =
+            self.load_secrets(COMMANDS_TABLE, cmd.id, &cmd.secret_env_vars, &mut secrets)?;
+        let level = Self::load_env_level(
+            &mut interpolator,
+            &cmd.env_files,
+            &cmd_env_vars,
+            cmd_secrets,
+            &working_directory,
+        )?;
+        interpolator.apply_env_level(&level)?;
+        Self::record_env_sources(
+            &mut env_sources,
+            &cmd.env_unset,
+            &level,
+            ValueSource::Command,
+        );
+
+        let executable = interpolator.expand(&cmd.command, "command")?;
+        let arguments = cmd
+            .arguments
+            .iter()
+            .map(|arg| {
+                let arg = parameters::substitute(arg, &parameter_values);
+                interpolator.expand(&arg, "arguments")
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
         let shell = cmd
             .shell
             .clone()
             .or_else(|| ancestors.iter().find_map(|g| g.shell.clone()))
-            .or_else(|| self.db.get_setting("default_shell").ok());
-
-        let mut env_map: HashMap<String, String> = HashMap::new();
-        let mut update_env_map = |env_vars: &Option<HashMap<String, String>>| {
-            if let Some(ref vars) = env_vars {
-                env_map.extend(vars.iter().map(|(k, v)| (k.clone(), v.clone())));
+            .or_else(|| {
+                self.db
+                    .get_setting("default_shell")
+                    .ok()
+                    .filter(|shell| !shell.is_empty())
+            });
+        let shell_source = Self::source_of(cmd.shell.is_some(), &ancestors, |g| g.shell.is_some())
+            .or_else(|| shell.is_some().then_some(ValueSource::Setting));
+        let shell_mode = cmd
+            .shell_mode
+            .or_else(|| ancestors.iter().find_map(|g| g.shell_mode));
+        // a mode and a shell inherited from different levels are only checked together here
+        if let (Some(name), Some(mode)) = (&shell, shell_mode) {
+            let family = self.db.get_shell_by_name(name).map(|s| s.family);
+            if family.is_ok_and(|family| !family.supports_mode(mode)) {
+                return Err(SerializableError {
+                    code: "INVALID_SHELL".to_string(),
+                    message: format!("Shell '{name}' has no login or interactive mode"),
+                });
             }
-        };
+        }
+        let source_files = ancestors
+            .iter()
+            .rev()
+            .filter_map(|g| g.source_files.as_ref())
+            .chain(&cmd.source_files)
+            .flatten()
+            .map(|path| interpolator.expand(path, "source_files"))
+            .collect::<Result<Vec<_>, _>>()?;
 
-        for group in ancestors.iter().rev() {
-            update_env_map(&group.env_vars);
+        // pkexec/sudo would elevate the local docker or ssh client, not the command it runs
+        if cmd.run_elevated && !target.is_local() {
+            return Err(SerializableError {
+                code: "ELEVATION_UNAVAILABLE".to_string(),
+                message: "A command running in a container or over ssh can't run elevated"
+                    .to_string(),
+            });
         }
-        update_env_map(&cmd.env_vars);
+        let elevation = if cmd.run_elevated {
+            let method = self.elevation_method()?;
+            let keys: Vec<&str> = env_sources.keys().map(String::as_str).collect();
+            method
+                .check_env(&keys)
+                .map_err(|message| SerializableError {
+                    code: "ELEVATION_UNAVAILABLE".to_string(),
+                    message,
+                })?;
+            Some(method)
+        } else {
+            None
+        };
 
-        let env_vars: Vec<(String, String)> = env_map.into_iter().collect();
+        let uses_execution_id = interpolator.uses_execution_id();
+        let env_vars: Vec<(String, String)> = interpolator.into_env().into_iter().collect();
+
+        // closest level wins per field, so fold from the command outwards
+        let resource_limits = ancestors
+            .iter()
+            .filter_map(|g| g.resource_limits.as_ref())
+            .fold(cmd.resource_limits.unwrap_or_default(), |limits, parent| {
+                limits.inherit_from(parent)
+            });
 
-        Ok(SpawnContext {
+        let sources = ResolvedSources {
+            working_directory: working_directory_source,
+            env: env_sources,
+            shell: shell_source,
+        };
+        let context = SpawnContext {
             command_id: cmd.id,
             name: cmd.name,
-            executable: cmd.command,
-            arguments: cmd.arguments,
+            executable,
+            arguments,
+            raw_arguments: cmd.raw_arguments,
             working_directory,
             env_vars,
+            clear_env: true,
             shell,
+            shell_mode,
+            source_files,
+            shell_definition: None,
+            resource_limits,
+            uses_execution_id,
+            parameters: parameter_values,
+            elevation,
+            target,
+            remote_working_directory,
+            secrets,
+            redactor: None,
+            confirmation_token: None,
+        };
+        Ok((context, sources))
+    }
+
+    /// Level of the closest command or group that sets a field
+    fn source_of(
+        command_sets: bool,
+        ancestors: &[Group],
+        group_sets: impl Fn(&Group) -> bool,
+    ) -> Option<ValueSource> {
+        if command_sets {
+            return Some(ValueSource::Command);
+        }
+        ancestors
+            .iter()
+            .find(|g| group_sets(g))
+            .map(ValueSource::from)
+    }
+
+    fn record_env_sources(
+        sources: &mut BTreeMap<String, ValueSource>,
+        unset: &Option<Vec<String>>,
+        level: &Option<HashMap<String, String>>,
+        source: ValueSource,
+    ) {
+        for key in unset.iter().flatten() {
+            sources.remove(key);
+        }
+        for key in level.iter().flat_map(|level| level.keys()) {
+            sources.insert(key.clone(), source.clone());
+        }
+    }
+
+    /// Clear text secrets of one level, their values are also added to `collected` (longest
+    /// first) so they can be masked
+    fn load_secrets(
+        &self,
+        table: &'static str,
+        owner_id: i64,
+        secret_env_vars: &Option<HashMap<String, SecretValue>>,
+        collected: &mut Vec<String>,
+    ) -> Result<HashMap<String, String>, SerializableError> {
+        if secret_env_vars
+            .as_ref()
+            .is_none_or(|secrets| secrets.is_empty())
+        {
+            return Ok(HashMap::new());
+        }
+
+        let secrets = self.db.get_secret_env_vars(table, owner_id)?;
+        collected.extend(secrets.values().filter(|v| !v.is_empty()).cloned());
+        collected.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+        collected.dedup();
+        Ok(secrets)
+    }
+
+    /// Env vars of one group/command level: its env files in listed order, then the inline
+    /// `env_vars` and secrets, each overriding the previous. Paths may reference variables of
+    /// outer levels. Secret values are taken literally.
+    fn load_env_level(
+        interpolator: &mut Interpolator,
+        env_files: &Option<Vec<String>>,
+        env_vars: &Option<HashMap<String, String>>,
+        secrets: HashMap<String, String>,
+        working_directory: &Path,
+    ) -> Result<Option<HashMap<String, String>>, SerializableError> {
+        let env_files = env_files.as_deref().unwrap_or_default();
+        if env_files.is_empty() && secrets.is_empty() {
+            return Ok(env_vars.clone());
+        }
+
+        let mut level = HashMap::new();
+        for env_file in env_files {
+            let path = interpolator.expand(env_file, "env_files")?;
+            // joining an absolute path replaces the working directory
+            let path = working_directory.join(Self::expand_home(&path).unwrap_or_default());
+            level.extend(dotenv::load(&path)?);
+        }
+        level.extend(
+            env_vars
+                .iter()
+                .flatten()
+                .map(|(k, v)| (k.clone(), v.clone())),
+        );
+        level.extend(
+            secrets
+                .into_iter()
+                .map(|(k, v)| (k, v.replace("${", "$${"))),
+        );
+        Ok(Some(level))
+    }
+
+    /// Built-in and custom rules from the settings. Broken custom rules are logged and skipped,
+    /// they never switch off the built-ins.
+    fn redactor(&self) -> Option<Arc<Redactor>> {
+        let builtins = self
+            .db
+            .get_setting("redact_common_tokens")
+            .map(|v| v != "false")
+            .unwrap_or(true);
+        let rules_json = self
+            .db
+            .get_setting("redaction_rules")
+            .unwrap_or_else(|_| "[]".to_string());
+
+        let redactor = redaction::parse_rules(&rules_json)
+            .and_then(|rules| Redactor::new(builtins, &rules))
+            .unwrap_or_else(|e| {
+                warn!(error = %e, "Ignoring custom redaction rules");
+                Redactor::new(builtins, &[]).unwrap_or_default()
+            });
+        (!redactor.is_empty()).then(|| Arc::new(redactor))
+    }
+
+    /// Fills `shell_definition` from the registry, with the flags of the shell mode
+    fn load_shell_definition(&self, context: &mut SpawnContext) -> Result<(), ProcessSpawnError> {
+        if let Some(ref shell) = context.shell {
+            let definition = self.shell_definition(shell)?;
+            context.shell_definition = Some(match context.shell_mode {
+                Some(mode) => definition.with_mode(mode),
+                None => definition,
+            });
+        }
+        Ok(())
+    }
+
+    /// Only shells from the registry can be used
+    fn shell_definition(&self, name: &str) -> Result<ShellDefinition, ProcessSpawnError> {
+        match self.db.get_shell_by_name(name) {
+            Ok(definition) => Ok(definition),
+            Err(DatabaseError::NotFound { .. }) => {
+                let available: Vec<String> = self
+                    .db
+                    .get_shells()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|shell| shell.name)
+                    .collect();
+                Err(ProcessSpawnError::InvalidShell(format!(
+                    "Shell '{name}' is not in the shell registry. Available: {}",
+                    available.join(", ")
+                )))
+            }
+            Err(e) => {
+                error!(shell = name, error = %e, "Failed to load shell");
+                Err(ProcessSpawnError::DatabaseError(
+                    "Could not load shell".to_string(),
+                ))
+            }
+        }
+    }
+
+    fn elevation_method(&self) -> Result<ElevationMethod, SerializableError> {
+        let setting = self
+            .db
+            .get_setting("elevation_method")
+            .unwrap_or_else(|_| "auto".to_string());
+        ElevationMethod::from_setting(&setting).ok_or_else(|| SerializableError {
+            code: "ELEVATION_UNAVAILABLE".to_string(),
+            message: match setting.as_str() {
+                "pkexec" | "sudo" => format!("'{setting}' is not installed"),
+                _ => "Running elevated needs pkexec or sudo".to_string(),
+            },
         })
     }
 
+    fn expand_home(path: &str) -> Option<PathBuf> {
+        if let Some(rest) = path.strip_prefix("~/") {
+            dirs::home_dir().map(|home| home.join(rest))
+        } else {
+            Some(PathBuf::from(path))
+        }
+    }
+
+    /// `EXECUTION_ID` is handled by the interpolator itself, it is only known at spawn time
+    fn builtin_variables(group_dir: Option<PathBuf>) -> HashMap<&'static str, String> {
+        let mut builtins = HashMap::from([("DATE", get_local_date_string())]);
+        if let Some(home) = dirs::home_dir() {
+            builtins.insert("HOME", home.to_string_lossy().into_owned());
+        }
+        if let Some(dir) = group_dir {
+            builtins.insert("GROUP_DIR", dir.to_string_lossy().into_owned());
+        }
+        builtins
+    }
+
+    /// Base environment before any group/command level is applied.
+    /// Variables that are not valid unicode are skipped, they can't be shown or edited anyway.
+    fn inherited_env(inheritance: &EnvInheritance) -> BTreeMap<String, String> {
+        let process_env = std::env::vars_os()
+            .filter_map(|(k, v)| Some((k.into_string().ok()?, v.into_string().ok()?)));
+
+        match inheritance {
+            EnvInheritance::Full => process_env.collect(),
+            EnvInheritance::Clean => BTreeMap::new(),
+            EnvInheritance::Allowlist { variables } => {
+                process_env.filter(|(k, _)| variables.contains(k)).collect()
+            }
+        }
+    }
+
     fn pid_is_alive(pid: u32) -> bool {
         #[cfg(unix)]
         {
@@ -365,6 +1043,9 @@ impl ProcessManager {
                 }
                 ProcessEvent::Stopped(evt) => {
                     self.emit_event("process-stopped", &evt);
+                    #[cfg(unix)]
+                    self.askpass_servers.remove(&evt.execution_id);
+                    self.askpass_answers.remove(&evt.execution_id);
 
                     if let Err(e) = self.db.update_execution_history_status(
                         evt.execution_id,
@@ -384,6 +1065,9 @@ impl ProcessManager {
                 ProcessEvent::LogBatch(lines) => {
                     self.emit_log_batch(&lines);
                 }
+                ProcessEvent::AskpassRequested(evt) => {
+                    self.emit_event("askpass-request", &evt);
+                }
             }
         }
     }
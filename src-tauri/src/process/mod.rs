@@ -3,9 +3,20 @@ pub mod manager;
 pub mod models;
 pub mod shell;
 
+mod cgroup;
+pub mod dotenv;
+pub mod elevation;
 pub mod errors;
+pub mod interpolation;
+#[cfg(unix)]
+mod limits;
 mod managed_process;
+pub mod parameters;
+pub mod preflight;
+pub mod redaction;
+pub mod safety;
 mod signals;
 mod streaming;
+pub mod target;
 #[cfg(test)]
 mod tests;
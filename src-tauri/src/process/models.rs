@@ -1,6 +1,15 @@
-use crate::constants::MAX_LOG_LINES;
-use crate::database::ExecutionStatus;
+use crate::constants::{MAX_LOG_LINES, SECRET_MASK};
+use crate::database::{
+    ExecutionStatus, ExecutionTarget, Group, ResourceLimits, ShellDefinition, ShellMode,
+};
+use crate::process::elevation::ElevationMethod;
+use crate::process::interpolation::fill_execution_id;
+use crate::process::redaction::Redactor;
+use crate::process::safety::SafetyOverride;
+use crate::process::shell;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
 
 /// Returned by `get_running_executions` on startup for orphan detection.
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -94,15 +103,221 @@ pub struct ProcessStoppedEvent {
     pub timestamp: String,
 }
 
+/// Emitted when `sudo -A` asks for a password, answered with `answer_askpass`
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AskpassRequestEvent {
+    pub execution_id: i64,
+    pub prompt: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct SpawnContext {
     pub command_id: i64,
     pub name: String,
     pub executable: String,
     pub arguments: Vec<String>,
+    /// Indices of `arguments` left unquoted for the shell, see `Command::raw_arguments`
+    #[serde(default)]
+    pub raw_arguments: Vec<usize>,
     pub working_directory: std::path::PathBuf,
     pub env_vars: Vec<(String, String)>,
+    /// When set, the process gets exactly `env_vars` instead of TGUI's environment plus `env_vars`.
+    /// Resolved contexts always set it, `env_vars` then is the full effective environment.
+    #[serde(default)]
+    pub clear_env: bool,
+    pub shell: Option<String>,
+    /// Overrides the flags of the registry entry, applied by `spawn_command`
+    #[serde(default)]
+    pub shell_mode: Option<ShellMode>,
+    /// Sourced by the shell before the command, outermost group first
+    #[serde(default)]
+    pub source_files: Vec<String>,
+    /// Registry entry of `shell`, looked up by `spawn_command`
+    #[serde(default)]
+    pub shell_definition: Option<ShellDefinition>,
+    /// Effective limits after group inheritance
+    #[serde(default)]
+    pub resource_limits: ResourceLimits,
+    /// `${EXECUTION_ID}` is still present in some field, filled in by `spawn_command`
+    #[serde(default)]
+    pub uses_execution_id: bool,
+    /// Parameter values used for this run, after defaults and validation
+    #[serde(default)]
+    pub parameters: BTreeMap<String, String>,
+    /// Set for commands with `run_elevated`, resolved from the `elevation_method` setting
+    #[serde(default)]
+    pub elevation: Option<ElevationMethod>,
+    #[serde(default)]
+    pub target: ExecutionTarget,
+    /// Configured working directory of a remote target, `working_directory` is then only used
+    /// for the local client
+    #[serde(default)]
+    pub remote_working_directory: Option<String>,
+    /// Values of secret env vars, longest first. Never serialized, masked wherever shown.
+    #[serde(skip)]
+    pub secrets: Vec<String>,
+    /// Pattern based redaction of the output, set by `spawn_command` from the settings
+    #[serde(skip)]
+    pub redactor: Option<Arc<Redactor>>,
+    /// Token from `check_spawn_safety`, required to run destructive or privileged commands
+    #[serde(skip)]
+    pub confirmation_token: Option<String>,
+}
+
+/// Stored as JSON in `execution_history.context`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionContext {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub parameters: BTreeMap<String, String>,
+    /// Set when the safety gate was passed with a confirmation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub safety_override: Option<SafetyOverride>,
+}
+
+impl SpawnContext {
+    pub fn fill_execution_id(&mut self, execution_id: i64) {
+        let fill = |value: &mut String| *value = fill_execution_id(value, execution_id);
+
+        fill(&mut self.executable);
+        self.arguments.iter_mut().for_each(fill);
+        self.source_files.iter_mut().for_each(fill);
+        self.env_vars.iter_mut().for_each(|(_, value)| fill(value));
+        let working_directory = self.working_directory.to_string_lossy();
+        self.working_directory = fill_execution_id(&working_directory, execution_id).into();
+        self.uses_execution_id = false;
+    }
+
+    /// Program and arguments as passed to the OS: the command line built for the shell,
+    /// wrapped for the execution target and then for the elevation method
+    pub fn invocation(&self, execution_id: i64) -> (String, Vec<String>) {
+        let result = shell::build_exec(
+            &self.executable,
+            &self.arguments,
+            &self.raw_arguments,
+            &self.source_files,
+            self.shell_definition.as_ref(),
+        );
+
+        // only what the group/command levels set, not TGUI's own environment
+        let env_keys: Vec<String> = self
+            .env_vars
+            .iter()
+            .filter(|(key, value)| std::env::var(key).ok().as_ref() != Some(value))
+            .map(|(key, _)| key.clone())
+            .collect();
+        let (executable, args) = self.target.wrap(
+            result.executable,
+            result.args,
+            execution_id,
+            self.remote_working_directory.as_deref(),
+            &env_keys,
+        );
+        match self.elevation {
+            Some(method) => method.wrap(executable, args, &self.working_directory, &env_keys),
+            None => (executable, args),
+        }
+    }
+
+    /// Replaces secret values, e.g. referenced as `${TOKEN}` in an argument
+    pub fn mask(&self, value: &str) -> String {
+        self.secrets
+            .iter()
+            .fold(value.to_string(), |value, secret| {
+                value.replace(secret.as_str(), SECRET_MASK)
+            })
+    }
+
+    /// Copy that is safe to show, secret values are masked in every interpolated field
+    pub fn masked(&self) -> Self {
+        let working_directory = self.working_directory.to_string_lossy();
+        Self {
+            executable: self.mask(&self.executable),
+            arguments: self.arguments.iter().map(|arg| self.mask(arg)).collect(),
+            working_directory: self.mask(&working_directory).into(),
+            source_files: self
+                .source_files
+                .iter()
+                .map(|path| self.mask(path))
+                .collect(),
+            remote_working_directory: self
+                .remote_working_directory
+                .as_deref()
+                .map(|directory| self.mask(directory)),
+            env_vars: self
+                .env_vars
+                .iter()
+                .map(|(key, value)| (key.clone(), self.mask(value)))
+                .collect(),
+            secrets: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// `None` when there is nothing worth recording
+    pub fn history_context(&self, safety_override: Option<SafetyOverride>) -> Option<String> {
+        if self.parameters.is_empty() && safety_override.is_none() {
+            return None;
+        }
+        let context = ExecutionContext {
+            parameters: self.parameters.clone(),
+            safety_override,
+        };
+        serde_json::to_string(&context).ok()
+    }
+}
+
+/// Level an effective value was taken from
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "level", rename_all = "snake_case")]
+pub enum ValueSource {
+    /// TGUI's own environment, filtered by the env inheritance
+    Inherited,
+    Group {
+        id: i64,
+        name: String,
+    },
+    Command,
+    /// The `default_shell` setting
+    Setting,
+    /// Nothing configured, e.g. the home directory as working directory
+    Default,
+}
+
+impl From<&Group> for ValueSource {
+    fn from(group: &Group) -> Self {
+        Self::Group {
+            id: group.id,
+            name: group.name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewEnvVar {
+    pub key: String,
+    /// Secret values are masked
+    pub value: String,
+    pub source: ValueSource,
+}
+
+/// What `spawn_command` would run, returned by `preview_spawn` without spawning anything.
+/// Secret values are masked everywhere, `${EXECUTION_ID}` is left in place.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SpawnPreview {
+    /// Program and argv after the shell, target and elevation wrapping
+    pub executable: String,
+    pub arguments: Vec<String>,
+    pub working_directory: std::path::PathBuf,
+    pub remote_working_directory: Option<String>,
+    /// Where the configured directory came from, local or remote
+    pub working_directory_source: ValueSource,
+    pub env_vars: Vec<PreviewEnvVar>,
     pub shell: Option<String>,
+    pub shell_source: Option<ValueSource>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -112,6 +327,12 @@ pub struct StreamingConfig {
     pub batch_size: usize,
     pub batch_timeout_ms: u64,
     pub max_line_length: usize,
+    /// Replaced with `SECRET_MASK` in log lines
+    #[serde(skip)]
+    pub secrets: Vec<String>,
+    /// Applied after `secrets`
+    #[serde(skip)]
+    pub redactor: Option<Arc<Redactor>>,
 }
 
 impl Default for StreamingConfig {
@@ -121,6 +342,8 @@ impl Default for StreamingConfig {
             batch_size: 50,
             batch_timeout_ms: 50,
             max_line_length: MAX_LOG_LINES,
+            secrets: Vec::new(),
+            redactor: None,
         }
     }
 }
@@ -132,6 +355,8 @@ impl StreamingConfig {
             batch_size: 1,
             batch_timeout_ms: 0,
             max_line_length: 10000,
+            secrets: Vec::new(),
+            redactor: None,
         }
     }
 }
@@ -0,0 +1,79 @@
+use crate::database::CommandParameter;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum ParameterError {
+    #[error("Missing value for required parameter '{0}'")]
+    Missing(String),
+    #[error("Invalid value for parameter '{name}': {reason}")]
+    Invalid { name: String, reason: String },
+    #[error("Unknown parameter '{0}'")]
+    Unknown(String),
+}
+
+/// Checks the supplied values against the declared parameters and fills in defaults.
+/// Optional parameters without value or default become empty strings.
+pub fn resolve_values(
+    parameters: &[CommandParameter],
+    supplied: &HashMap<String, String>,
+) -> Result<BTreeMap<String, String>, ParameterError> {
+    if let Some(unknown) = supplied
+        .keys()
+        .find(|key| !parameters.iter().any(|p| &p.name == *key))
+    {
+        return Err(ParameterError::Unknown(unknown.clone()));
+    }
+
+    let mut values = BTreeMap::new();
+    for parameter in parameters {
+        let value = supplied
+            .get(&parameter.name)
+            .filter(|v| !v.is_empty())
+            .or(parameter.default.as_ref());
+
+        match value {
+            Some(value) => {
+                let value =
+                    parameter
+                        .validate_value(value)
+                        .map_err(|reason| ParameterError::Invalid {
+                            name: parameter.name.clone(),
+                            reason,
+                        })?;
+                values.insert(parameter.name.clone(), value);
+            }
+            None if parameter.required => {
+                return Err(ParameterError::Missing(parameter.name.clone()))
+            }
+            None => {
+                values.insert(parameter.name.clone(), String::new());
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// Replaces `{{name}}` for declared parameters only, other `{{...}}` (e.g. docker/go templates)
+/// are left untouched. Values are escaped so `${` coming from a parameter stays literal
+/// during variable interpolation.
+pub fn substitute(input: &str, values: &BTreeMap<String, String>) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + len;
+        output.push_str(&rest[..start]);
+
+        match values.get(rest[start + 2..end].trim()) {
+            Some(value) => output.push_str(&value.replace("${", "$${")),
+            None => output.push_str(&rest[start..end + 2]),
+        }
+        rest = &rest[end + 2..];
+    }
+
+    output.push_str(rest);
+    output
+}
@@ -0,0 +1,267 @@
+use crate::database::{ExecutionTarget, ShellDefinition};
+use crate::process::errors::ProcessSpawnError;
+use crate::process::models::SpawnContext;
+use serde::Serialize;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemKind {
+    ExecutableNotFound,
+    NotExecutable,
+    WorkingDirectoryMissing,
+    WorkingDirectoryNotAccessible,
+    InvalidShell,
+    InvalidEnvVar,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The spawn would fail, `spawn_command` refuses it
+    Error,
+    /// Might be fine, e.g. a shell alias that can't be seen from outside the shell
+    Warning,
+}
+
+/// Something found by `check` before a command is spawned
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightProblem {
+    pub kind: ProblemKind,
+    pub severity: Severity,
+    /// Command field to fix, e.g. `working_directory`
+    pub field: &'static str,
+    /// Secret values are masked
+    pub message: String,
+}
+
+impl PreflightProblem {
+    pub fn error(kind: ProblemKind, field: &'static str, message: String) -> Self {
+        Self {
+            kind,
+            severity: Severity::Error,
+            field,
+            message,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl From<PreflightProblem> for ProcessSpawnError {
+    fn from(problem: PreflightProblem) -> Self {
+        match problem.kind {
+            ProblemKind::ExecutableNotFound => Self::ExecutableNotFound(problem.message),
+            ProblemKind::NotExecutable | ProblemKind::WorkingDirectoryNotAccessible => {
+                Self::PermissionDenied(problem.message)
+            }
+            ProblemKind::WorkingDirectoryMissing => Self::InvalidWorkingDirectory(problem.message),
+            ProblemKind::InvalidShell => Self::InvalidShell(problem.message),
+            ProblemKind::InvalidEnvVar => Self::InvalidEnvVar(problem.message),
+        }
+    }
+}
+
+/// Checks a resolved command against the local system: the executable on the PATH the
+/// process will get, the working directory, the shell and the env var names.
+/// `shell` is the registry entry of `context.shell`, an unknown shell is reported by the caller.
+pub fn check(context: &SpawnContext, shell: Option<&ShellDefinition>) -> Vec<PreflightProblem> {
+    let mut problems = Vec::new();
+    let path_var = effective_path(context);
+    let remote_client = !context.target.is_local();
+
+    check_env_vars(context, &mut problems);
+    check_working_directory(&context.working_directory, &mut problems);
+    // a remote command runs in the shell found on the remote side
+    if let Some(shell) = shell.filter(|_| !remote_client) {
+        if let Lookup::NotFound | Lookup::NotExecutable(_) =
+            lookup(&shell.path, &context.working_directory, path_var.as_deref())
+        {
+            problems.push(PreflightProblem::error(
+                ProblemKind::InvalidShell,
+                "shell",
+                format!(
+                    "'{}' of shell '{}' is not executable",
+                    shell.path, shell.name
+                ),
+            ));
+        }
+    }
+
+    // remote commands are looked up on the remote side, only the client has to be here
+    let (program, field) = match &context.target {
+        ExecutionTarget::Local => (context.executable.as_str(), "executable"),
+        ExecutionTarget::Container { runtime, .. } => (runtime.program(), "execution_target"),
+        ExecutionTarget::Ssh { .. } => ("ssh", "execution_target"),
+    };
+    match lookup(program, &context.working_directory, path_var.as_deref()) {
+        Lookup::Found => {}
+        Lookup::NotExecutable(path) => problems.push(PreflightProblem::error(
+            ProblemKind::NotExecutable,
+            field,
+            format!("'{}' is missing the execute permission", path.display()),
+        )),
+        Lookup::NotFound => problems.push(PreflightProblem {
+            kind: ProblemKind::ExecutableNotFound,
+            // aliases, functions and builtins only exist inside the shell
+            severity: if shell.is_some() && !remote_client {
+                Severity::Warning
+            } else {
+                Severity::Error
+            },
+            field,
+            message: format!("'{program}' is not on the PATH"),
+        }),
+    }
+
+    for problem in &mut problems {
+        problem.message = context.mask(&problem.message);
+    }
+    problems
+}
+
+fn check_env_vars(context: &SpawnContext, problems: &mut Vec<PreflightProblem>) {
+    for (key, value) in &context.env_vars {
+        let reason = if key.is_empty() {
+            "has an empty name"
+        } else if key.contains('=') {
+            "contains '=' in its name"
+        } else if key.contains('\0') || value.contains('\0') {
+            "contains a NUL character"
+        } else {
+            continue;
+        };
+        problems.push(PreflightProblem::error(
+            ProblemKind::InvalidEnvVar,
+            "env_vars",
+            format!("'{}' {reason}", key.replace('\0', "\\0")),
+        ));
+    }
+}
+
+fn check_working_directory(dir: &Path, problems: &mut Vec<PreflightProblem>) {
+    if !dir.is_dir() {
+        problems.push(PreflightProblem::error(
+            ProblemKind::WorkingDirectoryMissing,
+            "working_directory",
+            format!("'{}' is not an existing directory", dir.display()),
+        ));
+    } else if !can_enter(dir) {
+        problems.push(PreflightProblem::error(
+            ProblemKind::WorkingDirectoryNotAccessible,
+            "working_directory",
+            format!("'{}' can't be entered", dir.display()),
+        ));
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn can_enter(dir: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(path) = std::ffi::CString::new(dir.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: path is a valid NUL terminated string that outlives the call
+    unsafe { libc::access(path.as_ptr(), libc::X_OK) == 0 }
+}
+#[cfg(target_os = "windows")]
+fn can_enter(dir: &Path) -> bool {
+    std::fs::read_dir(dir).is_ok()
+}
+
+/// The PATH the spawned process sees, `Command` looks the program up on it as well
+fn effective_path(context: &SpawnContext) -> Option<OsString> {
+    context
+        .env_vars
+        .iter()
+        .rev()
+        .find(|(key, _)| is_path_key(key))
+        .map(|(_, value)| OsString::from(value))
+        .or_else(|| {
+            if context.clear_env {
+                None
+            } else {
+                std::env::var_os("PATH")
+            }
+        })
+}
+
+fn is_path_key(key: &str) -> bool {
+    if cfg!(target_os = "windows") {
+        key.eq_ignore_ascii_case("PATH")
+    } else {
+        key == "PATH"
+    }
+}
+
+enum Lookup {
+    Found,
+    /// First match on the PATH, it can't be run
+    NotExecutable(PathBuf),
+    NotFound,
+}
+
+/// Programs containing a separator are taken relative to the working directory, like
+/// `Command` does after changing into it
+fn lookup(program: &str, working_directory: &Path, path_var: Option<&OsStr>) -> Lookup {
+    let candidates: Vec<PathBuf> = if program.contains('/') || program.contains('\\') {
+        with_extensions(working_directory.join(program))
+    } else if program.is_empty() {
+        Vec::new()
+    } else {
+        path_var
+            .map(|paths| {
+                std::env::split_paths(paths)
+                    .flat_map(|dir| with_extensions(dir.join(program)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut not_executable = None;
+    for candidate in candidates {
+        if !candidate.is_file() {
+            continue;
+        }
+        if is_executable(&candidate) {
+            return Lookup::Found;
+        }
+        not_executable.get_or_insert(candidate);
+    }
+    not_executable.map_or(Lookup::NotFound, Lookup::NotExecutable)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn with_extensions(path: PathBuf) -> Vec<PathBuf> {
+    vec![path]
+}
+#[cfg(target_os = "windows")]
+fn with_extensions(path: PathBuf) -> Vec<PathBuf> {
+    if path.extension().is_some() {
+        return vec![path];
+    }
+    let pathext = std::env::var("PATHEXT").unwrap_or_else(|_| ".EXE;.CMD;.BAT;.COM".to_string());
+    pathext
+        .split(';')
+        .filter(|ext| !ext.is_empty())
+        .map(|ext| {
+            let mut name = path.clone().into_os_string();
+            name.push(ext);
+            PathBuf::from(name)
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "windows"))]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o111 != 0)
+}
+#[cfg(target_os = "windows")]
+fn is_executable(_path: &Path) -> bool {
+    true
+}
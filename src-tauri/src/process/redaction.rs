@@ -0,0 +1,124 @@
+use crate::constants::SECRET_MASK;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::sync::LazyLock;
+
+/// A user defined rule, stored as JSON list in the `redaction_rules` setting.
+/// When the pattern has a `(?P<secret>...)` group only that part is masked, the whole match
+/// otherwise.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionRule {
+    pub name: String,
+    pub pattern: String,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum RedactionError {
+    #[error("Invalid redaction rules: {0}")]
+    InvalidJson(String),
+    #[error("Invalid pattern for redaction rule '{name}': {reason}")]
+    InvalidPattern { name: String, reason: String },
+}
+
+/// Common token formats, enabled by the `redact_common_tokens` setting
+const BUILTIN_RULES: &[(&str, &str)] = &[
+    (
+        "bearer token",
+        r"(?i)\bbearer\s+(?P<secret>[A-Za-z0-9\-._~+/]{8,}=*)",
+    ),
+    (
+        "basic auth header",
+        r"(?i)\bauthorization:\s*basic\s+(?P<secret>[A-Za-z0-9+/]{8,}={0,2})",
+    ),
+    (
+        "url credentials",
+        r"(?i)\b[a-z][a-z0-9+.\-]*://[^\s:/@]+:(?P<secret>[^\s@/]+)@",
+    ),
+    ("aws access key id", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+    (
+        "aws secret access key",
+        r#"(?i)aws_?secret_?access_?key\s*[=:]\s*["']?(?P<secret>[A-Za-z0-9/+=]{40})"#,
+    ),
+    (
+        "github token",
+        r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})",
+    ),
+    ("gitlab token", r"\bglpat-[A-Za-z0-9\-_]{20,}"),
+    ("slack token", r"\bxox[abposr]-[A-Za-z0-9\-]{10,}"),
+    (
+        "json web token",
+        r"\beyJ[A-Za-z0-9_\-]{5,}\.[A-Za-z0-9_\-]{5,}\.[A-Za-z0-9_\-]{5,}",
+    ),
+    (
+        "password assignment",
+        r#"(?i)\b(?:password|passwd|pwd|secret|api_?key|access_?token)\s*[=:]\s*["']?(?P<secret>[^\s"'&,;]{4,})"#,
+    ),
+];
+
+static BUILTIN_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    BUILTIN_RULES
+        .iter()
+        .map(|(_, pattern)| Regex::new(pattern).expect("built-in redaction pattern"))
+        .collect()
+});
+
+/// Compiled redaction stage applied to every log line before it is buffered or emitted
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    pub fn new(builtins: bool, rules: &[RedactionRule]) -> Result<Self, RedactionError> {
+        let mut patterns = if builtins {
+            BUILTIN_PATTERNS.clone()
+        } else {
+            Vec::new()
+        };
+        for rule in rules {
+            patterns.push(compile(rule)?);
+        }
+        Ok(Self { patterns })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn redact<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let mut line = Cow::Borrowed(line);
+        for pattern in &self.patterns {
+            if pattern.is_match(&line) {
+                line = Cow::Owned(pattern.replace_all(&line, mask_match).into_owned());
+            }
+        }
+        line
+    }
+}
+
+/// Parses the `redaction_rules` setting, patterns are only checked by `Redactor::new`
+pub fn parse_rules(json: &str) -> Result<Vec<RedactionRule>, RedactionError> {
+    serde_json::from_str(json).map_err(|e| RedactionError::InvalidJson(e.to_string()))
+}
+
+fn compile(rule: &RedactionRule) -> Result<Regex, RedactionError> {
+    Regex::new(&rule.pattern).map_err(|e| RedactionError::InvalidPattern {
+        name: rule.name.clone(),
+        reason: e.to_string(),
+    })
+}
+
+fn mask_match(caps: &Captures) -> String {
+    let whole = caps.get(0).expect("group 0 is always present");
+    match caps.name("secret") {
+        Some(secret) => {
+            let text = whole.as_str();
+            let start = secret.start() - whole.start();
+            let end = secret.end() - whole.start();
+            format!("{}{SECRET_MASK}{}", &text[..start], &text[end..])
+        }
+        None => SECRET_MASK.to_string(),
+    }
+}
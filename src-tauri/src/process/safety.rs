@@ -0,0 +1,68 @@
+use crate::database::ExplainResult;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Outcome of the spawn-time check of a resolved command line
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SafetyCheck {
+    /// Secret values are masked
+    pub command_line: String,
+    pub explanation: ExplainResult,
+    /// Deny-list pattern that matched, the command can't be run even with a confirmation
+    pub denied_by: Option<String>,
+    pub requires_confirmation: bool,
+    /// Passed back to `spawn_command` to run a command that requires confirmation.
+    /// Only valid once, for this exact command line and within `CONFIRMATION_TTL`.
+    pub confirmation_token: Option<String>,
+}
+
+/// Recorded in `execution_history.context` when a confirmation let a command through
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SafetyOverride {
+    /// Secret values are masked
+    pub command_line: String,
+    pub destructive: bool,
+    pub privileged: bool,
+}
+
+impl SafetyCheck {
+    pub fn reason(&self) -> String {
+        match (
+            self.explanation.is_destructive,
+            self.explanation.is_privileged,
+        ) {
+            (true, true) => "destructive and runs with sudo".to_string(),
+            (true, false) => "destructive".to_string(),
+            _ => "runs with sudo".to_string(),
+        }
+    }
+
+    pub fn to_override(&self) -> SafetyOverride {
+        SafetyOverride {
+            command_line: self.command_line.clone(),
+            destructive: self.explanation.is_destructive,
+            privileged: self.explanation.is_privileged,
+        }
+    }
+}
+
+/// Parses the `spawn_deny_list` setting, a JSON list of regex patterns matched against the
+/// whole command line
+pub fn parse_deny_list(json: &str) -> Result<Vec<Regex>, String> {
+    let patterns: Vec<String> =
+        serde_json::from_str(json).map_err(|e| format!("Invalid deny list: {e}"))?;
+    patterns
+        .iter()
+        .map(|pattern| {
+            Regex::new(pattern).map_err(|e| format!("Invalid deny list pattern '{pattern}': {e}"))
+        })
+        .collect()
+}
+
+pub fn denied_by<'a>(deny_list: &'a [Regex], command_line: &str) -> Option<&'a Regex> {
+    deny_list
+        .iter()
+        .find(|pattern| pattern.is_match(command_line))
+}
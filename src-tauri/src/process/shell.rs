@@ -1,8 +1,5 @@
-// TODO: allow shell configuring TGUI-33
-
-use std::path::Path;
-#[cfg(target_os = "windows")]
-use std::path::PathBuf;
+use crate::database::{ShellDefinition, ShellFamily, ShellMode};
+use std::path::{Path, PathBuf};
 
 use std::process::Command;
 use std::sync::{LazyLock, Mutex};
@@ -52,8 +49,25 @@ impl Shell {
         })
     }
 
+    /// Absolute path of an executable, `path` is either absolute or looked up in PATH
+    pub(crate) fn resolve_executable(path: &str) -> Option<PathBuf> {
+        let candidate = Path::new(path);
+        if !candidate.is_absolute() {
+            return Self::find_in_path(path);
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::metadata(candidate)
+                .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+                .then(|| candidate.to_path_buf())
+        }
+        #[cfg(target_os = "windows")]
+        candidate.is_file().then(|| candidate.to_path_buf())
+    }
+
     #[cfg(not(target_os = "windows"))]
-    fn find_in_path(command: &str) -> Option<std::path::PathBuf> {
+    fn find_in_path(command: &str) -> Option<PathBuf> {
         let path_var = std::env::var_os("PATH")?;
         for dir in std::env::split_paths(&path_var) {
             let candidate = dir.join(command);
@@ -259,21 +273,6 @@ pub fn get_shells_cached() -> Vec<ShellInfo> {
     shells
 }
 
-pub fn is_valid_shell(shell: &str) -> bool {
-    // let name = Path::new(shell)
-    //     .file_name()
-    //     .and_then(|n| n.to_str())
-    //     .unwrap_or(shell);
-
-    get_shells_cached()
-        .iter()
-        .any(|s| s.name == shell || s.path == shell)
-}
-
-pub fn get_allowed_shells() -> Vec<String> {
-    get_shells_cached().into_iter().map(|s| s.name).collect()
-}
-
 /// Result of `build_exec`.
 #[derive(Debug, Clone)]
 pub struct BuildResult {
@@ -281,75 +280,211 @@ pub struct BuildResult {
     pub args: Vec<String>,
 }
 
-/// Returns (executable, args_prefix) for the chosen shell.
+/// Replaced by the command line in `ShellDefinition::args_template`
+pub const CMD_PLACEHOLDER: &str = "{cmd}";
+
+impl ShellDefinition {
+    /// Registry entry for a detected shell, with the arguments it was always run with
+    pub fn builtin(name: &str, path: &str) -> Self {
+        let (args_template, family) = match name {
+            "cmd" => ("/C {cmd}", ShellFamily::Cmd),
+            "powershell" | "pwsh" => ("-NonInteractive -Command {cmd}", ShellFamily::Powershell),
+            "fish" => ("-c {cmd}", ShellFamily::Fish),
+            "nu" => ("-c {cmd}", ShellFamily::Nushell),
+            _ => ("-c {cmd}", ShellFamily::Posix),
+        };
+        Self {
+            id: 0,
+            name: name.to_string(),
+            path: path.to_string(),
+            args_template: args_template.to_string(),
+            family,
+            login: false,
+            interactive: false,
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    /// Copy started in `mode` instead of with the login and interactive flags of the registry
+    pub fn with_mode(&self, mode: ShellMode) -> Self {
+        Self {
+            login: mode == ShellMode::Login,
+            interactive: mode == ShellMode::Interactive,
+            ..self.clone()
+        }
+    }
+
+    /// Arguments of `args_template`, `None` unless it splits cleanly and has exactly one
+    /// `{cmd}` argument
+    pub fn template_args(&self) -> Option<Vec<String>> {
+        let args = shlex::split(&self.args_template)?;
+        let placeholders = args.iter().filter(|arg| *arg == CMD_PLACEHOLDER).count();
+        (placeholders == 1).then_some(args)
+    }
+}
+
+/// Returns (executable, args) for the chosen shell.
 ///
 /// When `shell` is None the command is executed directly (Command -> Group -> Application -> direct)
-/// When set, the full command string is passed to the shell as a single argument
+/// When set, the full command string is passed to the shell as a single argument, in place of
+/// `{cmd}` in the registry entry's argument template
 ///
 /// # Argument quoting
 /// For direct exec, arguments come from the already-parsed `Vec<String>` stored in the database, no re-quoting is needed.
-/// For shell exec we join them into a single string — the shell handles tokenisation itself,
-/// which supports pipelines, redirections, and `&&`/`||` operators the user might have typed.
+/// For shell exec we join them into a single string, each argument quoted for the shell's
+/// family so it arrives unchanged. Arguments listed in `raw_arguments` are left unquoted, the
+/// shell then expands globs and variables or sees pipelines and redirections in them.
 ///
-/// # Returns
-/// `(executable_name, prefix_args)` — caller appends the actual command/args after.
-pub fn build_exec(command: &str, arguments: &[String], shell: Option<&str>) -> BuildResult {
-    match shell {
-        None => BuildResult {
+/// `source_files` are sourced by the shell first, in order, and ignored without a shell
+pub fn build_exec(
+    command: &str,
+    arguments: &[String],
+    raw_arguments: &[usize],
+    source_files: &[String],
+    shell: Option<&ShellDefinition>,
+) -> BuildResult {
+    let Some(shell) = shell else {
+        return BuildResult {
             executable: command.to_string(),
             args: arguments.to_vec(),
-        },
+        };
+    };
 
-        #[cfg(target_os = "windows")]
-        Some("cmd") => BuildResult {
-            executable: "cmd.exe".to_string(),
-            args: vec!["/C".to_string(), join_command(command, arguments)],
-        },
-
-        Some(sh @ ("powershell" | "pwsh")) => {
-            #[cfg(target_os = "windows")]
-            let exe = if *sh == "pwsh" {
-                "pwsh.exe"
+    let command_line = source_files
+        .iter()
+        .map(|path| shell.family.source(path))
+        .chain(std::iter::once(join_command(
+            command,
+            arguments,
+            raw_arguments,
+            shell.family,
+        )))
+        .collect::<Vec<_>>()
+        .join(shell.family.separator());
+    // modes the family lacks are rejected on save, they are never passed on
+    let mut args = Vec::new();
+    if shell.login && shell.family.supports_mode(ShellMode::Login) {
+        args.push("-l".to_string());
+    }
+    if shell.interactive && shell.family.supports_mode(ShellMode::Interactive) {
+        args.push("-i".to_string());
+    }
+    match shell.template_args() {
+        Some(template) => args.extend(template.into_iter().map(|arg| {
+            if arg == CMD_PLACEHOLDER {
+                command_line.clone()
             } else {
-                "powershell.exe"
-            };
-            #[cfg(not(target_os = "windows"))]
-            let exe = sh; // pwsh is cross-platform
-
-            BuildResult {
-                executable: exe.to_string(),
-                args: vec![
-                    "-NonInteractive".to_string(),
-                    "-Command".to_string(),
-                    join_command(command, arguments),
-                ],
+                arg
             }
-        }
+        })),
+        // templates are validated on save
+        None => args.push(command_line),
+    }
 
-        Some(sh) => BuildResult {
-            executable: sh.to_string(),
-            args: vec!["-c".to_string(), join_command(command, arguments)],
-        },
+    BuildResult {
+        executable: shell.path.clone(),
+        args,
     }
 }
 
-/// Joins command + arguments into a single shell string.
-/// Arguments that contain whitespace are double-quoted.
-fn join_command(command: &str, arguments: &[String]) -> String {
+/// Joins command + arguments into a single shell string, quoted for `family` except for the
+/// arguments listed in `raw_arguments`
+pub(crate) fn join_command(
+    command: &str,
+    arguments: &[String],
+    raw_arguments: &[usize],
+    family: ShellFamily,
+) -> String {
     let mut parts = Vec::with_capacity(arguments.len() + 1);
-    parts.push(shell_quote(command));
-    for arg in arguments {
-        parts.push(shell_quote(arg));
+    parts.push(family.call(command));
+    for (index, arg) in arguments.iter().enumerate() {
+        if raw_arguments.contains(&index) {
+            parts.push(arg.clone());
+        } else {
+            parts.push(family.quote(arg));
+        }
     }
     parts.join(" ")
 }
 
-///  If the token already has quotes or contains special chars, pass it through.
-/// For simple whitespace cases we add double quotes.
-fn shell_quote(s: &str) -> String {
-    if s.contains(' ') && !s.starts_with('"') && !s.starts_with('\'') {
-        format!("\"{}\"", s.replace('"', "\\\""))
-    } else {
-        s.to_string()
+impl ShellFamily {
+    /// Only the POSIX, fish and Nushell families have login and interactive shells, all of
+    /// them take `-l` and `-i`
+    pub fn supports_mode(self, mode: ShellMode) -> bool {
+        mode == ShellMode::NonInteractive
+            || matches!(self, Self::Posix | Self::Fish | Self::Nushell)
+    }
+
+    /// Quotes `arg` so the shell passes it on as a single argument, unchanged. Arguments made
+    /// of characters without special meaning are left as they are.
+    pub fn quote(self, arg: &str) -> String {
+        if !arg.is_empty() && arg.chars().all(|c| self.is_plain(c)) {
+            return arg.to_string();
+        }
+        match self {
+            Self::Posix => format!("'{}'", arg.replace('\'', r"'\''")),
+            Self::Fish => format!("'{}'", arg.replace('\\', r"\\").replace('\'', r"\'")),
+            // single quoted strings have no escapes, raw strings can hold any quote
+            Self::Nushell if !arg.contains('\'') => format!("'{arg}'"),
+            Self::Nushell => {
+                let mut hashes = "#".to_string();
+                while arg.contains(&format!("'{hashes}")) {
+                    hashes.push('#');
+                }
+                format!("r{hashes}'{arg}'{hashes}")
+            }
+            Self::Powershell => format!("'{}'", arg.replace('\'', "''")),
+            Self::Cmd => format!("\"{}\"", arg.replace('"', "\"\"")),
+        }
+    }
+
+    /// `command` quoted to be called. PowerShell and Nushell take a quoted string for a value,
+    /// the call operator runs it instead.
+    fn call(self, command: &str) -> String {
+        let quoted = self.quote(command);
+        if quoted == command {
+            return quoted;
+        }
+        match self {
+            Self::Powershell => format!("& {quoted}"),
+            Self::Nushell => format!("^{quoted}"),
+            _ => quoted,
+        }
+    }
+
+    /// Runs `path` in the current shell, so it can set up aliases and the environment
+    fn source(self, path: &str) -> String {
+        // `~/` is only expanded unquoted
+        let quoted = match (self, path.strip_prefix("~/")) {
+            (Self::Posix | Self::Fish, Some(rest)) => format!("~/{}", self.quote(rest)),
+            _ => self.quote(path),
+        };
+        match self {
+            Self::Posix | Self::Powershell => format!(". {quoted}"),
+            Self::Fish | Self::Nushell => format!("source {quoted}"),
+            Self::Cmd => format!("call {quoted}"),
+        }
+    }
+
+    /// Runs the next command whatever the previous one returned, rc files often end with a
+    /// failed test
+    fn separator(self) -> &'static str {
+        match self {
+            Self::Cmd => " & ",
+            _ => "; ",
+        }
+    }
+
+    fn is_plain(self, c: char) -> bool {
+        if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | ':') {
+            return true;
+        }
+        match self {
+            Self::Posix | Self::Fish => matches!(c, '=' | '@' | '%' | '+' | ','),
+            // Windows paths
+            Self::Powershell | Self::Cmd => c == '\\',
+            Self::Nushell => matches!(c, '=' | '+'),
+        }
     }
 }
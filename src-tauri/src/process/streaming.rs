@@ -1,7 +1,9 @@
+use crate::constants::SECRET_MASK;
 use crate::process::log_buffer::LogBuffer;
 use crate::process::managed_process::ProcessEvent;
 use crate::process::models::{LogLineEvent, StreamingConfig};
 use crate::utils::get_utc_timestamp_string;
+use std::borrow::Cow;
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::{ChildStderr, ChildStdout};
@@ -127,6 +129,18 @@ impl LogStreamer {
     }
 
     fn create_log_line(&self, mut content: String, is_stderr: bool) -> Arc<LogLineEvent> {
+        // before truncating, a cut secret would not match anymore
+        for secret in &self.config.secrets {
+            if content.contains(secret.as_str()) {
+                content = content.replace(secret.as_str(), SECRET_MASK);
+            }
+        }
+        if let Some(redactor) = &self.config.redactor {
+            if let Cow::Owned(redacted) = redactor.redact(&content) {
+                content = redacted;
+            }
+        }
+
         if content.len() > self.config.max_line_length {
             let mut truncate_at = self.config.max_line_length;
             while !content.is_char_boundary(truncate_at) && truncate_at > 0 {
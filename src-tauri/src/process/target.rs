@@ -0,0 +1,121 @@
+use crate::database::{ContainerRuntime, ExecutionTarget};
+
+/// Runs the command in the background so its PID can be written for `kill_command`, `$1` is the
+/// directory to start in
+const REMOTE_SCRIPT: &str = r#"cd "$1" || exit 1; shift; "$@" & echo $! > "$PID_FILE"; wait $!; s=$?; rm -f "$PID_FILE"; exit $s"#;
+
+impl ExecutionTarget {
+    pub fn is_local(&self) -> bool {
+        matches!(self, Self::Local)
+    }
+
+    /// Wraps an already built executable and arguments so they run on the target.
+    /// `directory` is the remote working directory, the target's default when `None`.
+    /// `env_keys` are forwarded into containers by name, the values are taken from the
+    /// environment of the client so they don't show up in the process list. ssh doesn't
+    /// forward them, servers only accept what `AcceptEnv` allows.
+    pub fn wrap(
+        &self,
+        executable: String,
+        arguments: Vec<String>,
+        execution_id: i64,
+        directory: Option<&str>,
+        env_keys: &[String],
+    ) -> (String, Vec<String>) {
+        match self {
+            Self::Local => (executable, arguments),
+            Self::Container { runtime, container } => {
+                let mut args = vec!["exec".to_string()];
+                for key in env_keys {
+                    args.push("-e".to_string());
+                    args.push(key.clone());
+                }
+                args.push(container.clone());
+                args.extend(remote_command(
+                    executable,
+                    arguments,
+                    execution_id,
+                    directory,
+                ));
+                (runtime.program().to_string(), args)
+            }
+            Self::Ssh { host } => {
+                let remote = remote_command(executable, arguments, execution_id, directory)
+                    .iter()
+                    .map(|arg| quote(arg))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                (
+                    "ssh".to_string(),
+                    ssh_args(host).into_iter().chain([remote]).collect(),
+                )
+            }
+        }
+    }
+
+    /// Command that signals the remote process, killing the local client alone would leave it
+    /// running. `None` for local processes.
+    pub fn kill_command(&self, execution_id: i64, force: bool) -> Option<(String, Vec<String>)> {
+        let signal = if force { "KILL" } else { "TERM" };
+        let script = format!(r#"kill -s {signal} "$(cat {})""#, pid_file(execution_id));
+        match self {
+            Self::Local => None,
+            Self::Container { runtime, container } => Some((
+                runtime.program().to_string(),
+                vec![
+                    "exec".to_string(),
+                    container.clone(),
+                    "sh".to_string(),
+                    "-c".to_string(),
+                    script,
+                ],
+            )),
+            Self::Ssh { host } => Some((
+                "ssh".to_string(),
+                ssh_args(host).into_iter().chain([script]).collect(),
+            )),
+        }
+    }
+}
+
+impl ContainerRuntime {
+    pub fn program(self) -> &'static str {
+        match self {
+            Self::Docker => "docker",
+            Self::Podman => "podman",
+        }
+    }
+}
+
+fn remote_command(
+    executable: String,
+    arguments: Vec<String>,
+    execution_id: i64,
+    directory: Option<&str>,
+) -> Vec<String> {
+    let script = format!("PID_FILE={}; {REMOTE_SCRIPT}", pid_file(execution_id));
+    ["sh", "-c", &script, "sh", directory.unwrap_or(".")]
+        .into_iter()
+        .map(String::from)
+        .chain(std::iter::once(executable))
+        .chain(arguments)
+        .collect()
+}
+
+/// Per TGUI instance, several instances may share a container or host
+fn pid_file(execution_id: i64) -> String {
+    format!("/tmp/tgui-{}-{execution_id}.pid", std::process::id())
+}
+
+fn ssh_args(host: &str) -> Vec<String> {
+    ["-o", "BatchMode=yes", host, "--"]
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+fn quote(arg: &str) -> String {
+    shlex::try_quote(arg)
+        .map(|quoted| quoted.into_owned())
+        .unwrap_or_else(|_| arg.replace('\0', ""))
+}
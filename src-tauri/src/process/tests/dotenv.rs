@@ -0,0 +1,106 @@
+use crate::process::dotenv::{load, parse, DotenvError};
+use crate::process::errors::ProcessSpawnError;
+use std::path::Path;
+
+fn entries(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn parse_plain_values_comments_and_export() {
+    let content = "# database\n\nDB_HOST=localhost\nexport DB_PORT = 5432 \n  # indented comment\n";
+    assert_eq!(
+        parse(content).unwrap(),
+        entries(&[("DB_HOST", "localhost"), ("DB_PORT", "5432")])
+    );
+}
+
+#[test]
+fn parse_unquoted_value_strips_inline_comment_only_after_whitespace() {
+    let content = "URL=http://host/#anchor\nNAME=tgui # the app\nEMPTY=\n";
+    assert_eq!(
+        parse(content).unwrap(),
+        entries(&[
+            ("URL", "http://host/#anchor"),
+            ("NAME", "tgui"),
+            ("EMPTY", "")
+        ])
+    );
+}
+
+#[test]
+fn parse_double_quoted_escapes_and_multiline() {
+    let content =
+        "GREETING=\"hello\\n\\\"world\\\"\" # comment\nKEY=\"line1\nline2\"\nPATHS=\"C:\\dir\"\n";
+    assert_eq!(
+        parse(content).unwrap(),
+        entries(&[
+            ("GREETING", "hello\n\"world\""),
+            ("KEY", "line1\nline2"),
+            ("PATHS", "C:\\dir"),
+        ])
+    );
+}
+
+#[test]
+fn parse_single_quoted_is_literal() {
+    let content = "RAW='no \\n escapes # here'\nREF='${HOME}'\n";
+    assert_eq!(
+        parse(content).unwrap(),
+        entries(&[("RAW", "no \\n escapes # here"), ("REF", "$${HOME}")])
+    );
+}
+
+#[test]
+fn parse_keeps_references_for_interpolation() {
+    let content = "DIR=${HOME}/app\nQUOTED=\"${DIR}/bin\"\nESCAPED=\"\\${DIR}\"\n";
+    assert_eq!(
+        parse(content).unwrap(),
+        entries(&[
+            ("DIR", "${HOME}/app"),
+            ("QUOTED", "${DIR}/bin"),
+            ("ESCAPED", "$${DIR}"),
+        ])
+    );
+}
+
+#[test]
+fn parse_handles_crlf_line_endings() {
+    let content = "A=1\r\nB=\"2\"\r\n";
+    assert_eq!(parse(content).unwrap(), entries(&[("A", "1"), ("B", "2")]));
+}
+
+#[test]
+fn parse_errors_report_line_number() {
+    let cases = [
+        ("A=1\nnot a pair\n", 2, "expected '=' after 'not'"),
+        ("A=1\n\nB=\"open\n", 3, "unterminated double quote"),
+        ("C='open", 1, "unterminated single quote"),
+        ("A=\"x\" trailing\n", 1, "unexpected characters after value"),
+        ("BAD$KEY=1\n", 1, "invalid key 'BAD$KEY'"),
+        ("=1\n", 1, "expected KEY=VALUE"),
+    ];
+
+    for (content, line, reason) in cases {
+        assert_eq!(
+            parse(content),
+            Err(DotenvError {
+                line,
+                reason: reason.to_string()
+            }),
+            "content: {content:?}"
+        );
+    }
+}
+
+#[test]
+fn load_missing_file_is_env_file_error() {
+    let result = load(Path::new("/nonexistent/tgui/.env"));
+    assert!(matches!(
+        result,
+        Err(ProcessSpawnError::EnvFileError { path, .. }) if path == "/nonexistent/tgui/.env"
+    ));
+}
@@ -0,0 +1,117 @@
+use crate::process::elevation::ElevationMethod;
+use std::path::Path;
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[test]
+fn wrap_sudo_uses_askpass() {
+    let (executable, args) = ElevationMethod::Sudo.wrap(
+        "systemctl".to_string(),
+        strings(&["restart", "nginx"]),
+        Path::new("/srv"),
+        &[],
+    );
+    assert_eq!(executable, "sudo");
+    assert_eq!(
+        args,
+        strings(&["-A", "--", "systemctl", "restart", "nginx"])
+    );
+}
+
+#[test]
+fn wrap_pkexec_keeps_working_directory() {
+    let (executable, args) = ElevationMethod::Pkexec.wrap(
+        "sh".to_string(),
+        strings(&["-c", "make install"]),
+        Path::new("/srv/app"),
+        &strings(&["DATABASE_URL"]),
+    );
+    assert_eq!(executable, "pkexec");
+    assert_eq!(
+        args,
+        strings(&["env", "--chdir=/srv/app", "sh", "-c", "make install"])
+    );
+}
+
+#[test]
+fn wrap_sudo_preserves_configured_env_by_name() {
+    let (_, args) = ElevationMethod::Sudo.wrap(
+        "make".to_string(),
+        strings(&["install"]),
+        Path::new("/srv"),
+        &strings(&["PREFIX", "SUDO_ASKPASS", "DESTDIR", "TGUI_ASKPASS_SOCKET"]),
+    );
+    assert_eq!(
+        args,
+        strings(&[
+            "-A",
+            "--preserve-env=PREFIX,DESTDIR",
+            "--",
+            "make",
+            "install"
+        ])
+    );
+}
+
+#[test]
+fn pkexec_rejects_commands_that_set_env() {
+    let err = ElevationMethod::Pkexec
+        .check_env(&["PREFIX", "DESTDIR"])
+        .unwrap_err();
+    assert!(err.contains("PREFIX, DESTDIR"), "{err}");
+    assert!(ElevationMethod::Pkexec.check_env(&[]).is_ok());
+    assert!(ElevationMethod::Sudo.check_env(&["PREFIX"]).is_ok());
+}
+
+#[cfg(unix)]
+mod askpass {
+    use crate::process::elevation::{askpass_client, AskpassServer, ASKPASS_SOCKET_ENV};
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn prompt_is_answered_through_the_server() {
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+        let seen = prompts.clone();
+        let server = AskpassServer::start(move |prompt| {
+            seen.lock().unwrap().push(prompt);
+            async { Some("hunter2".to_string()) }
+        })
+        .unwrap();
+
+        let socket = server.socket_path();
+        let code = tokio::task::spawn_blocking(move || {
+            askpass_client(&socket, "[sudo] password for tgui:")
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(code, 0);
+        assert_eq!(*prompts.lock().unwrap(), vec!["[sudo] password for tgui:"]);
+        let env_vars = server.env_vars().unwrap();
+        assert!(env_vars.iter().any(|(k, _)| k == "SUDO_ASKPASS"));
+        assert!(env_vars
+            .iter()
+            .any(|(k, v)| k == ASKPASS_SOCKET_ENV && *v == server.socket_path().to_string_lossy()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn cancelled_prompt_fails_the_helper() {
+        let server = AskpassServer::start(|_| async { None }).unwrap();
+        let socket = server.socket_path();
+        let code = tokio::task::spawn_blocking(move || askpass_client(&socket, "Password:"))
+            .await
+            .unwrap();
+        assert_eq!(code, 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn socket_is_removed_on_drop() {
+        let server = AskpassServer::start(|_| async { None }).unwrap();
+        let socket = server.socket_path();
+        assert!(socket.exists());
+        drop(server);
+        assert!(!socket.exists());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::process::interpolation::{fill_execution_id, InterpolationError, Interpolator};
+use std::collections::{BTreeMap, HashMap};
+
+fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+fn interpolator(variables: &[(&str, &str)]) -> Interpolator {
+    let builtins = HashMap::from([("HOME", "/home/test".to_string())]);
+    Interpolator::new(vars(variables), builtins)
+}
+
+#[test]
+fn expand_without_references_is_unchanged() {
+    let mut interp = interpolator(&[]);
+    assert_eq!(
+        interp.expand("echo $PLAIN \"x\"", "command").unwrap(),
+        "echo $PLAIN \"x\""
+    );
+}
+
+#[test]
+fn expand_builtin_and_variable() {
+    let mut interp = interpolator(&[("project", "tgui")]);
+    assert_eq!(
+        interp
+            .expand("${HOME}/src/${project}", "arguments")
+            .unwrap(),
+        "/home/test/src/tgui"
+    );
+}
+
+#[test]
+fn expand_escaped_reference_is_literal() {
+    let mut interp = interpolator(&[]);
+    assert_eq!(
+        interp.expand("$${HOME} and ${HOME}", "arguments").unwrap(),
+        "${HOME} and /home/test"
+    );
+}
+
+#[test]
+fn expand_undefined_variable_is_error() {
+    let mut interp = interpolator(&[]);
+    assert_eq!(
+        interp.expand("${MISSING}", "arguments"),
+        Err(InterpolationError::Undefined {
+            name: "MISSING".to_string(),
+            field: "arguments".to_string()
+        })
+    );
+}
+
+#[test]
+fn expand_unterminated_and_invalid_names_are_errors() {
+    let mut interp = interpolator(&[]);
+    assert!(matches!(
+        interp.expand("${HOME", "command"),
+        Err(InterpolationError::Unterminated { .. })
+    ));
+    assert!(matches!(
+        interp.expand("${NOT VALID}", "command"),
+        Err(InterpolationError::InvalidName { .. })
+    ));
+    assert!(matches!(
+        interp.expand("${}", "command"),
+        Err(InterpolationError::InvalidName { .. })
+    ));
+}
+
+#[test]
+fn variables_reference_other_variables() {
+    let mut interp = interpolator(&[("root", "${HOME}/work"), ("app", "${root}/app")]);
+    assert_eq!(
+        interp.expand("${app}", "arguments").unwrap(),
+        "/home/test/work/app"
+    );
+}
+
+#[test]
+fn variable_cycle_is_detected() {
+    let mut interp = interpolator(&[("a", "${b}"), ("b", "${a}")]);
+    let err = interp.expand("${a}", "arguments").unwrap_err();
+    assert_eq!(
+        err,
+        InterpolationError::Circular {
+            chain: vec!["a".to_string(), "b".to_string(), "a".to_string()]
+        }
+    );
+}
+
+#[test]
+fn env_level_references_siblings() {
+    let mut interp = interpolator(&[]);
+    interp
+        .apply_env_level(&Some(vars(&[("BASE", "/opt"), ("BIN", "${BASE}/bin")])))
+        .unwrap();
+    let env = interp.into_env();
+    assert_eq!(env["BIN"], "/opt/bin");
+}
+
+#[test]
+fn env_self_reference_reads_level_below() {
+    let mut interp = interpolator(&[]);
+    interp.inherit(BTreeMap::from([(
+        "PATH".to_string(),
+        "/usr/bin".to_string(),
+    )]));
+    interp
+        .apply_env_level(&Some(vars(&[("PATH", "/group/bin:${PATH}")])))
+        .unwrap();
+    interp
+        .apply_env_level(&Some(vars(&[("PATH", "${HOME}/bin:${PATH}")])))
+        .unwrap();
+    assert_eq!(
+        interp.into_env()["PATH"],
+        "/home/test/bin:/group/bin:/usr/bin"
+    );
+}
+
+#[test]
+fn env_cycle_within_level_is_detected() {
+    let mut interp = interpolator(&[]);
+    let result = interp.apply_env_level(&Some(vars(&[("A", "${B}"), ("B", "${A}")])));
+    assert!(matches!(result, Err(InterpolationError::Circular { .. })));
+}
+
+#[test]
+fn inherited_values_are_not_interpolated() {
+    let mut interp = interpolator(&[]);
+    interp.inherit(BTreeMap::from([(
+        "PROMPT".to_string(),
+        "${MISSING}".to_string(),
+    )]));
+    assert_eq!(
+        interp.expand("${PROMPT}", "arguments").unwrap(),
+        "${MISSING}"
+    );
+}
+
+#[test]
+fn unset_removes_env_before_lookup() {
+    let mut interp = interpolator(&[]);
+    interp.inherit(BTreeMap::from([("TOKEN".to_string(), "x".to_string())]));
+    interp.unset(&Some(vec!["TOKEN".to_string()]));
+    assert!(matches!(
+        interp.expand("${TOKEN}", "arguments"),
+        Err(InterpolationError::Undefined { .. })
+    ));
+}
+
+#[test]
+fn execution_id_is_deferred() {
+    let mut interp = interpolator(&[]);
+    let expanded = interp
+        .expand("/tmp/run-${EXECUTION_ID}.log", "arguments")
+        .unwrap();
+    assert!(interp.uses_execution_id());
+    assert_eq!(fill_execution_id(&expanded, 42), "/tmp/run-42.log");
+}
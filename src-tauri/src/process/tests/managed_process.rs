@@ -6,8 +6,12 @@ use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio::time::timeout;
 
-use crate::database::ExecutionStatus;
+use crate::database::{ExecutionStatus, ResourceLimits};
+#[cfg(unix)]
+use crate::process::elevation::ElevationMethod;
 use crate::process::errors::ProcessKillError;
+#[cfg(unix)]
+use crate::process::limits::RawLimits;
 use crate::process::managed_process::{ManagedProcess, ProcessEvent};
 use crate::process::models::{ProcessStatus, SpawnContext};
 use crate::process::tests::{spawn_context, WAIT_TIMEOUT};
@@ -502,6 +506,58 @@ async fn graceful_kill_after_force_kill_returns_already_exited() {
     ));
 }
 
+/// Stands in for sudo on PATH: drops its own options and runs the command as the current user
+#[cfg(unix)]
+fn fake_sudo(dir: &std::path::Path) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let sudo = dir.join("sudo");
+    std::fs::write(
+        &sudo,
+        "#!/bin/sh\nwhile [ \"$1\" != -- ]; do shift; done\nshift\nexec \"$@\"\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&sudo, std::fs::Permissions::from_mode(0o755)).unwrap();
+    format!(
+        "{}:{}",
+        dir.display(),
+        std::env::var("PATH").unwrap_or_default()
+    )
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn elevated_process_is_killed_through_its_cgroup_or_refused() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let (tx, mut rx) = make_channel();
+    let ctx = SpawnContext {
+        elevation: Some(ElevationMethod::Sudo),
+        env_vars: vec![("PATH".into(), fake_sudo(dir.path()))],
+        ..spawn_context(1, "sleep", vec!["5"])
+    };
+    let mut process = ManagedProcess::spawn(1, ctx, tx, false)
+        .await
+        .expect("spawn failed");
+
+    match process.graceful_kill().await {
+        // a delegated cgroup was available
+        Ok(()) => {
+            let ProcessEvent::Stopped(evt) = wait_for_stopped(&mut rx).await else {
+                unreachable!()
+            };
+            assert_eq!(evt.status, ExecutionStatus::Cancelled);
+        }
+        Err(e) => {
+            assert!(matches!(e, ProcessKillError::ElevatedWithoutCgroup), "{e}");
+            assert!(matches!(
+                process.force_kill().await,
+                Err(ProcessKillError::ElevatedWithoutCgroup)
+            ));
+            assert!(process.is_running().await);
+        }
+    }
+}
+
 #[tokio::test]
 async fn kill_after_natural_exit_returns_already_exited() {
     let (tx, mut rx) = make_channel();
@@ -812,6 +868,7 @@ async fn env_vars_are_visible_inside_spawned_process() {
         working_directory: PathBuf::from("/tmp"),
         env_vars: vec![("TGUI_TEST_VAR".into(), "env_value_123".into())],
         shell: None,
+        ..Default::default()
     };
     let process = ManagedProcess::spawn(1, ctx, tx, false)
         .await
@@ -825,3 +882,158 @@ async fn env_vars_are_visible_inside_spawned_process() {
         "env var not visible inside process: {logs:?}"
     );
 }
+
+#[tokio::test]
+#[cfg(unix)]
+async fn resource_limits_are_applied_inside_spawned_process() {
+    let (tx, mut rx) = make_channel();
+    let ctx = SpawnContext {
+        resource_limits: ResourceLimits {
+            max_open_files: Some(64),
+            nice: Some(5),
+            ..Default::default()
+        },
+        ..spawn_context(1, "sh", vec!["-c", "echo files=$(ulimit -n) nice=$(nice)"])
+    };
+    let process = ManagedProcess::spawn(1, ctx, tx, false)
+        .await
+        .expect("spawn failed");
+    collect_until_stopped(&mut rx).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let logs = process.get_logs(0, 100).await;
+    assert!(
+        logs.iter()
+            .any(|l| l.content.contains("files=64") && l.content.contains("nice=5")),
+        "limits not applied inside process: {logs:?}"
+    );
+}
+
+#[tokio::test]
+#[cfg(target_os = "linux")]
+async fn memory_limit_leaves_the_address_space_alone() {
+    let (tx, mut rx) = make_channel();
+    let ctx = SpawnContext {
+        resource_limits: ResourceLimits {
+            max_memory_bytes: Some(1 << 30),
+            ..Default::default()
+        },
+        ..spawn_context(
+            1,
+            "grep",
+            vec!["-E", "Max (address space|data size)", "/proc/self/limits"],
+        )
+    };
+    let process = ManagedProcess::spawn(1, ctx, tx, false)
+        .await
+        .expect("spawn failed");
+    collect_until_stopped(&mut rx).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let logs = process.get_logs(0, 100).await;
+    let limit = |name: &str| {
+        logs.iter()
+            .find(|l| l.content.starts_with(name))
+            .and_then(|l| l.content[name.len()..].split_whitespace().next())
+            .map(str::to_string)
+    };
+    assert_eq!(limit("Max data size").as_deref(), Some("1073741824"));
+    assert_eq!(limit("Max address space").as_deref(), Some("unlimited"));
+}
+
+#[test]
+#[cfg(unix)]
+fn memory_rlimit_is_left_to_the_cgroup() {
+    let limits = ResourceLimits {
+        max_memory_bytes: Some(1 << 30),
+        max_open_files: Some(64),
+        ..Default::default()
+    };
+    let without_memory = ResourceLimits {
+        max_memory_bytes: None,
+        ..limits.clone()
+    };
+    assert_eq!(
+        RawLimits::from(&limits).in_cgroup(),
+        RawLimits::from(&without_memory)
+    );
+    assert_ne!(RawLimits::from(&limits), RawLimits::from(&without_memory));
+}
+
+#[tokio::test]
+#[cfg(target_os = "linux")]
+async fn process_limit_is_not_a_per_user_rlimit() {
+    let (tx, mut rx) = make_channel();
+    let ctx = SpawnContext {
+        resource_limits: ResourceLimits {
+            max_processes: Some(64),
+            ..Default::default()
+        },
+        ..spawn_context(1, "grep", vec!["Max processes", "/proc/self/limits"])
+    };
+    assert!(ctx.resource_limits.wants_cgroup());
+    let process = ManagedProcess::spawn(1, ctx, tx, false)
+        .await
+        .expect("spawn failed");
+    collect_until_stopped(&mut rx).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let logs = process.get_logs(0, 100).await;
+    assert!(
+        logs.iter().any(|l| l.content.starts_with("Max processes")),
+        "limits not listed: {logs:?}"
+    );
+    assert!(
+        !logs
+            .iter()
+            .any(|l| l.content.split_whitespace().nth(2) == Some("64")),
+        "max_processes set as RLIMIT_NPROC: {logs:?}"
+    );
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn requested_cgroup_still_spawns_when_unavailable() {
+    let (tx, mut rx) = make_channel();
+    let ctx = SpawnContext {
+        resource_limits: ResourceLimits {
+            max_open_files: Some(64),
+            use_cgroup: Some(true),
+            ..Default::default()
+        },
+        ..spawn_context(1, "sh", vec!["-c", "ulimit -n"])
+    };
+    let process = ManagedProcess::spawn(1, ctx, tx, false)
+        .await
+        .expect("spawn failed");
+    collect_until_stopped(&mut rx).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let logs = process.get_logs(0, 100).await;
+    assert!(
+        logs.iter().any(|l| l.content.trim() == "64"),
+        "rlimits not applied with cgroup requested: {logs:?}"
+    );
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn clear_env_spawns_with_only_given_vars() {
+    let (tx, mut rx) = make_channel();
+    let ctx = SpawnContext {
+        env_vars: vec![("ONLY_VAR".into(), "only_value".into())],
+        clear_env: true,
+        ..spawn_context(1, "/bin/sh", vec!["-c", "echo vars=$ONLY_VAR,$HOME"])
+    };
+    let process = ManagedProcess::spawn(1, ctx, tx, false)
+        .await
+        .expect("spawn failed");
+    collect_until_stopped(&mut rx).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let logs = process.get_logs(0, 100).await;
+    assert!(
+        logs.iter().any(|l| l.content.trim() == "vars=only_value,"),
+        "environment was not cleared: {logs:?}"
+    );
+}
@@ -1,13 +1,21 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::{sleep, timeout};
 
+use crate::constants::SECRET_MASK;
 use crate::database::tests::{CommandBuilder, GroupBuilder, TestDb};
-use crate::database::{Database, ExecutionHistory, ExecutionStatus, StatsTarget, TriggeredBy};
-use crate::process::errors::{ProcessKillError, ProcessManagerError};
-use crate::process::manager::ProcessManager;
-use crate::process::models::{ProcessStatus, SpawnContext};
+use crate::database::{
+    Command, CommandParameter, ContainerRuntime, Database, EnvInheritance, ExecutionHistory,
+    ExecutionStatus, ExecutionTarget, ParameterKind, ResourceLimits, ShellDefinition, ShellFamily,
+    ShellMode, StatsTarget, TriggeredBy,
+};
+use crate::process::elevation::ElevationMethod;
+use crate::process::errors::{ProcessKillError, ProcessManagerError, ProcessSpawnError};
+use crate::process::manager::{ProcessManager, CONFIRMATION_TTL};
+use crate::process::models::{ProcessStatus, SpawnContext, ValueSource};
+use crate::process::preflight::ProblemKind;
 use crate::process::tests::{spawn_context, WAIT_TIMEOUT};
 
 #[cfg(unix)]
@@ -143,6 +151,7 @@ async fn spawn_invalid_shell_returns_error_before_creating_db_row() {
         working_directory: PathBuf::from("/tmp"),
         env_vars: vec![],
         shell: Some("rm".into()),
+        ..Default::default()
     };
 
     let result = pm.spawn_command(ctx, TriggeredBy::Manual).await;
@@ -159,6 +168,95 @@ async fn spawn_invalid_shell_returns_error_before_creating_db_row() {
     );
 }
 
+#[tokio::test]
+#[cfg(unix)]
+async fn spawn_uses_args_template_of_registry_shell() {
+    let (pm, db, cmd_id) = make_manager_with_db();
+    let mut shell = ShellDefinition::builtin("quoted", "/bin/sh");
+    shell.args_template = r#"-c 'echo "registry: $0"' {cmd}"#.to_string();
+    db.create_shell(&shell).unwrap();
+
+    let mut ctx = spawn_context(cmd_id, "echo", vec!["hi"]);
+    ctx.shell = Some("quoted".into());
+    let id = pm
+        .spawn_command(ctx, TriggeredBy::Manual)
+        .await
+        .expect("spawn failed");
+
+    wait_until!("count = 0", pm.running_count().await == 0);
+    sleep(Duration::from_millis(100)).await;
+
+    let logs = pm.get_logs(id, 0, 100).await.expect("logs");
+    let contents: Vec<&str> = logs.iter().map(|l| l.content.as_str()).collect();
+    assert_eq!(contents, vec!["registry: echo hi"]);
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn spawn_through_shell_expands_only_raw_arguments() {
+    let (pm, _, cmd_id) = make_manager_with_db();
+
+    let mut ctx = spawn_context(cmd_id, "echo", vec!["$((1 + 2))", "$((3 + 4))"]);
+    ctx.shell = Some("sh".into());
+    ctx.raw_arguments = vec![1];
+    let id = pm
+        .spawn_command(ctx, TriggeredBy::Manual)
+        .await
+        .expect("spawn failed");
+
+    wait_until!("count = 0", pm.running_count().await == 0);
+    sleep(Duration::from_millis(100)).await;
+
+    let logs = pm.get_logs(id, 0, 100).await.expect("logs");
+    let contents: Vec<&str> = logs.iter().map(|l| l.content.as_str()).collect();
+    assert_eq!(contents, vec!["$((1 + 2)) 7"]);
+}
+
+#[tokio::test]
+async fn spawn_missing_executable_fails_preflight_without_history_row() {
+    let (pm, db, cmd_id) = make_manager_with_db();
+
+    let result = pm
+        .spawn_command(
+            spawn_context(cmd_id, "this_does_not_exist_tgui", vec![]),
+            TriggeredBy::Manual,
+        )
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(ProcessSpawnError::ExecutableNotFound(_))
+    ));
+    let history = db
+        .get_command_execution_history(cmd_id, None)
+        .unwrap_or_default();
+    assert!(
+        history.is_empty(),
+        "History row created for a failed pre-flight"
+    );
+}
+
+#[tokio::test]
+async fn validate_command_reports_unknown_shell_and_missing_directory() {
+    let pm = make_manager();
+    let mut ctx = spawn_context(1, "echo", vec![]);
+    ctx.shell = Some("not_a_registry_shell".into());
+    ctx.working_directory = PathBuf::from("/nonexistent_tgui/dir");
+
+    let kinds: Vec<ProblemKind> = pm
+        .validate_command(&ctx)
+        .into_iter()
+        .map(|p| p.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ProblemKind::InvalidShell,
+            ProblemKind::WorkingDirectoryMissing
+        ]
+    );
+}
+
 #[tokio::test]
 async fn spawn_failure_marks_db_row_as_failed_not_running() {
     let (pm, db, cmd_id) = make_manager_with_db();
@@ -552,6 +650,45 @@ async fn get_running_processes_excludes_stopped_processes() {
     pm.stop_all(true).await;
 }
 
+#[tokio::test]
+#[cfg(unix)]
+async fn spawned_output_is_redacted_with_configured_rules() {
+    let (pm, db, cmd_id) = make_manager_with_db();
+    db.set_setting(
+        "redaction_rules",
+        r#"[{"name": "ticket", "pattern": "TICKET-[0-9]+"}]"#,
+    )
+    .unwrap();
+
+    let id = pm
+        .spawn_command(
+            spawn_context(
+                cmd_id,
+                "sh",
+                vec![
+                    "-c",
+                    "echo Authorization: Bearer abcdefgh12345678; echo TICKET-42",
+                ],
+            ),
+            TriggeredBy::Manual,
+        )
+        .await
+        .expect("spawn echo failed");
+
+    wait_until!("count = 0", pm.running_count().await == 0);
+    sleep(Duration::from_millis(100)).await;
+
+    let logs = pm.get_logs(id, 0, 100).await.expect("logs");
+    let contents: Vec<&str> = logs.iter().map(|l| l.content.as_str()).collect();
+    assert_eq!(
+        contents,
+        vec![
+            format!("Authorization: Bearer {SECRET_MASK}"),
+            SECRET_MASK.to_string()
+        ]
+    );
+}
+
 #[tokio::test]
 #[cfg(unix)]
 async fn get_logs_offset_and_limit_paginate_correctly() {
@@ -1252,6 +1389,89 @@ async fn resolve_spawn_context_shell_falls_back_to_group() {
     assert_eq!(ctx.shell, group.shell);
 }
 
+#[tokio::test]
+async fn resolve_spawn_context_shell_mode_and_source_files_follow_groups() {
+    let db = create_test_db();
+
+    let mut parent = GroupBuilder::new("parent").build();
+    parent.shell_mode = Some(ShellMode::Interactive);
+    parent.source_files = Some(vec!["~/.nvm/nvm.sh".to_string()]);
+    let parent_id = db.create_group(&parent).expect("create parent");
+    let mut child = GroupBuilder::new("child").with_parent(parent_id).build();
+    child.source_files = Some(vec!["${HOME}/.venv/bin/activate".to_string()]);
+    let child_id = db.create_group(&child).expect("create child");
+
+    let mut cmd = CommandBuilder::new("test", "node")
+        .with_group(child_id)
+        .build();
+    cmd.source_files = Some(vec!["env.sh".to_string()]);
+    let cmd_id = db.create_command(&cmd).unwrap();
+
+    let pm = ProcessManager::new(db.clone(), None);
+    let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
+    let home = dirs::home_dir().unwrap();
+    assert_eq!(ctx.shell_mode, Some(ShellMode::Interactive));
+    assert_eq!(
+        ctx.source_files,
+        vec![
+            "~/.nvm/nvm.sh".to_string(),
+            format!("{}/.venv/bin/activate", home.display()),
+            "env.sh".to_string(),
+        ]
+    );
+
+    cmd.id = cmd_id;
+    cmd.shell_mode = Some(ShellMode::Login);
+    db.update_command(&cmd).unwrap();
+    let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
+    assert_eq!(ctx.shell_mode, Some(ShellMode::Login));
+}
+
+#[tokio::test]
+async fn resolve_spawn_context_rejects_inherited_shell_without_the_mode() {
+    let db = create_test_db();
+    let mut shell = ShellDefinition::builtin("cmd-like", "/bin/sh");
+    shell.family = ShellFamily::Cmd;
+    db.create_shell(&shell).unwrap();
+
+    let mut group = GroupBuilder::new("windows").build();
+    group.shell = Some("cmd-like".to_string());
+    let group_id = db.create_group(&group).unwrap();
+    let mut cmd = CommandBuilder::new("test", "dir")
+        .with_group(group_id)
+        .build();
+    cmd.shell_mode = Some(ShellMode::Interactive);
+    let cmd_id = db.create_command(&cmd).unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let err = pm.resolve_spawn_context(cmd_id).await.unwrap_err();
+    assert_eq!(err.code, "INVALID_SHELL");
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn spawn_sources_files_before_the_command() {
+    let (pm, _, cmd_id) = make_manager_with_db();
+    let dir = tempfile::TempDir::new().unwrap();
+    let rc = dir.path().join("rc.sh");
+    std::fs::write(&rc, "greet() { echo \"hello $1\"; }\nfalse\n").unwrap();
+
+    let mut ctx = spawn_context(cmd_id, "greet", vec!["world"]);
+    ctx.shell = Some("sh".into());
+    ctx.source_files = vec![rc.to_string_lossy().into_owned()];
+    let id = pm
+        .spawn_command(ctx, TriggeredBy::Manual)
+        .await
+        .expect("spawn failed");
+
+    wait_until!("count = 0", pm.running_count().await == 0);
+    sleep(Duration::from_millis(100)).await;
+
+    let logs = pm.get_logs(id, 0, 100).await.expect("logs");
+    let contents: Vec<&str> = logs.iter().map(|l| l.content.as_str()).collect();
+    assert_eq!(contents, vec!["hello world"]);
+}
+
 #[tokio::test]
 #[cfg(unix)]
 async fn resolve_spawn_context_shell_no_group_falls_back_to_default_setting() {
@@ -1290,197 +1510,738 @@ async fn resolve_spawn_context_shell_falls_back_to_default_setting() {
     assert_eq!(ctx.shell, Some("sh".to_string()));
 }
 
+fn env_value<'a>(ctx: &'a SpawnContext, key: &str) -> Option<&'a str> {
+    ctx.env_vars
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
+
 #[tokio::test]
-#[cfg(unix)]
-async fn resolve_spawn_context_env_vars_group_applied_as_base() {
+async fn resolve_spawn_context_inherits_full_env_by_default() {
     let db = create_test_db();
-
-    let mut group = GroupBuilder::new("group")
-        .with_env("GROUP_VAR", "from_group")
-        .build();
-    group.working_directory = Some("/tmp".to_string());
-
-    let group_id = db.create_group(&group).expect("create group");
-
     let cmd_id = db
         .create_command(
             &CommandBuilder::new("test", "echo test")
-                .with_group(group_id)
+                .with_env("CMD_VAR", "1")
                 .build(),
         )
         .unwrap();
 
     let pm = ProcessManager::new(db, None);
     let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
-
-    assert!(
-        ctx.env_vars
-            .iter()
-            .any(|(k, v)| k == "GROUP_VAR" && v == "from_group"),
-        "Group env var missing: {:?}",
-        ctx.env_vars
+    assert!(ctx.clear_env);
+    assert_eq!(env_value(&ctx, "CMD_VAR"), Some("1"));
+    assert_eq!(
+        env_value(&ctx, "PATH").map(String::from),
+        std::env::var("PATH").ok()
     );
 }
 
 #[tokio::test]
-#[cfg(unix)]
-async fn resolve_spawn_context_env_vars_command_overrides_group_for_same_key() {
+async fn resolve_spawn_context_clean_env_only_has_configured_vars() {
     let db = create_test_db();
-
-    let mut group = GroupBuilder::new("group")
-        .with_env("GROUP_VAR", "from_group")
-        .with_env("SHARED_KEY", "group_value")
-        .build();
-    group.working_directory = Some("/tmp".to_string());
-
-    let group_id = db.create_group(&group).expect("create group");
-
+    let group_id = db
+        .create_group(
+            &GroupBuilder::new("group")
+                .with_env("GROUP_VAR", "g")
+                .with_env_inheritance(EnvInheritance::Clean)
+                .build(),
+        )
+        .unwrap();
     let cmd_id = db
         .create_command(
             &CommandBuilder::new("test", "echo test")
                 .with_group(group_id)
-                .with_env("SHARED_KEY", "cmd_value")
-                .with_env("COMMAND_KEY", "from_cmd")
+                .with_env("CMD_VAR", "c")
                 .build(),
         )
         .unwrap();
 
     let pm = ProcessManager::new(db, None);
     let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
-
-    // SHARED_KEY should appear exactly once
-    let shared = ctx.env_vars.iter().filter(|(k, _)| k == "SHARED_KEY");
-    assert_eq!(
-        shared.count(),
-        1,
-        "SHARED_KEY should appear exactly once after dedup"
-    );
-    // Command overrides group for SHARED_KEY
-    let shared = ctx.env_vars.iter().find(|(k, _)| k == "SHARED_KEY");
     assert_eq!(
-        shared.map(|(_, v)| v.as_str()),
-        Some("cmd_value"),
-        "Command should override group"
+        ctx.env_vars,
+        vec![
+            ("CMD_VAR".to_string(), "c".to_string()),
+            ("GROUP_VAR".to_string(), "g".to_string())
+        ]
     );
-    assert!(shared
-        .iter()
-        .all(|(k, v)| k == "SHARED_KEY" && v == "cmd_value"));
-
-    assert!(ctx
-        .env_vars
-        .iter()
-        .any(|(k, v)| k == "GROUP_VAR" && v == "from_group"));
-
-    assert!(ctx
-        .env_vars
-        .iter()
-        .any(|(k, v)| k == "COMMAND_KEY" && v == "from_cmd"),);
 }
 
 #[tokio::test]
-#[cfg(unix)]
-async fn resolve_spawn_context_working_dir_walks_to_grandparent() {
+async fn resolve_spawn_context_allowlist_keeps_only_listed_vars() {
     let db = create_test_db();
-
-    let mut group = GroupBuilder::new("group").build();
-    group.working_directory = Some("/tmp".to_string());
-    let root_id = db.create_group(&group).expect("root group");
-
-    let child_id = db
-        .create_group(&GroupBuilder::new("child").with_parent(root_id).build())
-        .expect("child group");
-
     let cmd_id = db
         .create_command(
             &CommandBuilder::new("test", "echo test")
-                .with_group(child_id)
+                .with_env_inheritance(EnvInheritance::Allowlist {
+                    variables: vec!["PATH".to_string()],
+                })
+                .with_env("CMD_VAR", "c")
                 .build(),
         )
         .unwrap();
 
     let pm = ProcessManager::new(db, None);
-    let ctx = pm
-        .resolve_spawn_context(cmd_id)
-        .await
-        .expect("resolve failed");
-    assert_eq!(ctx.working_directory, PathBuf::from("/tmp"));
+    let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
+    assert!(ctx
+        .env_vars
+        .iter()
+        .all(|(k, _)| k == "PATH" || k == "CMD_VAR"));
+    assert_eq!(
+        env_value(&ctx, "PATH").map(String::from),
+        std::env::var("PATH").ok()
+    );
 }
 
 #[tokio::test]
-#[cfg(unix)]
-async fn resolve_spawn_context_closest_ancestor_wins_over_grandparent() {
+async fn resolve_spawn_context_command_inheritance_overrides_group() {
     let db = create_test_db();
-
-    let mut group = GroupBuilder::new("group").build();
-    group.working_directory = Some("/tmp".to_string());
-    let root_id = db.create_group(&group).expect("root group");
-
-    let mut dir_group = GroupBuilder::new("group").with_parent(root_id).build();
-    dir_group.working_directory = Some("/var/tmp".to_string());
-    let parent_id = db.create_group(&dir_group).expect("root group");
-
-    let mut shell_group = GroupBuilder::new("group").with_parent(parent_id).build();
-    shell_group.shell = Some("zsh".to_string());
-
-    let child_id = db.create_group(&shell_group).expect("child group");
-
+    let group_id = db
+        .create_group(
+            &GroupBuilder::new("group")
+                .with_env_inheritance(EnvInheritance::Clean)
+                .build(),
+        )
+        .unwrap();
     let cmd_id = db
         .create_command(
             &CommandBuilder::new("test", "echo test")
-                .with_group(child_id)
+                .with_group(group_id)
+                .with_env_inheritance(EnvInheritance::Full)
                 .build(),
         )
         .unwrap();
 
     let pm = ProcessManager::new(db, None);
-    let ctx = pm
-        .resolve_spawn_context(cmd_id)
-        .await
-        .expect("resolve failed");
-    assert_eq!(
-        ctx.working_directory,
-        PathBuf::from(dir_group.working_directory.unwrap())
-    );
-    assert_eq!(ctx.shell, shell_group.shell);
+    let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
+    assert!(env_value(&ctx, "PATH").is_some());
 }
 
 #[tokio::test]
-#[cfg(unix)]
-async fn resolve_spawn_context_env_vars_merge_across_ancestor_chain() {
+async fn resolve_spawn_context_unset_removes_inherited_and_group_vars() {
     let db = create_test_db();
-
-    let mut group = GroupBuilder::new("group")
-        .with_env("ROOT_VAR", "root")
-        .with_env("SHARED", "from_root")
-        .build();
-    group.working_directory = Some("/tmp".to_string());
-    let root_id = db.create_group(&group).expect("root group");
-
-    let child_id = db
+    let group_id = db
         .create_group(
-            &GroupBuilder::new("child")
-                .with_env("SHARED", "from_child")
-                .with_parent(root_id)
+            &GroupBuilder::new("group")
+                .with_env("GROUP_VAR", "g")
+                .with_env("KEPT", "k")
                 .build(),
         )
-        .expect("child");
-
+        .unwrap();
     let cmd_id = db
         .create_command(
             &CommandBuilder::new("test", "echo test")
-                .with_group(child_id)
+                .with_group(group_id)
+                .with_env_unset(vec!["PATH", "GROUP_VAR"])
                 .build(),
         )
         .unwrap();
 
     let pm = ProcessManager::new(db, None);
     let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
+    assert_eq!(env_value(&ctx, "PATH"), None);
+    assert_eq!(env_value(&ctx, "GROUP_VAR"), None);
+    assert_eq!(env_value(&ctx, "KEPT"), Some("k"));
+}
 
-    assert!(
-        ctx.env_vars
-            .iter()
-            .any(|(k, v)| k == "ROOT_VAR" && v == "root"),
-        "root var missing"
+#[tokio::test]
+async fn resolve_spawn_context_command_env_wins_over_own_unset() {
+    let db = create_test_db();
+    let cmd_id = db
+        .create_command(
+            &CommandBuilder::new("test", "echo test")
+                .with_env_unset(vec!["LANG"])
+                .with_env("LANG", "C")
+                .build(),
+        )
+        .unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
+    assert_eq!(env_value(&ctx, "LANG"), Some("C"));
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn resolve_spawn_context_interpolates_fields() {
+    let db = create_test_db();
+    let group_id = db
+        .create_group(
+            &GroupBuilder::new("group")
+                .with_variable("target", "release")
+                .with_env("OUT_DIR", "${GROUP_DIR}/${target}")
+                .build(),
+        )
+        .unwrap();
+    let mut group = db.get_group(group_id).unwrap();
+    group.working_directory = Some("/tmp".to_string());
+    db.update_group(&group).unwrap();
+
+    let mut cmd = CommandBuilder::new("test", "${target}-build")
+        .with_group(group_id)
+        .with_args(vec!["--out", "${OUT_DIR}", "$${literal}"])
+        .build();
+    cmd.working_directory = Some("${GROUP_DIR}".to_string());
+    let cmd_id = db.create_command(&cmd).unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
+    assert_eq!(ctx.executable, "release-build");
+    assert_eq!(ctx.arguments, vec!["--out", "/tmp/release", "${literal}"]);
+    assert_eq!(ctx.working_directory, PathBuf::from("/tmp"));
+    assert_eq!(env_value(&ctx, "OUT_DIR"), Some("/tmp/release"));
+    assert!(!ctx.uses_execution_id);
+}
+
+#[tokio::test]
+async fn resolve_spawn_context_closest_group_variable_wins() {
+    let db = create_test_db();
+    let root_id = db
+        .create_group(
+            &GroupBuilder::new("root")
+                .with_variable("profile", "dev")
+                .build(),
+        )
+        .unwrap();
+    let child_id = db
+        .create_group(
+            &GroupBuilder::new("child")
+                .with_parent(root_id)
+                .with_variable("profile", "prod")
+                .build(),
+        )
+        .unwrap();
+    let cmd_id = db
+        .create_command(
+            &CommandBuilder::new("test", "deploy")
+                .with_group(child_id)
+                .with_args(vec!["--profile=${profile}"])
+                .build(),
+        )
+        .unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
+    assert_eq!(ctx.arguments, vec!["--profile=prod"]);
+    assert_eq!(env_value(&ctx, "profile"), None);
+}
+
+#[tokio::test]
+async fn resolve_spawn_context_undefined_variable_returns_error() {
+    let db = create_test_db();
+    let cmd_id = db
+        .create_command(
+            &CommandBuilder::new("test", "echo")
+                .with_args(vec!["${TGUI_SURELY_UNDEFINED}"])
+                .build(),
+        )
+        .unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let err = pm.resolve_spawn_context(cmd_id).await.unwrap_err();
+    assert_eq!(err.code, "UNDEFINED_VARIABLE");
+    assert!(err.message.contains("TGUI_SURELY_UNDEFINED"));
+}
+
+#[tokio::test]
+async fn resolve_spawn_context_circular_env_returns_error() {
+    let db = create_test_db();
+    let cmd_id = db
+        .create_command(
+            &CommandBuilder::new("test", "echo")
+                .with_env("A", "${B}")
+                .with_env("B", "${A}")
+                .build(),
+        )
+        .unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let err = pm.resolve_spawn_context(cmd_id).await.unwrap_err();
+    assert_eq!(err.code, "CIRCULAR_VARIABLE");
+}
+
+#[tokio::test]
+async fn resolve_spawn_context_loads_env_files_in_precedence_order() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(
+        dir.path().join(".env"),
+        "SHARED=group-file\nGROUP_ONLY=g\nOVERRIDDEN=group-file\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join(".env.local"),
+        "export OVERRIDDEN=cmd-file\nAPP_URL=\"http://${HOST}:8080\"\n",
+    )
+    .unwrap();
+
+    let db = create_test_db();
+    let mut group = GroupBuilder::new("group")
+        .with_env_files(vec![".env"])
+        .with_env("HOST", "localhost")
+        .build();
+    group.working_directory = Some(dir.path().to_string_lossy().into_owned());
+    let group_id = db.create_group(&group).unwrap();
+    let cmd_id = db
+        .create_command(
+            &CommandBuilder::new("test", "echo test")
+                .with_group(group_id)
+                .with_env_files(vec![".env", ".env.local"])
+                .with_env("SHARED", "cmd-inline")
+                .build(),
+        )
+        .unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
+    assert_eq!(env_value(&ctx, "GROUP_ONLY"), Some("g"));
+    assert_eq!(env_value(&ctx, "SHARED"), Some("cmd-inline"));
+    assert_eq!(env_value(&ctx, "OVERRIDDEN"), Some("cmd-file"));
+    assert_eq!(env_value(&ctx, "APP_URL"), Some("http://localhost:8080"));
+}
+
+#[tokio::test]
+async fn resolve_spawn_context_env_file_errors_return_env_file_error() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::write(dir.path().join(".env"), "VALID=1\nBROKEN=\"unterminated\n").unwrap();
+
+    let db = create_test_db();
+    let mut broken = CommandBuilder::new("broken", "echo")
+        .with_env_files(vec![".env"])
+        .build();
+    broken.working_directory = Some(dir.path().to_string_lossy().into_owned());
+    let broken_id = db.create_command(&broken).unwrap();
+    let mut missing = CommandBuilder::new("missing", "echo")
+        .with_env_files(vec!["missing.env"])
+        .build();
+    missing.working_directory = Some(dir.path().to_string_lossy().into_owned());
+    let missing_id = db.create_command(&missing).unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let err = pm.resolve_spawn_context(broken_id).await.unwrap_err();
+    assert_eq!(err.code, "ENV_FILE_ERROR");
+    assert!(err.message.contains("line 2"));
+
+    let err = pm.resolve_spawn_context(missing_id).await.unwrap_err();
+    assert_eq!(err.code, "ENV_FILE_ERROR");
+    assert!(err.message.contains("missing.env"));
+}
+
+#[tokio::test]
+async fn resolve_spawn_context_injects_secrets_and_masks_them() {
+    let db = create_test_db();
+    let group_id = db
+        .create_group(
+            &GroupBuilder::new("group")
+                .with_secret("DB_PASSWORD", "hunter2")
+                .build(),
+        )
+        .unwrap();
+    let cmd_id = db
+        .create_command(
+            &CommandBuilder::new("test", "curl")
+                .with_group(group_id)
+                .with_secret("API_TOKEN", "tok-${literal}")
+                .with_args(vec!["-H", "Authorization: ${API_TOKEN}"])
+                .build(),
+        )
+        .unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
+    assert_eq!(env_value(&ctx, "DB_PASSWORD"), Some("hunter2"));
+    assert_eq!(env_value(&ctx, "API_TOKEN"), Some("tok-${literal}"));
+    assert_eq!(ctx.arguments[1], "Authorization: tok-${literal}");
+
+    let masked = ctx.masked();
+    assert_eq!(env_value(&masked, "DB_PASSWORD"), Some(SECRET_MASK));
+    assert_eq!(env_value(&masked, "API_TOKEN"), Some(SECRET_MASK));
+    assert_eq!(masked.arguments[1], format!("Authorization: {SECRET_MASK}"));
+    assert!(!serde_json::to_string(&ctx).unwrap().contains("\"secrets\""));
+}
+
+#[test]
+fn masked_context_masks_every_interpolated_field() {
+    let ctx = SpawnContext {
+        working_directory: PathBuf::from("/srv/hunter2"),
+        source_files: vec!["/etc/hunter2.sh".to_string()],
+        remote_working_directory: Some("/home/hunter2".to_string()),
+        secrets: vec!["hunter2".to_string()],
+        ..spawn_context(1, "echo", vec!["hunter2"])
+    };
+
+    let masked = ctx.masked();
+    assert_eq!(masked.arguments, vec![SECRET_MASK.to_string()]);
+    assert_eq!(
+        masked.working_directory,
+        PathBuf::from(format!("/srv/{SECRET_MASK}"))
+    );
+    assert_eq!(masked.source_files, vec![format!("/etc/{SECRET_MASK}.sh")]);
+    assert_eq!(
+        masked.remote_working_directory,
+        Some(format!("/home/{SECRET_MASK}"))
+    );
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn spawn_command_fills_execution_id() {
+    let db = create_test_db();
+    let cmd_id = db
+        .create_command(
+            &CommandBuilder::new("test", "sh")
+                .with_args(vec!["-c", "echo run=$RUN_ID"])
+                .with_env("RUN_ID", "${EXECUTION_ID}")
+                .build(),
+        )
+        .unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
+    assert!(ctx.uses_execution_id);
+
+    let id = pm
+        .spawn_command(ctx, TriggeredBy::Manual)
+        .await
+        .expect("spawn failed");
+
+    let expected = format!("run={id}");
+    wait_until!("log line", {
+        pm.get_logs(id, 0, 100)
+            .await
+            .unwrap_or_default()
+            .iter()
+            .any(|l| l.content.trim() == expected)
+    });
+}
+
+fn branch_parameter() -> CommandParameter {
+    CommandParameter {
+        name: "branch".to_string(),
+        label: None,
+        description: None,
+        kind: ParameterKind::String,
+        default: None,
+        required: true,
+    }
+}
+
+#[tokio::test]
+async fn resolve_spawn_context_with_params_substitutes_values() {
+    let db = create_test_db();
+    let cmd_id = db
+        .create_command(
+            &CommandBuilder::new("test", "git")
+                .with_args(vec!["checkout", "{{branch}}"])
+                .with_env("TARGET", "{{branch}}-${HOME}")
+                .with_parameter(branch_parameter())
+                .build(),
+        )
+        .unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let params = HashMap::from([("branch".to_string(), "feature/x".to_string())]);
+    let ctx = pm
+        .resolve_spawn_context_with_params(cmd_id, &params)
+        .await
+        .expect("resolve");
+
+    let home = dirs::home_dir().unwrap();
+    assert_eq!(ctx.arguments, vec!["checkout", "feature/x"]);
+    assert_eq!(
+        env_value(&ctx, "TARGET").map(String::from),
+        Some(format!("feature/x-{}", home.display()))
+    );
+    assert_eq!(ctx.parameters["branch"], "feature/x");
+}
+
+#[tokio::test]
+async fn resolve_spawn_context_missing_required_param_returns_error() {
+    let db = create_test_db();
+    let cmd_id = db
+        .create_command(
+            &CommandBuilder::new("test", "git")
+                .with_parameter(branch_parameter())
+                .build(),
+        )
+        .unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let err = pm.resolve_spawn_context(cmd_id).await.unwrap_err();
+    assert_eq!(err.code, "MISSING_PARAMETER");
+}
+
+#[tokio::test]
+async fn spawn_command_records_parameters_in_history_context() {
+    let db = create_test_db();
+    let cmd_id = db
+        .create_command(
+            &CommandBuilder::new("test", "echo")
+                .with_args(vec!["{{branch}}"])
+                .with_parameter(branch_parameter())
+                .build(),
+        )
+        .unwrap();
+
+    let pm = ProcessManager::new(db.clone(), None);
+    let params = HashMap::from([("branch".to_string(), "main".to_string())]);
+    let ctx = pm
+        .resolve_spawn_context_with_params(cmd_id, &params)
+        .await
+        .expect("resolve");
+    let id = pm
+        .spawn_command(ctx, TriggeredBy::Manual)
+        .await
+        .expect("spawn failed");
+
+    let context = db.get_execution_history(id).unwrap().context.unwrap();
+    let context: serde_json::Value = serde_json::from_str(&context).unwrap();
+    assert_eq!(context["parameters"]["branch"], "main");
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn resolve_spawn_context_resource_limits_inherit_per_field() {
+    let db = create_test_db();
+
+    let root = GroupBuilder::new("root")
+        .with_resource_limits(ResourceLimits {
+            max_processes: Some(100),
+            nice: Some(10),
+            ..Default::default()
+        })
+        .build();
+    let root_id = db.create_group(&root).expect("create root");
+
+    let child = GroupBuilder::new("child")
+        .with_parent(root_id)
+        .with_resource_limits(ResourceLimits {
+            max_memory_bytes: Some(1 << 30),
+            nice: Some(5),
+            ..Default::default()
+        })
+        .build();
+    let child_id = db.create_group(&child).expect("create child");
+
+    let cmd = CommandBuilder::new("test", "echo test")
+        .with_group(child_id)
+        .with_resource_limits(ResourceLimits {
+            nice: Some(2),
+            ..Default::default()
+        })
+        .build();
+    let cmd_id = db.create_command(&cmd).unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
+    assert_eq!(ctx.resource_limits.nice, Some(2));
+    assert_eq!(ctx.resource_limits.max_memory_bytes, Some(1 << 30));
+    assert_eq!(ctx.resource_limits.max_processes, Some(100));
+    assert_eq!(ctx.resource_limits.max_open_files, None);
+}
+
+#[tokio::test]
+async fn resolve_spawn_context_without_limits_is_empty() {
+    let db = create_test_db();
+    let cmd_id = create_test_command(&db);
+
+    let pm = ProcessManager::new(db, None);
+    let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
+    assert!(ctx.resource_limits.is_empty());
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn resolve_spawn_context_env_vars_group_applied_as_base() {
+    let db = create_test_db();
+
+    let mut group = GroupBuilder::new("group")
+        .with_env("GROUP_VAR", "from_group")
+        .build();
+    group.working_directory = Some("/tmp".to_string());
+
+    let group_id = db.create_group(&group).expect("create group");
+
+    let cmd_id = db
+        .create_command(
+            &CommandBuilder::new("test", "echo test")
+                .with_group(group_id)
+                .build(),
+        )
+        .unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
+
+    assert!(
+        ctx.env_vars
+            .iter()
+            .any(|(k, v)| k == "GROUP_VAR" && v == "from_group"),
+        "Group env var missing: {:?}",
+        ctx.env_vars
+    );
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn resolve_spawn_context_env_vars_command_overrides_group_for_same_key() {
+    let db = create_test_db();
+
+    let mut group = GroupBuilder::new("group")
+        .with_env("GROUP_VAR", "from_group")
+        .with_env("SHARED_KEY", "group_value")
+        .build();
+    group.working_directory = Some("/tmp".to_string());
+
+    let group_id = db.create_group(&group).expect("create group");
+
+    let cmd_id = db
+        .create_command(
+            &CommandBuilder::new("test", "echo test")
+                .with_group(group_id)
+                .with_env("SHARED_KEY", "cmd_value")
+                .with_env("COMMAND_KEY", "from_cmd")
+                .build(),
+        )
+        .unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
+
+    // SHARED_KEY should appear exactly once
+    let shared = ctx.env_vars.iter().filter(|(k, _)| k == "SHARED_KEY");
+    assert_eq!(
+        shared.count(),
+        1,
+        "SHARED_KEY should appear exactly once after dedup"
+    );
+    // Command overrides group for SHARED_KEY
+    let shared = ctx.env_vars.iter().find(|(k, _)| k == "SHARED_KEY");
+    assert_eq!(
+        shared.map(|(_, v)| v.as_str()),
+        Some("cmd_value"),
+        "Command should override group"
+    );
+    assert!(shared
+        .iter()
+        .all(|(k, v)| k == "SHARED_KEY" && v == "cmd_value"));
+
+    assert!(ctx
+        .env_vars
+        .iter()
+        .any(|(k, v)| k == "GROUP_VAR" && v == "from_group"));
+
+    assert!(ctx
+        .env_vars
+        .iter()
+        .any(|(k, v)| k == "COMMAND_KEY" && v == "from_cmd"),);
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn resolve_spawn_context_working_dir_walks_to_grandparent() {
+    let db = create_test_db();
+
+    let mut group = GroupBuilder::new("group").build();
+    group.working_directory = Some("/tmp".to_string());
+    let root_id = db.create_group(&group).expect("root group");
+
+    let child_id = db
+        .create_group(&GroupBuilder::new("child").with_parent(root_id).build())
+        .expect("child group");
+
+    let cmd_id = db
+        .create_command(
+            &CommandBuilder::new("test", "echo test")
+                .with_group(child_id)
+                .build(),
+        )
+        .unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let ctx = pm
+        .resolve_spawn_context(cmd_id)
+        .await
+        .expect("resolve failed");
+    assert_eq!(ctx.working_directory, PathBuf::from("/tmp"));
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn resolve_spawn_context_closest_ancestor_wins_over_grandparent() {
+    let db = create_test_db();
+
+    let mut group = GroupBuilder::new("group").build();
+    group.working_directory = Some("/tmp".to_string());
+    let root_id = db.create_group(&group).expect("root group");
+
+    let mut dir_group = GroupBuilder::new("group").with_parent(root_id).build();
+    dir_group.working_directory = Some("/var/tmp".to_string());
+    let parent_id = db.create_group(&dir_group).expect("root group");
+
+    let mut shell_group = GroupBuilder::new("group").with_parent(parent_id).build();
+    shell_group.shell = Some("zsh".to_string());
+
+    let child_id = db.create_group(&shell_group).expect("child group");
+
+    let cmd_id = db
+        .create_command(
+            &CommandBuilder::new("test", "echo test")
+                .with_group(child_id)
+                .build(),
+        )
+        .unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let ctx = pm
+        .resolve_spawn_context(cmd_id)
+        .await
+        .expect("resolve failed");
+    assert_eq!(
+        ctx.working_directory,
+        PathBuf::from(dir_group.working_directory.unwrap())
+    );
+    assert_eq!(ctx.shell, shell_group.shell);
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn resolve_spawn_context_env_vars_merge_across_ancestor_chain() {
+    let db = create_test_db();
+
+    let mut group = GroupBuilder::new("group")
+        .with_env("ROOT_VAR", "root")
+        .with_env("SHARED", "from_root")
+        .build();
+    group.working_directory = Some("/tmp".to_string());
+    let root_id = db.create_group(&group).expect("root group");
+
+    let child_id = db
+        .create_group(
+            &GroupBuilder::new("child")
+                .with_env("SHARED", "from_child")
+                .with_parent(root_id)
+                .build(),
+        )
+        .expect("child");
+
+    let cmd_id = db
+        .create_command(
+            &CommandBuilder::new("test", "echo test")
+                .with_group(child_id)
+                .build(),
+        )
+        .unwrap();
+
+    let pm = ProcessManager::new(db, None);
+    let ctx = pm.resolve_spawn_context(cmd_id).await.expect("resolve");
+
+    assert!(
+        ctx.env_vars
+            .iter()
+            .any(|(k, v)| k == "ROOT_VAR" && v == "root"),
+        "root var missing"
     );
     assert!(
         ctx.env_vars
@@ -1494,3 +2255,385 @@ async fn resolve_spawn_context_env_vars_merge_across_ancestor_chain() {
         "SHARED should appear once"
     );
 }
+
+#[tokio::test]
+#[cfg(unix)]
+async fn spawn_destructive_command_requires_confirmation_token() {
+    let (pm, db, cmd_id) = make_manager_with_db();
+    let ctx = spawn_context(cmd_id, "rm", vec!["-f", "/nonexistent/tgui-safety-test"]);
+
+    let result = pm.spawn_command(ctx.clone(), TriggeredBy::Manual).await;
+    assert!(matches!(
+        result,
+        Err(ProcessSpawnError::ConfirmationRequired(reason)) if reason == "destructive"
+    ));
+    let wrong_token = SpawnContext {
+        confirmation_token: Some("0000".to_string()),
+        ..ctx.clone()
+    };
+    let result = pm.spawn_command(wrong_token, TriggeredBy::Manual).await;
+    assert!(matches!(
+        result,
+        Err(ProcessSpawnError::ConfirmationRequired(_))
+    ));
+    assert!(db.get_latest_execution_for_command(cmd_id).is_none());
+
+    let check = pm.check_spawn_safety(&ctx).unwrap();
+    assert!(check.requires_confirmation);
+    assert!(check.explanation.is_destructive);
+    let confirmed = SpawnContext {
+        confirmation_token: check.confirmation_token,
+        ..ctx
+    };
+    let id = pm
+        .spawn_command(confirmed, TriggeredBy::Manual)
+        .await
+        .expect("confirmed spawn failed");
+
+    let context = db.get_execution_history(id).unwrap().context.unwrap();
+    let context: serde_json::Value = serde_json::from_str(&context).unwrap();
+    assert_eq!(
+        context["safetyOverride"],
+        serde_json::json!({
+            "commandLine": "rm -f /nonexistent/tgui-safety-test",
+            "destructive": true,
+            "privileged": false,
+        })
+    );
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn spawn_privileged_command_check_masks_secrets() {
+    let (pm, _db, cmd_id) = make_manager_with_db();
+    let ctx = SpawnContext {
+        secrets: vec!["hunter2".to_string()],
+        ..spawn_context(cmd_id, "sudo", vec!["-k", "echo", "hunter2"])
+    };
+
+    let check = pm.check_spawn_safety(&ctx).unwrap();
+    assert!(check.requires_confirmation);
+    assert!(check.explanation.is_privileged);
+    assert_eq!(check.command_line, format!("sudo -k echo {SECRET_MASK}"));
+    assert_eq!(check.reason(), "runs with sudo");
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn spawn_deny_list_blocks_even_confirmed_commands() {
+    let (pm, db, cmd_id) = make_manager_with_db();
+    db.set_setting("spawn_deny_list", r#"["^rm .*/nonexistent/"]"#)
+        .unwrap();
+    let ctx = spawn_context(cmd_id, "rm", vec!["-f", "/nonexistent/tgui-safety-test"]);
+
+    let check = pm.check_spawn_safety(&ctx).unwrap();
+    assert_eq!(check.denied_by.as_deref(), Some("^rm .*/nonexistent/"));
+    let confirmed = SpawnContext {
+        confirmation_token: check.confirmation_token,
+        ..ctx
+    };
+    let result = pm.spawn_command(confirmed, TriggeredBy::Manual).await;
+    assert!(matches!(result, Err(ProcessSpawnError::Denied(_))));
+    assert!(db.get_latest_execution_for_command(cmd_id).is_none());
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn spawn_with_invalid_deny_list_is_blocked() {
+    let (pm, db, cmd_id) = make_manager_with_db();
+    // set_setting rejects it, a broken value can only come from outside
+    db.conn()
+        .unwrap()
+        .execute(
+            "UPDATE settings SET value = '[\"(\"]' WHERE key = 'spawn_deny_list'",
+            [],
+        )
+        .unwrap();
+    let ctx = spawn_context(cmd_id, "echo", vec!["hello"]);
+
+    assert!(matches!(
+        pm.check_spawn_safety(&ctx),
+        Err(ProcessSpawnError::InvalidDenyList(_))
+    ));
+    let result = pm.spawn_command(ctx, TriggeredBy::Manual).await;
+    assert!(matches!(result, Err(ProcessSpawnError::InvalidDenyList(_))));
+    assert!(db.get_latest_execution_for_command(cmd_id).is_none());
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn confirmation_token_is_single_use() {
+    let (pm, _db, cmd_id) = make_manager_with_db();
+    let ctx = spawn_context(cmd_id, "rm", vec!["-f", "/nonexistent/tgui-safety-test"]);
+    let confirmed = SpawnContext {
+        confirmation_token: pm.check_spawn_safety(&ctx).unwrap().confirmation_token,
+        ..ctx
+    };
+
+    pm.spawn_command(confirmed.clone(), TriggeredBy::Manual)
+        .await
+        .expect("confirmed spawn failed");
+    let result = pm.spawn_command(confirmed, TriggeredBy::Manual).await;
+    assert!(matches!(
+        result,
+        Err(ProcessSpawnError::ConfirmationRequired(_))
+    ));
+}
+
+#[tokio::test(start_paused = true)]
+#[cfg(unix)]
+async fn confirmation_token_expires() {
+    let (pm, db, cmd_id) = make_manager_with_db();
+    let ctx = spawn_context(cmd_id, "rm", vec!["-f", "/nonexistent/tgui-safety-test"]);
+    let other = spawn_context(cmd_id, "rm", vec!["-f", "/nonexistent/other"]);
+    let token = pm.check_spawn_safety(&ctx).unwrap().confirmation_token;
+
+    // a token only confirms the command line it was issued for
+    let result = pm
+        .spawn_command(
+            SpawnContext {
+                confirmation_token: token,
+                ..other
+            },
+            TriggeredBy::Manual,
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(ProcessSpawnError::ConfirmationRequired(_))
+    ));
+
+    let token = pm.check_spawn_safety(&ctx).unwrap().confirmation_token;
+    tokio::time::advance(CONFIRMATION_TTL + Duration::from_secs(1)).await;
+    let result = pm
+        .spawn_command(
+            SpawnContext {
+                confirmation_token: token,
+                ..ctx
+            },
+            TriggeredBy::Manual,
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(ProcessSpawnError::ConfirmationRequired(_))
+    ));
+    assert!(db.get_latest_execution_for_command(cmd_id).is_none());
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn spawn_without_confirmation_when_setting_disabled() {
+    let (pm, db, cmd_id) = make_manager_with_db();
+    db.set_setting("confirm_dangerous_commands", "false")
+        .unwrap();
+    let ctx = spawn_context(cmd_id, "rm", vec!["-f", "/nonexistent/tgui-safety-test"]);
+
+    let check = pm.check_spawn_safety(&ctx).unwrap();
+    assert!(!check.requires_confirmation);
+    assert!(check.confirmation_token.is_none());
+
+    let id = pm
+        .spawn_command(ctx, TriggeredBy::Manual)
+        .await
+        .expect("spawn failed");
+    assert!(db.get_execution_history(id).unwrap().context.is_none());
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn spawn_elevated_command_requires_confirmation() {
+    let (pm, _db, cmd_id) = make_manager_with_db();
+    let ctx = SpawnContext {
+        elevation: Some(ElevationMethod::Sudo),
+        ..spawn_context(cmd_id, "systemctl", vec!["restart", "nginx"])
+    };
+
+    let check = pm.check_spawn_safety(&ctx).unwrap();
+    assert!(check.requires_confirmation);
+    assert!(check.explanation.is_privileged);
+
+    let result = pm.spawn_command(ctx, TriggeredBy::Manual).await;
+    assert!(matches!(
+        result,
+        Err(ProcessSpawnError::ConfirmationRequired(_))
+    ));
+}
+
+#[tokio::test]
+async fn answer_askpass_without_prompt_fails() {
+    let pm = make_manager();
+    let result = pm.answer_askpass(1, Some("secret".to_string()));
+    assert!(matches!(
+        result,
+        Err(ProcessManagerError::NoPendingPrompt(1))
+    ));
+}
+
+#[tokio::test]
+async fn resolve_spawn_context_inherits_group_target_and_keeps_remote_directory() {
+    let db = create_test_db();
+    let container = ExecutionTarget::Container {
+        runtime: ContainerRuntime::Docker,
+        container: "api".to_string(),
+    };
+    let group_id = db
+        .create_group(
+            &GroupBuilder::new("containers")
+                .with_target(container.clone())
+                .build(),
+        )
+        .unwrap();
+    let inherited = db
+        .create_command(&Command {
+            working_directory: Some("/nonexistent/app".to_string()),
+            ..CommandBuilder::new("migrate", "./manage.py migrate")
+                .with_group(group_id)
+                .build()
+        })
+        .unwrap();
+    let overridden = db
+        .create_command(
+            &CommandBuilder::new("local", "ls")
+                .with_group(group_id)
+                .with_target(ExecutionTarget::Local)
+                .build(),
+        )
+        .unwrap();
+    let pm = ProcessManager::new(db, None);
+
+    let ctx = pm.resolve_spawn_context(inherited).await.expect("resolve");
+    assert_eq!(ctx.target, container);
+    assert_eq!(
+        ctx.remote_working_directory.as_deref(),
+        Some("/nonexistent/app")
+    );
+    assert!(ctx.working_directory.exists());
+
+    let ctx = pm.resolve_spawn_context(overridden).await.expect("resolve");
+    assert_eq!(ctx.target, ExecutionTarget::Local);
+    assert!(ctx.remote_working_directory.is_none());
+}
+
+#[tokio::test]
+async fn resolve_spawn_context_rejects_elevation_on_a_remote_target() {
+    let db = create_test_db();
+    let group_id = db
+        .create_group(&GroupBuilder::new("servers").build())
+        .unwrap();
+    let cmd_id = db
+        .create_command(
+            &CommandBuilder::new("restart", "systemctl")
+                .elevated()
+                .with_group(group_id)
+                .build(),
+        )
+        .unwrap();
+    // stored before elevated commands were checked against the targets they inherit
+    db.conn()
+        .unwrap()
+        .execute(
+            "UPDATE groups SET execution_target = ?1 WHERE id = ?2",
+            rusqlite::params![r#"{"kind":"ssh","host":"build-box"}"#, group_id],
+        )
+        .unwrap();
+    let pm = ProcessManager::new(db, None);
+
+    let err = pm.resolve_spawn_context(cmd_id).await.unwrap_err();
+    assert_eq!(err.code, "ELEVATION_UNAVAILABLE");
+}
+
+#[tokio::test]
+#[cfg(unix)]
+async fn preview_spawn_shows_invocation_and_where_values_came_from() {
+    let db = create_test_db();
+
+    let mut parent = GroupBuilder::new("parent")
+        .with_env("A", "parent")
+        .with_env("B", "parent")
+        .build();
+    parent.working_directory = Some("/tmp".to_string());
+    let parent_id = db.create_group(&parent).expect("create parent");
+    let mut child = GroupBuilder::new("child")
+        .with_parent(parent_id)
+        .with_env("B", "child")
+        .build();
+    child.shell = Some("sh".to_string());
+    let child_id = db.create_group(&child).expect("create child");
+
+    let cmd = CommandBuilder::new("test", "echo")
+        .with_group(child_id)
+        .with_args(vec!["${TOKEN}"])
+        .with_env("C", "command")
+        .with_secret("TOKEN", "s3cret")
+        .build();
+    let cmd_id = db.create_command(&cmd).unwrap();
+
+    let pm = ProcessManager::new(db.clone(), None);
+    let preview = pm
+        .preview_spawn(cmd_id, &HashMap::new())
+        .await
+        .expect("preview");
+
+    let sh = db.get_shell_by_name("sh").unwrap();
+    assert_eq!(preview.executable, sh.path);
+    assert_eq!(
+        preview.arguments,
+        vec!["-c".to_string(), format!("echo {SECRET_MASK}")]
+    );
+    assert_eq!(preview.working_directory, PathBuf::from("/tmp"));
+    assert_eq!(
+        preview.working_directory_source,
+        ValueSource::Group {
+            id: parent_id,
+            name: "parent".to_string()
+        }
+    );
+    assert_eq!(preview.shell.as_deref(), Some("sh"));
+    assert_eq!(
+        preview.shell_source,
+        Some(ValueSource::Group {
+            id: child_id,
+            name: "child".to_string()
+        })
+    );
+
+    let env = |key: &str| {
+        preview
+            .env_vars
+            .iter()
+            .find(|var| var.key == key)
+            .map(|var| (var.value.clone(), var.source.clone()))
+            .unwrap_or_else(|| panic!("{key} missing"))
+    };
+    assert_eq!(
+        env("A").1,
+        ValueSource::Group {
+            id: parent_id,
+            name: "parent".to_string()
+        }
+    );
+    assert_eq!(
+        env("B"),
+        (
+            "child".to_string(),
+            ValueSource::Group {
+                id: child_id,
+                name: "child".to_string()
+            }
+        )
+    );
+    assert_eq!(env("C").1, ValueSource::Command);
+    assert_eq!(
+        env("TOKEN"),
+        (SECRET_MASK.to_string(), ValueSource::Command)
+    );
+    assert_eq!(env("PATH").1, ValueSource::Inherited);
+
+    // nothing was spawned
+    assert_eq!(pm.running_count().await, 0);
+    assert!(db
+        .get_command_execution_history(cmd_id, None)
+        .unwrap()
+        .is_empty());
+}
@@ -1,8 +1,16 @@
+mod dotenv;
+mod elevation;
+mod interpolation;
 mod log_buffer;
 mod managed_process;
 mod manager;
+mod parameters;
+#[cfg(unix)]
+mod preflight;
+mod redaction;
 mod shell;
 mod streaming;
+mod target;
 
 use crate::process::models::{LogLineEvent, SpawnContext};
 use crate::utils::get_utc_timestamp_string;
@@ -22,6 +30,7 @@ pub fn spawn_context(command_id: i64, executable: &str, arguments: Vec<&str>) ->
         working_directory: PathBuf::from("/"),
         env_vars: vec![],
         shell: None,
+        ..Default::default()
     }
 }
 
@@ -35,6 +44,7 @@ pub fn spawn_context(command_id: i64, executable: &str, arguments: Vec<&str>) ->
         working_directory: std::env::temp_dir(),
         env_vars: vec![],
         shell: None,
+        ..Default::default()
     }
 }
 
@@ -0,0 +1,125 @@
+use crate::database::{CommandParameter, ParameterKind};
+use crate::process::parameters::{resolve_values, substitute, ParameterError};
+use std::collections::{BTreeMap, HashMap};
+
+fn parameter(name: &str, kind: ParameterKind) -> CommandParameter {
+    CommandParameter {
+        name: name.to_string(),
+        label: None,
+        description: None,
+        kind,
+        default: None,
+        required: true,
+    }
+}
+
+fn supplied(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn resolve_values_uses_supplied_then_default() {
+    let parameters = vec![
+        parameter("branch", ParameterKind::String),
+        CommandParameter {
+            default: Some("8080".to_string()),
+            ..parameter(
+                "port",
+                ParameterKind::Number {
+                    min: Some(1.0),
+                    max: Some(65535.0),
+                },
+            )
+        },
+    ];
+
+    let values = resolve_values(&parameters, &supplied(&[("branch", "main")])).unwrap();
+    assert_eq!(values["branch"], "main");
+    assert_eq!(values["port"], "8080");
+}
+
+#[test]
+fn resolve_values_missing_required_is_error() {
+    let parameters = vec![parameter("branch", ParameterKind::String)];
+    assert_eq!(
+        resolve_values(&parameters, &HashMap::new()),
+        Err(ParameterError::Missing("branch".to_string()))
+    );
+}
+
+#[test]
+fn resolve_values_optional_without_default_is_empty() {
+    let parameters = vec![CommandParameter {
+        required: false,
+        ..parameter("extra", ParameterKind::String)
+    }];
+    let values = resolve_values(&parameters, &HashMap::new()).unwrap();
+    assert_eq!(values["extra"], "");
+}
+
+#[test]
+fn resolve_values_unknown_parameter_is_error() {
+    let parameters = vec![parameter("branch", ParameterKind::String)];
+    assert_eq!(
+        resolve_values(&parameters, &supplied(&[("branch", "x"), ("other", "y")])),
+        Err(ParameterError::Unknown("other".to_string()))
+    );
+}
+
+#[test]
+fn resolve_values_validates_kinds() {
+    let number = vec![parameter(
+        "port",
+        ParameterKind::Number {
+            min: Some(1.0),
+            max: Some(100.0),
+        },
+    )];
+    assert!(matches!(
+        resolve_values(&number, &supplied(&[("port", "abc")])),
+        Err(ParameterError::Invalid { .. })
+    ));
+    assert!(matches!(
+        resolve_values(&number, &supplied(&[("port", "101")])),
+        Err(ParameterError::Invalid { .. })
+    ));
+
+    let env = vec![parameter(
+        "env",
+        ParameterKind::Enum {
+            options: vec!["dev".to_string(), "prod".to_string()],
+        },
+    )];
+    assert!(resolve_values(&env, &supplied(&[("env", "prod")])).is_ok());
+    assert!(matches!(
+        resolve_values(&env, &supplied(&[("env", "staging")])),
+        Err(ParameterError::Invalid { .. })
+    ));
+
+    let flag = vec![parameter("verbose", ParameterKind::Boolean)];
+    let values = resolve_values(&flag, &supplied(&[("verbose", "YES")])).unwrap();
+    assert_eq!(values["verbose"], "true");
+}
+
+#[test]
+fn substitute_replaces_declared_placeholders_only() {
+    let values = BTreeMap::from([("branch".to_string(), "main".to_string())]);
+    assert_eq!(
+        substitute("git push origin {{branch}} {{ branch }}", &values),
+        "git push origin main main"
+    );
+    assert_eq!(
+        substitute("docker ps --format '{{.Names}}'", &values),
+        "docker ps --format '{{.Names}}'"
+    );
+    assert_eq!(substitute("{{branch", &values), "{{branch");
+}
+
+#[test]
+fn substitute_escapes_interpolation_in_values() {
+    let values = BTreeMap::from([("msg".to_string(), "${HOME}".to_string())]);
+    assert_eq!(substitute("{{msg}}", &values), "$${HOME}");
+}
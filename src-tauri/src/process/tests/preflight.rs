@@ -0,0 +1,160 @@
+use crate::database::{ContainerRuntime, ExecutionTarget, ShellDefinition};
+use crate::process::errors::ProcessSpawnError;
+use crate::process::preflight::{self, PreflightProblem, ProblemKind, Severity};
+use crate::process::tests::spawn_context;
+use std::os::unix::fs::PermissionsExt;
+
+fn kinds(problems: &[PreflightProblem]) -> Vec<(ProblemKind, Severity)> {
+    problems.iter().map(|p| (p.kind, p.severity)).collect()
+}
+
+#[test]
+fn valid_command_has_no_problems() {
+    let problems = preflight::check(&spawn_context(1, "echo", vec!["hi"]), None);
+    assert!(problems.is_empty(), "{problems:?}");
+}
+
+#[test]
+fn missing_executable_is_an_error() {
+    let problems = preflight::check(&spawn_context(1, "this_does_not_exist_tgui", vec![]), None);
+    assert_eq!(
+        kinds(&problems),
+        vec![(ProblemKind::ExecutableNotFound, Severity::Error)]
+    );
+    assert_eq!(problems[0].field, "executable");
+}
+
+#[test]
+fn missing_executable_through_a_shell_is_a_warning() {
+    let ctx = spawn_context(1, "my_alias_tgui", vec![]);
+    let shell = ShellDefinition::builtin("sh", "/bin/sh");
+    let problems = preflight::check(&ctx, Some(&shell));
+    assert_eq!(
+        kinds(&problems),
+        vec![(ProblemKind::ExecutableNotFound, Severity::Warning)]
+    );
+}
+
+#[test]
+fn executable_is_looked_up_on_the_path_the_process_gets() {
+    let mut ctx = spawn_context(1, "echo", vec![]);
+    ctx.env_vars = vec![("PATH".to_string(), "/nonexistent_tgui".to_string())];
+    assert_eq!(
+        kinds(&preflight::check(&ctx, None)),
+        vec![(ProblemKind::ExecutableNotFound, Severity::Error)]
+    );
+
+    let mut ctx = spawn_context(1, "echo", vec![]);
+    ctx.clear_env = true;
+    assert_eq!(preflight::check(&ctx, None).len(), 1);
+}
+
+#[test]
+fn relative_executable_is_resolved_from_the_working_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("run.sh");
+    std::fs::write(&script, "#!/bin/sh\n").unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+    let mut ctx = spawn_context(1, "./run.sh", vec![]);
+    ctx.working_directory = dir.path().to_path_buf();
+    let problems = preflight::check(&ctx, None);
+    assert_eq!(
+        kinds(&problems),
+        vec![(ProblemKind::NotExecutable, Severity::Error)]
+    );
+    assert!(matches!(
+        ProcessSpawnError::from(problems[0].clone()),
+        ProcessSpawnError::PermissionDenied(_)
+    ));
+
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    assert!(preflight::check(&ctx, None).is_empty());
+}
+
+#[test]
+fn missing_working_directory_is_an_error() {
+    let mut ctx = spawn_context(1, "echo", vec![]);
+    ctx.working_directory = "/nonexistent_tgui/dir".into();
+    let problems = preflight::check(&ctx, None);
+    assert_eq!(
+        kinds(&problems),
+        vec![(ProblemKind::WorkingDirectoryMissing, Severity::Error)]
+    );
+    assert_eq!(problems[0].field, "working_directory");
+}
+
+#[test]
+fn shell_with_a_missing_path_is_invalid() {
+    let ctx = spawn_context(1, "echo", vec![]);
+    let shell = ShellDefinition::builtin("gone", "/nonexistent_tgui/sh");
+    assert_eq!(
+        kinds(&preflight::check(&ctx, Some(&shell))),
+        vec![(ProblemKind::InvalidShell, Severity::Error)]
+    );
+}
+
+#[test]
+fn malformed_env_vars_are_reported() {
+    let mut ctx = spawn_context(1, "echo", vec![]);
+    ctx.env_vars = vec![
+        ("".to_string(), "empty".to_string()),
+        ("A=B".to_string(), "x".to_string()),
+        ("NUL".to_string(), "a\0b".to_string()),
+        ("FINE".to_string(), "ok".to_string()),
+    ];
+    let problems = preflight::check(&ctx, None);
+    assert_eq!(problems.len(), 3);
+    assert!(problems
+        .iter()
+        .all(|p| p.kind == ProblemKind::InvalidEnvVar && p.field == "env_vars"));
+}
+
+#[test]
+fn remote_targets_only_need_the_local_client() {
+    let mut ctx = spawn_context(1, "only_on_the_server_tgui", vec![]);
+    ctx.target = ExecutionTarget::Container {
+        runtime: ContainerRuntime::Docker,
+        container: "web".to_string(),
+    };
+    ctx.env_vars = vec![("PATH".to_string(), "/nonexistent_tgui".to_string())];
+    let problems = preflight::check(&ctx, None);
+    assert_eq!(
+        kinds(&problems),
+        vec![(ProblemKind::ExecutableNotFound, Severity::Error)]
+    );
+    assert_eq!(problems[0].field, "execution_target");
+    assert!(problems[0].message.contains("docker"));
+}
+
+#[test]
+fn remote_targets_use_the_shell_of_the_remote_side() {
+    let mut ctx = spawn_context(1, "echo", vec![]);
+    ctx.target = ExecutionTarget::Ssh {
+        host: "build-box".to_string(),
+    };
+    let shell = ShellDefinition::builtin("remote-zsh", "/nonexistent_tgui/zsh");
+    let problems = preflight::check(&ctx, Some(&shell));
+    assert!(
+        problems.iter().all(|p| p.kind != ProblemKind::InvalidShell),
+        "{problems:?}"
+    );
+}
+
+#[test]
+fn problems_are_serialized_for_the_editor() {
+    let problem = PreflightProblem::error(
+        ProblemKind::WorkingDirectoryMissing,
+        "working_directory",
+        "gone".to_string(),
+    );
+    assert_eq!(
+        serde_json::to_value(&problem).unwrap(),
+        serde_json::json!({
+            "kind": "working_directory_missing",
+            "severity": "error",
+            "field": "working_directory",
+            "message": "gone",
+        })
+    );
+}
@@ -1,8 +1,13 @@
-use crate::process::shell::{build_exec, get_allowed_shells, is_valid_shell};
+use crate::database::{ShellDefinition, ShellFamily, ShellMode};
+use crate::process::shell::build_exec;
+
+fn shell(name: &str) -> ShellDefinition {
+    ShellDefinition::builtin(name, name)
+}
 
 #[test]
 fn build_exec_no_shell_returns_executable_and_args_unchanged() {
-    let result = build_exec("npm", &["run".into(), "build".into()], None);
+    let result = build_exec("npm", &["run".into(), "build".into()], &[], &[], None);
     assert_eq!(result.executable, "npm");
     assert_eq!(result.args, vec!["run", "build"]);
 }
@@ -10,14 +15,14 @@ fn build_exec_no_shell_returns_executable_and_args_unchanged() {
 #[test]
 fn build_exec_no_shell_preserves_args_with_spaces_as_separate_elements() {
     // Direct exec: args with spaces are passed as-is, the OS handles quoting
-    let result = build_exec("echo", &["hello world".into()], None);
+    let result = build_exec("echo", &["hello world".into()], &[], &[], None);
     assert_eq!(result.executable, "echo");
     assert_eq!(result.args, vec!["hello world"]);
 }
 
 #[test]
 fn build_exec_no_shell_with_empty_args_returns_no_args() {
-    let result = build_exec("ls", &[], None);
+    let result = build_exec("ls", &[], &[], &[], None);
     assert_eq!(result.executable, "ls");
     assert!(result.args.is_empty());
 }
@@ -25,7 +30,7 @@ fn build_exec_no_shell_with_empty_args_returns_no_args() {
 #[cfg(not(target_os = "windows"))]
 #[test]
 fn build_exec_bash_wraps_in_c_flag() {
-    let result = build_exec("echo", &["hello".into()], Some("bash"));
+    let result = build_exec("echo", &["hello".into()], &[], &[], Some(&shell("bash")));
     assert_eq!(result.executable, "bash");
     assert_eq!(result.args[0], "-c");
     assert!(result.args[1].contains("echo"));
@@ -35,7 +40,7 @@ fn build_exec_bash_wraps_in_c_flag() {
 #[cfg(not(target_os = "windows"))]
 #[test]
 fn build_exec_sh_wraps_in_c_flag() {
-    let result = build_exec("cat", &["file.txt".into()], Some("sh"));
+    let result = build_exec("cat", &["file.txt".into()], &[], &[], Some(&shell("sh")));
     assert_eq!(result.executable, "sh");
     assert_eq!(result.args[0], "-c");
 }
@@ -43,7 +48,7 @@ fn build_exec_sh_wraps_in_c_flag() {
 #[cfg(not(target_os = "windows"))]
 #[test]
 fn build_exec_zsh_wraps_in_c_flag() {
-    let result = build_exec("echo", &[], Some("zsh"));
+    let result = build_exec("echo", &[], &[], &[], Some(&shell("zsh")));
     assert_eq!(result.executable, "zsh");
     assert_eq!(result.args[0], "-c");
 }
@@ -52,7 +57,13 @@ fn build_exec_zsh_wraps_in_c_flag() {
 #[test]
 fn build_exec_shell_joins_args_into_single_c_string() {
     // When using a shell, command + args are joined into one string passed to -c
-    let result = build_exec("echo", &["a".into(), "b".into(), "c".into()], Some("bash"));
+    let result = build_exec(
+        "echo",
+        &["a".into(), "b".into(), "c".into()],
+        &[],
+        &[],
+        Some(&shell("bash")),
+    );
     // args[0] is "-c", args[1] is the joined command string
     assert_eq!(result.args.len(), 2);
     let cmd_str = &result.args[1];
@@ -65,15 +76,27 @@ fn build_exec_shell_joins_args_into_single_c_string() {
 #[cfg(not(target_os = "windows"))]
 #[test]
 fn build_exec_shell_quotes_args_with_spaces() {
-    let result = build_exec("echo", &["hello world".into()], Some("bash"));
+    let result = build_exec(
+        "echo",
+        &["hello world".into()],
+        &[],
+        &[],
+        Some(&shell("bash")),
+    );
     let cmd_str = &result.args[1];
-    assert!(cmd_str.contains("\"hello world\""), "found {cmd_str}");
+    assert!(cmd_str.contains("'hello world'"), "found {cmd_str}");
 }
 
 #[cfg(not(target_os = "windows"))]
 #[test]
 fn build_exec_shell_already_quoted_args_with_spaces() {
-    let result = build_exec("echo", &["\"hello world\"".into()], Some("bash"));
+    let result = build_exec(
+        "echo",
+        &["\"hello world\"".into()],
+        &[],
+        &[],
+        Some(&shell("bash")),
+    );
     let cmd_str = &result.args[1];
     assert!(cmd_str.contains("\"hello world\""));
 }
@@ -81,7 +104,13 @@ fn build_exec_shell_already_quoted_args_with_spaces() {
 #[cfg(not(target_os = "windows"))]
 #[test]
 fn build_exec_shell_already_quoted_args_no_spaces() {
-    let result = build_exec("echo", &["\"hello\"".into()], Some("bash"));
+    let result = build_exec(
+        "echo",
+        &["\"hello\"".into()],
+        &[],
+        &[],
+        Some(&shell("bash")),
+    );
     let cmd_str = &result.args[1];
     assert!(cmd_str.contains("\"hello\""));
 }
@@ -89,7 +118,13 @@ fn build_exec_shell_already_quoted_args_no_spaces() {
 #[cfg(target_os = "windows")]
 #[test]
 fn build_exec_cmd_uses_slash_c() {
-    let result = build_exec("echo", &["hello".into()], Some("cmd"));
+    let result = build_exec(
+        "echo",
+        &["hello".into()],
+        &[],
+        &[],
+        Some(&ShellDefinition::builtin("cmd", "cmd.exe")),
+    );
     assert_eq!(result.executable, "cmd.exe");
     assert_eq!(result.args[0], "/C");
 }
@@ -97,7 +132,7 @@ fn build_exec_cmd_uses_slash_c() {
 #[cfg(target_os = "windows")]
 #[test]
 fn build_exec_powershell_adds_non_interactive_flag() {
-    let result = build_exec("Get-Process", &[], Some("powershell"));
+    let result = build_exec("Get-Process", &[], &[], &[], Some(&shell("powershell")));
     assert!(result.args.contains(&"-NonInteractive".to_string()));
     assert!(result.args.contains(&"-Command".to_string()));
 }
@@ -105,68 +140,243 @@ fn build_exec_powershell_adds_non_interactive_flag() {
 #[cfg(target_os = "windows")]
 #[test]
 fn build_exec_pwsh_uses_pwsh_executable() {
-    let result = build_exec("Get-Date", &[], Some("pwsh"));
+    let result = build_exec(
+        "Get-Date",
+        &[],
+        &[],
+        &[],
+        Some(&ShellDefinition::builtin("pwsh", "pwsh.exe")),
+    );
     assert_eq!(result.executable, "pwsh.exe");
 }
 
-#[cfg(target_os = "windows")]
 #[test]
-fn is_valid_shell_accepts_all_windows_allowed_shells() {
-    for shell in &["cmd", "powershell", "pwsh"] {
+fn build_exec_replaces_cmd_placeholder_in_template() {
+    let mut xonsh = shell("xonsh");
+    xonsh.args_template = "--no-rc -c {cmd}".to_string();
+    let result = build_exec("echo", &["hi".into()], &[], &[], Some(&xonsh));
+    assert_eq!(result.executable, "xonsh");
+    assert_eq!(result.args, vec!["--no-rc", "-c", "echo hi"]);
+}
+
+#[test]
+fn build_exec_prepends_login_and_interactive_flags() {
+    let mut bash = shell("bash");
+    bash.login = true;
+    bash.interactive = true;
+    let result = build_exec("ls", &[], &[], &[], Some(&bash));
+    assert_eq!(result.args, vec!["-l", "-i", "-c", "ls"]);
+}
+
+#[test]
+fn build_exec_omits_modes_the_family_lacks() {
+    for name in ["cmd", "pwsh"] {
+        let definition = shell(name).with_mode(ShellMode::Login);
+        let result = build_exec("dir", &[], &[], &[], Some(&definition));
         assert!(
-            is_valid_shell(shell),
-            "Expected '{shell}' to be valid on Windows"
+            !result.args.iter().any(|arg| arg == "-l"),
+            "{name}: {:?}",
+            result.args
         );
     }
+    let fish = shell("fish").with_mode(ShellMode::Interactive);
+    let result = build_exec("ls", &[], &[], &[], Some(&fish));
+    assert_eq!(result.args, vec!["-i", "-c", "ls"]);
 }
 
 #[test]
-fn is_valid_shell_rejects_empty_string() {
-    assert!(!is_valid_shell(""));
+fn build_exec_uses_registry_path_as_executable() {
+    let wrapper = ShellDefinition::builtin("wrapper", "/opt/tools/run-in-env");
+    let result = build_exec("make", &["test".into()], &[], &[], Some(&wrapper));
+    assert_eq!(result.executable, "/opt/tools/run-in-env");
+    assert_eq!(result.args, vec!["-c", "make test"]);
 }
 
 #[test]
-fn is_valid_shell_rejects_path_traversal() {
-    assert!(!is_valid_shell("../../../bin/sh"));
-    assert!(!is_valid_shell("/bin/bash"));
+fn template_args_requires_exactly_one_placeholder() {
+    let mut definition = shell("bash");
+    for (template, valid) in [
+        ("-c {cmd}", true),
+        ("-lc '{cmd}'", true),
+        ("-c", false),
+        ("-c {cmd} {cmd}", false),
+        ("-c pre{cmd}", false),
+        ("-c 'unclosed {cmd}", false),
+    ] {
+        definition.args_template = template.to_string();
+        assert_eq!(
+            definition.template_args().is_some(),
+            valid,
+            "template: {template}"
+        );
+    }
 }
 
 #[test]
-fn is_valid_shell_rejects_arbitrary_executables() {
-    assert!(!is_valid_shell("rm"));
-    assert!(!is_valid_shell("python"));
-    assert!(!is_valid_shell("node"));
+fn posix_quoting_keeps_special_characters_literal() {
+    let args: Vec<String> = ["$HOME", "`id`", "*.txt", "it's", "a\nb", ""]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let result = build_exec("printf", &args, &[], &[], Some(&shell("sh")));
+    assert_eq!(
+        result.args[1],
+        "printf '$HOME' '`id`' '*.txt' 'it'\\''s' 'a\nb' ''"
+    );
 }
 
+#[cfg(unix)]
 #[test]
-fn is_valid_shell_is_case_sensitive() {
-    assert!(!is_valid_shell("Bash"));
-    assert!(!is_valid_shell("BASH"));
+fn posix_quoted_arguments_reach_the_command_unchanged() {
+    let args: Vec<String> = ["$HOME", "`id`", "*", "it's", "a\"b", "x; rm -rf y"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let result = build_exec(
+        "printf",
+        &std::iter::once("%s\\n".to_string())
+            .chain(args.iter().cloned())
+            .collect::<Vec<_>>(),
+        &[],
+        &[],
+        Some(&shell("sh")),
+    );
+    let output = std::process::Command::new(&result.executable)
+        .args(&result.args)
+        .output()
+        .unwrap();
+    let printed: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(String::from)
+        .collect();
+    assert_eq!(printed, args);
 }
 
 #[test]
-fn allowed_shells_returns_non_empty_list() {
-    assert!(!get_allowed_shells().is_empty());
+fn raw_arguments_are_left_unquoted() {
+    let args: Vec<String> = ["*.log", "$HOME", "| wc -l"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let result = build_exec("ls", &args, &[0, 2], &[], Some(&shell("bash")));
+    assert_eq!(result.args[1], "ls *.log '$HOME' | wc -l");
 }
 
 #[test]
-fn allowed_shells_every_entry_passes_is_valid_shell() {
-    for shell in get_allowed_shells() {
-        assert!(
-            is_valid_shell(&shell),
-            "allowed_shells() returned '{shell}' but is_valid_shell() rejected it"
-        );
-    }
+fn raw_arguments_are_ignored_without_shell() {
+    let result = build_exec("ls", &["*.log".into()], &[0], &[], None);
+    assert_eq!(result.args, vec!["*.log"]);
 }
 
 #[test]
-fn allowed_shells_contains_no_duplicates() {
-    let shells = get_allowed_shells();
-    let mut seen = std::collections::HashSet::new();
-    for shell in &shells {
-        assert!(
-            seen.insert(shell.clone()),
-            "Duplicate shell in allowed_shells(): '{shell}'"
-        );
+fn fish_quoting_escapes_backslashes_and_quotes() {
+    assert_eq!(ShellFamily::Fish.quote("it's"), r"'it\'s'");
+    assert_eq!(ShellFamily::Fish.quote(r"a\b"), r"'a\\b'");
+    assert_eq!(ShellFamily::Fish.quote("(date)"), "'(date)'");
+}
+
+#[test]
+fn nushell_quoting_falls_back_to_raw_strings() {
+    assert_eq!(ShellFamily::Nushell.quote("$env.HOME"), "'$env.HOME'");
+    assert_eq!(ShellFamily::Nushell.quote("it's"), "r#'it's'#");
+    assert_eq!(ShellFamily::Nushell.quote("a'#b"), "r##'a'#b'##");
+}
+
+#[test]
+fn powershell_quoting_doubles_single_quotes() {
+    assert_eq!(ShellFamily::Powershell.quote("it's $x"), "'it''s $x'");
+    assert_eq!(
+        ShellFamily::Powershell.quote(r"C:\Temp\a.txt"),
+        r"C:\Temp\a.txt"
+    );
+    assert_eq!(ShellFamily::Powershell.quote("a,b"), "'a,b'");
+}
+
+#[test]
+fn quoted_commands_are_called_in_powershell_and_nushell() {
+    let args = vec!["--version".to_string()];
+    let result = build_exec(
+        r"C:\Program Files\app.exe",
+        &args,
+        &[],
+        &[],
+        Some(&shell("pwsh")),
+    );
+    assert_eq!(result.args[2], r"& 'C:\Program Files\app.exe' --version");
+    let result = build_exec(r"C:\Tools\app.exe", &args, &[], &[], Some(&shell("pwsh")));
+    assert_eq!(result.args[2], r"C:\Tools\app.exe --version");
+
+    let result = build_exec("/opt/my app/run", &args, &[], &[], Some(&shell("nu")));
+    assert_eq!(result.args[1], "^'/opt/my app/run' --version");
+    let result = build_exec("/opt/app/run", &args, &[], &[], Some(&shell("nu")));
+    assert_eq!(result.args[1], "/opt/app/run --version");
+
+    let result = build_exec("/opt/my app/run", &args, &[], &[], Some(&shell("bash")));
+    assert_eq!(result.args[1], "'/opt/my app/run' --version");
+}
+
+#[test]
+fn cmd_quoting_doubles_double_quotes() {
+    assert_eq!(
+        ShellFamily::Cmd.quote(r#"say "hi" & exit"#),
+        r#""say ""hi"" & exit""#
+    );
+}
+
+#[test]
+fn builtin_shells_get_their_family() {
+    for (name, family) in [
+        ("bash", ShellFamily::Posix),
+        ("fish", ShellFamily::Fish),
+        ("nu", ShellFamily::Nushell),
+        ("pwsh", ShellFamily::Powershell),
+        ("cmd", ShellFamily::Cmd),
+    ] {
+        assert_eq!(shell(name).family, family, "shell: {name}");
     }
 }
+
+#[test]
+fn build_exec_sources_files_before_the_command() {
+    let sources = vec![
+        "~/.nvm/nvm.sh".to_string(),
+        "/opt/my env/setup.sh".to_string(),
+    ];
+    let result = build_exec("node", &["-v".into()], &[], &sources, Some(&shell("bash")));
+    assert_eq!(
+        result.args[1],
+        ". ~/.nvm/nvm.sh; . '/opt/my env/setup.sh'; node -v"
+    );
+
+    let result = build_exec("node", &[], &[], &sources[..1], Some(&shell("fish")));
+    assert_eq!(result.args[1], "source ~/.nvm/nvm.sh; node");
+
+    let result = build_exec("node", &[], &[], &sources[..1], None);
+    assert_eq!(result.args, Vec::<String>::new());
+}
+
+#[test]
+fn build_exec_source_files_per_family() {
+    let sources = vec![r"C:\env\setup.bat".to_string()];
+    let cmd = ShellDefinition::builtin("cmd", "cmd.exe");
+    let result = build_exec("build", &[], &[], &sources, Some(&cmd));
+    assert_eq!(result.args, vec!["/C", r"call C:\env\setup.bat & build"]);
+
+    let sources = vec!["~/profile.ps1".to_string()];
+    let result = build_exec("build", &[], &[], &sources, Some(&shell("pwsh")));
+    assert_eq!(result.args[2], ". '~/profile.ps1'; build");
+}
+
+#[test]
+fn with_mode_overrides_registry_flags() {
+    let mut bash = shell("bash");
+    bash.login = true;
+
+    let interactive = bash.with_mode(ShellMode::Interactive);
+    assert!(!interactive.login && interactive.interactive);
+    let result = build_exec("ll", &[], &[], &[], Some(&interactive));
+    assert_eq!(result.args, vec!["-i", "-c", "ll"]);
+
+    let plain = bash.with_mode(ShellMode::NonInteractive);
+    assert!(!plain.login && !plain.interactive);
+}
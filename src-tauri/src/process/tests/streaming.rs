@@ -59,6 +59,7 @@ fn batching_config(batch_size: usize, timeout_ms: u64) -> StreamingConfig {
         batch_size,
         batch_timeout_ms: timeout_ms,
         max_line_length: 10000,
+        ..StreamingConfig::default()
     }
 }
 
@@ -327,6 +328,7 @@ async fn lines_exceeding_max_length_are_truncated_with_marker() {
         batch_size: 1,
         batch_timeout_ms: 0,
         max_line_length: 10,
+        ..StreamingConfig::default()
     };
     let (tx, rx) = tokio::sync::mpsc::channel(128);
     let (streamer, _buffer) = LogStreamer::new(1, 1, config);
@@ -345,6 +347,37 @@ async fn lines_exceeding_max_length_are_truncated_with_marker() {
     );
 }
 
+#[tokio::test]
+#[cfg(unix)]
+async fn secrets_are_redacted_before_truncation() {
+    let (mut child, stdout, stderr) = spawn_child(
+        "sh",
+        &["-c", "echo token=s3cr3t-value; echo s3cr3t-value >&2"],
+    )
+    .await;
+
+    let config = StreamingConfig {
+        max_line_length: 12,
+        secrets: vec!["s3cr3t-value".to_string()],
+        ..StreamingConfig::immediate()
+    };
+    let (tx, rx) = tokio::sync::mpsc::channel(128);
+    let (streamer, _buffer) = LogStreamer::new(1, 1, config);
+    let _handle = streamer.start(stdout, stderr, tx);
+    let lines = drain_process_events(rx).await;
+    let _ = child.wait().await;
+
+    assert_eq!(lines.len(), 2);
+    for line in &lines {
+        assert!(!line.content.contains("s3cr3t"), "leaked: {}", line.content);
+        assert!(
+            line.content.contains("****"),
+            "not masked: {}",
+            line.content
+        );
+    }
+}
+
 #[tokio::test]
 async fn process_with_no_output_produces_no_events() {
     #[cfg(unix)]
@@ -604,6 +637,7 @@ async fn unicode_multibyte_at_truncation_boundary() {
         batch_size: 1,
         batch_timeout_ms: 0,
         max_line_length: 15, // Cuts into middle of emoji sequence
+        ..StreamingConfig::default()
     };
 
     let (tx, rx) = tokio::sync::mpsc::channel(128);
@@ -660,6 +694,7 @@ async fn buffer_capacity_smaller_than_batch_size() {
         batch_size: 10,
         batch_timeout_ms: 50,
         max_line_length: 10000,
+        ..StreamingConfig::default()
     };
 
     let (tx, rx) = tokio::sync::mpsc::channel(128);
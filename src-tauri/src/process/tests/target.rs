@@ -0,0 +1,108 @@
+use crate::database::{ContainerRuntime, ExecutionTarget};
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+fn container() -> ExecutionTarget {
+    ExecutionTarget::Container {
+        runtime: ContainerRuntime::Podman,
+        container: "web".to_string(),
+    }
+}
+
+#[test]
+fn local_target_is_not_wrapped() {
+    let (executable, args) = ExecutionTarget::Local.wrap(
+        "echo".to_string(),
+        strings(&["hi"]),
+        1,
+        Some("/srv"),
+        &strings(&["KEY"]),
+    );
+    assert_eq!(executable, "echo");
+    assert_eq!(args, strings(&["hi"]));
+    assert!(ExecutionTarget::Local.kill_command(1, true).is_none());
+}
+
+#[test]
+fn container_target_forwards_env_by_name() {
+    let (executable, args) = container().wrap(
+        "npm".to_string(),
+        strings(&["run", "dev"]),
+        7,
+        Some("/app"),
+        &strings(&["API_TOKEN"]),
+    );
+    assert_eq!(executable, "podman");
+    assert_eq!(args[..4], strings(&["exec", "-e", "API_TOKEN", "web"]));
+    assert_eq!(args[4..6], strings(&["sh", "-c"]));
+    assert!(args[6].contains("-7.pid"));
+    assert_eq!(args[7..], strings(&["sh", "/app", "npm", "run", "dev"]));
+}
+
+#[test]
+fn ssh_target_quotes_the_remote_command() {
+    let target = ExecutionTarget::Ssh {
+        host: "deploy@build-box".to_string(),
+    };
+    let (executable, args) = target.wrap(
+        "echo".to_string(),
+        strings(&["hello world"]),
+        3,
+        None,
+        &strings(&["IGNORED"]),
+    );
+    assert_eq!(executable, "ssh");
+    assert_eq!(
+        args[..4],
+        strings(&["-o", "BatchMode=yes", "deploy@build-box", "--"])
+    );
+    assert_eq!(args.len(), 5);
+    assert!(args[4].starts_with("sh -c '"));
+    assert!(args[4].ends_with(" sh . echo 'hello world'"));
+    assert!(!args[4].contains("IGNORED"));
+}
+
+#[test]
+fn kill_command_signals_recorded_pid() {
+    let (program, args) = container().kill_command(7, false).unwrap();
+    assert_eq!(program, "podman");
+    assert_eq!(args[..4], strings(&["exec", "web", "sh", "-c"]));
+    assert!(args[4].starts_with("kill -s TERM \"$(cat /tmp/tgui-"));
+
+    let target = ExecutionTarget::Ssh {
+        host: "box".to_string(),
+    };
+    let (program, args) = target.kill_command(7, true).unwrap();
+    assert_eq!(program, "ssh");
+    assert!(args.last().unwrap().starts_with("kill -s KILL"));
+}
+
+/// Runs the part after `podman exec web` locally
+#[test]
+#[cfg(unix)]
+fn remote_script_changes_directory_and_keeps_exit_code() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir_path = dir.path().to_string_lossy().into_owned();
+    let (_, args) = container().wrap(
+        "sh".to_string(),
+        strings(&["-c", "pwd; exit 3"]),
+        4242,
+        Some(&dir_path),
+        &[],
+    );
+
+    let output = std::process::Command::new(&args[2])
+        .args(&args[3..])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    let pwd = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        std::fs::canonicalize(pwd.trim()).unwrap(),
+        std::fs::canonicalize(dir.path()).unwrap()
+    );
+    let pid_file = format!("/tmp/tgui-{}-4242.pid", std::process::id());
+    assert!(!std::path::Path::new(&pid_file).exists());
+}
@@ -3,3 +3,11 @@ use time::OffsetDateTime;
 pub fn get_utc_timestamp_string() -> String {
     OffsetDateTime::now_utc().to_string()
 }
+
+/// Local date as YYYY-MM-DD, falls back to UTC when the local offset can't be determined
+pub fn get_local_date_string() -> String {
+    OffsetDateTime::now_local()
+        .unwrap_or_else(|_| OffsetDateTime::now_utc())
+        .date()
+        .to_string()
+}